@@ -293,6 +293,117 @@ fn path_aa_tiny_skia(bencher: &mut Bencher) {
     });
 }
 
+#[bench]
+fn path_aa_analytic_tiny_skia(bencher: &mut Bencher) {
+    use tiny_skia::*;
+
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint.anti_alias = true;
+    paint.rasterizer = Rasterizer::Analytic;
+
+    let mut pb = PathBuilder::new();
+    pb.move_to(500.0, 20.0);
+    pb.cubic_to(650.0, 320.0, 770.0, 650.0, 800.0, 980.0);
+    pb.line_to(20.0, 380.0);
+    pb.line_to(200.0, 980.0);
+    pb.cubic_to(230.0, 650.0, 350.0, 320.0, 500.0, 20.0);
+    pb.close();
+    let path = pb.finish().unwrap();
+
+    let mut pixmap = Pixmap::new(1000, 1000).unwrap();
+
+    bencher.iter(|| {
+        pixmap.fill_path(&path, &paint, FillRule::EvenOdd, Transform::identity(), None);
+    });
+}
+
+#[bench]
+fn circle_aa_tiny_skia(bencher: &mut Bencher) {
+    use tiny_skia::*;
+
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint.anti_alias = true;
+
+    let path = PathBuilder::from_circle(500.0, 500.0, 480.0).unwrap();
+
+    let mut pixmap = Pixmap::new(1000, 1000).unwrap();
+
+    bencher.iter(|| {
+        pixmap.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+    });
+}
+
+#[bench]
+fn circle_aa_analytic_tiny_skia(bencher: &mut Bencher) {
+    use tiny_skia::*;
+
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint.anti_alias = true;
+    paint.rasterizer = Rasterizer::Analytic;
+
+    let path = PathBuilder::from_circle(500.0, 500.0, 480.0).unwrap();
+
+    let mut pixmap = Pixmap::new(1000, 1000).unwrap();
+
+    bencher.iter(|| {
+        pixmap.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+    });
+}
+
+// A star polygon with 5000 edges crossing each other.
+fn complex_path() -> tiny_skia::Path {
+    let mut pb = tiny_skia::PathBuilder::new();
+    for i in 0..5000 {
+        let a = (i * 2499 % 5000) as f32 / 5000.0 * 2.0 * std::f32::consts::PI;
+        let (x, y) = (500.0 + 480.0 * a.cos(), 500.0 + 480.0 * a.sin());
+        if i == 0 {
+            pb.move_to(x, y);
+        } else {
+            pb.line_to(x, y);
+        }
+    }
+    pb.close();
+    pb.finish().unwrap()
+}
+
+#[bench]
+fn complex_path_aa_tiny_skia(bencher: &mut Bencher) {
+    use tiny_skia::*;
+
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint.anti_alias = true;
+
+    let path = complex_path();
+
+    let mut pixmap = Pixmap::new(1000, 1000).unwrap();
+
+    bencher.iter(|| {
+        pixmap.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+    });
+}
+
+#[bench]
+fn complex_path_aa_analytic_tiny_skia(bencher: &mut Bencher) {
+    use tiny_skia::*;
+
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint.anti_alias = true;
+    paint.rasterizer = Rasterizer::Analytic;
+
+    let path = complex_path();
+
+    let mut pixmap = Pixmap::new(1000, 1000).unwrap();
+
+    bencher.iter(|| {
+        pixmap.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+    });
+}
+
 #[cfg(feature = "skia-rs")]
 #[bench]
 fn path_aa_skia(bencher: &mut Bencher) {
@@ -0,0 +1,261 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use crate::{Path, Point};
+
+use crate::floating_point::NormalizedF32Exclusive;
+use crate::path::PathSegment;
+use crate::path_builder::PathBuilder;
+use crate::path_geometry::{self, CubicCoeff};
+
+#[cfg(all(not(feature = "std"), feature = "no-std-float"))]
+use crate::NoStdFloat;
+
+// The maximum distance between a cubic and a quad that shares end points with it and has
+// a control point at (3 * (p1 + p2) - p0 - p3) / 4 is |p3 - 3 * p2 + 3 * p1 - p0| * sqrt(3) / 36.
+const CUBIC_TO_QUAD_ERROR_FACTOR: f32 = 0.0481125224;
+
+// Keeps tiny tolerances from producing an enormous path.
+const MAX_QUADS_PER_CUBIC: usize = 1024;
+
+impl Path {
+    /// Returns a path where all quadratic curves are converted into cubic ones.
+    ///
+    /// The conversion is exact. Lines are preserved.
+    ///
+    /// Returns `None` when the final path has an invalid bounding box.
+    pub fn to_cubics(&self) -> Option<Path> {
+        let mut pb = PathBuilder::with_capacity(self.len(), self.points.len() + self.len());
+        let mut last_point = Point::zero();
+        for segment in self.segments() {
+            match segment {
+                PathSegment::MoveTo(p) => pb.move_to(p.x, p.y),
+                PathSegment::LineTo(p) => pb.line_to(p.x, p.y),
+                PathSegment::QuadTo(p1, p2) => {
+                    let cubic = path_geometry::quad_to_cubic(&[last_point, p1, p2]);
+                    pb.cubic_to_pt(cubic[1], cubic[2], p2);
+                }
+                PathSegment::CubicTo(p1, p2, p3) => pb.cubic_to_pt(p1, p2, p3),
+                PathSegment::Close => pb.close(),
+            }
+
+            if let Some(p) = pb.last_point() {
+                last_point = p;
+            }
+        }
+
+        pb.finish()
+    }
+
+    /// Returns a path where all cubic curves are approximated by quadratic ones.
+    ///
+    /// Each cubic is split into the smallest number of quads that guarantees
+    /// that the distance between the original and the approximated curve
+    /// is not larger than `tolerance`. Lines and quads are preserved.
+    ///
+    /// A cubic is never split into more than 1024 quads,
+    /// so the tolerance is not guaranteed when it's tiny compared to the curve.
+    ///
+    /// Returns `None` when `tolerance` is not positive
+    /// or when the final path has an invalid bounding box.
+    pub fn to_quads(&self, tolerance: f32) -> Option<Path> {
+        if !(tolerance > 0.0 && tolerance.is_finite()) {
+            return None;
+        }
+
+        let mut pb = PathBuilder::with_capacity(self.len(), self.points.len());
+        let mut last_point = Point::zero();
+        for segment in self.segments() {
+            match segment {
+                PathSegment::MoveTo(p) => pb.move_to(p.x, p.y),
+                PathSegment::LineTo(p) => pb.line_to(p.x, p.y),
+                PathSegment::QuadTo(p1, p2) => pb.quad_to_pt(p1, p2),
+                PathSegment::CubicTo(p1, p2, p3) => {
+                    cubic_to_quads(&[last_point, p1, p2, p3], tolerance, &mut pb)
+                }
+                PathSegment::Close => pb.close(),
+            }
+
+            if let Some(p) = pb.last_point() {
+                last_point = p;
+            }
+        }
+
+        pb.finish()
+    }
+}
+
+fn cubic_to_quads(src: &[Point; 4], tolerance: f32, pb: &mut PathBuilder) {
+    let count = quads_count(src, tolerance);
+
+    // Split the cubic into `count` parts with an equal `t` span.
+    let mut cubic = *src;
+    let mut tmp = [Point::zero(); 7];
+    for i in 0..count - 1 {
+        let t = NormalizedF32Exclusive::new_bounded(1.0 / (count - i) as f32);
+        path_geometry::chop_cubic_at2(&cubic, t, &mut tmp);
+        pb.quad_to_pt(cubic_to_quad_control_point(&tmp[0..4]), tmp[3]);
+        cubic = [tmp[3], tmp[4], tmp[5], tmp[6]];
+    }
+
+    pb.quad_to_pt(cubic_to_quad_control_point(&cubic), cubic[3]);
+}
+
+fn quads_count(src: &[Point; 4], tolerance: f32) -> usize {
+    // Splitting a cubic into `n` parts reduces the third difference by n^3.
+    let d = Point::from_f32x2(CubicCoeff::from_points(src).a);
+    let n = (d.length() * CUBIC_TO_QUAD_ERROR_FACTOR / tolerance).powf(1.0 / 3.0);
+    if n.is_finite() {
+        // Compensate for the floating point inaccuracy around exact cube roots.
+        ((n * 1.00001).ceil() as usize).clamp(1, MAX_QUADS_PER_CUBIC)
+    } else {
+        MAX_QUADS_PER_CUBIC
+    }
+}
+
+fn cubic_to_quad_control_point(cubic: &[Point]) -> Point {
+    ((cubic[1] + cubic[2]).scaled(3.0) - cubic[0] - cubic[3]).scaled(0.25)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NormalizedF32;
+
+    fn cubic_path(cubic: &[Point; 4]) -> Path {
+        let mut pb = PathBuilder::new();
+        pb.move_to(cubic[0].x, cubic[0].y);
+        pb.cubic_to_pt(cubic[1], cubic[2], cubic[3]);
+        pb.finish().unwrap()
+    }
+
+    fn max_distance(cubic: &[Point; 4], quads: &Path) -> f32 {
+        // The error bound holds for the same parameter value,
+        // which is stricter than the geometric distance.
+        let count = quads.len() - 1;
+        let mut max: f32 = 0.0;
+        let mut last = Point::zero();
+        let mut index = 0;
+        for segment in quads.segments() {
+            match segment {
+                PathSegment::MoveTo(p) => last = p,
+                PathSegment::QuadTo(p1, p2) => {
+                    let quad = [last, p1, p2];
+                    for i in 0..=50 {
+                        let t = i as f32 / 50.0;
+                        let p = path_geometry::eval_quad_at(&quad, NormalizedF32::new_clamped(t));
+                        let cubic_t = NormalizedF32::new_clamped((index as f32 + t) / count as f32);
+                        let e = path_geometry::eval_cubic_pos_at(cubic, cubic_t);
+                        max = max.max(e.distance(p));
+                    }
+                    last = p2;
+                    index += 1;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        max
+    }
+
+    #[test]
+    fn quads_to_cubics() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(20.0, 10.0);
+        pb.quad_to(50.0, 70.0, 80.0, 10.0);
+        pb.close();
+        let path = pb.finish().unwrap();
+
+        let cubics = path.to_cubics().unwrap();
+        assert_eq!(
+            cubics.verbs(),
+            &[
+                crate::PathVerb::Move,
+                crate::PathVerb::Line,
+                crate::PathVerb::Cubic,
+                crate::PathVerb::Close
+            ]
+        );
+        assert_eq!(cubics.points()[2], Point::from_xy(40.0, 50.0));
+        assert_eq!(cubics.points()[3], Point::from_xy(60.0, 50.0));
+
+        // The elevated curve is identical.
+        let quad = [
+            Point::from_xy(20.0, 10.0),
+            Point::from_xy(50.0, 70.0),
+            Point::from_xy(80.0, 10.0),
+        ];
+        let cubic = [
+            cubics.points()[1],
+            cubics.points()[2],
+            cubics.points()[3],
+            cubics.points()[4],
+        ];
+        for i in 0..=10 {
+            let t = NormalizedF32::new_clamped(i as f32 / 10.0);
+            let p1 = path_geometry::eval_quad_at(&quad, t);
+            let p2 = path_geometry::eval_cubic_pos_at(&cubic, t);
+            assert!(p1.distance(p2) < 0.0001);
+        }
+    }
+
+    #[test]
+    fn cubic_to_quads_error_bound() {
+        let cubic = [
+            Point::from_xy(10.0, 10.0),
+            Point::from_xy(200.0, 10.0),
+            Point::from_xy(-100.0, 190.0),
+            Point::from_xy(190.0, 190.0),
+        ];
+        let path = cubic_path(&cubic);
+
+        for tolerance in [1.0, 0.25, 0.05, 0.01] {
+            let quads = path.to_quads(tolerance).unwrap();
+            assert!(max_distance(&cubic, &quads) <= tolerance);
+        }
+
+        let coarse = path.to_quads(1.0).unwrap().len();
+        let fine = path.to_quads(0.01).unwrap().len();
+        assert!(coarse < fine);
+    }
+
+    #[test]
+    fn quadratic_cubic_is_a_single_quad() {
+        // A cubic that was elevated from a quad.
+        let cubic = [
+            Point::from_xy(20.0, 10.0),
+            Point::from_xy(40.0, 50.0),
+            Point::from_xy(60.0, 50.0),
+            Point::from_xy(80.0, 10.0),
+        ];
+        let quads = cubic_path(&cubic).to_quads(0.001).unwrap();
+        assert_eq!(quads.len(), 2);
+        assert!(quads.points()[1].distance(Point::from_xy(50.0, 70.0)) < 0.001);
+    }
+
+    #[test]
+    fn to_quads_invalid_tolerance() {
+        let path = cubic_path(&[
+            Point::from_xy(20.0, 10.0),
+            Point::from_xy(40.0, 50.0),
+            Point::from_xy(60.0, 50.0),
+            Point::from_xy(80.0, 10.0),
+        ]);
+        assert!(path.to_quads(0.0).is_none());
+        assert!(path.to_quads(-1.0).is_none());
+        assert!(path.to_quads(f32::NAN).is_none());
+    }
+
+    #[test]
+    fn to_quads_tiny_tolerance() {
+        let path = cubic_path(&[
+            Point::from_xy(0.0, 0.0),
+            Point::from_xy(100000.0, 0.0),
+            Point::from_xy(-100000.0, 100000.0),
+            Point::from_xy(100000.0, 100000.0),
+        ]);
+        let quads = path.to_quads(1e-9).unwrap();
+        assert_eq!(quads.len(), MAX_QUADS_PER_CUBIC + 1);
+    }
+}
@@ -41,6 +41,9 @@ pub struct StrokeDash {
     interval_len: NonZeroPositiveF32,
     first_len: f32, // TODO: PositiveF32
     first_index: usize,
+    fit_to_contour: bool,
+    continue_across_contours: bool,
+    center_on_corners: bool,
 }
 
 impl StrokeDash {
@@ -73,8 +76,64 @@ impl StrokeDash {
             interval_len,
             first_len,
             first_index,
+            fit_to_contour: false,
+            continue_across_contours: false,
+            center_on_corners: false,
         })
     }
+
+    /// Stretches or shrinks the pattern, so an integer number of periods fits each contour.
+    ///
+    /// Prevents closed contours from ending with a partial dash.
+    ///
+    /// Default: false
+    pub fn set_fit_to_contour(&mut self, flag: bool) {
+        self.fit_to_contour = flag;
+    }
+
+    /// Continues the pattern from where the previous contour ended,
+    /// instead of restarting it at the dash offset on each contour.
+    ///
+    /// Default: false
+    pub fn set_continue_across_contours(&mut self, flag: bool) {
+        self.continue_across_contours = flag;
+    }
+
+    /// Places dashes, so that corners and ends of open contours are in the middle of a dash.
+    ///
+    /// The pattern is fitted between each pair of adjacent corners and the dash offset is ignored.
+    /// Closed contours without corners are dashed like with
+    /// [`set_fit_to_contour`](Self::set_fit_to_contour).
+    ///
+    /// Default: false
+    pub fn set_center_on_corners(&mut self, flag: bool) {
+        self.center_on_corners = flag;
+    }
+
+    /// Returns the dash array.
+    pub fn array(&self) -> &[f32] {
+        &self.array
+    }
+
+    /// Returns the dash offset, normalized to the `0..interval` range.
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+
+    /// Checks that the pattern is fitted to each contour.
+    pub fn fit_to_contour(&self) -> bool {
+        self.fit_to_contour
+    }
+
+    /// Checks that the pattern continues across contours.
+    pub fn continue_across_contours(&self) -> bool {
+        self.continue_across_contours
+    }
+
+    /// Checks that dashes are centered on corners.
+    pub fn center_on_corners(&self) -> bool {
+        self.center_on_corners
+    }
 }
 
 #[cfg(test)]
@@ -110,6 +169,182 @@ mod tests {
 
         assert!(path.dash(&stroke_dash, 1.0).is_some());
     }
+
+    fn contours(path: &Path) -> Vec<ContourMeasure> {
+        ContourMeasureIter::new(path, 1.0).collect()
+    }
+
+    fn square() -> Path {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.line_to(100.0, 0.0);
+        pb.line_to(100.0, 100.0);
+        pb.line_to(0.0, 100.0);
+        pb.close();
+        pb.finish().unwrap()
+    }
+
+    #[test]
+    fn fit_to_contour() {
+        let path = square();
+        let mut stroke_dash = StrokeDash::new(vec![30.0, 25.0], 0.0).unwrap();
+
+        // 400 / 55 is not an integer, so the last dash is partial and joined with the first one.
+        let dashes = contours(&path.dash(&stroke_dash, 1.0).unwrap());
+        assert_eq!(dashes.len(), 7);
+        assert!((dashes[6].length - 45.0).abs() < 0.01);
+
+        stroke_dash.set_fit_to_contour(true);
+        let dashes = contours(&path.dash(&stroke_dash, 1.0).unwrap());
+        assert_eq!(dashes.len(), 7);
+        for dash in dashes {
+            assert!((dash.length - 30.0 * 400.0 / 385.0).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn continue_across_contours() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.line_to(15.0, 0.0);
+        pb.move_to(0.0, 10.0);
+        pb.line_to(15.0, 10.0);
+        let path = pb.finish().unwrap();
+
+        let mut stroke_dash = StrokeDash::new(vec![10.0, 10.0], 0.0).unwrap();
+        let dashed = path.dash(&stroke_dash, 1.0).unwrap();
+        assert_eq!(dashed.points()[2], Point::from_xy(0.0, 10.0));
+
+        // The second contour starts with the remaining 5 units of a gap.
+        stroke_dash.set_continue_across_contours(true);
+        let dashed = path.dash(&stroke_dash, 1.0).unwrap();
+        assert_eq!(dashed.points()[2], Point::from_xy(5.0, 10.0));
+        assert_eq!(dashed.points()[3], Point::from_xy(15.0, 10.0));
+    }
+
+    #[test]
+    fn center_on_corners_open() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.line_to(100.0, 0.0);
+        pb.line_to(100.0, 100.0);
+        let path = pb.finish().unwrap();
+
+        let mut stroke_dash = StrokeDash::new(vec![10.0, 10.0], 3.0).unwrap();
+        stroke_dash.set_center_on_corners(true);
+        let dashed = path.dash(&stroke_dash, 1.0).unwrap();
+
+        let dashes = contours(&dashed);
+        assert_eq!(dashes.len(), 11);
+        // Half dashes at the ends.
+        assert!((dashes[0].length - 5.0).abs() < 0.01);
+        assert!((dashes[10].length - 5.0).abs() < 0.01);
+        // A full dash around the corner.
+        assert!((dashes[5].length - 10.0).abs() < 0.01);
+        assert_eq!(dashes[5].points[1], Point::from_xy(100.0, 0.0));
+    }
+
+    #[test]
+    fn center_on_corners_closed() {
+        let mut stroke_dash = StrokeDash::new(vec![10.0, 12.0], 0.0).unwrap();
+        stroke_dash.set_center_on_corners(true);
+        let dashed = square().dash(&stroke_dash, 1.0).unwrap();
+
+        // 100 / 22 rounds to 5 periods per side, with a dash shared by each pair of sides.
+        let dashes = contours(&dashed);
+        assert_eq!(dashes.len(), 20);
+        let scale = 100.0 / 110.0;
+        for dash in &dashes {
+            assert!((dash.length - 10.0 * scale).abs() < 0.01);
+        }
+
+        // The first corner is in the middle of the last dash.
+        let last = dashes.last().unwrap();
+        assert!(last.points.contains(&Point::from_xy(0.0, 0.0)));
+    }
+
+    #[test]
+    fn center_on_corners_smooth() {
+        let path = PathBuilder::from_circle(50.0, 50.0, 40.0).unwrap();
+        let mut stroke_dash = StrokeDash::new(vec![10.0, 10.0], 0.0).unwrap();
+        stroke_dash.set_center_on_corners(true);
+        let dashed = path.dash(&stroke_dash, 1.0).unwrap();
+
+        // Without corners the pattern is just fitted.
+        let dashes = contours(&dashed);
+        let length = contours(&path)[0].length;
+        let count = (length / 20.0).round();
+        assert_eq!(dashes.len(), count as usize);
+    }
+
+    #[test]
+    fn center_on_corners_continue_across_contours() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.line_to(100.0, 0.0);
+        pb.line_to(100.0, 100.0);
+        let corner_path = pb.finish().unwrap();
+        let circle = PathBuilder::from_circle(50.0, 50.0, 40.0).unwrap();
+
+        let mut pb = PathBuilder::new();
+        pb.push_path(&corner_path);
+        pb.push_path(&circle);
+        let path = pb.finish().unwrap();
+
+        let mut stroke_dash = StrokeDash::new(vec![10.0, 10.0], 0.0).unwrap();
+        stroke_dash.set_center_on_corners(true);
+        stroke_dash.set_continue_across_contours(true);
+        let dashed = path.dash(&stroke_dash, 1.0).unwrap();
+
+        // The circle continues the half dash the first contour ended with.
+        let mut circle_dash = StrokeDash::new(vec![10.0, 10.0], 5.0).unwrap();
+        circle_dash.set_fit_to_contour(true);
+        let mut pb = PathBuilder::new();
+        pb.push_path(&corner_path.dash(&stroke_dash, 1.0).unwrap());
+        pb.push_path(&circle.dash(&circle_dash, 1.0).unwrap());
+        let expected = pb.finish().unwrap();
+        assert_eq!(dashed, expected);
+
+        stroke_dash.set_continue_across_contours(false);
+        assert_ne!(path.dash(&stroke_dash, 1.0).unwrap(), expected);
+    }
+
+    #[test]
+    fn center_on_corners_short_section_far_away() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.line_to(100000.0, 0.0);
+        pb.line_to(100000.0, 0.01);
+        pb.line_to(100100.0, 0.01);
+        let path = pb.finish().unwrap();
+
+        let mut stroke_dash = StrokeDash::new(vec![10.0, 10.0], 0.0).unwrap();
+        stroke_dash.set_center_on_corners(true);
+        let dashed = path.dash(&stroke_dash, 1.0).unwrap();
+
+        // 5001 and 6 dashes in the long sections. The short section is too small
+        // to be dashed and joins the dashes at its ends into one.
+        let dashes = contours(&dashed);
+        assert_eq!(dashes.len(), 5001 + 6 - 1);
+    }
+
+    #[test]
+    fn center_on_corners_zero_length_dashes() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.line_to(100.0, 0.0);
+        pb.line_to(100.0, 100.0);
+        let path = pb.finish().unwrap();
+
+        let mut stroke_dash = StrokeDash::new(vec![0.0, 10.0], 0.0).unwrap();
+        stroke_dash.set_center_on_corners(true);
+        let dashed = path.dash(&stroke_dash, 1.0).unwrap();
+
+        // 11 dots per side, with the one at the corner shared.
+        assert_eq!(dashed.points().len(), 21 * 2);
+        assert_eq!(dashed.bounds(), path.bounds());
+        assert!(dashed.points().contains(&Point::from_xy(100.0, 0.0)));
+    }
 }
 
 // Adjust phase to be between 0 and len, "flipping" phase if negative.
@@ -167,6 +402,16 @@ impl Path {
     }
 }
 
+// Since the path length / dash length ratio may be arbitrarily large, we can exert
+// significant memory pressure while attempting to build the filtered path. To avoid this,
+// we simply give up dashing beyond a certain threshold.
+//
+// The original bug report (http://crbug.com/165432) is based on a path yielding more than
+// 90 million dash segments and crashing the memory allocator. A limit of 1 million
+// segments seems reasonable: at 2 verbs per segment * 9 bytes per verb, this caps the
+// maximum dash memory overhead at roughly 17MB per path.
+const MAX_DASH_COUNT: usize = 1000000;
+
 fn dash_impl(src: &Path, dash: &StrokeDash, res_scale: f32) -> Option<Path> {
     // We do not support the `cull_path` branch here.
     // Skia has a lot of code for cases when a path contains only a single zero-length line
@@ -176,79 +421,235 @@ fn dash_impl(src: &Path, dash: &StrokeDash, res_scale: f32) -> Option<Path> {
     // We also doesn't support the `SpecialLineRec` case.
     // I have no idea what the point in it.
 
-    fn is_even(x: usize) -> bool {
-        x % 2 == 0
-    }
-
     let mut pb = PathBuilder::new();
     let mut dash_count = 0.0;
+    // An interval index and its remaining length at the end of the previous contour.
+    let mut next_phase = (dash.first_index, dash.first_len);
     for contour in ContourMeasureIter::new(src, res_scale) {
-        let mut skip_first_segment = contour.is_closed;
-        let mut added_segment = false;
         let length = contour.length;
-        let mut index = dash.first_index;
 
-        // Since the path length / dash length ratio may be arbitrarily large, we can exert
-        // significant memory pressure while attempting to build the filtered path. To avoid this,
-        // we simply give up dashing beyond a certain threshold.
-        //
-        // The original bug report (http://crbug.com/165432) is based on a path yielding more than
-        // 90 million dash segments and crashing the memory allocator. A limit of 1 million
-        // segments seems reasonable: at 2 verbs per segment * 9 bytes per verb, this caps the
-        // maximum dash memory overhead at roughly 17MB per path.
-        const MAX_DASH_COUNT: usize = 1000000;
+        if dash.center_on_corners {
+            let corners = contour.corner_distances();
+            if !corners.is_empty() {
+                next_phase =
+                    dash_between_corners(&contour, &corners, dash, &mut dash_count, &mut pb)?;
+                continue;
+            }
+        }
+
         dash_count += length * (dash.array.len() >> 1) as f32 / dash.interval_len.get();
         if dash_count > MAX_DASH_COUNT as f32 {
             return None;
         }
 
-        // Using double precision to avoid looping indefinitely due to single precision rounding
-        // (for extreme path_length/dash_length ratios). See test_infinite_dash() unittest.
-        let mut distance = 0.0;
-        let mut d_len = dash.first_len;
-
-        while distance < length {
-            debug_assert!(d_len >= 0.0);
-            added_segment = false;
-            if is_even(index) && !skip_first_segment {
-                added_segment = true;
-                contour.push_segment(distance, distance + d_len, true, &mut pb);
-            }
+        let phase = if dash.continue_across_contours {
+            next_phase
+        } else {
+            (dash.first_index, dash.first_len)
+        };
+
+        let scale = if dash.fit_to_contour || dash.center_on_corners {
+            fit_scale(length, dash.interval_len.get())
+        } else {
+            1.0
+        };
+
+        next_phase = dash_contour(&contour, dash, phase, scale, &mut pb);
+    }
+
+    pb.finish()
+}
+
+// Dashes a contour starting from the specified interval and its remaining length.
+// Returns the same for the next contour.
+fn dash_contour(
+    contour: &ContourMeasure,
+    dash: &StrokeDash,
+    (first_index, first_len): (usize, f32),
+    scale: f32,
+    pb: &mut PathBuilder,
+) -> (usize, f32) {
+    fn is_even(x: usize) -> bool {
+        x % 2 == 0
+    }
 
-            distance += d_len;
+    let mut skip_first_segment = contour.is_closed;
+    let mut added_segment = false;
+    let length = contour.length;
+    let mut index = first_index;
+
+    // Using double precision to avoid looping indefinitely due to single precision rounding
+    // (for extreme path_length/dash_length ratios). See test_infinite_dash() unittest.
+    let mut distance = 0.0;
+    let mut d_len = first_len * scale;
+
+    while distance < length {
+        debug_assert!(d_len >= 0.0);
+        added_segment = false;
+        if is_even(index) && !skip_first_segment {
+            added_segment = true;
+            contour.push_segment(distance, distance + d_len, true, pb);
+        }
+
+        distance += d_len;
+
+        // clear this so we only respect it the first time around
+        skip_first_segment = false;
+
+        // wrap around our intervals array if necessary
+        index += 1;
+        debug_assert!(index <= dash.array.len());
+        if index == dash.array.len() {
+            index = 0;
+        }
 
-            // clear this so we only respect it the first time around
-            skip_first_segment = false;
+        // fetch our next d_len
+        d_len = dash.array[index] * scale;
+    }
+
+    // extend if we ended on a segment and we need to join up with the (skipped) initial segment
+    if contour.is_closed && is_even(first_index) && first_len >= 0.0 {
+        contour.push_segment(0.0, first_len * scale, !added_segment, pb);
+    }
+
+    // The next contour continues the interval that crossed the end of this one.
+    let overshoot = distance - length;
+    if overshoot > 0.0 {
+        let prev_index = if index == 0 {
+            dash.array.len() - 1
+        } else {
+            index - 1
+        };
+        (prev_index, overshoot / scale)
+    } else {
+        (index, dash.array[index])
+    }
+}
+
+// Fits the pattern between each pair of adjacent corners, starting and ending
+// in the middle of the first dash. Dashes that meet at a corner are merged.
+//
+// Returns the phase for the next contour, like `dash_contour`,
+// or `None` when the scaled pattern produces too many dashes.
+fn dash_between_corners(
+    contour: &ContourMeasure,
+    corners: &[f32],
+    dash: &StrokeDash,
+    dash_count: &mut f32,
+    pb: &mut PathBuilder,
+) -> Option<(usize, f32)> {
+    let length = contour.length;
+    // Zero-length dashes are drawn as dots, so only non-zero intervals have to be placeable.
+    let min_interval = dash
+        .array
+        .iter()
+        .copied()
+        .filter(|d_len| *d_len > 0.0)
+        .fold(f32::MAX, f32::min);
+
+    // Distances are not wrapped, so the last section of a closed contour ends
+    // at the first corner plus the contour length.
+    let mut bounds = corners.to_vec();
+    if contour.is_closed {
+        bounds.push(corners[0] + length);
+    }
+
+    let mut ranges: Vec<(f32, f32)> = Vec::new();
+    for section in bounds.windows(2) {
+        let (start, end) = (section[0], section[1]);
+        let scale = fit_scale(end - start, dash.interval_len.get());
+
+        // Intervals that are below the distance precision cannot be placed,
+        // so such a short section is drawn solid and merged with its neighbours.
+        if min_interval * scale <= end * f32::EPSILON {
+            push_dash_range(&mut ranges, start, end);
+            continue;
+        }
+
+        // The section starts and ends in the middle of a dash,
+        // so it has one dash more than the number of whole periods.
+        let periods = ((end - start) / (scale * dash.interval_len.get())).round();
+        *dash_count += (periods + 1.0) * (dash.array.len() >> 1) as f32;
+        if *dash_count > MAX_DASH_COUNT as f32 {
+            return None;
+        }
+
+        // Positions are computed from the section start instead of being accumulated,
+        // since adding a short interval to a large distance may not change it at all.
+        let mut period = 0.0;
+        'section: loop {
+            let mut offset = period * dash.interval_len.get() - dash.array[0].half();
+            for (index, d_len) in dash.array.iter().copied().enumerate() {
+                // A zero-length dash can start at the very end of the section.
+                let dash_start = start + offset * scale;
+                if dash_start > end {
+                    break 'section;
+                }
 
-            // wrap around our intervals array if necessary
-            index += 1;
-            debug_assert!(index <= dash.array.len());
-            if index == dash.array.len() {
-                index = 0;
+                offset += d_len;
+                if index % 2 == 0 {
+                    let dash_end = start + offset * scale;
+                    push_dash_range(&mut ranges, dash_start.max(start), dash_end.min(end));
+                }
             }
 
-            // fetch our next d_len
-            d_len = dash.array[index];
+            period += 1.0;
         }
+    }
 
-        // extend if we ended on a segment and we need to join up with the (skipped) initial segment
-        if contour.is_closed && is_even(dash.first_index) && dash.first_len >= 0.0 {
-            contour.push_segment(0.0, dash.first_len, !added_segment, &mut pb);
+    // Join the dashes around the first corner of a closed contour.
+    if contour.is_closed && ranges.len() > 1 {
+        let first = ranges[0];
+        let last = ranges[ranges.len() - 1];
+        if first.0 == bounds[0] && last.1 == bounds[bounds.len() - 1] {
+            ranges.remove(0);
+            let len = ranges.len();
+            ranges[len - 1].1 = first.1 + length;
         }
     }
 
-    pb.finish()
+    for (start, end) in ranges {
+        if end <= length {
+            contour.push_segment(start, end, true, pb);
+        } else if start >= length {
+            contour.push_segment(start - length, end - length, true, pb);
+        } else {
+            contour.push_segment(start, length, true, pb);
+            contour.push_segment(0.0, end - length, false, pb);
+        }
+    }
+
+    // The contour ends in the middle of the first dash.
+    Some((0, dash.array[0].half()))
+}
+
+fn push_dash_range(ranges: &mut Vec<(f32, f32)>, start: f32, end: f32) {
+    match ranges.last_mut() {
+        Some(last) if last.1 == start => last.1 = end,
+        _ => ranges.push((start, end)),
+    }
+}
+
+// Returns a pattern scale, so an integer number of periods fits the length.
+fn fit_scale(length: f32, interval_len: f32) -> f32 {
+    let count = (length / interval_len).round().max(1.0);
+    let scale = length / (count * interval_len);
+    if scale.is_finite() && scale > 0.0 {
+        scale
+    } else {
+        1.0
+    }
 }
 
 const MAX_T_VALUE: u32 = 0x3FFFFFFF;
 
-struct ContourMeasureIter<'a> {
+pub(crate) struct ContourMeasureIter<'a> {
     iter: PathSegmentsIter<'a>,
     tolerance: f32,
 }
 
 impl<'a> ContourMeasureIter<'a> {
-    fn new(path: &'a Path, res_scale: f32) -> Self {
+    pub(crate) fn new(path: &'a Path, res_scale: f32) -> Self {
         // can't use tangents, since we need [0..1..................2] to be seen
         // as definitely not a line (it is when drawn, but not parametrically)
         // so we compare midpoints
@@ -406,14 +807,84 @@ impl Segment {
 }
 
 #[derive(Default, Debug)]
-struct ContourMeasure {
+pub(crate) struct ContourMeasure {
     segments: Vec<Segment>,
     points: Vec<Point>,
-    length: f32,
-    is_closed: bool,
+    pub(crate) length: f32,
+    pub(crate) is_closed: bool,
 }
 
 impl ContourMeasure {
+    /// Returns a position and a unit tangent at the specified distance along the contour.
+    ///
+    /// The distance is clamped to the contour length.
+    pub(crate) fn pos_tan(&self, distance: f32) -> Option<(Point, Point)> {
+        if self.segments.is_empty() {
+            return None;
+        }
+
+        let distance = distance.bound(0.0, self.length);
+        let (seg_index, t) = self.distance_to_segment(distance)?;
+        let seg = self.segments[seg_index];
+
+        let mut pos = Point::zero();
+        let mut tangent = Point::zero();
+        compute_pos_tan(
+            &self.points[seg.point_index..],
+            seg.kind,
+            t,
+            Some(&mut pos),
+            Some(&mut tangent),
+        );
+
+        Some((pos, tangent))
+    }
+
+    // Returns distances to vertices where the contour direction changes.
+    // Ends of open contours are treated as corners as well.
+    fn corner_distances(&self) -> Vec<f32> {
+        // About 1 degree.
+        const CORNER_COS: f32 = 0.9998;
+
+        let tangent = |seg: &Segment, t: NormalizedF32| {
+            let mut tangent = Point::zero();
+            compute_pos_tan(
+                &self.points[seg.point_index..],
+                seg.kind,
+                t,
+                None,
+                Some(&mut tangent),
+            );
+            tangent
+        };
+
+        let is_corner = |prev: &Segment, next: &Segment| {
+            tangent(prev, NormalizedF32::ONE).dot(tangent(next, NormalizedF32::ZERO)) < CORNER_COS
+        };
+
+        let mut corners = Vec::new();
+        let (first, last) = match (self.segments.first(), self.segments.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return corners,
+        };
+
+        if !self.is_closed || is_corner(last, first) {
+            corners.push(0.0);
+        }
+
+        for pair in self.segments.windows(2) {
+            if pair[0].point_index != pair[1].point_index && is_corner(&pair[0], &pair[1]) {
+                corners.push(pair[0].distance);
+            }
+        }
+
+        if !self.is_closed {
+            corners.push(self.length);
+        }
+
+        corners
+    }
+
     fn push_segment(
         &self,
         mut start_d: f32,
@@ -0,0 +1,671 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+// Curve fitting is based on "An Algorithm for Automatically Fitting Digitized Curves"
+// by Philip J. Schneider, Graphics Gems, 1990.
+
+use alloc::vec::Vec;
+
+use crate::Point;
+
+use crate::path_builder::PathBuilder;
+use crate::scalar::{Scalar, SCALAR_NEARLY_ZERO};
+
+#[cfg(all(not(feature = "std"), feature = "no-std-float"))]
+use crate::NoStdFloat;
+
+// How many times we try to improve the parametrization before splitting.
+const MAX_REPARAMETRIZE_ITERATIONS: usize = 4;
+
+impl PathBuilder {
+    /// Adds a smooth curve that approximates the points.
+    ///
+    /// Fits as few cubic segments as possible, so that no point is further than `tolerance`
+    /// from the resulting curve. Useful for freehand input and noisy traces.
+    ///
+    /// Turns sharper than `corner_angle` (in degrees) are preserved as corners
+    /// instead of being smoothed out. Turns are measured over a distance of a few
+    /// tolerances, therefore noise doesn't produce corners.
+    /// Use 180 to disable corners detection.
+    ///
+    /// Starts a new contour. Does nothing when there are less than two distinct points
+    /// or when `tolerance` is not positive.
+    pub fn push_fitted_curve(&mut self, points: &[Point], tolerance: f32, corner_angle: f32) {
+        if !(tolerance > 0.0 && tolerance.is_finite()) {
+            return;
+        }
+
+        let points = dedup_points(points);
+        if points.len() < 2 {
+            return;
+        }
+
+        self.move_to(points[0].x, points[0].y);
+
+        // To ignore noise, tangents are estimated using points that are
+        // at least a few tolerances away.
+        let radius = tolerance * 4.0;
+
+        let corners = find_corners(&points, radius, corner_angle);
+        let mut first = 0;
+        for last in corners
+            .into_iter()
+            .chain(core::iter::once(points.len() - 1))
+        {
+            let part = &points[first..=last];
+            let t1 = unit_vector(next_neighbor(part, 0, radius) - part[0]);
+            let t2 =
+                unit_vector(prev_neighbor(part, part.len() - 1, radius) - part[part.len() - 1]);
+            fit_cubics(part, t1, t2, radius, tolerance * tolerance, self);
+            first = last;
+        }
+    }
+
+    /// Adds a centripetal Catmull-Rom spline that passes through all the points.
+    ///
+    /// Unlike the uniform variant, the centripetal one never produces cusps
+    /// or self-intersections within a segment.
+    ///
+    /// Starts a new contour. Does nothing when there are less than two distinct points.
+    pub fn push_catmull_rom(&mut self, points: &[Point], closed: bool) {
+        let mut points = dedup_points(points);
+        if closed && points.len() > 2 && points[0] == points[points.len() - 1] {
+            points.pop();
+        }
+
+        let len = points.len();
+        if len < 2 {
+            return;
+        }
+
+        self.move_to(points[0].x, points[0].y);
+
+        let get = |i: isize| -> Point {
+            if closed {
+                points[i.rem_euclid(len as isize) as usize]
+            } else if i < 0 {
+                // Reflect the missing end points, which makes the end segments well-behaved.
+                points[0] + (points[0] - points[1])
+            } else if i as usize >= len {
+                points[len - 1] + (points[len - 1] - points[len - 2])
+            } else {
+                points[i as usize]
+            }
+        };
+
+        let segments = if closed { len } else { len - 1 };
+        for i in 0..segments as isize {
+            let p0 = get(i - 1);
+            let p1 = get(i);
+            let p2 = get(i + 1);
+            let p3 = get(i + 2);
+
+            // With alpha = 0.5, d^2 is just the chord length.
+            let d1_sqr = p0.distance(p1);
+            let d2_sqr = p1.distance(p2);
+            let d3_sqr = p2.distance(p3);
+            let d1 = d1_sqr.sqrt();
+            let d2 = d2_sqr.sqrt();
+            let d3 = d3_sqr.sqrt();
+
+            let c1 = if d1.is_nearly_zero() {
+                p1
+            } else {
+                let a = 2.0 * d1_sqr + 3.0 * d1 * d2 + d2_sqr;
+                let n = 3.0 * d1 * (d1 + d2);
+                (p2.scaled(d1_sqr) - p0.scaled(d2_sqr) + p1.scaled(a)).scaled(n.invert())
+            };
+
+            let c2 = if d3.is_nearly_zero() {
+                p2
+            } else {
+                let a = 2.0 * d3_sqr + 3.0 * d3 * d2 + d2_sqr;
+                let n = 3.0 * d3 * (d3 + d2);
+                (p1.scaled(d3_sqr) - p3.scaled(d2_sqr) + p2.scaled(a)).scaled(n.invert())
+            };
+
+            self.cubic_to_pt(c1, c2, p2);
+        }
+
+        if closed {
+            self.close();
+        }
+    }
+
+    /// Adds a monotone cubic spline that passes through all the points.
+    ///
+    /// The points are treated as samples of a function, therefore their x coordinates
+    /// must be strictly increasing. The resulting curve doesn't overshoot the data,
+    /// i.e. it's monotonic between the points. Uses the Fritsch-Carlson method.
+    ///
+    /// Starts a new contour. Does nothing when there are less than two points
+    /// or when x coordinates are not strictly increasing.
+    pub fn push_monotone_spline(&mut self, points: &[Point]) {
+        if points.len() < 2 {
+            return;
+        }
+
+        if points.windows(2).any(|w| !(w[1].x > w[0].x)) {
+            return;
+        }
+
+        // Secant slopes.
+        let secants: Vec<f32> = points
+            .windows(2)
+            .map(|w| (w[1].y - w[0].y) / (w[1].x - w[0].x))
+            .collect();
+
+        // Tangents.
+        let mut tangents = Vec::with_capacity(points.len());
+        tangents.push(secants[0]);
+        for w in secants.windows(2) {
+            if w[0] * w[1] <= 0.0 {
+                tangents.push(0.0);
+            } else {
+                tangents.push((w[0] + w[1]).half());
+            }
+        }
+        tangents.push(secants[secants.len() - 1]);
+
+        // Restrict tangents to preserve monotonicity.
+        for (i, secant) in secants.iter().copied().enumerate() {
+            if secant == 0.0 {
+                tangents[i] = 0.0;
+                tangents[i + 1] = 0.0;
+                continue;
+            }
+
+            let a = tangents[i] / secant;
+            let b = tangents[i + 1] / secant;
+            let s = a * a + b * b;
+            if s > 9.0 {
+                let t = 3.0 / s.sqrt();
+                tangents[i] = t * a * secant;
+                tangents[i + 1] = t * b * secant;
+            }
+        }
+
+        self.move_to(points[0].x, points[0].y);
+        for (i, w) in points.windows(2).enumerate() {
+            let h = (w[1].x - w[0].x) / 3.0;
+            self.cubic_to(
+                w[0].x + h,
+                w[0].y + tangents[i] * h,
+                w[1].x - h,
+                w[1].y - tangents[i + 1] * h,
+                w[1].x,
+                w[1].y,
+            );
+        }
+    }
+}
+
+fn dedup_points(points: &[Point]) -> Vec<Point> {
+    let mut result: Vec<Point> = Vec::with_capacity(points.len());
+    for p in points {
+        if !p.is_finite() {
+            continue;
+        }
+
+        if let Some(last) = result.last() {
+            if last.almost_equal(*p) {
+                continue;
+            }
+        }
+
+        result.push(*p);
+    }
+
+    result
+}
+
+fn unit_vector(mut v: Point) -> Point {
+    v.normalize();
+    v
+}
+
+// Returns the closest preceding point that is at least `radius` away from `points[i]`.
+fn prev_neighbor(points: &[Point], i: usize, radius: f32) -> Point {
+    let p = points[i];
+    *points[..i]
+        .iter()
+        .rev()
+        .find(|q| q.distance(p) >= radius)
+        .unwrap_or(&points[0])
+}
+
+// Returns the closest following point that is at least `radius` away from `points[i]`.
+fn next_neighbor(points: &[Point], i: usize, radius: f32) -> Point {
+    let p = points[i];
+    *points[i + 1..]
+        .iter()
+        .find(|q| q.distance(p) >= radius)
+        .unwrap_or(&points[points.len() - 1])
+}
+
+// Returns indices of the interior points that should be preserved as corners.
+//
+// The sharpest point of a turn is used as a corner.
+fn find_corners(points: &[Point], radius: f32, corner_angle: f32) -> Vec<usize> {
+    let mut corners = Vec::new();
+    if !(corner_angle < 180.0) || points.len() < 3 {
+        return corners;
+    }
+
+    let threshold = corner_angle.max(0.0).to_radians().cos();
+
+    let mut candidate: Option<(usize, f32)> = None;
+    for i in 1..points.len() - 1 {
+        let p = points[i];
+        let prev = prev_neighbor(points, i, radius);
+        let next = next_neighbor(points, i, radius);
+
+        let cos = unit_vector(p - prev).dot(unit_vector(next - p));
+        if cos < threshold {
+            match candidate {
+                Some((_, best)) if best <= cos => {}
+                _ => candidate = Some((i, cos)),
+            }
+        } else if let Some((index, _)) = candidate.take() {
+            corners.push(index);
+        }
+    }
+
+    if let Some((index, _)) = candidate {
+        corners.push(index);
+    }
+
+    corners
+}
+
+struct FitTask {
+    first: usize,
+    last: usize,
+    t1: Point,
+    t2: Point,
+}
+
+fn fit_cubics(
+    points: &[Point],
+    t1: Point,
+    t2: Point,
+    radius: f32,
+    tolerance_sqd: f32,
+    pb: &mut PathBuilder,
+) {
+    // Use an explicit stack instead of a recursion, since the number of points is unbounded.
+    // The right part is pushed first, so the segments are emitted in order.
+    let mut stack = alloc::vec![FitTask {
+        first: 0,
+        last: points.len() - 1,
+        t1,
+        t2,
+    }];
+
+    let mut u = Vec::new();
+    while let Some(task) = stack.pop() {
+        let part = &points[task.first..=task.last];
+
+        if part.len() == 2 {
+            let dist = part[0].distance(part[1]) / 3.0;
+            pb.cubic_to_pt(
+                part[0] + task.t1.scaled(dist),
+                part[1] + task.t2.scaled(dist),
+                part[1],
+            );
+            continue;
+        }
+
+        chord_length_parametrize(part, &mut u);
+        let mut bezier = generate_bezier(part, &u, task.t1, task.t2);
+        let (mut max_error, mut split) = compute_max_error(part, &bezier, &u);
+        if max_error < tolerance_sqd {
+            pb.cubic_to_pt(bezier[1], bezier[2], bezier[3]);
+            continue;
+        }
+
+        // If the error is not too large, try reparametrization.
+        if max_error < tolerance_sqd * 4.0 {
+            for _ in 0..MAX_REPARAMETRIZE_ITERATIONS {
+                reparametrize(part, &bezier, &mut u);
+                bezier = generate_bezier(part, &u, task.t1, task.t2);
+                let (e, s) = compute_max_error(part, &bezier, &u);
+                max_error = e;
+                split = s;
+                if max_error < tolerance_sqd {
+                    break;
+                }
+            }
+
+            if max_error < tolerance_sqd {
+                pb.cubic_to_pt(bezier[1], bezier[2], bezier[3]);
+                continue;
+            }
+        }
+
+        // Fitting failed. Split at the point of maximum error and fit recursively.
+        let mut center = prev_neighbor(part, split, radius) - next_neighbor(part, split, radius);
+        if !center.normalize() {
+            // Fallback to the incoming direction.
+            center = unit_vector(part[split - 1] - part[split]);
+        }
+
+        let split = task.first + split;
+
+        stack.push(FitTask {
+            first: split,
+            last: task.last,
+            t1: -center,
+            t2: task.t2,
+        });
+        stack.push(FitTask {
+            first: task.first,
+            last: split,
+            t1: task.t1,
+            t2: center,
+        });
+    }
+}
+
+fn chord_length_parametrize(points: &[Point], u: &mut Vec<f32>) {
+    u.clear();
+    u.push(0.0);
+    for i in 1..points.len() {
+        let d = u[i - 1] + points[i].distance(points[i - 1]);
+        u.push(d);
+    }
+
+    let total = u[u.len() - 1];
+    for v in u.iter_mut() {
+        *v /= total;
+    }
+}
+
+// Uses least-squares method to find Bezier control points for a region.
+fn generate_bezier(points: &[Point], u: &[f32], t1: Point, t2: Point) -> [Point; 4] {
+    let first = points[0];
+    let last = points[points.len() - 1];
+
+    let mut c = [[0.0f32; 2]; 2];
+    let mut x = [0.0f32; 2];
+    for (p, t) in points.iter().zip(u.iter().copied()) {
+        let (b0, b1, b2, b3) = bernstein(t);
+        let a1 = t1.scaled(b1);
+        let a2 = t2.scaled(b2);
+
+        c[0][0] += a1.dot(a1);
+        c[0][1] += a1.dot(a2);
+        c[1][1] += a2.dot(a2);
+
+        let tmp = *p - (first.scaled(b0 + b1) + last.scaled(b2 + b3));
+        x[0] += a1.dot(tmp);
+        x[1] += a2.dot(tmp);
+    }
+    c[1][0] = c[0][1];
+
+    // Compute the determinants of C and X.
+    let det_c0_c1 = c[0][0] * c[1][1] - c[1][0] * c[0][1];
+    let det_c0_x = c[0][0] * x[1] - c[1][0] * x[0];
+    let det_x_c1 = x[0] * c[1][1] - x[1] * c[0][1];
+
+    let (alpha_l, alpha_r) = if det_c0_c1 == 0.0 {
+        (0.0, 0.0)
+    } else {
+        (det_x_c1 / det_c0_c1, det_c0_x / det_c0_c1)
+    };
+
+    // If alpha is negative or too small, use the Wu/Barsky heuristic.
+    // This also protects us from coincident control points that lead to a division by zero.
+    let seg_length = first.distance(last);
+    let epsilon = SCALAR_NEARLY_ZERO * seg_length;
+    if !(alpha_l >= epsilon && alpha_r >= epsilon && alpha_l.is_finite() && alpha_r.is_finite()) {
+        let dist = seg_length / 3.0;
+        return [first, first + t1.scaled(dist), last + t2.scaled(dist), last];
+    }
+
+    [
+        first,
+        first + t1.scaled(alpha_l),
+        last + t2.scaled(alpha_r),
+        last,
+    ]
+}
+
+fn bernstein(t: f32) -> (f32, f32, f32, f32) {
+    let mt = 1.0 - t;
+    (mt * mt * mt, 3.0 * t * mt * mt, 3.0 * t * t * mt, t * t * t)
+}
+
+fn eval_bezier(bezier: &[Point; 4], t: f32) -> Point {
+    let (b0, b1, b2, b3) = bernstein(t);
+    bezier[0].scaled(b0) + bezier[1].scaled(b1) + bezier[2].scaled(b2) + bezier[3].scaled(b3)
+}
+
+// Returns the maximum squared distance and the index of the furthest point.
+fn compute_max_error(points: &[Point], bezier: &[Point; 4], u: &[f32]) -> (f32, usize) {
+    let mut max_dist = 0.0;
+    let mut split = points.len() / 2;
+    for i in 1..points.len() - 1 {
+        let dist = eval_bezier(bezier, u[i]).distance_to_sqd(points[i]);
+        if dist >= max_dist {
+            max_dist = dist;
+            split = i;
+        }
+    }
+
+    (max_dist, split)
+}
+
+// Uses Newton-Raphson iteration to find better parameters for the points.
+fn reparametrize(points: &[Point], bezier: &[Point; 4], u: &mut [f32]) {
+    let d1 = [
+        (bezier[1] - bezier[0]).scaled(3.0),
+        (bezier[2] - bezier[1]).scaled(3.0),
+        (bezier[3] - bezier[2]).scaled(3.0),
+    ];
+    let d2 = [(d1[1] - d1[0]).scaled(2.0), (d1[2] - d1[1]).scaled(2.0)];
+
+    for (p, t) in points.iter().zip(u.iter_mut()) {
+        let q = eval_bezier(bezier, *t);
+
+        let mt = 1.0 - *t;
+        let q1 = d1[0].scaled(mt * mt) + d1[1].scaled(2.0 * *t * mt) + d1[2].scaled(*t * *t);
+        let q2 = d2[0].scaled(mt) + d2[1].scaled(*t);
+
+        let diff = q - *p;
+        let numerator = diff.dot(q1);
+        let denominator = q1.dot(q1) + diff.dot(q2);
+        if denominator != 0.0 {
+            let new_t = *t - numerator / denominator;
+            if new_t.is_finite() {
+                *t = new_t.bound(0.0, 1.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::PathSegment;
+    use crate::path_geometry;
+    use crate::{NormalizedF32, Path};
+
+    fn distance_to_path(path: &Path, p: Point) -> f32 {
+        let mut min = f32::MAX;
+        let mut last = Point::zero();
+        for segment in path.segments() {
+            match segment {
+                PathSegment::MoveTo(p0) => last = p0,
+                PathSegment::CubicTo(p1, p2, p3) => {
+                    let cubic = [last, p1, p2, p3];
+                    for i in 0..=500 {
+                        let t = NormalizedF32::new_clamped(i as f32 / 500.0);
+                        min = min.min(path_geometry::eval_cubic_pos_at(&cubic, t).distance(p));
+                    }
+                    last = p3;
+                }
+                _ => {}
+            }
+        }
+
+        min
+    }
+
+    #[test]
+    fn fit_arc() {
+        let points: Vec<Point> = (0..=50)
+            .map(|i| {
+                let a = (i as f32 / 50.0 * 180.0).to_radians();
+                Point::from_xy(100.0 + 50.0 * a.cos(), 100.0 - 50.0 * a.sin())
+            })
+            .collect();
+
+        let mut pb = PathBuilder::new();
+        pb.push_fitted_curve(&points, 0.5, 60.0);
+        let path = pb.finish().unwrap();
+
+        // A half circle requires just a few cubics.
+        assert!(path.len() <= 5);
+        for p in &points {
+            assert!(distance_to_path(&path, *p) < 0.5);
+        }
+    }
+
+    #[test]
+    fn fit_noisy_line() {
+        let points: Vec<Point> = (0..=100)
+            .map(|i| {
+                let noise = if i % 2 == 0 { 0.2 } else { -0.2 };
+                Point::from_xy(i as f32, 50.0 + noise)
+            })
+            .collect();
+
+        let mut pb = PathBuilder::new();
+        pb.push_fitted_curve(&points, 1.0, 60.0);
+        let path = pb.finish().unwrap();
+        assert_eq!(path.len(), 2);
+    }
+
+    #[test]
+    fn fit_preserves_corners() {
+        let mut points = Vec::new();
+        for i in 0..=20 {
+            points.push(Point::from_xy(i as f32 * 5.0, 0.0));
+        }
+        for i in 1..=20 {
+            points.push(Point::from_xy(100.0, i as f32 * 5.0));
+        }
+
+        let mut pb = PathBuilder::new();
+        pb.push_fitted_curve(&points, 0.5, 60.0);
+        let path = pb.finish().unwrap();
+
+        assert_eq!(path.len(), 3);
+        assert!(path.points().contains(&Point::from_xy(100.0, 0.0)));
+
+        // Without corners detection, the corner requires a lot more segments.
+        let mut pb = PathBuilder::new();
+        pb.push_fitted_curve(&points, 0.5, 180.0);
+        let path = pb.finish().unwrap();
+        assert!(path.len() > 3);
+        for p in &points {
+            assert!(distance_to_path(&path, *p) < 0.5);
+        }
+    }
+
+    #[test]
+    fn fit_invalid() {
+        let mut pb = PathBuilder::new();
+        pb.push_fitted_curve(&[], 1.0, 60.0);
+        pb.push_fitted_curve(&[Point::from_xy(1.0, 1.0)], 1.0, 60.0);
+        pb.push_fitted_curve(&[Point::from_xy(1.0, 1.0); 5], 1.0, 60.0);
+        pb.push_fitted_curve(&[Point::zero(), Point::from_xy(1.0, 1.0)], 0.0, 60.0);
+        assert!(pb.is_empty());
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_points() {
+        let points = [
+            Point::from_xy(10.0, 10.0),
+            Point::from_xy(50.0, 80.0),
+            Point::from_xy(51.0, 81.0),
+            Point::from_xy(90.0, 20.0),
+        ];
+
+        let mut pb = PathBuilder::new();
+        pb.push_catmull_rom(&points, false);
+        let path = pb.finish().unwrap();
+        assert_eq!(path.len(), 4);
+        for p in &points {
+            assert!(path.points().contains(p));
+        }
+
+        let mut pb = PathBuilder::new();
+        pb.push_catmull_rom(&points, true);
+        let path = pb.finish().unwrap();
+        assert_eq!(path.len(), 6);
+        assert_eq!(path.verbs().last(), Some(&crate::PathVerb::Close));
+    }
+
+    #[test]
+    fn catmull_rom_collinear() {
+        let points = [
+            Point::from_xy(0.0, 0.0),
+            Point::from_xy(10.0, 0.0),
+            Point::from_xy(30.0, 0.0),
+        ];
+
+        let mut pb = PathBuilder::new();
+        pb.push_catmull_rom(&points, false);
+        let path = pb.finish().unwrap();
+        assert!(path.points().iter().all(|p| p.y == 0.0));
+        assert!(path.points().iter().all(|p| p.x >= 0.0 && p.x <= 30.0));
+    }
+
+    #[test]
+    fn monotone_spline() {
+        let points = [
+            Point::from_xy(0.0, 0.0),
+            Point::from_xy(10.0, 0.0),
+            Point::from_xy(20.0, 50.0),
+            Point::from_xy(30.0, 51.0),
+            Point::from_xy(40.0, 51.0),
+            Point::from_xy(50.0, 10.0),
+        ];
+
+        let mut pb = PathBuilder::new();
+        pb.push_monotone_spline(&points);
+        let path = pb.finish().unwrap();
+        assert_eq!(path.len(), 6);
+
+        // No overshoots.
+        let bounds = path.compute_tight_bounds().unwrap();
+        assert_eq!(bounds.top(), 0.0);
+        assert_eq!(bounds.bottom(), 51.0);
+
+        // Flat regions stay flat.
+        let mut last = Point::zero();
+        for segment in path.segments() {
+            if let PathSegment::CubicTo(p1, p2, p3) = segment {
+                if last.y == p3.y {
+                    assert_eq!(p1.y, p3.y);
+                    assert_eq!(p2.y, p3.y);
+                }
+                last = p3;
+            }
+        }
+    }
+
+    #[test]
+    fn monotone_spline_invalid() {
+        let mut pb = PathBuilder::new();
+        pb.push_monotone_spline(&[Point::from_xy(0.0, 0.0), Point::from_xy(0.0, 10.0)]);
+        pb.push_monotone_spline(&[
+            Point::from_xy(0.0, 0.0),
+            Point::from_xy(10.0, 10.0),
+            Point::from_xy(5.0, 10.0),
+        ]);
+        assert!(pb.is_empty());
+    }
+}
@@ -35,10 +35,15 @@ extern crate std;
 
 extern crate alloc;
 
+mod curve_convert;
 mod dash;
 mod f32x2_t;
 mod f32x4_t;
+mod fit;
 mod floating_point;
+mod marker;
+mod nib;
+mod offset;
 mod path;
 mod path_builder;
 pub mod path_geometry;
@@ -47,10 +52,13 @@ mod scalar;
 mod size;
 mod stroker;
 mod transform;
+mod warp;
 
 pub use dash::StrokeDash;
 pub use f32x2_t::f32x2;
 pub use floating_point::*;
+pub use marker::{MarkerKind, MarkerOrient, MarkerPosition};
+pub use nib::{Nib, NibStroker};
 pub use path::*;
 pub use path_builder::*;
 pub use rect::*;
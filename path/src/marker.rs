@@ -0,0 +1,306 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use alloc::vec::Vec;
+
+use crate::{Path, Point, Transform};
+
+use crate::path::PathSegment;
+
+/// A marker position kind.
+///
+/// Matches SVG `marker-start`, `marker-mid` and `marker-end`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MarkerKind {
+    /// The first vertex of the path.
+    Start,
+    /// Any vertex other than the first and the last one.
+    Mid,
+    /// The last vertex of the path.
+    End,
+}
+
+/// A marker orientation.
+///
+/// Matches SVG marker `orient` attribute.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MarkerOrient {
+    /// The marker's x-axis is aligned with the path direction at the vertex.
+    ///
+    /// On vertices between two segments the direction bisects
+    /// the incoming and the outgoing ones.
+    Auto,
+    /// Same as `Auto`, but the start marker is rotated by 180 degrees.
+    ///
+    /// Useful for arrowheads at both ends of a path.
+    AutoStartReverse,
+    /// A fixed rotation angle in degrees.
+    Angle(f32),
+}
+
+/// A marker placement on a path.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct MarkerPosition {
+    /// A vertex kind.
+    pub kind: MarkerKind,
+    /// A transform from the marker coordinates to the path coordinates.
+    ///
+    /// Maps the marker origin to the vertex and rotates the marker according to orientation.
+    pub transform: Transform,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Vertex {
+    point: Point,
+    incoming: Option<Point>,
+    outgoing: Option<Point>,
+}
+
+impl Vertex {
+    fn new(point: Point, incoming: Option<Point>) -> Self {
+        Vertex {
+            point,
+            incoming,
+            outgoing: None,
+        }
+    }
+}
+
+impl Path {
+    /// Returns marker positions for all path vertices, following SVG markers rules.
+    ///
+    /// Each `MoveTo` and each segment end is a vertex. A `Close` adds a vertex
+    /// at the contour start as well.
+    ///
+    /// Draw a marker by concatenating its [`transform`](MarkerPosition::transform)
+    /// with the path transform. Scaling by a stroke width and the marker reference point
+    /// are left to the caller.
+    pub fn marker_positions(&self, orient: MarkerOrient) -> Vec<MarkerPosition> {
+        let vertices = self.marker_vertices();
+
+        let mut positions = Vec::with_capacity(vertices.len());
+        for (i, vertex) in vertices.iter().enumerate() {
+            let kind = if i == 0 {
+                MarkerKind::Start
+            } else if i == vertices.len() - 1 {
+                MarkerKind::End
+            } else {
+                MarkerKind::Mid
+            };
+
+            let rotation = match orient {
+                MarkerOrient::Auto | MarkerOrient::AutoStartReverse => {
+                    let mut dir = vertex_direction(vertex);
+                    if kind == MarkerKind::Start && orient == MarkerOrient::AutoStartReverse {
+                        dir = -dir;
+                    }
+
+                    Transform::from_row(dir.x, dir.y, -dir.y, dir.x, 0.0, 0.0)
+                }
+                MarkerOrient::Angle(angle) => Transform::from_rotate(angle),
+            };
+
+            let transform =
+                Transform::from_translate(vertex.point.x, vertex.point.y).pre_concat(rotation);
+            positions.push(MarkerPosition { kind, transform });
+        }
+
+        positions
+    }
+
+    fn marker_vertices(&self) -> Vec<Vertex> {
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(self.len());
+        let mut contour_start = 0;
+        let mut last_point = Point::zero();
+        for segment in self.segments() {
+            let (start_dir, end_dir, end) = match segment {
+                PathSegment::MoveTo(p) => {
+                    contour_start = vertices.len();
+                    vertices.push(Vertex::new(p, None));
+                    last_point = p;
+                    continue;
+                }
+                PathSegment::LineTo(p) => {
+                    let dir = non_zero(&[p - last_point]);
+                    (dir, dir, p)
+                }
+                PathSegment::QuadTo(p1, p2) => (
+                    non_zero(&[p1 - last_point, p2 - last_point]),
+                    non_zero(&[p2 - p1, p2 - last_point]),
+                    p2,
+                ),
+                PathSegment::CubicTo(p1, p2, p3) => (
+                    non_zero(&[p1 - last_point, p2 - last_point, p3 - last_point]),
+                    non_zero(&[p3 - p2, p3 - p1, p3 - last_point]),
+                    p3,
+                ),
+                PathSegment::Close => {
+                    let start = vertices[contour_start];
+                    let dir = non_zero(&[start.point - last_point])
+                        .or_else(|| vertices.last().and_then(|v| v.incoming));
+                    if let Some(last) = vertices.last_mut() {
+                        last.outgoing = last.outgoing.or(dir);
+                    }
+
+                    // The start of a closed contour is entered by the closing segment,
+                    // and the closing vertex is left by the first segment.
+                    vertices[contour_start].incoming = dir;
+                    let mut closing = Vertex::new(start.point, dir);
+                    closing.outgoing = start.outgoing;
+                    vertices.push(closing);
+                    last_point = start.point;
+                    continue;
+                }
+            };
+
+            if let Some(last) = vertices.last_mut() {
+                last.outgoing = start_dir;
+            }
+
+            vertices.push(Vertex::new(end, end_dir));
+            last_point = end;
+        }
+
+        vertices
+    }
+}
+
+fn non_zero(vectors: &[Point]) -> Option<Point> {
+    vectors.iter().copied().find(|v| !v.is_zero())
+}
+
+// Returns a unit direction, bisecting incoming and outgoing ones.
+fn vertex_direction(vertex: &Vertex) -> Point {
+    let normalized = |v: Option<Point>| {
+        let mut v = v?;
+        if v.normalize() {
+            Some(v)
+        } else {
+            None
+        }
+    };
+
+    match (normalized(vertex.incoming), normalized(vertex.outgoing)) {
+        (Some(incoming), Some(outgoing)) => {
+            let mut dir = incoming + outgoing;
+            if dir.normalize() {
+                dir
+            } else {
+                // The path turns back. The bisector is perpendicular to the path.
+                Point::from_xy(-incoming.y, incoming.x)
+            }
+        }
+        (Some(dir), None) | (None, Some(dir)) => dir,
+        (None, None) => Point::from_xy(1.0, 0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathBuilder;
+
+    fn assert_direction(position: &MarkerPosition, x: f32, y: f32, dx: f32, dy: f32) {
+        let ts = position.transform;
+        assert!((ts.tx - x).abs() < 0.0001 && (ts.ty - y).abs() < 0.0001);
+        assert!((ts.sx - dx).abs() < 0.0001 && (ts.ky - dy).abs() < 0.0001);
+    }
+
+    #[test]
+    fn open_polyline() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(50.0, 10.0);
+        pb.line_to(50.0, 50.0);
+        let path = pb.finish().unwrap();
+
+        let positions = path.marker_positions(MarkerOrient::Auto);
+        assert_eq!(positions.len(), 3);
+        assert_eq!(positions[0].kind, MarkerKind::Start);
+        assert_eq!(positions[1].kind, MarkerKind::Mid);
+        assert_eq!(positions[2].kind, MarkerKind::End);
+
+        let h = core::f32::consts::FRAC_1_SQRT_2;
+        assert_direction(&positions[0], 10.0, 10.0, 1.0, 0.0);
+        assert_direction(&positions[1], 50.0, 10.0, h, h);
+        assert_direction(&positions[2], 50.0, 50.0, 0.0, 1.0);
+
+        // Maps the marker x-axis along the path.
+        let mut p = [Point::from_xy(5.0, 0.0)];
+        positions[2].transform.map_points(&mut p);
+        assert!(p[0].distance(Point::from_xy(50.0, 55.0)) < 0.0001);
+    }
+
+    #[test]
+    fn auto_start_reverse() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(50.0, 10.0);
+        let path = pb.finish().unwrap();
+
+        let positions = path.marker_positions(MarkerOrient::AutoStartReverse);
+        assert_direction(&positions[0], 10.0, 10.0, -1.0, 0.0);
+        assert_direction(&positions[1], 50.0, 10.0, 1.0, 0.0);
+    }
+
+    #[test]
+    fn fixed_angle() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(50.0, 10.0);
+        let path = pb.finish().unwrap();
+
+        let positions = path.marker_positions(MarkerOrient::Angle(90.0));
+        assert_direction(&positions[0], 10.0, 10.0, 0.0, 1.0);
+        assert_direction(&positions[1], 50.0, 10.0, 0.0, 1.0);
+    }
+
+    #[test]
+    fn curve_tangents() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.cubic_to(0.0, 0.0, 10.0, 10.0, 20.0, 10.0);
+        let path = pb.finish().unwrap();
+
+        // The first control point coincides with the start.
+        let h = core::f32::consts::FRAC_1_SQRT_2;
+        let positions = path.marker_positions(MarkerOrient::Auto);
+        assert_direction(&positions[0], 0.0, 0.0, h, h);
+        assert_direction(&positions[1], 20.0, 10.0, 1.0, 0.0);
+    }
+
+    #[test]
+    fn closed_contours() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(50.0, 10.0);
+        pb.line_to(50.0, 50.0);
+        pb.line_to(10.0, 50.0);
+        pb.close();
+        pb.move_to(100.0, 100.0);
+        pb.line_to(120.0, 100.0);
+        let path = pb.finish().unwrap();
+
+        let positions = path.marker_positions(MarkerOrient::Auto);
+        let kinds: Vec<_> = positions.iter().map(|p| p.kind).collect();
+        assert_eq!(
+            kinds,
+            &[
+                MarkerKind::Start,
+                MarkerKind::Mid,
+                MarkerKind::Mid,
+                MarkerKind::Mid,
+                MarkerKind::Mid,
+                MarkerKind::Mid,
+                MarkerKind::End,
+            ]
+        );
+
+        // The contour start and the closing vertex bisect the closing and the first segments.
+        let h = core::f32::consts::FRAC_1_SQRT_2;
+        assert_direction(&positions[0], 10.0, 10.0, h, -h);
+        assert_direction(&positions[3], 10.0, 50.0, -h, -h);
+        assert_direction(&positions[4], 10.0, 10.0, h, -h);
+        assert_direction(&positions[5], 100.0, 100.0, 1.0, 0.0);
+    }
+}
@@ -0,0 +1,484 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use alloc::vec::Vec;
+
+use crate::{Path, Point, Transform};
+
+use crate::offset;
+use crate::path::PathSegment;
+use crate::path_builder::PathBuilder;
+use crate::scalar::Scalar;
+
+#[cfg(all(not(feature = "std"), feature = "no-std-float"))]
+use crate::NoStdFloat;
+
+const MIN_ELLIPSE_POINTS: usize = 8;
+const MAX_ELLIPSE_POINTS: usize = 256;
+
+/// A convex pen shape used for calligraphic stroking.
+///
+/// Both shapes are centered on the path.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Nib {
+    /// An ellipse with the specified radii, rotated by `angle` degrees.
+    Ellipse {
+        /// A horizontal radius before rotation.
+        rx: f32,
+        /// A vertical radius before rotation.
+        ry: f32,
+        /// A rotation angle in degrees.
+        angle: f32,
+    },
+    /// A rectangle with the specified size, rotated by `angle` degrees.
+    ///
+    /// A flat nib can be represented by a rectangle with a small height.
+    Rect {
+        /// A width before rotation.
+        width: f32,
+        /// A height before rotation.
+        height: f32,
+        /// A rotation angle in degrees.
+        angle: f32,
+    },
+}
+
+impl Path {
+    /// Returns a path stroked with a calligraphic nib.
+    ///
+    /// `resolution_scale` can be obtained via
+    /// [`compute_resolution_scale`](crate::PathStroker::compute_resolution_scale).
+    ///
+    /// If you plan stroking multiple paths, you can try using [`NibStroker`]
+    /// which will preserve temporary allocations required during stroking.
+    pub fn stroke_nib(&self, nib: &Nib, resolution_scale: f32) -> Option<Path> {
+        NibStroker::new().stroke(self, nib, resolution_scale)
+    }
+}
+
+/// A calligraphic path stroker.
+///
+/// Unlike [`PathStroker`](crate::PathStroker), which offsets a path by a constant distance,
+/// it sweeps a convex [`Nib`] along the path, so the stroke width depends on the stroke direction.
+///
+/// The result is a boundary of the swept area without self-intersections or overlapping
+/// contours, so it can be filled using any fill rule. It consists of lines only.
+///
+/// To produce a dashed stroke, dash the path via [`Path::dash`] first.
+#[derive(Clone, Default, Debug)]
+pub struct NibStroker {
+    pen: Vec<Point>,
+    polyline: Vec<Point>,
+    tolerance: f32,
+}
+
+impl NibStroker {
+    /// Creates a new stroker.
+    pub fn new() -> Self {
+        NibStroker::default()
+    }
+
+    /// Strokes the path.
+    ///
+    /// Can be called multiple times to reuse allocated buffers.
+    ///
+    /// Returns `None` when the nib has a negative, zero or non-finite size,
+    /// when `resolution_scale` is not positive or when the result is empty.
+    pub fn stroke(&mut self, path: &Path, nib: &Nib, resolution_scale: f32) -> Option<Path> {
+        // Matches the `PathStroker` precision.
+        // Half of it goes to the curves flattening and half to the pen approximation,
+        // so `tolerance` stores the share of each.
+        self.tolerance = (resolution_scale * 8.0).invert().half();
+        if !(self.tolerance > 0.0 && self.tolerance.is_finite()) {
+            return None;
+        }
+
+        self.build_pen(nib)?;
+
+        let mut pb = PathBuilder::new();
+        let mut has_segments = false;
+        let mut last_point = Point::zero();
+        self.polyline.clear();
+
+        let mut iter = path.segments();
+        iter.set_auto_close(true);
+        for segment in iter {
+            match segment {
+                PathSegment::MoveTo(p) => {
+                    if has_segments {
+                        self.sweep_polyline(&mut pb);
+                    }
+
+                    self.polyline.clear();
+                    self.polyline.push(p);
+                    has_segments = false;
+                }
+                PathSegment::LineTo(p) => {
+                    self.polyline.push(p);
+                    has_segments = true;
+                }
+                PathSegment::QuadTo(p1, p2) => {
+                    let polyline = &mut self.polyline;
+                    offset::flatten_quad(&[last_point, p1, p2], self.tolerance, |p| {
+                        polyline.push(p)
+                    });
+                    has_segments = true;
+                }
+                PathSegment::CubicTo(p1, p2, p3) => {
+                    let polyline = &mut self.polyline;
+                    offset::flatten_cubic(&[last_point, p1, p2, p3], self.tolerance, |p| {
+                        polyline.push(p)
+                    });
+                    has_segments = true;
+                }
+                PathSegment::Close => {}
+            }
+
+            if let Some(p) = self.polyline.last() {
+                last_point = *p;
+            }
+        }
+
+        if has_segments {
+            self.sweep_polyline(&mut pb);
+        }
+
+        // Removes the overlaps, like loops at inner turns and crossings.
+        offset::union(&pb.finish()?, self.tolerance)
+    }
+
+    fn build_pen(&mut self, nib: &Nib) -> Option<()> {
+        fn is_valid_size(a: f32, b: f32) -> bool {
+            a >= 0.0 && b >= 0.0 && (a > 0.0 || b > 0.0) && (a * b).is_finite()
+        }
+
+        self.pen.clear();
+        match *nib {
+            Nib::Ellipse { rx, ry, angle } => {
+                if !is_valid_size(rx, ry) || !angle.is_finite() {
+                    return None;
+                }
+
+                // An inscribed polygon deviates from the ellipse by r * (1 - cos(PI / n)).
+                let r = rx.max(ry);
+                let cos = (1.0 - self.tolerance / r).max(-1.0);
+                let count = (core::f32::consts::PI / cos.acos()).ceil();
+                let count = if count.is_finite() {
+                    (count as usize).clamp(MIN_ELLIPSE_POINTS, MAX_ELLIPSE_POINTS)
+                } else {
+                    MAX_ELLIPSE_POINTS
+                };
+                // Make sure that the polygon touches the ellipse extremes.
+                let count = count + (4 - count % 4) % 4;
+
+                for i in 0..count {
+                    let a = i as f32 / count as f32 * 2.0 * core::f32::consts::PI;
+                    self.pen.push(Point::from_xy(rx * a.cos(), ry * a.sin()));
+                }
+
+                Transform::from_rotate(angle).map_points(&mut self.pen);
+            }
+            Nib::Rect {
+                width,
+                height,
+                angle,
+            } => {
+                if !is_valid_size(width, height) || !angle.is_finite() {
+                    return None;
+                }
+
+                let (w, h) = (width.half(), height.half());
+                self.pen.push(Point::from_xy(-w, -h));
+                self.pen.push(Point::from_xy(w, -h));
+                self.pen.push(Point::from_xy(w, h));
+                self.pen.push(Point::from_xy(-w, h));
+
+                Transform::from_rotate(angle).map_points(&mut self.pen);
+            }
+        }
+
+        // Removes duplicates and makes the points order match the turn direction.
+        convex_hull(&mut self.pen);
+
+        Some(())
+    }
+
+    // The area swept by a convex pen along a line is the convex hull of the pen
+    // placed at both line ends, bounded by the pen points furthest to the left and right.
+    // A polyline is outlined by connecting these points along both sides, walking around
+    // the pen on the outer side of turns and ends, while inner turns go through the pivot
+    // point, like in `PathStroker`. The union then removes the resulting overlaps.
+    fn sweep_polyline(&mut self, pb: &mut PathBuilder) {
+        self.polyline.dedup();
+        let first = match self.polyline.first() {
+            Some(p) => *p,
+            None => return,
+        };
+
+        // A zero-length contour leaves a single pen imprint.
+        if self.polyline.len() == 1 {
+            pb.move_to(first.x + self.pen[0].x, first.y + self.pen[0].y);
+            for p in &self.pen[1..] {
+                pb.line_to(first.x + p.x, first.y + p.y);
+            }
+            pb.close();
+            return;
+        }
+
+        let start = first + self.pen[self.support(self.polyline[1] - first)];
+        pb.move_to(start.x, start.y);
+        self.push_side(false, pb);
+        self.push_side(true, pb);
+        pb.close();
+    }
+
+    // Pushes the left side of the polyline in the specified direction,
+    // followed by the pen outline around its end.
+    fn push_side(&self, reversed: bool, pb: &mut PathBuilder) {
+        let len = self.polyline.len();
+        let point = |i: usize| {
+            if reversed {
+                self.polyline[len - 1 - i]
+            } else {
+                self.polyline[i]
+            }
+        };
+
+        let mut dir = point(1) - point(0);
+        let mut support = self.support(dir);
+        for i in 1..len {
+            let p = point(i);
+            let end = p + self.pen[support];
+            pb.line_to(end.x, end.y);
+
+            // The end is a U-turn.
+            let next_dir = if i + 1 < len { point(i + 1) - p } else { -dir };
+            let next_support = self.support(next_dir);
+            let cross = dir.cross(next_dir);
+            if cross < 0.0 || (cross == 0.0 && dir.dot(next_dir) < 0.0) {
+                // The left side is the outer one, so the support point moves backward
+                // along the pen.
+                let mut k = support;
+                while k != next_support {
+                    k = (k + self.pen.len() - 1) % self.pen.len();
+                    pb.line_to(p.x + self.pen[k].x, p.y + self.pen[k].y);
+                }
+            } else {
+                if cross > 0.0 {
+                    pb.line_to(p.x, p.y);
+                }
+
+                let start = p + self.pen[next_support];
+                pb.line_to(start.x, start.y);
+            }
+
+            dir = next_dir;
+            support = next_support;
+        }
+    }
+
+    // Returns the index of the pen point furthest to the left of the direction.
+    fn support(&self, dir: Point) -> usize {
+        let normal = Point::from_xy(-dir.y, dir.x);
+        let mut index = 0;
+        let mut max = f32::NEG_INFINITY;
+        for (i, p) in self.pen.iter().enumerate() {
+            let d = p.dot(normal);
+            if d > max {
+                max = d;
+                index = i;
+            }
+        }
+
+        index
+    }
+}
+
+// Andrew's monotone chain. Replaces `points` with hull vertices
+// in a consistent direction, so all hulls have the same winding.
+fn convex_hull(points: &mut Vec<Point>) {
+    points.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(core::cmp::Ordering::Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(core::cmp::Ordering::Equal))
+    });
+
+    if points.len() < 3 {
+        return;
+    }
+
+    fn turn(o: Point, a: Point, b: Point) -> f32 {
+        (a - o).cross(b - o)
+    }
+
+    let mut hull: Vec<Point> = Vec::with_capacity(points.len() + 1);
+    for p in points.iter() {
+        while hull.len() >= 2 && turn(hull[hull.len() - 2], hull[hull.len() - 1], *p) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(*p);
+    }
+
+    let lower_len = hull.len() + 1;
+    for p in points.iter().rev().skip(1) {
+        while hull.len() >= lower_len && turn(hull[hull.len() - 2], hull[hull.len() - 1], *p) <= 0.0
+        {
+            hull.pop();
+        }
+        hull.push(*p);
+    }
+
+    // The last point is the same as the first one.
+    hull.pop();
+    *points = hull;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Rect, StrokeDash};
+    use alloc::vec;
+
+    fn line(x1: f32, y1: f32, x2: f32, y2: f32) -> Path {
+        let mut pb = PathBuilder::new();
+        pb.move_to(x1, y1);
+        pb.line_to(x2, y2);
+        pb.finish().unwrap()
+    }
+
+    fn assert_rect_eq(r1: Rect, r2: Rect) {
+        assert!((r1.left() - r2.left()).abs() < 0.01);
+        assert!((r1.top() - r2.top()).abs() < 0.01);
+        assert!((r1.right() - r2.right()).abs() < 0.01);
+        assert!((r1.bottom() - r2.bottom()).abs() < 0.01);
+    }
+
+    #[test]
+    fn width_depends_on_direction() {
+        let nib = Nib::Ellipse {
+            rx: 10.0,
+            ry: 2.0,
+            angle: 0.0,
+        };
+
+        let horizontal = line(20.0, 50.0, 80.0, 50.0).stroke_nib(&nib, 1.0).unwrap();
+        assert_rect_eq(
+            horizontal.compute_tight_bounds().unwrap(),
+            Rect::from_ltrb(10.0, 48.0, 90.0, 52.0).unwrap(),
+        );
+
+        let vertical = line(50.0, 20.0, 50.0, 80.0).stroke_nib(&nib, 1.0).unwrap();
+        assert_rect_eq(
+            vertical.compute_tight_bounds().unwrap(),
+            Rect::from_ltrb(40.0, 18.0, 60.0, 82.0).unwrap(),
+        );
+    }
+
+    #[test]
+    fn rotated_flat_nib() {
+        let nib = Nib::Rect {
+            width: 20.0,
+            height: 0.0,
+            angle: 90.0,
+        };
+
+        // A stroke along the nib has no area.
+        let path = line(50.0, 20.0, 50.0, 80.0);
+        let stroked = path.stroke_nib(&nib, 1.0);
+        assert!(stroked.is_none());
+
+        let path = line(20.0, 50.0, 80.0, 50.0);
+        let stroked = path.stroke_nib(&nib, 1.0).unwrap();
+        assert_rect_eq(
+            stroked.compute_tight_bounds().unwrap(),
+            Rect::from_ltrb(20.0, 40.0, 80.0, 60.0).unwrap(),
+        );
+    }
+
+    #[test]
+    fn curve_stays_within_tolerance() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 100.0);
+        pb.cubic_to(10.0, 10.0, 190.0, 10.0, 190.0, 100.0);
+        let path = pb.finish().unwrap();
+
+        let nib = Nib::Ellipse {
+            rx: 5.0,
+            ry: 5.0,
+            angle: 0.0,
+        };
+        let stroked = path.stroke_nib(&nib, 1.0).unwrap();
+
+        // A circular nib produces the same outline as a regular round stroke.
+        let bounds = stroked.compute_tight_bounds().unwrap();
+        assert!((bounds.left() - 5.0).abs() < 0.25);
+        assert!((bounds.right() - 195.0).abs() < 0.25);
+        assert!((bounds.top() - 27.5).abs() < 0.25);
+        assert!((bounds.bottom() - 105.0).abs() < 0.25);
+    }
+
+    #[test]
+    fn dot() {
+        let nib = Nib::Rect {
+            width: 4.0,
+            height: 2.0,
+            angle: 0.0,
+        };
+        let stroked = line(10.0, 10.0, 10.0, 10.0).stroke_nib(&nib, 1.0).unwrap();
+        assert_eq!(
+            stroked.bounds(),
+            Rect::from_ltrb(8.0, 9.0, 12.0, 11.0).unwrap()
+        );
+    }
+
+    #[test]
+    fn dashed() {
+        let path = line(0.0, 10.0, 100.0, 10.0);
+        let dash = StrokeDash::new(vec![10.0, 10.0], 0.0).unwrap();
+        let dashed = path.dash(&dash, 1.0).unwrap();
+
+        let nib = Nib::Rect {
+            width: 2.0,
+            height: 2.0,
+            angle: 0.0,
+        };
+        let stroked = dashed.stroke_nib(&nib, 1.0).unwrap();
+        let contours = stroked
+            .verbs()
+            .iter()
+            .filter(|v| **v == crate::PathVerb::Move)
+            .count();
+        assert_eq!(contours, 5);
+    }
+
+    #[test]
+    fn invalid_nib() {
+        let path = line(20.0, 50.0, 80.0, 50.0);
+        let nibs = [
+            Nib::Ellipse {
+                rx: 0.0,
+                ry: 0.0,
+                angle: 0.0,
+            },
+            Nib::Ellipse {
+                rx: -1.0,
+                ry: 2.0,
+                angle: 0.0,
+            },
+            Nib::Rect {
+                width: f32::NAN,
+                height: 2.0,
+                angle: 0.0,
+            },
+            Nib::Rect {
+                width: 2.0,
+                height: 2.0,
+                angle: f32::INFINITY,
+            },
+        ];
+
+        for nib in &nibs {
+            assert!(path.stroke_nib(nib, 1.0).is_none());
+        }
+    }
+}
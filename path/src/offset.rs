@@ -0,0 +1,1036 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+// Offsetting is a boolean operation between the filled area and a stroke of its boundary.
+// Aligned strokes are a similar operation between the filled area and a double width stroke.
+//
+// To get an outline without self-intersections, both are flattened and snap rounded
+// to an integer grid: edges are split at every grid pixel containing an edge end
+// or an intersection they pass through. The resulting pieces meet only at their ends,
+// so the pieces separating the resulting area from the outside can be found using
+// exact winding numbers on both sides and linked into contours.
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use crate::{FillRule, LineCap, LineJoin, Path, Point, Stroke};
+
+use crate::floating_point::NormalizedF32;
+use crate::path::PathSegment;
+use crate::path_builder::PathBuilder;
+use crate::path_geometry;
+
+#[cfg(all(not(feature = "std"), feature = "no-std-float"))]
+use crate::NoStdFloat;
+
+// The stroker and the flattening split a quarter of a path unit error evenly.
+pub(crate) const STROKE_RESOLUTION_SCALE: f32 = 2.0;
+pub(crate) const FLATTEN_TOLERANCE: f32 = 0.125;
+const MAX_FLATTEN_SEGMENTS: usize = 1024;
+
+// The grid is fine enough to not affect the precision,
+// i.e. 256 steps per path unit for the default flattening tolerance.
+// For large coordinates it gets coarser, to keep intersections math within i128.
+const GRID_STEPS_PER_TOLERANCE: f32 = 32.0;
+const MAX_GRID_COORD: f32 = (1u32 << 30) as f32;
+
+const MAX_WINDING_BANDS: usize = 1024;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Source {
+    Fill,
+    Stroke,
+    // Strokes of open contours, which are added to an aligned stroke as is.
+    Open,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Operation {
+    Outset,
+    Inset,
+    Simplify,
+    StrokeInside,
+    StrokeOutside,
+}
+
+impl Operation {
+    fn is_inside(self, winding: Winding, fill_rule: FillRule) -> bool {
+        let fill = match fill_rule {
+            FillRule::Winding => winding.fill != 0,
+            FillRule::EvenOdd => winding.fill % 2 != 0,
+        };
+        let stroke = winding.stroke != 0;
+        let open = winding.open != 0;
+        match self {
+            Operation::Outset => fill || stroke,
+            Operation::Inset => fill && !stroke,
+            Operation::Simplify => fill,
+            Operation::StrokeInside => (fill && stroke) || open,
+            Operation::StrokeOutside => (!fill && stroke) || open,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+struct Winding {
+    fill: i32,
+    stroke: i32,
+    open: i32,
+}
+
+impl Winding {
+    fn add(self, other: Winding) -> Winding {
+        Winding {
+            fill: self.fill + other.fill,
+            stroke: self.stroke + other.stroke,
+            open: self.open + other.open,
+        }
+    }
+
+    fn sub(self, other: Winding) -> Winding {
+        Winding {
+            fill: self.fill - other.fill,
+            stroke: self.stroke - other.stroke,
+            open: self.open - other.open,
+        }
+    }
+}
+
+// Points are ordered top to bottom and then left to right.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct GridPoint {
+    y: i64,
+    x: i64,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Edge<T> {
+    p0: T,
+    p1: T,
+    source: Source,
+}
+
+// An undirected piece of the arrangement, shared by all the coincident edges.
+//
+// The winding contributions are for the `p0` to `p1` direction, where `p0` < `p1`.
+#[derive(Copy, Clone, Debug)]
+struct Piece {
+    p0: GridPoint,
+    p1: GridPoint,
+    winding: Winding,
+}
+
+impl Piece {
+    fn is_horizontal(&self) -> bool {
+        self.p0.y == self.p1.y
+    }
+}
+
+impl Path {
+    /// Returns a boundary of the filled area, grown or shrunk by `distance`.
+    ///
+    /// A positive distance outsets the area and a negative one insets it.
+    /// `line_join` and `miter_limit` affect corners that move away from the area,
+    /// i.e. convex corners when outsetting and concave ones when insetting,
+    /// while the opposite corners stay sharp. A zero distance simply removes
+    /// self-intersections.
+    ///
+    /// The filled area is defined by the nonzero fill rule with all contours closed.
+    /// Unlike a stroke, the result has no self-intersections or overlapping contours,
+    /// holes are oriented opposite to the outer contours, and it can be filled
+    /// using any fill rule.
+    ///
+    /// Curves are flattened, so the result consists of lines only. It deviates from
+    /// the exact offset by up to a quarter of a path unit. Scale the path up beforehand
+    /// when more precision is required.
+    ///
+    /// Returns `None` when `distance` is not finite or when the resulting area is empty.
+    pub fn offset(&self, distance: f32, line_join: LineJoin, miter_limit: f32) -> Option<Path> {
+        if !distance.is_finite() {
+            return None;
+        }
+
+        let mut lines = Vec::new();
+        flatten(self, Source::Fill, FLATTEN_TOLERANCE, &mut lines);
+
+        let operation = if distance == 0.0 {
+            Operation::Simplify
+        } else {
+            // Every contour is filled as closed, so the boundary includes closing lines.
+            let mut closed = PathBuilder::new();
+            let mut iter = self.segments();
+            iter.set_auto_close(true);
+            for segment in iter {
+                match segment {
+                    PathSegment::MoveTo(p) => closed.move_to(p.x, p.y),
+                    PathSegment::LineTo(p) => closed.line_to(p.x, p.y),
+                    PathSegment::QuadTo(p1, p2) => closed.quad_to(p1.x, p1.y, p2.x, p2.y),
+                    PathSegment::CubicTo(p1, p2, p3) => {
+                        closed.cubic_to(p1.x, p1.y, p2.x, p2.y, p3.x, p3.y)
+                    }
+                    PathSegment::Close => closed.close(),
+                }
+            }
+
+            // A stroke covers everything closer to the boundary than the distance.
+            let stroke = Stroke {
+                width: distance.abs() * 2.0,
+                miter_limit,
+                line_cap: LineCap::Butt,
+                line_join,
+                ..Stroke::default()
+            };
+
+            if let Some(outline) = closed
+                .finish()
+                .and_then(|path| path.stroke(&stroke, STROKE_RESOLUTION_SCALE))
+            {
+                flatten(&outline, Source::Stroke, FLATTEN_TOLERANCE, &mut lines);
+            }
+
+            if distance > 0.0 {
+                Operation::Outset
+            } else {
+                Operation::Inset
+            }
+        };
+
+        resolve(&lines, operation, FillRule::Winding, FLATTEN_TOLERANCE)
+    }
+}
+
+// Returns the boundary of an aligned stroke, i.e. the area of the `stroke` outline
+// inside or outside of the `fill` area, united with the `open` outline area.
+//
+// Curves are flattened with `tolerance`.
+pub(crate) fn clip_stroke(
+    fill: &Path,
+    fill_rule: FillRule,
+    stroke: &Path,
+    open: Option<&Path>,
+    inside: bool,
+    tolerance: f32,
+) -> Option<Path> {
+    let mut lines = Vec::new();
+    flatten(fill, Source::Fill, tolerance, &mut lines);
+    flatten(stroke, Source::Stroke, tolerance, &mut lines);
+    if let Some(open) = open {
+        flatten(open, Source::Open, tolerance, &mut lines);
+    }
+
+    let operation = if inside {
+        Operation::StrokeInside
+    } else {
+        Operation::StrokeOutside
+    };
+
+    resolve(&lines, operation, fill_rule, tolerance)
+}
+
+// Returns the boundary of the nonzero filled area of a path consisting of lines only,
+// like a self-overlapping outline.
+pub(crate) fn union(path: &Path, tolerance: f32) -> Option<Path> {
+    let mut lines = Vec::new();
+    flatten(path, Source::Fill, tolerance, &mut lines);
+    resolve(&lines, Operation::Simplify, FillRule::Winding, tolerance)
+}
+
+fn resolve(
+    lines: &[Edge<Point>],
+    operation: Operation,
+    fill_rule: FillRule,
+    tolerance: f32,
+) -> Option<Path> {
+    let mut max_coord = 0.0f32;
+    for line in lines {
+        max_coord = max_coord
+            .max(line.p0.x.abs())
+            .max(line.p0.y.abs())
+            .max(line.p1.x.abs())
+            .max(line.p1.y.abs());
+    }
+
+    if !max_coord.is_finite() {
+        return None;
+    }
+
+    let grid_scale = GRID_STEPS_PER_TOLERANCE / tolerance;
+    let scale = if max_coord * grid_scale > MAX_GRID_COORD {
+        MAX_GRID_COORD / max_coord
+    } else {
+        grid_scale
+    };
+
+    let to_grid = |p: Point| GridPoint {
+        y: (p.y * scale).round() as i64,
+        x: (p.x * scale).round() as i64,
+    };
+
+    let edges: Vec<_> = lines
+        .iter()
+        .map(|line| Edge {
+            p0: to_grid(line.p0),
+            p1: to_grid(line.p1),
+            source: line.source,
+        })
+        .filter(|edge| edge.p0 != edge.p1)
+        .collect();
+
+    let pieces = build_arrangement(&edges);
+    let boundary = collect_boundary(&pieces, operation, fill_rule);
+    link_boundary(&boundary, scale)
+}
+
+fn flatten(path: &Path, source: Source, tolerance: f32, lines: &mut Vec<Edge<Point>>) {
+    let mut push_line = |p0: Point, p1: Point| {
+        if p0 != p1 {
+            lines.push(Edge { p0, p1, source });
+        }
+    };
+
+    // Every contour is closed, otherwise windings would depend on the place they're measured at.
+    let mut start = Point::zero();
+    let mut last = Point::zero();
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => {
+                push_line(last, start);
+                start = p;
+                last = p;
+            }
+            PathSegment::LineTo(p) => {
+                push_line(last, p);
+                last = p;
+            }
+            PathSegment::QuadTo(p1, p2) => {
+                flatten_quad(&[last, p1, p2], tolerance, |p| {
+                    push_line(last, p);
+                    last = p;
+                });
+            }
+            PathSegment::CubicTo(p1, p2, p3) => {
+                flatten_cubic(&[last, p1, p2, p3], tolerance, |p| {
+                    push_line(last, p);
+                    last = p;
+                });
+            }
+            PathSegment::Close => {
+                push_line(last, start);
+                last = start;
+            }
+        }
+    }
+
+    push_line(last, start);
+}
+
+// Flattens a quad into lines, passing the points after the first one to `push`.
+pub(crate) fn flatten_quad(points: &[Point; 3], tolerance: f32, mut push: impl FnMut(Point)) {
+    let dd = (points[0] - points[1].scaled(2.0) + points[2]).length();
+    let count = segments_count(dd, 0.25, tolerance);
+    for i in 1..=count {
+        let t = NormalizedF32::new_clamped(i as f32 / count as f32);
+        push(path_geometry::eval_quad_at(points, t));
+    }
+}
+
+// Flattens a cubic into lines, passing the points after the first one to `push`.
+pub(crate) fn flatten_cubic(points: &[Point; 4], tolerance: f32, mut push: impl FnMut(Point)) {
+    let dd1 = (points[0] - points[1].scaled(2.0) + points[2]).length();
+    let dd2 = (points[1] - points[2].scaled(2.0) + points[3]).length();
+    let count = segments_count(dd1.max(dd2), 0.75, tolerance);
+    for i in 1..=count {
+        let t = NormalizedF32::new_clamped(i as f32 / count as f32);
+        push(path_geometry::eval_cubic_pos_at(points, t));
+    }
+}
+
+// Uses Wang's formula to find the number of lines needed to stay within tolerance.
+fn segments_count(max_second_diff: f32, degree_factor: f32, tolerance: f32) -> usize {
+    let n = (degree_factor * max_second_diff / tolerance).sqrt().ceil();
+    if n.is_finite() {
+        (n as usize).clamp(1, MAX_FLATTEN_SEGMENTS)
+    } else {
+        1
+    }
+}
+
+fn build_arrangement(edges: &[Edge<GridPoint>]) -> Vec<Piece> {
+    let mut hot_pixels = Vec::with_capacity(edges.len() * 2);
+    for edge in edges {
+        hot_pixels.push(edge.p0);
+        hot_pixels.push(edge.p1);
+    }
+
+    // Sweep along the x-axis to skip edges that cannot intersect.
+    let mut order: Vec<usize> = (0..edges.len()).collect();
+    order.sort_unstable_by_key(|i| edges[*i].p0.x.min(edges[*i].p1.x));
+    for (i, &a) in order.iter().enumerate() {
+        let ea = &edges[a];
+        let max_x = ea.p0.x.max(ea.p1.x);
+        let (min_y, max_y) = (ea.p0.y.min(ea.p1.y), ea.p0.y.max(ea.p1.y));
+        for &b in &order[i + 1..] {
+            let eb = &edges[b];
+            if eb.p0.x.min(eb.p1.x) > max_x {
+                break;
+            }
+
+            if eb.p0.y.min(eb.p1.y) > max_y || eb.p0.y.max(eb.p1.y) < min_y {
+                continue;
+            }
+
+            if let Some(p) = intersect(ea, eb) {
+                hot_pixels.push(p);
+            }
+        }
+    }
+
+    hot_pixels.sort_unstable();
+    hot_pixels.dedup();
+
+    let mut pieces: Vec<Piece> = Vec::new();
+    let mut piece_indices = BTreeMap::new();
+    let mut pixels = Vec::new();
+    for edge in edges {
+        let (min_x, max_x) = (edge.p0.x.min(edge.p1.x), edge.p0.x.max(edge.p1.x));
+        let (min_y, max_y) = (edge.p0.y.min(edge.p1.y), edge.p0.y.max(edge.p1.y));
+        let d = sub(edge.p1, edge.p0);
+
+        // Includes the edge ends.
+        pixels.clear();
+        let first = hot_pixels.partition_point(|p| p.y < min_y);
+        for &pixel in &hot_pixels[first..] {
+            if pixel.y > max_y {
+                break;
+            }
+
+            if pixel.x >= min_x && pixel.x <= max_x && passes_through(edge, pixel) {
+                pixels.push((dot(sub(pixel, edge.p0), d), pixel));
+            }
+        }
+
+        pixels.sort_unstable();
+
+        for pair in pixels.windows(2) {
+            let (p0, p1) = (pair[0].1, pair[1].1);
+            if p0 == p1 {
+                continue;
+            }
+
+            let (p0, p1, sign) = if p0 < p1 { (p0, p1, 1) } else { (p1, p0, -1) };
+            let index = *piece_indices.entry((p0, p1)).or_insert_with(|| {
+                pieces.push(Piece {
+                    p0,
+                    p1,
+                    winding: Winding::default(),
+                });
+                pieces.len() - 1
+            });
+
+            match edge.source {
+                Source::Fill => pieces[index].winding.fill += sign,
+                Source::Stroke => pieces[index].winding.stroke += sign,
+                Source::Open => pieces[index].winding.open += sign,
+            }
+        }
+    }
+
+    pieces
+}
+
+// Returns a rounded intersection point of non-parallel edges.
+//
+// Parallel edges are split at each other's ends, since those are hot pixels anyway.
+fn intersect(ea: &Edge<GridPoint>, eb: &Edge<GridPoint>) -> Option<GridPoint> {
+    let da = sub(ea.p1, ea.p0);
+    let db = sub(eb.p1, eb.p0);
+    let ab = sub(eb.p0, ea.p0);
+
+    let mut denom = cross(da, db);
+    let mut ta = cross(ab, db);
+    let mut tb = cross(ab, da);
+    if denom == 0 {
+        return None;
+    }
+
+    if denom < 0 {
+        denom = -denom;
+        ta = -ta;
+        tb = -tb;
+    }
+
+    if ta < 0 || ta > denom || tb < 0 || tb > denom {
+        return None;
+    }
+
+    Some(GridPoint {
+        y: ea.p0.y + div_round(da.1 * ta, denom) as i64,
+        x: ea.p0.x + div_round(da.0 * ta, denom) as i64,
+    })
+}
+
+// Checks that an edge touches a pixel, i.e. a square with a side of 1 around a grid point.
+fn passes_through(edge: &Edge<GridPoint>, pixel: GridPoint) -> bool {
+    // Doubled coordinates keep pixel corners integer.
+    let d = sub(edge.p1, edge.p0);
+    let center = sub(pixel, edge.p0);
+    let mut has_left = false;
+    let mut has_right = false;
+    for &(cx, cy) in &[(-1, -1), (1, -1), (1, 1), (-1, 1)] {
+        let corner = (center.0 * 2 + cx, center.1 * 2 + cy);
+        let side = cross(d, corner);
+        has_left |= side >= 0;
+        has_right |= side <= 0;
+    }
+
+    has_left && has_right
+}
+
+// Keeps pieces that separate the resulting area from the outside,
+// oriented to have the area on the right in a y-down coordinate system.
+//
+// Pieces are split into half-edges going in both directions. The winding on the left
+// side of a half-edge, assuming a y-up coordinate system, is shared with the next half-edge
+// around the same face and differs from the opposite half-edge by the piece winding.
+// So windings are found by a ray casting once per connected group of pieces.
+fn collect_boundary(
+    pieces: &[Piece],
+    operation: Operation,
+    fill_rule: FillRule,
+) -> Vec<(GridPoint, GridPoint)> {
+    // Half-edge `2 * i` goes from `p0` to `p1` of piece `i` and `2 * i + 1` goes back.
+    let half_edge = |index: usize| {
+        let piece = &pieces[index / 2];
+        if index & 1 == 0 {
+            (piece.p0, sub(piece.p1, piece.p0))
+        } else {
+            (piece.p1, sub(piece.p0, piece.p1))
+        }
+    };
+
+    // Grouped by the origin and sorted counterclockwise within a group.
+    let mut outgoing: Vec<usize> = (0..pieces.len() * 2).collect();
+    outgoing.sort_unstable_by(|a, b| {
+        let (origin_a, dir_a) = half_edge(*a);
+        let (origin_b, dir_b) = half_edge(*b);
+        origin_a
+            .cmp(&origin_b)
+            .then_with(|| compare_directions(dir_a, dir_b))
+    });
+
+    let mut position = vec![0; outgoing.len()];
+    let mut group_start = vec![0; outgoing.len()];
+    let mut group_end = vec![0; outgoing.len()];
+    let mut start = 0;
+    for i in 0..outgoing.len() {
+        position[outgoing[i]] = i;
+        if i + 1 == outgoing.len() || half_edge(outgoing[i]).0 != half_edge(outgoing[i + 1]).0 {
+            for j in start..=i {
+                group_start[j] = start;
+                group_end[j] = i;
+            }
+
+            start = i + 1;
+        }
+    }
+
+    // The face boundary continues with the sharpest left turn,
+    // i.e. the outgoing half-edge preceding the opposite one.
+    let next = |index: usize| {
+        let i = position[index ^ 1];
+        let i = if i == group_start[i] {
+            group_end[i]
+        } else {
+            i - 1
+        };
+        outgoing[i]
+    };
+
+    let bands = WindingBands::new(pieces);
+    let mut left: Vec<Option<Winding>> = vec![None; outgoing.len()];
+    let mut stack = Vec::new();
+    for (index, piece) in pieces.iter().enumerate() {
+        if left[index * 2].is_some() {
+            continue;
+        }
+
+        // The winding right next to the piece middle, below it for horizontal pieces
+        // and to the right otherwise. The piece itself contributes to the left side only.
+        let below_or_right = bands.winding(pieces, index);
+        left[index * 2] = Some(if piece.is_horizontal() {
+            below_or_right
+        } else {
+            below_or_right.add(piece.winding)
+        });
+
+        stack.push(index * 2);
+        while let Some(edge) = stack.pop() {
+            let winding = match left[edge] {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let next_edge = next(edge);
+            if left[next_edge].is_none() {
+                left[next_edge] = Some(winding);
+                stack.push(next_edge);
+            }
+
+            let opposite = edge ^ 1;
+            if left[opposite].is_none() {
+                let piece_winding = pieces[edge / 2].winding;
+                left[opposite] = Some(if edge & 1 == 0 {
+                    winding.sub(piece_winding)
+                } else {
+                    winding.add(piece_winding)
+                });
+                stack.push(opposite);
+            }
+        }
+    }
+
+    let mut boundary = Vec::new();
+    for (index, piece) in pieces.iter().enumerate() {
+        // When the area is on the `forward` side only, the piece keeps its direction.
+        let forward = left[index * 2].unwrap_or_default();
+        let backward = left[index * 2 + 1].unwrap_or_default();
+        match (
+            operation.is_inside(forward, fill_rule),
+            operation.is_inside(backward, fill_rule),
+        ) {
+            (true, false) => boundary.push((piece.p0, piece.p1)),
+            (false, true) => boundary.push((piece.p1, piece.p0)),
+            _ => {}
+        }
+    }
+
+    boundary
+}
+
+fn link_boundary(boundary: &[(GridPoint, GridPoint)], scale: f32) -> Option<Path> {
+    let mut outgoing: BTreeMap<GridPoint, Vec<usize>> = BTreeMap::new();
+    for (index, (p0, _)) in boundary.iter().enumerate() {
+        outgoing.entry(*p0).or_default().push(index);
+    }
+
+    let mut used = Vec::new();
+    used.resize(boundary.len(), false);
+    let mut points = Vec::new();
+    let mut pb = PathBuilder::new();
+    for first in 0..boundary.len() {
+        if used[first] {
+            continue;
+        }
+
+        points.clear();
+        let start = boundary[first].0;
+        let mut index = first;
+        loop {
+            used[index] = true;
+            let (p0, p1) = boundary[index];
+            points.push(p0);
+            if p1 == start {
+                break;
+            }
+
+            // Contours touching at a vertex are separated by taking the sharpest
+            // turn towards the area.
+            let incoming = sub(p1, p0);
+            let mut next = None;
+            let mut best_turn = f64::NEG_INFINITY;
+            for &i in outgoing.get(&p1).into_iter().flatten() {
+                let turn = turn_angle(incoming, sub(boundary[i].1, boundary[i].0));
+                if !used[i] && turn > best_turn {
+                    next = Some(i);
+                    best_turn = turn;
+                }
+            }
+
+            match next {
+                Some(next) => index = next,
+                None => break,
+            }
+        }
+
+        push_polygon(&points, scale, &mut pb);
+    }
+
+    pb.finish()
+}
+
+// Pushes a closed polygon, skipping vertices in the middle of straight lines
+// produced by the splitting.
+fn push_polygon(points: &[GridPoint], scale: f32, pb: &mut PathBuilder) {
+    let len = points.len();
+    let is_corner = |i: usize| {
+        let prev = points[(i + len - 1) % len];
+        let curr = points[i];
+        let next = points[(i + 1) % len];
+        let d0 = sub(curr, prev);
+        let d1 = sub(next, curr);
+        cross(d0, d1) != 0 || dot(d0, d1) < 0
+    };
+
+    let corners = (0..len).filter(|i| is_corner(*i));
+    if corners.clone().count() < 3 {
+        return;
+    }
+
+    let mut is_first = true;
+    for i in corners {
+        let x = points[i].x as f32 / scale;
+        let y = points[i].y as f32 / scale;
+        if is_first {
+            pb.move_to(x, y);
+            is_first = false;
+        } else {
+            pb.line_to(x, y);
+        }
+    }
+
+    pb.close();
+}
+
+// Horizontal bands of pieces, to avoid testing all of them for each winding query.
+struct WindingBands {
+    top: i64,
+    height: i64,
+    bands: Vec<Vec<usize>>,
+}
+
+impl WindingBands {
+    fn new(pieces: &[Piece]) -> Self {
+        let top = pieces.iter().map(|p| p.p0.y).min().unwrap_or(0);
+        let bottom = pieces.iter().map(|p| p.p1.y).max().unwrap_or(0);
+
+        let count = ((pieces.len() as f32).sqrt() as usize).clamp(1, MAX_WINDING_BANDS);
+        let mut bands = WindingBands {
+            top,
+            height: bottom - top + 1,
+            bands: Vec::new(),
+        };
+        bands.bands.resize_with(count, Vec::new);
+
+        for (index, piece) in pieces.iter().enumerate() {
+            let first = bands.band(piece.p0.y);
+            let last = bands.band(piece.p1.y);
+            for band in &mut bands.bands[first..=last] {
+                band.push(index);
+            }
+        }
+
+        bands
+    }
+
+    fn band(&self, y: i64) -> usize {
+        let index = i128::from(y - self.top) * self.bands.len() as i128 / i128::from(self.height);
+        (index.max(0) as usize).min(self.bands.len() - 1)
+    }
+
+    // Returns a winding right next to the piece middle, to the right and below it,
+    // ignoring the piece itself.
+    fn winding(&self, pieces: &[Piece], index: usize) -> Winding {
+        let piece = &pieces[index];
+
+        // In doubled coordinates.
+        let mx = i128::from(piece.p0.x) + i128::from(piece.p1.x);
+        let my = i128::from(piece.p0.y) + i128::from(piece.p1.y);
+
+        let mut winding = Winding::default();
+        for &other_index in &self.bands[self.band(my.div_euclid(2) as i64)] {
+            let other = &pieces[other_index];
+            if other_index == index || other.is_horizontal() {
+                continue;
+            }
+
+            let (x0, y0) = (i128::from(other.p0.x) * 2, i128::from(other.p0.y) * 2);
+            let (x1, y1) = (i128::from(other.p1.x) * 2, i128::from(other.p1.y) * 2);
+            let crosses = y0 <= my && my < y1;
+
+            // Checks that the crossing is to the right, knowing that `y1 > y0`.
+            if crosses && (x0 - mx) * (y1 - y0) + (my - y0) * (x1 - x0) > 0 {
+                winding = winding.add(other.winding);
+            }
+        }
+
+        winding
+    }
+}
+
+// Returns a value growing monotonically with the clockwise angle from `d0` to `d1`
+// in a y-down coordinate system, in the -2..=2 range.
+fn turn_angle(d0: (i128, i128), d1: (i128, i128)) -> f64 {
+    let y = cross(d0, d1) as f64;
+    let x = dot(d0, d1) as f64;
+
+    // A "diamond angle", which is a cheaper atan2 replacement.
+    let angle = if y >= 0.0 {
+        if x >= 0.0 {
+            y / (x + y)
+        } else {
+            1.0 - x / (-x + y)
+        }
+    } else if x < 0.0 {
+        2.0 - y / (-x - y)
+    } else {
+        3.0 + x / (x - y)
+    };
+
+    if angle > 2.0 {
+        angle - 4.0
+    } else {
+        angle
+    }
+}
+
+// Orders directions counterclockwise, assuming a y-up coordinate system,
+// starting from the positive x-axis.
+fn compare_directions(d0: (i128, i128), d1: (i128, i128)) -> Ordering {
+    let half = |d: (i128, i128)| d.1 < 0 || (d.1 == 0 && d.0 < 0);
+    half(d0).cmp(&half(d1)).then_with(|| 0.cmp(&cross(d0, d1)))
+}
+
+fn div_round(n: i128, d: i128) -> i128 {
+    let q = n.div_euclid(d);
+    if n.rem_euclid(d) * 2 >= d {
+        q + 1
+    } else {
+        q
+    }
+}
+
+fn sub(a: GridPoint, b: GridPoint) -> (i128, i128) {
+    (i128::from(a.x - b.x), i128::from(a.y - b.y))
+}
+
+fn cross(a: (i128, i128), b: (i128, i128)) -> i128 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn dot(a: (i128, i128), b: (i128, i128)) -> i128 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scalar::Scalar;
+    use crate::Rect;
+
+    // Lines only. Holes have a negative area.
+    fn area(path: &Path) -> f32 {
+        let mut area = 0.0;
+        let mut start = Point::zero();
+        let mut last = Point::zero();
+        let mut iter = path.segments();
+        iter.set_auto_close(true);
+        for segment in iter {
+            let p = match segment {
+                PathSegment::MoveTo(p) => {
+                    start = p;
+                    last = p;
+                    continue;
+                }
+                PathSegment::LineTo(p) => p,
+                PathSegment::Close => start,
+                _ => unreachable!(),
+            };
+
+            area += last.cross(p);
+            last = p;
+        }
+
+        area.half()
+    }
+
+    fn contours(path: &Path) -> usize {
+        path.segments()
+            .filter(|s| matches!(s, PathSegment::MoveTo(_)))
+            .count()
+    }
+
+    fn square(pb: &mut PathBuilder, left: f32, top: f32, size: f32) {
+        pb.push_rect(Rect::from_xywh(left, top, size, size).unwrap());
+    }
+
+    #[test]
+    fn outset_and_inset() {
+        let mut pb = PathBuilder::new();
+        square(&mut pb, 10.0, 10.0, 20.0);
+        let path = pb.finish().unwrap();
+
+        let outset = path.offset(5.0, LineJoin::Miter, 4.0).unwrap();
+        assert_eq!(
+            outset.bounds(),
+            Rect::from_ltrb(5.0, 5.0, 35.0, 35.0).unwrap()
+        );
+        assert_eq!(outset.len(), 5);
+        assert_eq!(area(&outset), 900.0);
+
+        let inset = path.offset(-5.0, LineJoin::Miter, 4.0).unwrap();
+        assert_eq!(
+            inset.bounds(),
+            Rect::from_ltrb(15.0, 15.0, 25.0, 25.0).unwrap()
+        );
+        assert_eq!(inset.len(), 5);
+        assert_eq!(area(&inset), 100.0);
+    }
+
+    #[test]
+    fn round_join() {
+        let mut pb = PathBuilder::new();
+        square(&mut pb, 10.0, 10.0, 20.0);
+        let path = pb.finish().unwrap();
+
+        let outset = path.offset(5.0, LineJoin::Round, 4.0).unwrap();
+        let expected = 400.0 + 4.0 * 20.0 * 5.0 + core::f32::consts::PI * 25.0;
+        assert!((area(&outset) - expected).abs() < 2.0);
+        assert_eq!(contours(&outset), 1);
+
+        // Convex corners stay sharp when insetting.
+        let inset = path.offset(-5.0, LineJoin::Round, 4.0).unwrap();
+        assert_eq!(area(&inset), 100.0);
+    }
+
+    #[test]
+    fn merges_overlapping_contours() {
+        let mut pb = PathBuilder::new();
+        square(&mut pb, 0.0, 0.0, 20.0);
+        square(&mut pb, 10.0, 10.0, 20.0);
+        let path = pb.finish().unwrap();
+
+        let simplified = path.offset(0.0, LineJoin::Miter, 4.0).unwrap();
+        assert_eq!(contours(&simplified), 1);
+        assert_eq!(simplified.len(), 9);
+        assert_eq!(area(&simplified), 700.0);
+
+        let outset = path.offset(2.0, LineJoin::Miter, 4.0).unwrap();
+        assert_eq!(contours(&outset), 1);
+        assert_eq!(area(&outset), 24.0 * 24.0 * 2.0 - 14.0 * 14.0);
+    }
+
+    #[test]
+    fn removes_self_intersections() {
+        // A bow tie.
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.line_to(20.0, 20.0);
+        pb.line_to(20.0, 0.0);
+        pb.line_to(0.0, 20.0);
+        pb.close();
+        let path = pb.finish().unwrap();
+
+        let simplified = path.offset(0.0, LineJoin::Miter, 4.0).unwrap();
+        assert_eq!(contours(&simplified), 2);
+        // Both triangles are oriented the same way.
+        assert_eq!(area(&simplified), 200.0);
+    }
+
+    #[test]
+    fn holes() {
+        let mut pb = PathBuilder::new();
+        square(&mut pb, 0.0, 0.0, 40.0);
+        pb.move_to(10.0, 10.0);
+        pb.line_to(10.0, 30.0);
+        pb.line_to(30.0, 30.0);
+        pb.line_to(30.0, 10.0);
+        pb.close();
+        let path = pb.finish().unwrap();
+
+        let outset = path.offset(5.0, LineJoin::Miter, 4.0).unwrap();
+        assert_eq!(contours(&outset), 2);
+        assert_eq!(area(&outset), 50.0 * 50.0 - 10.0 * 10.0);
+
+        // The hole closes completely.
+        let outset = path.offset(10.0, LineJoin::Miter, 4.0).unwrap();
+        assert_eq!(contours(&outset), 1);
+        assert_eq!(area(&outset), 60.0 * 60.0);
+
+        let inset = path.offset(-2.0, LineJoin::Miter, 4.0).unwrap();
+        assert_eq!(contours(&inset), 2);
+        assert_eq!(area(&inset), 36.0 * 36.0 - 24.0 * 24.0);
+    }
+
+    #[test]
+    fn curves() {
+        let path = PathBuilder::from_circle(50.0, 50.0, 20.0).unwrap();
+
+        let outset = path.offset(10.0, LineJoin::Miter, 4.0).unwrap();
+        let expected = core::f32::consts::PI * 30.0 * 30.0;
+        assert!((area(&outset) - expected).abs() / expected < 0.01);
+
+        let inset = path.offset(-10.0, LineJoin::Miter, 4.0).unwrap();
+        let expected = core::f32::consts::PI * 10.0 * 10.0;
+        assert!((area(&inset) - expected).abs() / expected < 0.01);
+        assert_eq!(contours(&inset), 1);
+    }
+
+    fn assert_no_self_intersections(path: &Path) {
+        let mut lines = Vec::new();
+        flatten(path, Source::Fill, FLATTEN_TOLERANCE, &mut lines);
+
+        let orient = |a: Point, b: Point, c: Point| {
+            let ab = (f64::from(b.x - a.x), f64::from(b.y - a.y));
+            let ac = (f64::from(c.x - a.x), f64::from(c.y - a.y));
+            let v = ab.0 * ac.1 - ab.1 * ac.0;
+            if v > 0.0 {
+                1
+            } else if v < 0.0 {
+                -1
+            } else {
+                0
+            }
+        };
+
+        for (i, a) in lines.iter().enumerate() {
+            for b in &lines[i + 1..] {
+                let o1 = orient(a.p0, a.p1, b.p0);
+                let o2 = orient(a.p0, a.p1, b.p1);
+                let o3 = orient(b.p0, b.p1, a.p0);
+                let o4 = orient(b.p0, b.p1, a.p1);
+                let crosses = o1 * o2 < 0 && o3 * o4 < 0;
+                assert!(!crosses, "{:?} crosses {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn star() {
+        // A pentagram with curved edges.
+        let mut pb = PathBuilder::new();
+        pb.move_to(50.0, 0.0);
+        pb.quad_to(60.0, 50.0, 79.4, 90.5);
+        pb.line_to(2.4, 34.5);
+        pb.cubic_to(40.0, 40.0, 60.0, 30.0, 97.6, 34.5);
+        pb.line_to(20.6, 90.5);
+        pb.close();
+        let path = pb.finish().unwrap();
+
+        for (distance, join) in [
+            (0.0, LineJoin::Miter),
+            (3.0, LineJoin::Round),
+            (3.0, LineJoin::Miter),
+            (-1.0, LineJoin::Bevel),
+            (-2.0, LineJoin::Round),
+        ]
+        .iter()
+        {
+            let result = path.offset(*distance, *join, 4.0).unwrap();
+            assert_no_self_intersections(&result);
+        }
+    }
+
+    #[test]
+    fn empty_result() {
+        let mut pb = PathBuilder::new();
+        square(&mut pb, 10.0, 10.0, 20.0);
+        let path = pb.finish().unwrap();
+
+        assert!(path.offset(-10.0, LineJoin::Miter, 4.0).is_none());
+        assert!(path.offset(-20.0, LineJoin::Round, 4.0).is_none());
+        assert!(path.offset(f32::NAN, LineJoin::Miter, 4.0).is_none());
+        assert!(path.offset(f32::INFINITY, LineJoin::Miter, 4.0).is_none());
+    }
+}
@@ -24,6 +24,21 @@ pub enum PathVerb {
     Close,
 }
 
+/// A path filling rule.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FillRule {
+    /// Specifies that "inside" is computed by a non-zero sum of signed edge crossings.
+    Winding,
+    /// Specifies that "inside" is computed by an odd number of edge crossings.
+    EvenOdd,
+}
+
+impl Default for FillRule {
+    fn default() -> Self {
+        FillRule::Winding
+    }
+}
+
 /// A Bezier path.
 ///
 /// Can be created via [`PathBuilder`].
@@ -248,6 +248,16 @@ pub(crate) fn find_quad_max_curvature(src: &[Point; 3]) -> NormalizedF32 {
     NormalizedF32::new(t).unwrap()
 }
 
+// Elevates a quad to the cubic that traces exactly the same curve.
+pub(crate) fn quad_to_cubic(src: &[Point; 3]) -> [Point; 4] {
+    [
+        src[0],
+        src[0] + (src[1] - src[0]).scaled(2.0 / 3.0),
+        src[2] + (src[1] - src[2]).scaled(2.0 / 3.0),
+        src[2],
+    ]
+}
+
 pub(crate) fn eval_quad_at(src: &[Point; 3], t: NormalizedF32) -> Point {
     Point::from_f32x2(QuadCoeff::from_points(src).eval(f32x2::splat(t.get())))
 }
@@ -6,10 +6,13 @@
 
 // Based on SkStroke.cpp
 
-use crate::{Path, Point, Transform};
+use alloc::vec::Vec;
+
+use crate::{FillRule, Path, Point, Rect, Transform};
 
 use crate::dash::StrokeDash;
 use crate::floating_point::{NonZeroPositiveF32, NormalizedF32, NormalizedF32Exclusive};
+use crate::offset;
 use crate::path::{PathSegment, PathSegmentsIter};
 use crate::path_builder::{PathBuilder, PathDirection};
 use crate::path_geometry;
@@ -65,6 +68,38 @@ pub struct Stroke {
     ///
     /// Default: None
     pub dash: Option<StrokeDash>,
+
+    /// A stroke alignment.
+    ///
+    /// Affects only closed contours. Open contours, including dashes, are always centered.
+    ///
+    /// Unlike centered strokes, inside and outside strokes are clipped to the filled area
+    /// and consist only of line segments, since curves are flattened.
+    ///
+    /// Default: Center
+    pub alignment: StrokeAlignment,
+
+    /// A fill rule that defines the filled area for aligned strokes.
+    ///
+    /// Should match the one the path is filled with.
+    ///
+    /// Default: Winding
+    pub fill_rule: FillRule,
+
+    /// Enables high-precision stroking of curves.
+    ///
+    /// Curves are split at curvature extrema, inflections and cusps, and their offsets
+    /// are fitted with cubics deviating from the exact offset curve by no more than
+    /// the specified distance in device pixels. Must be positive.
+    ///
+    /// When not set, a faster approximation with an error of up to a quarter
+    /// of a device pixel is used.
+    ///
+    /// Inside and outside strokes are flattened, so for them the precision
+    /// only sets the flattening tolerance.
+    ///
+    /// Default: None
+    pub precision: Option<f32>,
 }
 
 impl Default for Stroke {
@@ -75,6 +110,9 @@ impl Default for Stroke {
             line_cap: LineCap::default(),
             line_join: LineJoin::default(),
             dash: None,
+            alignment: StrokeAlignment::default(),
+            fill_rule: FillRule::default(),
+            precision: None,
         }
     }
 }
@@ -96,6 +134,25 @@ impl Default for LineCap {
     }
 }
 
+/// A stroke position relative to the path.
+///
+/// The filled area is defined by closed contours and [`Stroke::fill_rule`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum StrokeAlignment {
+    /// The stroke is centered on the path.
+    Center,
+    /// The stroke lies entirely inside the filled area.
+    Inside,
+    /// The stroke lies entirely outside the filled area.
+    Outside,
+}
+
+impl Default for StrokeAlignment {
+    fn default() -> Self {
+        StrokeAlignment::Center
+    }
+}
+
 /// Specifies how corners are drawn when a shape is stroked.
 ///
 /// Join affects the four corners of a stroked rectangle, and the connected segments in a
@@ -128,6 +185,11 @@ impl Default for LineJoin {
 
 const QUAD_RECURSIVE_LIMIT: usize = 3;
 
+// High-precision mode limits.
+const OFFSET_MAX_DEPTH: u8 = 10;
+const OFFSET_SAMPLES: usize = 8;
+const OFFSET_MIN_T_STEP: f32 = 0.0001;
+
 // quads with extreme widths (e.g. (0,1) (1,6) (0,3) width=5e7) recurse to point of failure
 // largest seen for normal cubics: 5, 26
 // largest seen for normal quads: 11
@@ -193,6 +255,76 @@ impl Path {
     pub fn stroke(&self, stroke: &Stroke, resolution_scale: f32) -> Option<Path> {
         PathStroker::new().stroke(self, stroke, resolution_scale)
     }
+
+    /// Computes the device-space bounds of a stroked path without stroking it.
+    ///
+    /// The result is conservative: it accounts for stroke width, miter limit and caps
+    /// the same way [`PathStroker`] does, and for the extra pixel touched by hairlines.
+    /// Dashing can only make the stroke smaller, so it's ignored.
+    ///
+    /// Returns `None` when stroke width is negative or not finite,
+    /// when the transform is invalid or in case of a numeric overflow.
+    pub fn compute_stroke_bounds(&self, stroke: &Stroke, ts: Transform) -> Option<Rect> {
+        if !(stroke.width >= 0.0 && stroke.width.is_finite() && ts.is_finite()) {
+            return None;
+        }
+
+        // Like in Skia, a stroke outline never leaves the control points bounds
+        // outset by the stroke radius, multiplied by the miter limit or the square cap diagonal.
+        let mut multiplier = 1.0f32;
+        if matches!(stroke.line_join, LineJoin::Miter | LineJoin::MiterClip) {
+            multiplier = multiplier.max(stroke.miter_limit);
+        }
+
+        if stroke.line_cap == LineCap::Square {
+            multiplier = multiplier.max(core::f32::consts::SQRT_2);
+        }
+
+        let max_scale = max_scale(&ts);
+        // The stroker approximates joins and offset curves with an error of up to
+        // a quarter of a device pixel, unless a lower precision was requested.
+        // Aligned strokes extend by the whole width to one side of the path.
+        let radius = if stroke.alignment == StrokeAlignment::Center {
+            stroke.width.half()
+        } else {
+            stroke.width
+        };
+        let error = stroke
+            .precision
+            .map_or(0.25, |precision| precision.max(0.25));
+        let mut outset = radius * multiplier * max_scale + error;
+
+        // Thin strokes can be rendered as anti-aliased hairlines,
+        // which can touch one extra pixel around the line.
+        if stroke.width * max_scale <= 1.0 {
+            let hairline_outset = if stroke.line_cap == LineCap::Butt {
+                1.0
+            } else {
+                1.5
+            };
+            outset = outset.max(hairline_outset);
+        }
+
+        let bounds = if ts.is_identity() {
+            self.bounds
+        } else {
+            let mut points = self.points.clone();
+            ts.map_points(&mut points);
+            Rect::from_points(&points)?
+        };
+
+        bounds.outset(outset, outset)
+    }
+}
+
+// Returns the largest singular value of the transform, i.e. the maximum length of a unit vector
+// after mapping. Unlike `get_scale`, it handles skew.
+fn max_scale(ts: &Transform) -> f32 {
+    let (a, b, c, d) = (ts.sx as f64, ts.ky as f64, ts.kx as f64, ts.sy as f64);
+    let sum = a * a + b * b + c * c + d * d;
+    let det = a * d - b * c;
+    let disc = (sum * sum - 4.0 * det * det).max(0.0);
+    ((sum + disc.sqrt()) * 0.5).sqrt() as f32
 }
 
 /// A path stroker.
@@ -232,6 +364,8 @@ pub struct PathStroker {
     recursion_depth: i32, // track stack depth to abort if numerics run amok
     found_tangents: bool, // do less work until tangents meet (cubic)
     join_completed: bool, // previous join was not degenerate
+
+    precision: Option<f32>, // offset curves tolerance in path units, when set
 }
 
 impl Default for PathStroker {
@@ -275,6 +409,8 @@ impl PathStroker {
             recursion_depth: 0,
             found_tangents: false,
             join_completed: false,
+
+            precision: None,
         }
     }
 
@@ -308,6 +444,15 @@ impl PathStroker {
     /// [`compute_resolution_scale`](Self::compute_resolution_scale).
     pub fn stroke(&mut self, path: &Path, stroke: &Stroke, resolution_scale: f32) -> Option<Path> {
         let width = NonZeroPositiveF32::new(stroke.width)?;
+        self.precision = match stroke.precision {
+            Some(precision) => Some(NonZeroPositiveF32::new(precision)?.get() / resolution_scale),
+            None => None,
+        };
+
+        if stroke.alignment != StrokeAlignment::Center {
+            return self.stroke_aligned(path, width, stroke, resolution_scale);
+        }
+
         self.stroke_inner(
             path,
             width,
@@ -318,6 +463,73 @@ impl PathStroker {
         )
     }
 
+    // Closed contours are stroked with a double width and the outline is intersected
+    // with the filled area or subtracted from it. Open contours are added centered.
+    fn stroke_aligned(
+        &mut self,
+        path: &Path,
+        width: NonZeroPositiveF32,
+        stroke: &Stroke,
+        res_scale: f32,
+    ) -> Option<Path> {
+        let mut closed = PathBuilder::new();
+        let mut open = PathBuilder::new();
+        for (contour, is_closed) in split_contours(path) {
+            if is_closed {
+                closed.push_path_builder(&contour);
+            } else {
+                open.push_path_builder(&contour);
+            }
+        }
+
+        let open = match open.finish() {
+            Some(open) => self.stroke_inner(
+                &open,
+                width,
+                stroke.miter_limit,
+                stroke.line_cap,
+                stroke.line_join,
+                res_scale,
+            ),
+            None => None,
+        };
+
+        let closed = match closed.finish() {
+            Some(v) => v,
+            None => return open,
+        };
+
+        // The stroker and the flattening split the error evenly.
+        let (stroke_res_scale, tolerance) = match self.precision {
+            Some(precision) => {
+                self.precision = Some(precision.half());
+                (res_scale, precision.half())
+            }
+            None => (
+                res_scale * offset::STROKE_RESOLUTION_SCALE,
+                offset::FLATTEN_TOLERANCE / res_scale,
+            ),
+        };
+
+        let outline = self.stroke_inner(
+            &closed,
+            NonZeroPositiveF32::new(width.get() * 2.0)?,
+            stroke.miter_limit,
+            LineCap::Butt,
+            stroke.line_join,
+            stroke_res_scale,
+        )?;
+
+        offset::clip_stroke(
+            &closed,
+            stroke.fill_rule,
+            &outline,
+            open.as_ref(),
+            stroke.alignment == StrokeAlignment::Inside,
+            tolerance,
+        )
+    }
+
     fn stroke_inner(
         &mut self,
         path: &Path,
@@ -519,21 +731,27 @@ impl PathStroker {
             return;
         }
 
-        let mut quad_points = QuadConstruct::default();
-        self.init_quad(
-            StrokeType::Outer,
-            NormalizedF32::ZERO,
-            NormalizedF32::ONE,
-            &mut quad_points,
-        );
-        self.quad_stroke(&quad, &mut quad_points);
-        self.init_quad(
-            StrokeType::Inner,
-            NormalizedF32::ZERO,
-            NormalizedF32::ONE,
-            &mut quad_points,
-        );
-        self.quad_stroke(&quad, &mut quad_points);
+        if let Some(precision) = self.precision {
+            // A quad is exactly representable by a cubic.
+            let cubic = path_geometry::quad_to_cubic(&quad);
+            self.offset_cubic(&cubic, precision);
+        } else {
+            let mut quad_points = QuadConstruct::default();
+            self.init_quad(
+                StrokeType::Outer,
+                NormalizedF32::ZERO,
+                NormalizedF32::ONE,
+                &mut quad_points,
+            );
+            self.quad_stroke(&quad, &mut quad_points);
+            self.init_quad(
+                StrokeType::Inner,
+                NormalizedF32::ZERO,
+                NormalizedF32::ONE,
+                &mut quad_points,
+            );
+            self.quad_stroke(&quad, &mut quad_points);
+        }
 
         let ok = set_normal_unit_normal(
             quad[1],
@@ -598,22 +816,26 @@ impl PathStroker {
             return;
         }
 
-        let mut t_values = path_geometry::new_t_values();
-        let t_values = path_geometry::find_cubic_inflections(&cubic, &mut t_values);
-        let mut last_t = NormalizedF32::ZERO;
-        for index in 0..=t_values.len() {
-            let next_t = t_values
-                .get(index)
-                .cloned()
-                .map(|n| n.to_normalized())
-                .unwrap_or(NormalizedF32::ONE);
-
-            let mut quad_points = QuadConstruct::default();
-            self.init_quad(StrokeType::Outer, last_t, next_t, &mut quad_points);
-            self.cubic_stroke(&cubic, &mut quad_points);
-            self.init_quad(StrokeType::Inner, last_t, next_t, &mut quad_points);
-            self.cubic_stroke(&cubic, &mut quad_points);
-            last_t = next_t;
+        if let Some(precision) = self.precision {
+            self.offset_cubic(&cubic, precision);
+        } else {
+            let mut t_values = path_geometry::new_t_values();
+            let t_values = path_geometry::find_cubic_inflections(&cubic, &mut t_values);
+            let mut last_t = NormalizedF32::ZERO;
+            for index in 0..=t_values.len() {
+                let next_t = t_values
+                    .get(index)
+                    .cloned()
+                    .map(|n| n.to_normalized())
+                    .unwrap_or(NormalizedF32::ONE);
+
+                let mut quad_points = QuadConstruct::default();
+                self.init_quad(StrokeType::Outer, last_t, next_t, &mut quad_points);
+                self.cubic_stroke(&cubic, &mut quad_points);
+                self.init_quad(StrokeType::Inner, last_t, next_t, &mut quad_points);
+                self.cubic_stroke(&cubic, &mut quad_points);
+                last_t = next_t;
+            }
         }
 
         if let Some(cusp) = path_geometry::find_cubic_cusp(&cubic) {
@@ -628,6 +850,17 @@ impl PathStroker {
         self.post_join_to(pt3, normal_cd, unit_cd);
     }
 
+    // High-precision mode.
+    //
+    // Splits the cubic at curvature extrema, inflections and a cusp, so the curvature
+    // is monotonic on each piece, and fits offsets on both sides with cubics.
+    fn offset_cubic(&mut self, cubic: &[Point; 4], precision: f32) {
+        let (t_values, len) = offset_split_points(cubic);
+        let t_values = &t_values[..len];
+        offset_cubic_side(cubic, t_values, self.radius, precision, &mut self.outer);
+        offset_cubic_side(cubic, t_values, -self.radius, precision, &mut self.inner);
+    }
+
     fn cubic_stroke(&mut self, cubic: &[Point; 4], quad_points: &mut QuadConstruct) -> bool {
         if !self.found_tangents {
             let result_type = self.tangents_meet(cubic, quad_points);
@@ -1693,6 +1926,194 @@ fn miter_joiner_inner(
     );
 }
 
+fn split_contours(path: &Path) -> Vec<(PathBuilder, bool)> {
+    let mut contours = Vec::new();
+    let mut pb = PathBuilder::new();
+    let mut is_closed = false;
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => {
+                if !pb.is_empty() {
+                    contours.push((core::mem::take(&mut pb), is_closed));
+                }
+
+                pb.move_to(p.x, p.y);
+                is_closed = false;
+            }
+            PathSegment::LineTo(p) => pb.line_to(p.x, p.y),
+            PathSegment::QuadTo(p1, p2) => pb.quad_to_pt(p1, p2),
+            PathSegment::CubicTo(p1, p2, p3) => pb.cubic_to_pt(p1, p2, p3),
+            PathSegment::Close => {
+                pb.close();
+                is_closed = true;
+            }
+        }
+    }
+
+    if !pb.is_empty() {
+        contours.push((pb, is_closed));
+    }
+
+    contours
+}
+
+// Returns sorted t values, including 0 and 1, at which a cubic has to be split
+// before fitting its offset.
+fn offset_split_points(cubic: &[Point; 4]) -> ([f32; 8], usize) {
+    let mut candidates = [0.0; 6];
+    let mut count = 0;
+
+    let mut max_curvature = [NormalizedF32::ZERO; 3];
+    for t in path_geometry::find_cubic_max_curvature(cubic, &mut max_curvature) {
+        candidates[count] = t.get();
+        count += 1;
+    }
+
+    let mut inflections = path_geometry::new_t_values();
+    for t in path_geometry::find_cubic_inflections(cubic, &mut inflections) {
+        candidates[count] = t.get();
+        count += 1;
+    }
+
+    if let Some(t) = path_geometry::find_cubic_cusp(cubic) {
+        candidates[count] = t.get();
+        count += 1;
+    }
+
+    let candidates = &mut candidates[..count];
+    candidates.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+
+    let mut t_values = [0.0; 8];
+    let mut len = 1;
+    for &t in candidates.iter() {
+        if t - t_values[len - 1] > OFFSET_MIN_T_STEP && 1.0 - t > OFFSET_MIN_T_STEP {
+            t_values[len] = t;
+            len += 1;
+        }
+    }
+
+    t_values[len] = 1.0;
+    (t_values, len + 1)
+}
+
+fn offset_cubic_side(
+    cubic: &[Point; 4],
+    t_values: &[f32],
+    offset: f32,
+    precision: f32,
+    builder: &mut PathBuilder,
+) {
+    for t in t_values.windows(2) {
+        // The offset is discontinuous at a cusp. The gap is covered by the cusp circle.
+        let (start, _) = cubic_offset_at(cubic, t[0], t[1], offset);
+        let connected = match builder.last_point() {
+            Some(pt) => pt.almost_equal(start),
+            None => false,
+        };
+        if !connected {
+            builder.line_to(start.x, start.y);
+        }
+
+        offset_cubic_to(cubic, t[0], t[1], offset, precision, 0, builder);
+    }
+}
+
+// Fits an offset of the `t0..t1` cubic piece with a cubic matching the exact offset
+// positions and derivatives at the ends, subdividing until it's within `precision`.
+fn offset_cubic_to(
+    cubic: &[Point; 4],
+    t0: f32,
+    t1: f32,
+    offset: f32,
+    precision: f32,
+    depth: u8,
+    builder: &mut PathBuilder,
+) {
+    let (start, start_derivative) = cubic_offset_at(cubic, t0, t1, offset);
+    let (end, end_derivative) = cubic_offset_at(cubic, t1, t0, offset);
+    let dt = (t1 - t0) / 3.0;
+    let fitted = [
+        start,
+        start + start_derivative.scaled(dt),
+        end - end_derivative.scaled(dt),
+        end,
+    ];
+
+    // Parametric distance is never smaller than the geometric one.
+    let fits = (1..OFFSET_SAMPLES).all(|i| {
+        let s = i as f32 / OFFSET_SAMPLES as f32;
+        let (exact, _) = cubic_offset_at(cubic, t0 + (t1 - t0) * s, t1, offset);
+        let pt = path_geometry::eval_cubic_pos_at(&fitted, NormalizedF32::new_clamped(s));
+        pt.distance(exact) <= precision
+    });
+
+    if fits {
+        builder.cubic_to(
+            fitted[1].x,
+            fitted[1].y,
+            fitted[2].x,
+            fitted[2].y,
+            end.x,
+            end.y,
+        );
+    } else if depth == OFFSET_MAX_DEPTH || !fitted.iter().all(|pt| pt.is_finite()) {
+        // Numerics ran amok near a cusp, which is covered by the cusp circle anyway.
+        builder.line_to(end.x, end.y);
+    } else {
+        let mid = (t0 + t1).half();
+        offset_cubic_to(cubic, t0, mid, offset, precision, depth + 1, builder);
+        offset_cubic_to(cubic, mid, t1, offset, precision, depth + 1, builder);
+    }
+}
+
+// Returns a point offset from the cubic at `t` along the normal and its derivative.
+//
+// When the cubic derivative vanishes at `t`, like at a cusp or at a coincident
+// control point, the normal is taken slightly towards `toward`.
+fn cubic_offset_at(cubic: &[Point; 4], t: f32, toward: f32, offset: f32) -> (Point, Point) {
+    let pos = path_geometry::eval_cubic_pos_at(cubic, NormalizedF32::new_clamped(t));
+
+    let mut eval_t = t;
+    let (mut d1, mut d2) = cubic_derivatives(cubic, eval_t);
+    if degenerate_vector(d1) {
+        eval_t = t + (toward - t) * OFFSET_MIN_T_STEP;
+        let (nd1, nd2) = cubic_derivatives(cubic, eval_t);
+        d1 = nd1;
+        d2 = nd2;
+    }
+
+    let speed = d1.length();
+    let mut unit = d1;
+    if !unit.normalize() {
+        unit = cubic[3] - cubic[0];
+        if !unit.normalize() {
+            return (pos, d1);
+        }
+    }
+
+    let mut normal = unit;
+    normal.rotate_ccw();
+
+    // The normal derivative is the tangent derivative rotated the same way.
+    let mut normal_derivative = (d2 - unit.scaled(unit.dot(d2))).scaled(speed.invert());
+    normal_derivative.rotate_ccw();
+
+    (
+        pos + normal.scaled(offset),
+        d1 + normal_derivative.scaled(offset),
+    )
+}
+
+fn cubic_derivatives(cubic: &[Point; 4], t: f32) -> (Point, Point) {
+    let a = cubic[1] - cubic[0];
+    let b = cubic[2] - cubic[1];
+    let c = cubic[3] - cubic[2];
+    let mt = 1.0 - t;
+    let d1 = (a.scaled(mt * mt) + b.scaled(2.0 * t * mt) + c.scaled(t * t)).scaled(3.0);
+    let d2 = ((b - a).scaled(mt) + (c - b).scaled(t)).scaled(6.0);
+    (d1, d2)
+}
+
 fn set_normal_unit_normal(
     before: Point,
     after: Point,
@@ -2202,4 +2623,608 @@ mod tests {
 
         assert!(PathStroker::new().stroke(&path, &stroke, 1.0).is_some());
     }
+
+    fn rect_contains(outer: Rect, inner: Rect) -> bool {
+        outer.left() <= inner.left()
+            && outer.top() <= inner.top()
+            && outer.right() >= inner.right()
+            && outer.bottom() >= inner.bottom()
+    }
+
+    fn check_stroke_bounds(path: &Path, stroke: &Stroke, ts: Transform) -> Rect {
+        let res_scale = PathStroker::compute_resolution_scale(&ts);
+        let stroked = path
+            .stroke(stroke, res_scale)
+            .unwrap()
+            .transform(ts)
+            .unwrap();
+        let bounds = path.compute_stroke_bounds(stroke, ts).unwrap();
+        assert!(rect_contains(bounds, stroked.compute_tight_bounds().unwrap()));
+        bounds
+    }
+
+    fn zigzag() -> Path {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(50.0, 20.0);
+        pb.line_to(10.0, 30.0);
+        pb.quad_to(60.0, 80.0, 90.0, 20.0);
+        pb.cubic_to(100.0, 0.0, 40.0, -10.0, 70.0, 90.0);
+        pb.finish().unwrap()
+    }
+
+    #[test]
+    fn stroke_bounds_contain_stroke() {
+        let path = zigzag();
+        let transforms = [
+            Transform::identity(),
+            Transform::from_scale(2.0, 0.5),
+            Transform::from_rotate(30.0),
+            Transform::from_row(1.0, 0.3, 0.8, 1.0, 5.0, -3.0),
+        ];
+        let caps = [LineCap::Butt, LineCap::Round, LineCap::Square];
+        let joins = [
+            LineJoin::Miter,
+            LineJoin::MiterClip,
+            LineJoin::Round,
+            LineJoin::Bevel,
+        ];
+
+        for ts in transforms {
+            for line_cap in caps {
+                for line_join in joins {
+                    let stroke = Stroke {
+                        width: 8.0,
+                        miter_limit: 10.0,
+                        line_cap,
+                        line_join,
+                        ..Stroke::default()
+                    };
+                    check_stroke_bounds(&path, &stroke, ts);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stroke_bounds_aligned() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(50.0, 20.0);
+        pb.line_to(10.0, 30.0);
+        pb.quad_to(60.0, 80.0, 90.0, 20.0);
+        pb.close();
+        let path = pb.finish().unwrap();
+
+        for alignment in [StrokeAlignment::Inside, StrokeAlignment::Outside] {
+            let stroke = Stroke {
+                width: 6.0,
+                line_join: LineJoin::Round,
+                alignment,
+                ..Stroke::default()
+            };
+            check_stroke_bounds(&path, &stroke, Transform::from_rotate(15.0));
+        }
+    }
+
+    #[test]
+    fn stroke_bounds_simple_line() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(50.0, 10.0);
+        let path = pb.finish().unwrap();
+
+        let stroke = Stroke {
+            width: 4.0,
+            line_join: LineJoin::Round,
+            ..Stroke::default()
+        };
+        let bounds = check_stroke_bounds(&path, &stroke, Transform::identity());
+        assert_eq!(bounds, Rect::from_ltrb(7.75, 7.75, 52.25, 12.25).unwrap());
+
+        let bounds = check_stroke_bounds(&path, &stroke, Transform::from_scale(2.0, 2.0));
+        assert_eq!(
+            bounds,
+            Rect::from_ltrb(15.75, 15.75, 104.25, 24.25).unwrap()
+        );
+    }
+
+    #[test]
+    fn stroke_bounds_hairline() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(50.0, 10.0);
+        let path = pb.finish().unwrap();
+
+        let stroke = Stroke {
+            width: 0.0,
+            ..Stroke::default()
+        };
+        let bounds = path
+            .compute_stroke_bounds(&stroke, Transform::identity())
+            .unwrap();
+        assert_eq!(bounds, Rect::from_ltrb(9.0, 9.0, 51.0, 11.0).unwrap());
+    }
+
+    #[test]
+    fn stroke_bounds_invalid() {
+        let path = zigzag();
+        let mut stroke = Stroke {
+            width: -1.0,
+            ..Stroke::default()
+        };
+        assert!(path
+            .compute_stroke_bounds(&stroke, Transform::identity())
+            .is_none());
+
+        stroke.width = f32::NAN;
+        assert!(path
+            .compute_stroke_bounds(&stroke, Transform::identity())
+            .is_none());
+    }
+
+    // A nonzero winding test for paths with lines only.
+    fn path_contains(path: &Path, x: f32, y: f32) -> bool {
+        let mut winding = 0;
+        let mut iter = path.segments();
+        iter.set_auto_close(true);
+        let mut start = Point::zero();
+        let mut last = Point::zero();
+        for segment in iter {
+            let p = match segment {
+                PathSegment::MoveTo(p) => {
+                    start = p;
+                    last = p;
+                    continue;
+                }
+                PathSegment::LineTo(p) => p,
+                PathSegment::Close => start,
+                _ => unreachable!(),
+            };
+
+            if (last.y <= y) != (p.y <= y) {
+                let t = (y - last.y) / (p.y - last.y);
+                if last.x + t * (p.x - last.x) > x {
+                    winding += if p.y > last.y { 1 } else { -1 };
+                }
+            }
+
+            last = p;
+        }
+
+        winding != 0
+    }
+
+    fn push_square(pb: &mut PathBuilder, left: f32, right: f32, clockwise: bool) {
+        pb.move_to(left, left);
+        if clockwise {
+            pb.line_to(right, left);
+            pb.line_to(right, right);
+            pb.line_to(left, right);
+        } else {
+            pb.line_to(left, right);
+            pb.line_to(right, right);
+            pb.line_to(right, left);
+        }
+        pb.close();
+    }
+
+    fn stroke_aligned(path: &Path, alignment: StrokeAlignment) -> Path {
+        let stroke = Stroke {
+            width: 4.0,
+            alignment,
+            ..Stroke::default()
+        };
+        path.stroke(&stroke, 1.0).unwrap()
+    }
+
+    #[test]
+    fn inside_and_outside() {
+        for clockwise in [true, false] {
+            let mut pb = PathBuilder::new();
+            push_square(&mut pb, 10.0, 50.0, clockwise);
+            let path = pb.finish().unwrap();
+
+            let inside = stroke_aligned(&path, StrokeAlignment::Inside);
+            assert!(path_contains(&inside, 12.0, 30.0));
+            assert!(path_contains(&inside, 48.0, 30.0));
+            assert!(!path_contains(&inside, 8.0, 30.0));
+            assert!(!path_contains(&inside, 15.0, 30.0));
+            assert!(!path_contains(&inside, 30.0, 30.0));
+            assert_eq!(
+                inside.compute_tight_bounds().unwrap(),
+                Rect::from_ltrb(10.0, 10.0, 50.0, 50.0).unwrap()
+            );
+
+            let outside = stroke_aligned(&path, StrokeAlignment::Outside);
+            assert!(path_contains(&outside, 8.0, 30.0));
+            assert!(path_contains(&outside, 52.0, 30.0));
+            assert!(!path_contains(&outside, 5.0, 30.0));
+            assert!(!path_contains(&outside, 12.0, 30.0));
+            assert!(!path_contains(&outside, 30.0, 30.0));
+            assert_eq!(
+                outside.compute_tight_bounds().unwrap(),
+                Rect::from_ltrb(6.0, 6.0, 54.0, 54.0).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn inside_with_hole() {
+        let mut pb = PathBuilder::new();
+        push_square(&mut pb, 10.0, 50.0, true);
+        push_square(&mut pb, 20.0, 40.0, false);
+        let path = pb.finish().unwrap();
+
+        let inside = stroke_aligned(&path, StrokeAlignment::Inside);
+        assert!(path_contains(&inside, 12.0, 30.0));
+        assert!(path_contains(&inside, 18.0, 30.0));
+        assert!(!path_contains(&inside, 15.0, 30.0));
+        assert!(!path_contains(&inside, 22.0, 30.0));
+        assert!(!path_contains(&inside, 30.0, 30.0));
+
+        let outside = stroke_aligned(&path, StrokeAlignment::Outside);
+        assert!(path_contains(&outside, 8.0, 30.0));
+        assert!(path_contains(&outside, 22.0, 30.0));
+        assert!(!path_contains(&outside, 12.0, 30.0));
+        assert!(!path_contains(&outside, 18.0, 30.0));
+        assert!(!path_contains(&outside, 30.0, 30.0));
+    }
+
+    #[test]
+    fn inside_thin_rect() {
+        let path = PathBuilder::from_rect(Rect::from_ltrb(10.0, 10.0, 110.0, 14.0).unwrap());
+
+        // The stroke is wider than the rect, so it covers the whole rect and nothing else.
+        let stroke = Stroke {
+            width: 10.0,
+            alignment: StrokeAlignment::Inside,
+            ..Stroke::default()
+        };
+        let inside = path.stroke(&stroke, 1.0).unwrap();
+        assert!(path_contains(&inside, 60.0, 12.0));
+        assert!(!path_contains(&inside, 60.0, 9.0));
+        assert!(!path_contains(&inside, 60.0, 15.0));
+        assert_eq!(
+            inside.compute_tight_bounds().unwrap(),
+            Rect::from_ltrb(10.0, 10.0, 110.0, 14.0).unwrap()
+        );
+    }
+
+    #[test]
+    fn opposite_winding_contours() {
+        let mut pb = PathBuilder::new();
+        push_square(&mut pb, 10.0, 50.0, true);
+        push_square(&mut pb, 60.0, 100.0, false);
+        let path = pb.finish().unwrap();
+
+        // The squares are on a diagonal, so check each one at its own center line.
+        let inside = stroke_aligned(&path, StrokeAlignment::Inside);
+        for (x, y) in [(12.0, 30.0), (48.0, 30.0), (62.0, 80.0), (98.0, 80.0)] {
+            assert!(path_contains(&inside, x, y));
+        }
+        for (x, y) in [(8.0, 30.0), (30.0, 30.0), (58.0, 80.0), (80.0, 80.0), (102.0, 80.0)] {
+            assert!(!path_contains(&inside, x, y));
+        }
+
+        let outside = stroke_aligned(&path, StrokeAlignment::Outside);
+        for (x, y) in [(8.0, 30.0), (52.0, 30.0), (58.0, 80.0), (102.0, 80.0)] {
+            assert!(path_contains(&outside, x, y));
+        }
+        for (x, y) in [(5.0, 30.0), (12.0, 30.0), (30.0, 30.0), (48.0, 30.0)] {
+            assert!(!path_contains(&outside, x, y));
+        }
+        for (x, y) in [(55.0, 80.0), (62.0, 80.0), (80.0, 80.0), (98.0, 80.0)] {
+            assert!(!path_contains(&outside, x, y));
+        }
+    }
+
+    #[test]
+    fn even_odd_hole() {
+        // Both contours have the same direction, so the inner one is a hole only for even-odd.
+        let mut pb = PathBuilder::new();
+        push_square(&mut pb, 10.0, 50.0, true);
+        push_square(&mut pb, 20.0, 40.0, true);
+        let path = pb.finish().unwrap();
+
+        let stroke = Stroke {
+            width: 4.0,
+            alignment: StrokeAlignment::Inside,
+            fill_rule: FillRule::EvenOdd,
+            ..Stroke::default()
+        };
+        let inside = path.stroke(&stroke, 1.0).unwrap();
+        assert!(path_contains(&inside, 12.0, 30.0));
+        assert!(path_contains(&inside, 18.0, 30.0));
+        assert!(!path_contains(&inside, 15.0, 30.0));
+        assert!(!path_contains(&inside, 22.0, 30.0));
+        assert!(!path_contains(&inside, 30.0, 30.0));
+
+        let outside = path
+            .stroke(
+                &Stroke {
+                    alignment: StrokeAlignment::Outside,
+                    ..stroke.clone()
+                },
+                1.0,
+            )
+            .unwrap();
+        assert!(path_contains(&outside, 8.0, 30.0));
+        assert!(path_contains(&outside, 22.0, 30.0));
+        assert!(!path_contains(&outside, 12.0, 30.0));
+        assert!(!path_contains(&outside, 18.0, 30.0));
+        assert!(!path_contains(&outside, 30.0, 30.0));
+
+        // With the nonzero rule there is no hole, so the inner contour is stroked on both sides.
+        let inside = path
+            .stroke(
+                &Stroke {
+                    fill_rule: FillRule::Winding,
+                    ..stroke
+                },
+                1.0,
+            )
+            .unwrap();
+        assert!(path_contains(&inside, 12.0, 30.0));
+        assert!(path_contains(&inside, 18.0, 30.0));
+        assert!(path_contains(&inside, 22.0, 30.0));
+        assert!(!path_contains(&inside, 15.0, 30.0));
+        assert!(!path_contains(&inside, 30.0, 30.0));
+    }
+
+    #[test]
+    fn open_contour_is_centered() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(50.0, 10.0);
+        let path = pb.finish().unwrap();
+
+        let centered = stroke_aligned(&path, StrokeAlignment::Center);
+        let inside = stroke_aligned(&path, StrokeAlignment::Inside);
+        assert_eq!(centered, inside);
+    }
+
+    // Flattens the outline finely enough for the flattening error to be negligible.
+    fn flatten(path: &Path) -> Vec<(Point, Point)> {
+        const STEPS: usize = 256;
+
+        let mut lines = Vec::new();
+        let mut iter = path.segments();
+        iter.set_auto_close(true);
+        let mut start = Point::zero();
+        let mut last = Point::zero();
+        for segment in iter {
+            let cubic = match segment {
+                PathSegment::MoveTo(p) => {
+                    start = p;
+                    last = p;
+                    continue;
+                }
+                PathSegment::LineTo(p) => [last, last, p, p],
+                PathSegment::QuadTo(p1, p2) => path_geometry::quad_to_cubic(&[last, p1, p2]),
+                PathSegment::CubicTo(p1, p2, p3) => [last, p1, p2, p3],
+                PathSegment::Close => [last, last, start, start],
+            };
+
+            let mut prev = cubic[0];
+            for i in 1..=STEPS {
+                let t = NormalizedF32::new_clamped(i as f32 / STEPS as f32);
+                let p = path_geometry::eval_cubic_pos_at(&cubic, t);
+                lines.push((prev, p));
+                prev = p;
+            }
+
+            last = cubic[3];
+        }
+
+        lines
+    }
+
+    fn distance_to_line(p: Point, a: Point, b: Point) -> f32 {
+        let ab = b - a;
+        let len = ab.length_sqd();
+        let t = if len > 0.0 {
+            ((p - a).dot(ab) / len).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        p.distance(a + ab.scaled(t))
+    }
+
+    // Returns the points of the analytic offsets of the cubic on both sides at `t`.
+    fn offset_at(cubic: &[Point; 4], half_width: f32, t: f32) -> [Point; 2] {
+        let mt = 1.0 - t;
+        let pos = cubic[0].scaled(mt * mt * mt)
+            + cubic[1].scaled(3.0 * mt * mt * t)
+            + cubic[2].scaled(3.0 * mt * t * t)
+            + cubic[3].scaled(t * t * t);
+        let mut tangent = (cubic[1] - cubic[0]).scaled(mt * mt)
+            + (cubic[2] - cubic[1]).scaled(2.0 * mt * t)
+            + (cubic[3] - cubic[2]).scaled(t * t);
+        assert!(tangent.normalize());
+        let normal = Point::from_xy(tangent.y, -tangent.x).scaled(half_width);
+        [pos + normal, pos - normal]
+    }
+
+    // Returns the distance from the point to the analytic offset of the cubic on one side.
+    fn distance_to_offset(p: Point, cubic: &[Point; 4], half_width: f32, side: usize) -> f32 {
+        const STEPS: usize = 200;
+
+        let distance = |t: f32| p.distance(offset_at(cubic, half_width, t)[side]);
+        let samples: Vec<f32> = (0..=STEPS)
+            .map(|i| distance(i as f32 / STEPS as f32))
+            .collect();
+
+        // Refine every local minimum of the samples using a golden-section search,
+        // since the offset can fold back and pass close to the point more than once.
+        let ratio = (5.0f32.sqrt() - 1.0) / 2.0;
+        let mut min_distance = f32::MAX;
+        for i in 0..=STEPS {
+            let prev = samples[i.saturating_sub(1)];
+            let next = samples[(i + 1).min(STEPS)];
+            if samples[i] > prev || samples[i] > next {
+                continue;
+            }
+
+            let mut lo = i.saturating_sub(1) as f32 / STEPS as f32;
+            let mut hi = (i + 1).min(STEPS) as f32 / STEPS as f32;
+            for _ in 0..40 {
+                let t1 = hi - (hi - lo) * ratio;
+                let t2 = lo + (hi - lo) * ratio;
+                if distance(t1) < distance(t2) {
+                    hi = t2;
+                } else {
+                    lo = t1;
+                }
+            }
+
+            min_distance = min_distance.min(distance((lo + hi) / 2.0));
+        }
+
+        min_distance
+    }
+
+    // Returns the largest distance between the analytic offsets of the cubic on both sides
+    // and the stroke outline, measured in both directions.
+    fn max_offset_error(cubic: &[Point; 4], stroke: &Stroke) -> f32 {
+        let mut pb = PathBuilder::new();
+        pb.move_to(cubic[0].x, cubic[0].y);
+        pb.cubic_to(
+            cubic[1].x, cubic[1].y, cubic[2].x, cubic[2].y, cubic[3].x, cubic[3].y,
+        );
+        let outline = pb.finish().unwrap().stroke(stroke, 1.0).unwrap();
+        let lines = flatten(&outline);
+        let half_width = stroke.width.half();
+
+        let distance_to_lines = |p: Point, lines: &[(Point, Point)]| {
+            lines
+                .iter()
+                .map(|(a, b)| distance_to_line(p, *a, *b))
+                .fold(f32::MAX, f32::min)
+        };
+
+        // From the exact offsets to the outline.
+        let mut max_error = 0.0f32;
+        for i in 1..200 {
+            for p in offset_at(cubic, half_width, i as f32 / 200.0).iter() {
+                max_error = max_error.max(distance_to_lines(*p, &lines));
+            }
+        }
+
+        // From the outline to the exact offsets, which catches an outline bulging away from them.
+        let start = offset_at(cubic, half_width, 0.0);
+        let end = offset_at(cubic, half_width, 1.0);
+        let caps = [(start[0], start[1]), (end[0], end[1])];
+        for (i, (p, _)) in lines.iter().enumerate() {
+            // Butt caps are not part of the offsets. Checking every 4th point is enough.
+            if i % 4 != 0 || distance_to_lines(*p, &caps) < 0.001 {
+                continue;
+            }
+
+            let error = distance_to_offset(*p, cubic, half_width, 0)
+                .min(distance_to_offset(*p, cubic, half_width, 1));
+            max_error = max_error.max(error);
+        }
+
+        max_error
+    }
+
+    fn test_cubics() -> [[Point; 4]; 3] {
+        [
+            [
+                Point::from_xy(10.0, 10.0),
+                Point::from_xy(10.0, 120.0),
+                Point::from_xy(200.0, -60.0),
+                Point::from_xy(200.0, 60.0),
+            ],
+            [
+                Point::from_xy(0.0, 0.0),
+                Point::from_xy(100.0, 0.0),
+                Point::from_xy(100.0, 100.0),
+                Point::from_xy(0.0, 100.0),
+            ],
+            // A tight turn with a high curvature in the middle.
+            [
+                Point::from_xy(0.0, 0.0),
+                Point::from_xy(150.0, 20.0),
+                Point::from_xy(150.0, 30.0),
+                Point::from_xy(0.0, 50.0),
+            ],
+        ]
+    }
+
+    #[test]
+    fn matches_analytic_offset() {
+        for precision in [0.1, 0.01].iter() {
+            for cubic in test_cubics().iter() {
+                let stroke = Stroke {
+                    width: 30.0,
+                    precision: Some(*precision),
+                    ..Stroke::default()
+                };
+
+                // Allow for the f32 rounding of the sampled offsets.
+                let error = max_offset_error(cubic, &stroke);
+                assert!(error <= precision + 0.001, "{} > {}", error, precision);
+            }
+        }
+    }
+
+    #[test]
+    fn more_precise_than_default() {
+        let cubic = &test_cubics()[2];
+        let precise = Stroke {
+            width: 30.0,
+            precision: Some(0.01),
+            ..Stroke::default()
+        };
+        let default = Stroke {
+            width: 30.0,
+            ..Stroke::default()
+        };
+
+        assert!(max_offset_error(cubic, &precise) < max_offset_error(cubic, &default));
+    }
+
+    #[test]
+    fn quad_and_cusp() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.quad_to(100.0, 200.0, 190.0, 10.0);
+        pb.move_to(0.0, 0.0);
+        pb.cubic_to(100.0, 100.0, 0.0, 100.0, 100.0, 0.0);
+        let path = pb.finish().unwrap();
+
+        let stroke = Stroke {
+            width: 20.0,
+            precision: Some(0.05),
+            ..Stroke::default()
+        };
+        let outline = path.stroke(&stroke, 1.0).unwrap();
+        assert!(outline.points().iter().all(|p| p.is_finite()));
+
+        let bounds = path
+            .compute_stroke_bounds(&stroke, Transform::identity())
+            .unwrap();
+        let tight = outline.compute_tight_bounds().unwrap();
+        assert!(bounds.left() <= tight.left() && bounds.top() <= tight.top());
+        assert!(bounds.right() >= tight.right() && bounds.bottom() >= tight.bottom());
+    }
+
+    #[test]
+    fn invalid_precision() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.quad_to(100.0, 200.0, 190.0, 10.0);
+        let path = pb.finish().unwrap();
+
+        for precision in [0.0, -1.0, f32::NAN, f32::INFINITY].iter() {
+            let stroke = Stroke {
+                precision: Some(*precision),
+                ..Stroke::default()
+            };
+            assert!(path.stroke(&stroke, 1.0).is_none());
+        }
+    }
 }
@@ -0,0 +1,435 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use arrayref::array_ref;
+
+use crate::{f32x2, Path, Point};
+
+use crate::dash::{ContourMeasure, ContourMeasureIter};
+use crate::floating_point::NormalizedF32Exclusive;
+use crate::path::PathSegment;
+use crate::path_builder::PathBuilder;
+use crate::path_geometry::{self, CubicCoeff};
+use crate::scalar::Scalar;
+
+// Each level halves a segment, so this allows up to 65536 cubics per source segment.
+// Only reachable for mappings that are not smooth, like bending along a polyline corner.
+const MAX_SUBDIVISION_DEPTH: u8 = 16;
+
+impl Path {
+    /// Returns a path with an arbitrary mapping applied to it.
+    ///
+    /// Unlike [`Path::transform`], the mapping doesn't have to be affine,
+    /// so it can be used for projections, envelope or perspective-like distortions.
+    ///
+    /// Segments are subdivided until the mapped curve is within `tolerance`
+    /// from the exact mapping. All segments of the resulting path are cubics,
+    /// including the implicit closing lines.
+    ///
+    /// `f` must be continuous and is expected to be smooth. It will be called for points
+    /// outside of the path bounds as well, up to `tolerance` away.
+    ///
+    /// Returns `None` when `tolerance` is not positive, when the mapping produces
+    /// non-finite points or when the final path has an invalid bounding box.
+    pub fn map_points_nonlinear<F: Fn(Point) -> Point>(
+        &self,
+        f: F,
+        tolerance: f32,
+    ) -> Option<Path> {
+        map_points_nonlinear_impl(self, f, tolerance)
+    }
+
+    /// Bends the current path along another path.
+    ///
+    /// The x coordinate of each point is treated as a distance along the first contour
+    /// of `target` and the y coordinate as an offset along the contour normal.
+    /// The normal points to the right of the contour direction, therefore bending along
+    /// a horizontal line that starts at the origin will not change the path.
+    ///
+    /// Points before the start or past the end of an open contour are extended along
+    /// the end tangents, while a closed contour is wrapped around.
+    ///
+    /// Curves are subdivided until the result is within a quarter of a pixel from
+    /// the exact mapping. All segments of the resulting path are cubics.
+    ///
+    /// `resolution_scale` can be obtained via
+    /// [`compute_resolution_scale`](crate::PathStroker::compute_resolution_scale).
+    ///
+    /// Returns `None` when `target` has a zero length
+    /// or when the final path has an invalid bounding box.
+    pub fn warp_along(&self, target: &Path, resolution_scale: f32) -> Option<Path> {
+        let contour = ContourMeasureIter::new(target, resolution_scale).next()?;
+        if !(contour.length > 0.0) {
+            return None;
+        }
+
+        let tolerance = 0.25 * resolution_scale.invert();
+        map_points_nonlinear_impl(self, |p| warp_point(&contour, p), tolerance)
+    }
+}
+
+fn warp_point(contour: &ContourMeasure, p: Point) -> Point {
+    let mut distance = p.x;
+    if contour.is_closed {
+        distance %= contour.length;
+        if distance < 0.0 {
+            distance += contour.length;
+        }
+    }
+
+    let (mut pos, tangent) = match contour.pos_tan(distance) {
+        Some(v) => v,
+        None => return Point::from_xy(f32::NAN, f32::NAN),
+    };
+
+    // Extrapolate along the end tangents.
+    if distance < 0.0 {
+        pos += tangent.scaled(distance);
+    } else if distance > contour.length {
+        pos += tangent.scaled(distance - contour.length);
+    }
+
+    let normal = Point::from_xy(-tangent.y, tangent.x);
+    pos + normal.scaled(p.y)
+}
+
+fn map_points_nonlinear_impl<F: Fn(Point) -> Point>(
+    src: &Path,
+    f: F,
+    tolerance: f32,
+) -> Option<Path> {
+    if !(tolerance > 0.0 && tolerance.is_finite()) {
+        return None;
+    }
+
+    let mut pb = PathBuilder::with_capacity(src.len(), src.points.len() * 3);
+    let mut last_move_to = Point::zero();
+    let mut last_point = Point::zero();
+    for segment in src.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => {
+                let mp = f(p);
+                if !mp.is_finite() {
+                    return None;
+                }
+
+                pb.move_to(mp.x, mp.y);
+                last_move_to = p;
+                last_point = p;
+            }
+            PathSegment::LineTo(p) => {
+                map_cubic(&f, &line_to_cubic(last_point, p), tolerance, 0, &mut pb)?;
+                last_point = p;
+            }
+            PathSegment::QuadTo(p1, p2) => {
+                map_cubic(
+                    &f,
+                    &path_geometry::quad_to_cubic(&[last_point, p1, p2]),
+                    tolerance,
+                    0,
+                    &mut pb,
+                )?;
+                last_point = p2;
+            }
+            PathSegment::CubicTo(p1, p2, p3) => {
+                map_cubic(&f, &[last_point, p1, p2, p3], tolerance, 0, &mut pb)?;
+                last_point = p3;
+            }
+            PathSegment::Close => {
+                // The implicit closing line is not straight anymore after mapping.
+                if last_point != last_move_to {
+                    let line = line_to_cubic(last_point, last_move_to);
+                    map_cubic(&f, &line, tolerance, 0, &mut pb)?;
+                }
+
+                pb.close();
+                last_point = last_move_to;
+            }
+        }
+    }
+
+    pb.finish()
+}
+
+fn line_to_cubic(p0: Point, p1: Point) -> [Point; 4] {
+    let d = p1 - p0;
+    [p0, p0 + d.scaled(1.0 / 3.0), p0 + d.scaled(2.0 / 3.0), p1]
+}
+
+// Approximates the mapped cubic by a cubic with the same end points and end tangents.
+// The tangents are computed using the chain rule and a numeric Jacobian of the mapping,
+// which makes affine mappings exact.
+fn map_cubic<F: Fn(Point) -> Point>(
+    f: &F,
+    src: &[Point; 4],
+    tolerance: f32,
+    depth: u8,
+    pb: &mut PathBuilder,
+) -> Option<()> {
+    let p0 = f(src[0]);
+    let p3 = f(src[3]);
+
+    let d0 = map_vector(f, src[0], src[1] - src[0], tolerance);
+    let d3 = map_vector(f, src[3], src[3] - src[2], tolerance);
+    let mapped = [p0, p0 + d0, p3 - d3, p3];
+    if !mapped.iter().all(|p| p.is_finite()) {
+        return None;
+    }
+
+    if depth < MAX_SUBDIVISION_DEPTH && !is_within_tolerance(f, src, &mapped, tolerance) {
+        let mut tmp = [Point::zero(); 7];
+        path_geometry::chop_cubic_at2(src, NormalizedF32Exclusive::HALF, &mut tmp);
+        map_cubic(f, array_ref![tmp, 0, 4], tolerance, depth + 1, pb)?;
+        map_cubic(f, array_ref![tmp, 3, 4], tolerance, depth + 1, pb)?;
+        return Some(());
+    }
+
+    pb.cubic_to_pt(mapped[1], mapped[2], mapped[3]);
+    Some(())
+}
+
+// Maps vector `v` at point `p` using a central difference approximation
+// of the mapping's Jacobian.
+fn map_vector<F: Fn(Point) -> Point>(f: &F, p: Point, v: Point, h: f32) -> Point {
+    if v.is_zero() {
+        return Point::zero();
+    }
+
+    let dx = f(Point::from_xy(p.x + h, p.y)) - f(Point::from_xy(p.x - h, p.y));
+    let dy = f(Point::from_xy(p.x, p.y + h)) - f(Point::from_xy(p.x, p.y - h));
+
+    let inv_2h = (h + h).invert();
+    let mut r = dx.scaled(v.x * inv_2h) + dy.scaled(v.y * inv_2h);
+
+    // The mapping can be undefined near `p`.
+    // Fallback to a zero-length handle and let subdivision do the rest.
+    if !r.is_finite() {
+        r = Point::zero();
+    }
+
+    r
+}
+
+fn is_within_tolerance<F: Fn(Point) -> Point>(
+    f: &F,
+    src: &[Point; 4],
+    mapped: &[Point; 4],
+    tolerance: f32,
+) -> bool {
+    let src_coeff = CubicCoeff::from_points(src);
+    let mapped_coeff = CubicCoeff::from_points(mapped);
+    let tolerance_sqd = tolerance * tolerance;
+    [0.25, 0.5, 0.75].iter().all(|t| {
+        let t = f32x2::splat(*t);
+        let expected = f(Point::from_f32x2(src_coeff.eval(t)));
+        let actual = Point::from_f32x2(mapped_coeff.eval(t));
+        expected.distance_to_sqd(actual) <= tolerance_sqd
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn check_points(path: &Path, f: impl Fn(Point) -> bool) {
+        assert!(path.points().iter().all(|p| f(*p)), "{:?}", path);
+    }
+
+    #[test]
+    fn along_horizontal_line() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.line_to(200.0, 0.0);
+        let target = pb.finish().unwrap();
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, -10.0);
+        pb.quad_to(50.0, 30.0, 90.0, -10.0);
+        pb.close();
+        let path = pb.finish().unwrap();
+
+        let warped = path.warp_along(&target, 1.0).unwrap();
+        assert_eq!(warped.verbs().len(), 4);
+        let bounds = warped.compute_tight_bounds().unwrap();
+        assert!(bounds.left().is_nearly_equal(10.0));
+        assert!(bounds.top().is_nearly_equal(-10.0));
+        assert!(bounds.right().is_nearly_equal(90.0));
+        assert!(bounds.bottom().is_nearly_equal(10.0));
+    }
+
+    #[test]
+    fn along_circle() {
+        let target = PathBuilder::from_circle(100.0, 100.0, 50.0).unwrap();
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, -10.0);
+        pb.line_to(150.0, -10.0);
+        let path = pb.finish().unwrap();
+
+        let warped = path.warp_along(&target, 1.0).unwrap();
+        assert!(warped.len() > 2);
+
+        let center = Point::from_xy(100.0, 100.0);
+        check_points(&warped, |p| {
+            let d = p.distance(center);
+            // Control points are slightly outside the arc.
+            d > 59.5 && d < 63.0
+        });
+
+        // End points must be on the arc.
+        for segment in warped.segments() {
+            if let PathSegment::CubicTo(_, _, p) = segment {
+                assert!((p.distance(center) - 60.0).abs() < 0.25);
+            }
+        }
+    }
+
+    #[test]
+    fn wraps_closed_contour() {
+        let target = PathBuilder::from_rect(crate::Rect::from_xywh(0.0, 0.0, 10.0, 10.0).unwrap());
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(45.0, 0.0);
+        pb.line_to(55.0, 0.0);
+        let path = pb.finish().unwrap();
+
+        // 45..55 is 5..15 along the rect perimeter, i.e. around the top-right corner.
+        let warped = path.warp_along(&target, 1.0).unwrap();
+        assert_eq!(warped.points()[0], Point::from_xy(5.0, 0.0));
+        let last = *warped.points().last().unwrap();
+        assert!(last.distance(Point::from_xy(10.0, 5.0)) < 0.01);
+    }
+
+    #[test]
+    fn nonlinear_affine() {
+        let path = PathBuilder::from_circle(50.0, 50.0, 20.0).unwrap();
+
+        let ts = crate::Transform::from_row(1.5, 0.2, -0.3, 0.8, 10.0, 20.0);
+        let mapped = path
+            .map_points_nonlinear(
+                |mut p| {
+                    ts.map_point(&mut p);
+                    p
+                },
+                0.1,
+            )
+            .unwrap();
+        let transformed = path.transform(ts).unwrap();
+
+        // An affine mapping doesn't require any subdivision.
+        assert_eq!(mapped.len(), transformed.len());
+        let bounds1 = mapped.compute_tight_bounds().unwrap();
+        let bounds2 = transformed.compute_tight_bounds().unwrap();
+        assert!((bounds1.left() - bounds2.left()).abs() < 0.01);
+        assert!((bounds1.top() - bounds2.top()).abs() < 0.01);
+        assert!((bounds1.right() - bounds2.right()).abs() < 0.01);
+        assert!((bounds1.bottom() - bounds2.bottom()).abs() < 0.01);
+    }
+
+    #[test]
+    fn nonlinear_bilinear_quad() {
+        // Maps a unit square onto an arbitrary quadrilateral.
+        let quad = [
+            Point::from_xy(10.0, 10.0),
+            Point::from_xy(90.0, 20.0),
+            Point::from_xy(80.0, 90.0),
+            Point::from_xy(20.0, 70.0),
+        ];
+        let bilinear = |p: Point| {
+            let top = quad[0] + (quad[1] - quad[0]).scaled(p.x);
+            let bottom = quad[3] + (quad[2] - quad[3]).scaled(p.x);
+            top + (bottom - top).scaled(p.y)
+        };
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.line_to(1.0, 0.0);
+        pb.line_to(1.0, 1.0);
+        pb.line_to(0.0, 1.0);
+        pb.close();
+        let path = pb.finish().unwrap();
+
+        let mapped = path.map_points_nonlinear(bilinear, 0.01).unwrap();
+        assert_eq!(mapped.points()[0], quad[0]);
+        for corner in &quad {
+            assert!(mapped.points().iter().any(|p| p.distance(*corner) < 0.001));
+        }
+    }
+
+    #[test]
+    fn nonlinear_fisheye() {
+        let center = Point::from_xy(50.0, 50.0);
+        let fisheye = |p: Point| {
+            let v = p - center;
+            let r = v.length();
+            center + v.scaled((r / 50.0).sqrt() * 50.0 / r.max(0.001))
+        };
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 30.0);
+        pb.cubic_to(30.0, 10.0, 70.0, 10.0, 90.0, 30.0);
+        let path = pb.finish().unwrap();
+
+        let tolerance = 0.05;
+        let mapped = path.map_points_nonlinear(fisheye, tolerance).unwrap();
+        assert!(mapped.len() > 2);
+
+        // Compare against the exactly mapped curve.
+        let src = [
+            Point::from_xy(10.0, 30.0),
+            Point::from_xy(30.0, 10.0),
+            Point::from_xy(70.0, 10.0),
+            Point::from_xy(90.0, 30.0),
+        ];
+        let exact: Vec<Point> = (0..=2000)
+            .map(|i| {
+                let t = crate::NormalizedF32::new_clamped(i as f32 / 2000.0);
+                fisheye(path_geometry::eval_cubic_pos_at(&src, t))
+            })
+            .collect();
+
+        let mut last_point = Point::zero();
+        for segment in mapped.segments() {
+            match segment {
+                PathSegment::MoveTo(p) => last_point = p,
+                PathSegment::CubicTo(p1, p2, p3) => {
+                    let cubic = [last_point, p1, p2, p3];
+                    for i in 0..=10 {
+                        let t = crate::NormalizedF32::new_clamped(i as f32 / 10.0);
+                        let p = path_geometry::eval_cubic_pos_at(&cubic, t);
+                        let dist = exact.iter().map(|e| e.distance(p)).fold(f32::MAX, f32::min);
+                        assert!(dist < tolerance + 0.05);
+                    }
+                    last_point = p3;
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn nonlinear_invalid() {
+        let path = PathBuilder::from_circle(50.0, 50.0, 20.0).unwrap();
+        assert!(path.map_points_nonlinear(|p| p, 0.0).is_none());
+        assert!(path.map_points_nonlinear(|p| p, f32::NAN).is_none());
+        assert!(path
+            .map_points_nonlinear(|p| Point::from_xy(p.x / (p.y - 50.0), p.y), 0.1)
+            .is_none());
+    }
+
+    #[test]
+    fn zero_length_target() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10.0, 10.0);
+        pb.line_to(10.0, 10.0);
+        let target = pb.finish().unwrap();
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, 0.0);
+        pb.line_to(10.0, 0.0);
+        let path = pb.finish().unwrap();
+
+        assert!(path.warp_along(&target, 1.0).is_none());
+    }
+}
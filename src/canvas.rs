@@ -0,0 +1,462 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use tiny_skia_path::{IntRect, Path, PathBuilder, Rect, Stroke, Transform};
+
+use crate::{
+    BlendMode, FillRule, Mask, Paint, Picture, Pixmap, PixmapMut, PixmapPaint, PixmapRef, Shader,
+};
+
+#[derive(Copy, Clone, Debug)]
+struct State {
+    transform: Transform,
+    // An index in `Canvas::masks`.
+    clip: Option<usize>,
+    // The number of masks when the state was saved.
+    // Later masks are not referenced by this and previous states.
+    masks: usize,
+    // The number of active layers.
+    layers: usize,
+}
+
+struct Layer {
+    // `None` when the layer bounds are empty and everything is clipped out.
+    pixmap: Option<Pixmap>,
+    // Layer bounds in the canvas coordinates.
+    rect: IntRect,
+    paint: PixmapPaint,
+    // The clip and the user mask combined. Has the layer size.
+    mask: Option<Mask>,
+}
+
+/// A stateful drawing context on top of a [`PixmapMut`].
+///
+/// Unlike the low-level `PixmapMut` API, tracks the current transform and clip,
+/// which can be saved and restored, similar to Skia's `SkCanvas`
+/// or HTML Canvas `CanvasRenderingContext2D`.
+///
+/// Transformations are applied in the local coordinates, i.e. `canvas.translate()`
+/// followed by `canvas.scale()` scales the content first and then translates it.
+///
+/// Clipping is done using [`Mask`]s of the pixmap size, which are allocated
+/// and intersected by the canvas as needed.
+///
+/// Group opacity and isolated blending are supported via
+/// [`push_layer`](Self::push_layer).
+pub struct Canvas<'a> {
+    pixmap: PixmapMut<'a>,
+    state: State,
+    stack: Vec<State>,
+    masks: Vec<Mask>,
+    layers: Vec<Layer>,
+}
+
+impl<'a> Canvas<'a> {
+    /// Creates a new canvas with an identity transform and no clip.
+    pub fn new(pixmap: PixmapMut<'a>) -> Self {
+        Canvas {
+            pixmap,
+            state: State {
+                transform: Transform::identity(),
+                clip: None,
+                masks: 0,
+                layers: 0,
+            },
+            stack: Vec::new(),
+            masks: Vec::new(),
+            layers: Vec::new(),
+        }
+    }
+
+    /// Returns the underlying pixmap.
+    ///
+    /// Content drawn into layers that were not popped yet is not there.
+    pub fn pixmap(&mut self) -> &mut PixmapMut<'a> {
+        &mut self.pixmap
+    }
+
+    /// Returns canvas's width.
+    pub fn width(&self) -> u32 {
+        self.pixmap.width()
+    }
+
+    /// Returns canvas's height.
+    pub fn height(&self) -> u32 {
+        self.pixmap.height()
+    }
+
+    /// Saves the current transform and clip.
+    ///
+    /// Returns the number of saved states before this call,
+    /// which can be passed to [`restore_to_count`](Self::restore_to_count).
+    pub fn save(&mut self) -> usize {
+        self.state.masks = self.masks.len();
+        self.stack.push(self.state);
+        self.stack.len() - 1
+    }
+
+    /// Restores the last saved transform and clip.
+    ///
+    /// When the state was saved by [`push_layer`](Self::push_layer),
+    /// the layer is composited onto the canvas or the previous layer.
+    ///
+    /// Does nothing when there are no saved states.
+    pub fn restore(&mut self) {
+        if let Some(state) = self.stack.pop() {
+            // Masks created after the save are no longer referenced.
+            // Layer masks are in the layer coordinates, so the parent clip
+            // must be restored before compositing.
+            self.state = state;
+            self.masks.truncate(state.masks);
+
+            while self.layers.len() > state.layers {
+                self.composite_layer();
+            }
+        }
+    }
+
+    /// Saves the current state and redirects drawing into a new offscreen layer.
+    ///
+    /// `bounds` are in the current coordinates and limit the layer size.
+    /// Without `bounds` the layer covers the whole canvas (or the parent layer).
+    ///
+    /// When the layer is popped via [`restore`](Self::restore) or
+    /// [`pop_layer`](Self::pop_layer), it's composited back using `opacity`
+    /// and `blend_mode`, the same way as [`PixmapMut::draw_pixmap`] does.
+    /// The current clip and an optional `mask` are applied at this point.
+    /// The `mask` must have the canvas size and is in the canvas coordinates.
+    ///
+    /// The layer starts transparent and unclipped and keeps the current transform.
+    ///
+    /// Returns the number of saved states before this call,
+    /// which can be passed to [`restore_to_count`](Self::restore_to_count).
+    pub fn push_layer(
+        &mut self,
+        bounds: Option<Rect>,
+        opacity: f32,
+        blend_mode: BlendMode,
+        mask: Option<&Mask>,
+    ) -> usize {
+        let count = self.save();
+
+        let (target_rect, (tx, ty)) = (self.target_rect(), self.target_offset());
+        let rect = match bounds {
+            Some(bounds) => bounds
+                .transform(self.state.transform)
+                .and_then(|r| r.round_out())
+                .and_then(|r| r.intersect(&target_rect)),
+            None => Some(target_rect),
+        };
+
+        let paint = PixmapPaint {
+            opacity,
+            blend_mode,
+            ..PixmapPaint::default()
+        };
+
+        let mut layer = Layer {
+            pixmap: None,
+            rect: target_rect,
+            paint,
+            mask: None,
+        };
+
+        if let Some(rect) = rect {
+            let masks = &self.masks;
+            let clip = self.state.clip.map(|i| &masks[i]);
+            let clip = clip.and_then(|m| crop_mask(m, rect.translate(-tx, -ty)?));
+            let mask = mask.and_then(|m| {
+                if m.width() == self.width() && m.height() == self.height() {
+                    crop_mask(m, rect)
+                } else {
+                    log::warn!("Layer mask is expected to have the canvas size");
+                    None
+                }
+            });
+
+            layer.mask = match (clip, mask) {
+                (Some(mut clip), Some(mask)) => {
+                    for (a, b) in clip.data_mut().iter_mut().zip(mask.data()) {
+                        *a = crate::color::premultiply_u8(*a, *b);
+                    }
+                    Some(clip)
+                }
+                (clip, mask) => clip.or(mask),
+            };
+
+            layer.pixmap = Pixmap::new(rect.width(), rect.height());
+            layer.rect = rect;
+        }
+
+        self.layers.push(layer);
+        self.state.layers = self.layers.len();
+        self.state.clip = None;
+
+        count
+    }
+
+    /// Restores the state saved by the last [`push_layer`](Self::push_layer)
+    /// and composites the layer.
+    ///
+    /// Does nothing when there are no layers.
+    pub fn pop_layer(&mut self) {
+        if self.layers.is_empty() {
+            return;
+        }
+
+        let count = self.layers.len() - 1;
+        while self.layers.len() > count {
+            self.restore();
+        }
+    }
+
+    /// Returns the number of active layers.
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    fn composite_layer(&mut self) {
+        let layer = match self.layers.pop() {
+            Some(v) => v,
+            None => return,
+        };
+
+        let pixmap = match layer.pixmap {
+            Some(v) => v,
+            None => return,
+        };
+
+        let (tx, ty) = self.target_offset();
+        let (x, y) = (layer.rect.x() - tx, layer.rect.y() - ty);
+        let mask = layer.mask.as_ref();
+        match self.layers.last_mut() {
+            Some(parent) => {
+                if let Some(ref mut parent) = parent.pixmap {
+                    parent
+                        .as_mut()
+                        .draw_layer(x, y, pixmap.as_ref(), &layer.paint, mask);
+                }
+            }
+            None => self
+                .pixmap
+                .draw_layer(x, y, pixmap.as_ref(), &layer.paint, mask),
+        }
+    }
+
+    // Returns the current drawing target bounds in the canvas coordinates.
+    fn target_rect(&self) -> IntRect {
+        match self.layers.last() {
+            Some(layer) => layer.rect,
+            None => self.pixmap.size().to_int_rect(0, 0),
+        }
+    }
+
+    fn target_offset(&self) -> (i32, i32) {
+        let rect = self.target_rect();
+        (rect.x(), rect.y())
+    }
+
+    // Returns the current transform in the drawing target coordinates.
+    fn target_transform(&self) -> Transform {
+        let (tx, ty) = self.target_offset();
+        self.state.transform.post_translate(-tx as f32, -ty as f32)
+    }
+
+    // Custom shaders are positioned in the canvas coordinates, unlike the layer pixmap.
+    fn target_paint<'p>(&self, paint: &'p Paint<'p>) -> Cow<'p, Paint<'p>> {
+        let (tx, ty) = self.target_offset();
+        match paint.shader {
+            Shader::Custom(_) if tx != 0 || ty != 0 => {
+                let mut paint = paint.clone();
+                paint.shader.offset_origin(tx, ty);
+                Cow::Owned(paint)
+            }
+            _ => Cow::Borrowed(paint),
+        }
+    }
+
+    fn draw<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut PixmapMut, Transform, Option<&Mask>),
+    {
+        let ts = self.target_transform();
+        let masks = &self.masks;
+        let mask = self.state.clip.map(|i| &masks[i]);
+        match self.layers.last_mut() {
+            Some(layer) => {
+                if let Some(ref mut pixmap) = layer.pixmap {
+                    f(&mut pixmap.as_mut(), ts, mask);
+                }
+            }
+            None => f(&mut self.pixmap, ts, mask),
+        }
+    }
+
+    /// Restores states until only `count` of them remain saved.
+    pub fn restore_to_count(&mut self, count: usize) {
+        while self.stack.len() > count {
+            self.restore();
+        }
+    }
+
+    /// Returns the number of saved states.
+    pub fn save_count(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Returns the current transform.
+    pub fn transform(&self) -> Transform {
+        self.state.transform
+    }
+
+    /// Replaces the current transform.
+    pub fn set_transform(&mut self, ts: Transform) {
+        self.state.transform = ts;
+    }
+
+    /// Resets the current transform to identity.
+    pub fn reset_transform(&mut self) {
+        self.state.transform = Transform::identity();
+    }
+
+    /// Translates the canvas.
+    pub fn translate(&mut self, tx: f32, ty: f32) {
+        self.state.transform = self.state.transform.pre_translate(tx, ty);
+    }
+
+    /// Scales the canvas.
+    pub fn scale(&mut self, sx: f32, sy: f32) {
+        self.state.transform = self.state.transform.pre_scale(sx, sy);
+    }
+
+    /// Rotates the canvas by `angle` degrees.
+    pub fn rotate(&mut self, angle: f32) {
+        self.state.transform = self.state.transform.pre_rotate(angle);
+    }
+
+    /// Applies a transform to the canvas.
+    pub fn concat(&mut self, ts: Transform) {
+        self.state.transform = self.state.transform.pre_concat(ts);
+    }
+
+    /// Returns the current clip mask.
+    ///
+    /// `None` means that nothing is clipped.
+    ///
+    /// Inside a layer, the mask has the layer size and is in the layer coordinates.
+    pub fn clip_mask(&self) -> Option<&Mask> {
+        self.state.clip.map(|i| &self.masks[i])
+    }
+
+    /// Intersects the current clip with a rectangle in the current coordinates.
+    pub fn clip_rect(&mut self, rect: Rect, anti_alias: bool) {
+        let path = PathBuilder::from_rect(rect);
+        self.clip_path(&path, FillRule::Winding, anti_alias);
+    }
+
+    /// Intersects the current clip with a path in the current coordinates.
+    pub fn clip_path(&mut self, path: &Path, fill_rule: FillRule, anti_alias: bool) {
+        let ts = self.target_transform();
+        let saved_masks = self.stack.last().map_or(0, |state| state.masks);
+        match self.state.clip {
+            // The mask isn't shared with a saved state and can be modified in place.
+            Some(index) if index >= saved_masks => {
+                self.masks[index].intersect_path(path, fill_rule, anti_alias, ts);
+            }
+            Some(index) => {
+                let mut mask = self.masks[index].clone();
+                mask.intersect_path(path, fill_rule, anti_alias, ts);
+                self.push_mask(mask);
+            }
+            None => {
+                let size = self.target_rect().size();
+                let mut mask = match Mask::new(size.width(), size.height()) {
+                    Some(v) => v,
+                    None => return, // technically unreachable
+                };
+                mask.fill_path(path, fill_rule, anti_alias, ts);
+                self.push_mask(mask);
+            }
+        }
+    }
+
+    fn push_mask(&mut self, mask: Mask) {
+        self.masks.push(mask);
+        self.state.clip = Some(self.masks.len() - 1);
+    }
+
+    /// Draws a filled rectangle using the current transform and clip.
+    ///
+    /// See [`PixmapMut::fill_rect`] for details.
+    pub fn fill_rect(&mut self, rect: Rect, paint: &Paint) {
+        let paint = self.target_paint(paint);
+        self.draw(|pixmap, ts, mask| pixmap.fill_rect(rect, &paint, ts, mask));
+    }
+
+    /// Draws a filled path using the current transform and clip.
+    ///
+    /// See [`PixmapMut::fill_path`] for details.
+    pub fn fill_path(&mut self, path: &Path, paint: &Paint, fill_rule: FillRule) {
+        let paint = self.target_paint(paint);
+        self.draw(|pixmap, ts, mask| pixmap.fill_path(path, &paint, fill_rule, ts, mask));
+    }
+
+    /// Strokes a path using the current transform and clip.
+    ///
+    /// See [`PixmapMut::stroke_path`] for details.
+    pub fn stroke_path(&mut self, path: &Path, paint: &Paint, stroke: &Stroke) {
+        let paint = self.target_paint(paint);
+        self.draw(|pixmap, ts, mask| pixmap.stroke_path(path, &paint, stroke, ts, mask));
+    }
+
+    /// Draws a `Pixmap` at the specified position using the current transform and clip.
+    ///
+    /// See [`PixmapMut::draw_pixmap`] for details.
+    pub fn draw_pixmap(&mut self, x: i32, y: i32, pixmap: PixmapRef, paint: &PixmapPaint) {
+        self.draw(|target, ts, mask| target.draw_pixmap(x, y, pixmap, paint, ts, mask));
+    }
+
+    /// Plays back a `Picture` using the current transform and clip.
+    ///
+    /// See [`PixmapMut::draw_picture`] for details.
+    pub fn draw_picture(&mut self, picture: &Picture) {
+        let origin = self.target_offset();
+        self.draw(|target, ts, mask| target.draw_picture_at(picture, ts, mask, origin));
+    }
+}
+
+impl core::fmt::Debug for Canvas<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Canvas")
+            .field("width", &self.pixmap.width())
+            .field("height", &self.pixmap.height())
+            .field("transform", &self.state.transform)
+            .field("save_count", &self.stack.len())
+            .field("layer_count", &self.layers.len())
+            .finish()
+    }
+}
+
+// Copies a mask region into a new mask. Pixels outside the mask are zero.
+fn crop_mask(mask: &Mask, rect: IntRect) -> Option<Mask> {
+    let mut cropped = Mask::new(rect.width(), rect.height())?;
+    let src_rect = match mask.size().to_int_rect(0, 0).intersect(&rect) {
+        Some(v) => v,
+        None => return Some(cropped),
+    };
+
+    let src_stride = mask.width() as usize;
+    let dst_stride = rect.width() as usize;
+    let len = src_rect.width() as usize;
+    let dx = (src_rect.x() - rect.x()) as usize;
+    for y in src_rect.top()..src_rect.bottom() {
+        let src = y as usize * src_stride + src_rect.x() as usize;
+        let dst = (y - rect.y()) as usize * dst_stride + dx;
+        cropped.data_mut()[dst..dst + len].copy_from_slice(&mask.data()[src..src + len]);
+    }
+
+    Some(cropped)
+}
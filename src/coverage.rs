@@ -0,0 +1,182 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use alloc::vec;
+
+use tiny_skia_path::{IntRect, Path, Scalar, Transform};
+
+use crate::{AntiAliasQuality, FillRule, LengthU32, Rasterizer};
+
+use crate::alpha_runs::AlphaRun;
+use crate::blitter::Blitter;
+use crate::color::AlphaU8;
+use crate::geom::{IntSizeExt, ScreenIntRect};
+use crate::painter::DrawTiler;
+use crate::scan;
+
+/// A path coverage consumer.
+///
+/// See [`rasterize_path`] for details.
+pub trait CoverageSink {
+    /// Consumes a horizontal span of pixels with the same coverage.
+    ///
+    /// `width` and `coverage` are never zero. A coverage of 255 means a fully covered pixel.
+    fn span(&mut self, x: i32, y: i32, width: u32, coverage: u8);
+}
+
+/// Rasterization options.
+///
+/// The same as the corresponding [`Paint`](crate::Paint) fields.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CoverageOptions {
+    /// Enables anti-aliasing.
+    ///
+    /// Default: true
+    pub anti_alias: bool,
+
+    /// An anti-aliasing rasterizer.
+    ///
+    /// Has no effect when `anti_alias` is disabled.
+    ///
+    /// Default: Supersampling
+    pub rasterizer: Rasterizer,
+
+    /// A supersampling anti-aliasing quality.
+    ///
+    /// Has no effect when `anti_alias` is disabled. The `Analytic` rasterizer
+    /// uses it only for rows that are supersampled.
+    ///
+    /// Default: Normal
+    pub anti_alias_quality: AntiAliasQuality,
+}
+
+impl Default for CoverageOptions {
+    fn default() -> Self {
+        CoverageOptions {
+            anti_alias: true,
+            rasterizer: Rasterizer::default(),
+            anti_alias_quality: AntiAliasQuality::default(),
+        }
+    }
+}
+
+/// Rasterizes a filled path and streams the resulting coverage into a sink.
+///
+/// Uses the same rasterizers as [`PixmapMut::fill_path`](crate::PixmapMut::fill_path),
+/// selected by `options`, but without writing any pixels. Which is useful
+/// for computing a coverage area or rendering into a custom pixel format.
+///
+/// Only spans inside the `clip` rectangle are reported and each pixel is reported
+/// at most once. Pixels with zero coverage are skipped.
+pub fn rasterize_path<S: CoverageSink + ?Sized>(
+    path: &Path,
+    fill_rule: FillRule,
+    options: CoverageOptions,
+    transform: Transform,
+    clip: IntRect,
+    sink: &mut S,
+) {
+    if !transform.is_identity() {
+        let path = match path.clone().transform(transform) {
+            Some(v) => v,
+            None => {
+                log::warn!("path transformation failed");
+                return;
+            }
+        };
+
+        rasterize_path(&path, fill_rule, options, Transform::identity(), clip, sink);
+        return;
+    }
+
+    // Skip empty paths and horizontal/vertical lines.
+    let path_bounds = path.bounds();
+    if path_bounds.width().is_nearly_zero() || path_bounds.height().is_nearly_zero() {
+        log::warn!("empty paths and horizontal/vertical lines cannot be filled");
+        return;
+    }
+
+    if crate::painter::is_too_big_for_math(path) {
+        log::warn!("path coordinates are too big");
+        return;
+    }
+
+    // Rasterizers are working with unsigned coordinates, therefore the path is moved
+    // to the origin of each tile. Large clips are split into tiles just like pixmaps.
+    let tiles = match DrawTiler::new(clip.width(), clip.height()) {
+        Some(tiler) => tiler.collect(),
+        None => vec![clip.size().to_screen_int_rect(0, 0)],
+    };
+
+    let mut path = path.clone(); // TODO: avoid cloning
+    let mut origin = (0, 0);
+    for tile in tiles {
+        let tile_origin = (clip.x() + tile.x() as i32, clip.y() + tile.y() as i32);
+        if tile_origin != origin {
+            let ts = Transform::from_translate(
+                (i64::from(origin.0) - i64::from(tile_origin.0)) as f32,
+                (i64::from(origin.1) - i64::from(tile_origin.1)) as f32,
+            );
+            path = match path.transform(ts) {
+                Some(v) => v,
+                None => {
+                    log::warn!("path transformation failed");
+                    return;
+                }
+            };
+            origin = tile_origin;
+        }
+
+        let clip_rect = tile.size().to_screen_int_rect(0, 0);
+        let mut blitter = SinkBlitter { sink, origin };
+        fill_path(&path, fill_rule, options, &clip_rect, &mut blitter);
+    }
+}
+
+fn fill_path(
+    path: &Path,
+    fill_rule: FillRule,
+    options: CoverageOptions,
+    clip: &ScreenIntRect,
+    blitter: &mut dyn Blitter,
+) {
+    if options.anti_alias {
+        let rasterizer = options.rasterizer;
+        let quality = options.anti_alias_quality;
+        scan::fill_path_aa(path, fill_rule, rasterizer, quality, clip, blitter);
+    } else {
+        scan::path::fill_path(path, fill_rule, clip, blitter);
+    }
+}
+
+/// Converts blitter calls into coverage spans.
+struct SinkBlitter<'a, S: CoverageSink + ?Sized> {
+    sink: &'a mut S,
+    origin: (i32, i32),
+}
+
+impl<S: CoverageSink + ?Sized> SinkBlitter<'_, S> {
+    fn span(&mut self, x: u32, y: u32, width: u32, coverage: AlphaU8) {
+        if coverage != 0 {
+            let x = x as i32 + self.origin.0;
+            let y = y as i32 + self.origin.1;
+            self.sink.span(x, y, width, coverage);
+        }
+    }
+}
+
+impl<S: CoverageSink + ?Sized> Blitter for SinkBlitter<'_, S> {
+    fn blit_h(&mut self, x: u32, y: u32, width: LengthU32) {
+        self.span(x, y, width.get(), 255);
+    }
+
+    fn blit_anti_h(&mut self, mut x: u32, y: u32, aa: &mut [AlphaU8], runs: &mut [AlphaRun]) {
+        let mut offset = 0;
+        while let Some(run) = runs[offset] {
+            let width = u32::from(run.get());
+            self.span(x, y, width, aa[offset]);
+            x += width;
+            offset += usize::from(run.get());
+        }
+    }
+}
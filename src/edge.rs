@@ -511,7 +511,9 @@ fn diff_to_shift(dx: FDot6, dy: FDot6, shift_aa: i32) -> i32 {
     // this is chosen by heuristic: make it as big as possible (to minimize segments)
     // ... but small enough so that our curves still look smooth
     // When shift > 0, we're using AA and everything is scaled up so we can
-    // lower the accuracy.
+    // lower the accuracy. But not below the default supersampling,
+    // otherwise higher quality levels would be limited by the curve flattening.
+    let shift_aa = shift_aa.min(2);
     dist = (dist + (1 << (2 + shift_aa))) >> (3 + shift_aa);
 
     // each subdivision (shift value) cuts this dist (error) by 1/4
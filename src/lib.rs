@@ -5,6 +5,7 @@
 It doesn't look like cairo, QPainter (Qt), HTML Canvas or even Skia itself.
 Instead, `tiny-skia` provides a set of low-level drawing APIs
 and a user should manage the world transform, clipping mask and style manually.
+For a more conventional stateful API, see [`Canvas`].
 
 See the `examples/` directory for usage examples.
 */
@@ -37,7 +38,9 @@ extern crate alloc;
 mod alpha_runs;
 mod blend_mode;
 mod blitter;
+mod canvas;
 mod color;
+mod coverage;
 mod edge;
 mod edge_builder;
 mod edge_clipper;
@@ -48,26 +51,37 @@ mod mask;
 mod math;
 mod path64;
 mod path_geometry;
+mod picture;
 mod pipeline;
 mod pixmap;
 mod scan;
 mod shaders;
+mod tiling;
 mod wide;
 
 mod painter; // Keep it under `pixmap` for a better order in the docs.
 
 pub use blend_mode::BlendMode;
+pub use canvas::Canvas;
 pub use color::{Color, ColorSpace, ColorU8, PremultipliedColor, PremultipliedColorU8};
 pub use color::{ALPHA_OPAQUE, ALPHA_TRANSPARENT, ALPHA_U8_OPAQUE, ALPHA_U8_TRANSPARENT};
+pub use coverage::{rasterize_path, CoverageOptions, CoverageSink};
 pub use mask::{Mask, MaskType};
-pub use painter::{FillRule, Paint};
+pub use painter::{AntiAliasQuality, Paint, Rasterizer};
+pub use picture::{PdfDocument, Picture, PictureError, PictureRecorder};
 pub use pixmap::{Pixmap, PixmapMut, PixmapRef, BYTES_PER_PIXEL};
-pub use shaders::{FilterQuality, GradientStop, PixmapPaint, SpreadMode};
-pub use shaders::{LinearGradient, Pattern, RadialGradient, Shader};
+pub use shaders::{Custom, CustomShader, FilterQuality, GradientStop, PixmapPaint, SpreadMode};
+pub use shaders::{GradientInterpolation, HueInterpolation, InterpolationColorSpace};
+pub use shaders::{LinearGradient, Pattern, RadialGradient, Shader, SweepGradient};
+pub use tiling::{TileExecutor, TileJob};
 
+#[cfg(feature = "std")]
+pub use tiling::ThreadExecutor;
+
+pub use tiny_skia_path::{FillRule, Path, PathBuilder, PathSegment, PathSegmentsIter, PathStroker};
 pub use tiny_skia_path::{IntRect, IntSize, NonZeroRect, Point, Rect, Size, Transform};
-pub use tiny_skia_path::{LineCap, LineJoin, Stroke, StrokeDash};
-pub use tiny_skia_path::{Path, PathBuilder, PathSegment, PathSegmentsIter, PathStroker};
+pub use tiny_skia_path::{LineCap, LineJoin, Nib, NibStroker, Stroke, StrokeAlignment, StrokeDash};
+pub use tiny_skia_path::{MarkerKind, MarkerOrient, MarkerPosition};
 
 /// An integer length that is guarantee to be > 0
 type LengthU32 = core::num::NonZeroU32;
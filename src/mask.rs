@@ -16,7 +16,7 @@ use crate::painter::DrawTiler;
 use crate::pipeline::RasterPipelineBlitter;
 use crate::pixmap::SubPixmapMut;
 use crate::scan;
-use crate::{FillRule, PixmapRef};
+use crate::{AntiAliasQuality, FillRule, PixmapRef};
 
 /// A mask type.
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -159,6 +159,7 @@ impl Mask {
             size: self.size,
             real_width: self.size.width() as usize,
             data: &mut self.data,
+            data_offset: 0,
         }
     }
 
@@ -171,6 +172,7 @@ impl Mask {
             size: rect.size(),
             real_width: self.size.width() as usize,
             data: &mut self.data[offset..],
+            data_offset: 0,
         })
     }
 
@@ -256,6 +258,35 @@ impl Mask {
         fill_rule: FillRule,
         anti_alias: bool,
         transform: Transform,
+    ) {
+        let quality = if anti_alias {
+            Some(AntiAliasQuality::default())
+        } else {
+            None
+        };
+
+        self.fill_path_impl(path, fill_rule, quality, transform);
+    }
+
+    /// Draws a filled anti-aliased path onto the mask using the specified quality.
+    ///
+    /// Behaves like [`Mask::fill_path()`] otherwise.
+    pub fn fill_path_with_quality(
+        &mut self,
+        path: &Path,
+        fill_rule: FillRule,
+        quality: AntiAliasQuality,
+        transform: Transform,
+    ) {
+        self.fill_path_impl(path, fill_rule, Some(quality), transform);
+    }
+
+    fn fill_path_impl(
+        &mut self,
+        path: &Path,
+        fill_rule: FillRule,
+        quality: Option<AntiAliasQuality>,
+        transform: Transform,
     ) {
         if transform.is_identity() {
             // This is sort of similar to SkDraw::drawPath
@@ -301,8 +332,14 @@ impl Mask {
                     // We're ignoring "errors" here, because `fill_path` will return `None`
                     // when rendering a tile that doesn't have a path on it.
                     // Which is not an error in this case.
-                    if anti_alias {
-                        scan::path_aa::fill_path(&path, fill_rule, &clip_rect, &mut blitter);
+                    if let Some(quality) = quality {
+                        scan::path_aa::fill_path(
+                            &path,
+                            fill_rule,
+                            quality,
+                            &clip_rect,
+                            &mut blitter,
+                        );
                     } else {
                         scan::path::fill_path(&path, fill_rule, &clip_rect, &mut blitter);
                     }
@@ -321,8 +358,8 @@ impl Mask {
                     None => return, // nothing to do, all good
                 };
 
-                if anti_alias {
-                    scan::path_aa::fill_path(path, fill_rule, &clip_rect, &mut blitter);
+                if let Some(quality) = quality {
+                    scan::path_aa::fill_path(path, fill_rule, quality, &clip_rect, &mut blitter);
                 } else {
                     scan::path::fill_path(path, fill_rule, &clip_rect, &mut blitter);
                 }
@@ -336,7 +373,7 @@ impl Mask {
                 }
             };
 
-            self.fill_path(&path, fill_rule, anti_alias, Transform::identity());
+            self.fill_path_impl(&path, fill_rule, quality, Transform::identity());
         }
     }
 
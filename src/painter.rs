@@ -8,28 +8,75 @@ use crate::*;
 
 use tiny_skia_path::{PathStroker, Scalar, SCALAR_MAX};
 
+use crate::blitter::Blitter;
 use crate::geom::ScreenIntRect;
 use crate::mask::SubMaskRef;
 use crate::pipeline::{RasterPipelineBlitter, RasterPipelineBuilder};
 use crate::pixmap::SubPixmapMut;
 use crate::scan;
+use crate::tiling::{self, TileExecutor};
 
 use crate::geom::IntSizeExt;
 #[cfg(all(not(feature = "std"), feature = "no-std-float"))]
 use tiny_skia_path::NoStdFloat;
 
-/// A path filling rule.
-#[derive(Copy, Clone, PartialEq, Debug)]
-pub enum FillRule {
-    /// Specifies that "inside" is computed by a non-zero sum of signed edge crossings.
-    Winding,
-    /// Specifies that "inside" is computed by an odd number of edge crossings.
-    EvenOdd,
+/// An anti-aliasing rasterizer.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Rasterizer {
+    /// Accumulates the coverage of multiple samples per pixel.
+    ///
+    /// The number of samples is controlled by `Paint::anti_alias_quality`.
+    Supersampling,
+    /// Computes the exact area covered by a path inside each pixel.
+    ///
+    /// Has a higher coverage precision than supersampling. Simple paths are rendered
+    /// at a similar speed, while paths with many overlapping edges can be about twice as slow.
+    /// Rows with too many crossing edges are supersampled instead,
+    /// which keeps the worst case close to the supersampling one.
+    Analytic,
+}
+
+impl Default for Rasterizer {
+    fn default() -> Self {
+        Rasterizer::Supersampling
+    }
+}
+
+/// A supersampling anti-aliasing quality.
+///
+/// Higher quality levels produce more accurate coverage at the cost of speed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AntiAliasQuality {
+    /// 2x2 samples per pixel.
+    ///
+    /// Suitable for previews.
+    Low,
+    /// 4x4 samples per pixel.
+    Normal,
+    /// 16x16 samples per pixel.
+    ///
+    /// Paths larger than 2047 pixels are rendered in several strips.
+    High,
+}
+
+impl AntiAliasQuality {
+    /// Returns the number of samples per pixel.
+    pub fn samples(self) -> u32 {
+        1 << (2 * self.supersample_shift())
+    }
+
+    pub(crate) fn supersample_shift(self) -> u32 {
+        match self {
+            AntiAliasQuality::Low => 1,
+            AntiAliasQuality::Normal => 2,
+            AntiAliasQuality::High => 4,
+        }
+    }
 }
 
-impl Default for FillRule {
+impl Default for AntiAliasQuality {
     fn default() -> Self {
-        FillRule::Winding
+        AntiAliasQuality::Normal
     }
 }
 
@@ -51,6 +98,21 @@ pub struct Paint<'a> {
     /// Default: true
     pub anti_alias: bool,
 
+    /// An anti-aliasing rasterizer used to fill paths.
+    ///
+    /// Has no effect when `anti_alias` is disabled.
+    ///
+    /// Default: Supersampling
+    pub rasterizer: Rasterizer,
+
+    /// A supersampling anti-aliasing quality.
+    ///
+    /// Has no effect when `anti_alias` is disabled. The `Analytic` rasterizer
+    /// uses it only for rows that are supersampled.
+    ///
+    /// Default: Normal
+    pub anti_alias_quality: AntiAliasQuality,
+
     /// Colorspace for blending.
     ///
     /// This enables gamma correction during the blend operation.  While skia supports
@@ -85,6 +147,8 @@ impl Default for Paint<'_> {
             shader: Shader::SolidColor(Color::BLACK),
             blend_mode: BlendMode::default(),
             anti_alias: true,
+            rasterizer: Rasterizer::default(),
+            anti_alias_quality: AntiAliasQuality::default(),
             colorspace: ColorSpace::default(),
             force_hq_pipeline: false,
         }
@@ -139,6 +203,39 @@ impl Pixmap {
             .fill_path(path, paint, fill_rule, transform, mask);
     }
 
+    /// Draws a filled path onto the pixmap using multiple threads.
+    ///
+    /// See [`PixmapMut::fill_path_parallel`](struct.PixmapMut.html#method.fill_path_parallel)
+    /// for details.
+    pub fn fill_path_parallel(
+        &mut self,
+        path: &Path,
+        paint: &Paint,
+        fill_rule: FillRule,
+        transform: Transform,
+        mask: Option<&Mask>,
+        executor: &dyn TileExecutor,
+    ) {
+        self.as_mut()
+            .fill_path_parallel(path, paint, fill_rule, transform, mask, executor);
+    }
+
+    /// Draws a filled marker path at each marker position.
+    ///
+    /// See [`PixmapMut::fill_markers`](struct.PixmapMut.html#method.fill_markers) for details.
+    pub fn fill_markers(
+        &mut self,
+        marker: &Path,
+        positions: &[MarkerPosition],
+        paint: &Paint,
+        fill_rule: FillRule,
+        transform: Transform,
+        mask: Option<&Mask>,
+    ) {
+        self.as_mut()
+            .fill_markers(marker, positions, paint, fill_rule, transform, mask);
+    }
+
     /// Strokes a path.
     ///
     /// See [`PixmapMut::stroke_path`](struct.PixmapMut.html#method.stroke_path) for details.
@@ -154,6 +251,23 @@ impl Pixmap {
             .stroke_path(path, paint, stroke, transform, mask);
     }
 
+    /// Strokes a path using multiple threads.
+    ///
+    /// See [`PixmapMut::stroke_path_parallel`](struct.PixmapMut.html#method.stroke_path_parallel)
+    /// for details.
+    pub fn stroke_path_parallel(
+        &mut self,
+        path: &Path,
+        paint: &Paint,
+        stroke: &Stroke,
+        transform: Transform,
+        mask: Option<&Mask>,
+        executor: &dyn TileExecutor,
+    ) {
+        self.as_mut()
+            .stroke_path_parallel(path, paint, stroke, transform, mask, executor);
+    }
+
     /// Draws a `Pixmap` on top of the current `Pixmap`.
     ///
     /// See [`PixmapMut::draw_pixmap`](struct.PixmapMut.html#method.draw_pixmap) for details.
@@ -170,12 +284,37 @@ impl Pixmap {
             .draw_pixmap(x, y, pixmap, paint, transform, mask);
     }
 
+    /// Draws a `Pixmap` on top of the current `Pixmap` using multiple threads.
+    ///
+    /// See [`PixmapMut::draw_pixmap_parallel`](struct.PixmapMut.html#method.draw_pixmap_parallel)
+    /// for details.
+    pub fn draw_pixmap_parallel(
+        &mut self,
+        x: i32,
+        y: i32,
+        pixmap: PixmapRef,
+        paint: &PixmapPaint,
+        transform: Transform,
+        mask: Option<&Mask>,
+        executor: &dyn TileExecutor,
+    ) {
+        self.as_mut()
+            .draw_pixmap_parallel(x, y, pixmap, paint, transform, mask, executor);
+    }
+
     /// Applies a masks.
     ///
     /// See [`PixmapMut::apply_mask`](struct.PixmapMut.html#method.apply_mask) for details.
     pub fn apply_mask(&mut self, mask: &Mask) {
         self.as_mut().apply_mask(mask);
     }
+
+    /// Draws a `Picture`.
+    ///
+    /// See [`PixmapMut::draw_picture`](struct.PixmapMut.html#method.draw_picture) for details.
+    pub fn draw_picture(&mut self, picture: &Picture, transform: Transform, mask: Option<&Mask>) {
+        self.as_mut().draw_picture(picture, transform, mask);
+    }
 }
 
 impl PixmapMut<'_> {
@@ -195,28 +334,39 @@ impl PixmapMut<'_> {
         paint: &Paint,
         transform: Transform,
         mask: Option<&Mask>,
+    ) {
+        self.fill_rect_impl(rect, paint, transform, mask, None);
+    }
+
+    fn fill_rect_impl(
+        &mut self,
+        rect: Rect,
+        paint: &Paint,
+        transform: Transform,
+        mask: Option<&Mask>,
+        executor: Option<&dyn TileExecutor>,
     ) {
         // TODO: we probably can use tiler for rect too
         if transform.is_identity() && !DrawTiler::required(self.width(), self.height()) {
             // TODO: ignore rects outside the pixmap
 
-            let clip = self.size().to_screen_int_rect(0, 0);
-
             let mask = mask.map(|mask| mask.as_submask());
-            let mut subpix = self.as_subpixmap();
-            let mut blitter = match RasterPipelineBlitter::new(paint, mask, &mut subpix) {
-                Some(v) => v,
-                None => return, // nothing to do, all good
-            };
-
-            if paint.anti_alias {
-                scan::fill_rect_aa(&rect, &clip, &mut blitter);
-            } else {
-                scan::fill_rect(&rect, &clip, &mut blitter);
-            }
+            tiling::render(
+                paint,
+                mask,
+                self.as_subpixmap(),
+                executor,
+                |clip, blitter| {
+                    if paint.anti_alias {
+                        scan::fill_rect_aa(&rect, clip, blitter);
+                    } else {
+                        scan::fill_rect(&rect, clip, blitter);
+                    }
+                },
+            );
         } else {
             let path = PathBuilder::from_rect(rect);
-            self.fill_path(&path, paint, FillRule::Winding, transform, mask);
+            self.fill_path_impl(&path, paint, FillRule::Winding, transform, mask, executor);
         }
     }
 
@@ -228,6 +378,34 @@ impl PixmapMut<'_> {
         fill_rule: FillRule,
         transform: Transform,
         mask: Option<&Mask>,
+    ) {
+        self.fill_path_impl(path, paint, fill_rule, transform, mask, None);
+    }
+
+    /// Draws a filled path onto the pixmap using multiple threads.
+    ///
+    /// The pixmap is split into row bands, which are rasterized, shaded and blended by `executor`.
+    /// The output is identical to [`fill_path`](Self::fill_path).
+    pub fn fill_path_parallel(
+        &mut self,
+        path: &Path,
+        paint: &Paint,
+        fill_rule: FillRule,
+        transform: Transform,
+        mask: Option<&Mask>,
+        executor: &dyn TileExecutor,
+    ) {
+        self.fill_path_impl(path, paint, fill_rule, transform, mask, Some(executor));
+    }
+
+    fn fill_path_impl(
+        &mut self,
+        path: &Path,
+        paint: &Paint,
+        fill_rule: FillRule,
+        transform: Transform,
+        mask: Option<&Mask>,
+        executor: Option<&dyn TileExecutor>,
     ) {
         if transform.is_identity() {
             // This is sort of similar to SkDraw::drawPath
@@ -259,51 +437,30 @@ impl PixmapMut<'_> {
                             return;
                         }
                     };
-                    paint.shader.transform(ts);
+                    paint.shader.translate_tile(ts);
 
-                    let clip_rect = tile.size().to_screen_int_rect(0, 0);
-                    let mut subpix = match self.subpixmap(tile.to_int_rect()) {
+                    let subpix = match self.subpixmap(tile.to_int_rect()) {
                         Some(v) => v,
                         None => continue, // technically unreachable
                     };
 
-                    let submask = mask.and_then(|mask| mask.submask(tile.to_int_rect()));
-                    let mut blitter = match RasterPipelineBlitter::new(&paint, submask, &mut subpix)
-                    {
-                        Some(v) => v,
-                        None => continue, // nothing to do, all good
-                    };
-
                     // We're ignoring "errors" here, because `fill_path` will return `None`
                     // when rendering a tile that doesn't have a path on it.
                     // Which is not an error in this case.
-                    if paint.anti_alias {
-                        scan::path_aa::fill_path(&path, fill_rule, &clip_rect, &mut blitter);
-                    } else {
-                        scan::path::fill_path(&path, fill_rule, &clip_rect, &mut blitter);
-                    }
+                    let submask = mask.and_then(|mask| mask.submask(tile.to_int_rect()));
+                    fill_path_scan(&path, &paint, fill_rule, submask, subpix, executor);
 
                     let ts = Transform::from_translate(tile.x() as f32, tile.y() as f32);
                     path = match path.transform(ts) {
                         Some(v) => v,
                         None => return, // technically unreachable
                     };
-                    paint.shader.transform(ts);
+                    paint.shader.translate_tile(ts);
                 }
             } else {
-                let clip_rect = self.size().to_screen_int_rect(0, 0);
                 let submask = mask.map(|mask| mask.as_submask());
-                let mut subpix = self.as_subpixmap();
-                let mut blitter = match RasterPipelineBlitter::new(paint, submask, &mut subpix) {
-                    Some(v) => v,
-                    None => return, // nothing to do, all good
-                };
-
-                if paint.anti_alias {
-                    scan::path_aa::fill_path(path, fill_rule, &clip_rect, &mut blitter);
-                } else {
-                    scan::path::fill_path(path, fill_rule, &clip_rect, &mut blitter);
-                }
+                let subpix = self.as_subpixmap();
+                fill_path_scan(path, paint, fill_rule, submask, subpix, executor);
             }
         } else {
             let path = match path.clone().transform(transform) {
@@ -317,7 +474,36 @@ impl PixmapMut<'_> {
             let mut paint = paint.clone();
             paint.shader.transform(transform);
 
-            self.fill_path(&path, &paint, fill_rule, Transform::identity(), mask)
+            self.fill_path_impl(
+                &path,
+                &paint,
+                fill_rule,
+                Transform::identity(),
+                mask,
+                executor,
+            )
+        }
+    }
+
+    /// Draws a filled marker path at each marker position.
+    ///
+    /// Positions can be obtained via [`Path::marker_positions`] and filtered by
+    /// [`MarkerKind`] to draw different markers at the start, middle and end vertices.
+    ///
+    /// The marker is defined in its own coordinates, with the origin placed at the vertex
+    /// and the x-axis pointing along the path. `transform` is the path transform.
+    pub fn fill_markers(
+        &mut self,
+        marker: &Path,
+        positions: &[MarkerPosition],
+        paint: &Paint,
+        fill_rule: FillRule,
+        transform: Transform,
+        mask: Option<&Mask>,
+    ) {
+        for position in positions {
+            let ts = transform.pre_concat(position.transform);
+            self.fill_path(marker, paint, fill_rule, ts, mask);
         }
     }
 
@@ -331,6 +517,7 @@ impl PixmapMut<'_> {
     ///    then the original path.
     /// 2. If a stroke width is thinner than 1px (after applying the transformation),
     ///    we will use hairline stroking, which doesn't involve a separate path allocation.
+    ///    Hairlines are always centered, so this is not used for aligned strokes.
     ///
     /// Also, if a `stroke` has a dash array, then path will be converted into
     /// a dashed path first and then stroked. Which means a yet another allocation.
@@ -341,6 +528,36 @@ impl PixmapMut<'_> {
         stroke: &Stroke,
         transform: Transform,
         mask: Option<&Mask>,
+    ) {
+        self.stroke_path_impl(path, paint, stroke, transform, mask, None);
+    }
+
+    /// Strokes a path using multiple threads.
+    ///
+    /// The path is stroked on the calling thread, then the pixmap is split into row bands,
+    /// which are rasterized, shaded and blended by `executor`.
+    /// Hairlines are still rasterized on the calling thread, see [`TileExecutor`] for details.
+    /// The output is identical to [`stroke_path`](Self::stroke_path).
+    pub fn stroke_path_parallel(
+        &mut self,
+        path: &Path,
+        paint: &Paint,
+        stroke: &Stroke,
+        transform: Transform,
+        mask: Option<&Mask>,
+        executor: &dyn TileExecutor,
+    ) {
+        self.stroke_path_impl(path, paint, stroke, transform, mask, Some(executor));
+    }
+
+    fn stroke_path_impl(
+        &mut self,
+        path: &Path,
+        paint: &Paint,
+        stroke: &Stroke,
+        transform: Transform,
+        mask: Option<&Mask>,
+        executor: Option<&dyn TileExecutor>,
     ) {
         if stroke.width < 0.0 {
             log::warn!("negative stroke width isn't allowed");
@@ -400,9 +617,9 @@ impl PixmapMut<'_> {
                             return;
                         }
                     };
-                    paint.shader.transform(ts);
+                    paint.shader.translate_tile(ts);
 
-                    let mut subpix = match self.subpixmap(tile.to_int_rect()) {
+                    let subpix = match self.subpixmap(tile.to_int_rect()) {
                         Some(v) => v,
                         None => continue, // technically unreachable
                     };
@@ -411,17 +628,18 @@ impl PixmapMut<'_> {
                     // We're ignoring "errors" here, because `stroke_hairline` will return `None`
                     // when rendering a tile that doesn't have a path on it.
                     // Which is not an error in this case.
-                    Self::stroke_hairline(&path, &paint, stroke.line_cap, submask, &mut subpix);
+                    let line_cap = stroke.line_cap;
+                    Self::stroke_hairline(&path, &paint, line_cap, submask, subpix, executor);
 
                     let ts = Transform::from_translate(tile.x() as f32, tile.y() as f32);
                     path = match path.transform(ts) {
                         Some(v) => v,
                         None => return,
                     };
-                    paint.shader.transform(ts);
+                    paint.shader.translate_tile(ts);
                 }
             } else {
-                let subpix = &mut self.as_subpixmap();
+                let subpix = self.as_subpixmap();
                 let submask = mask.map(|mask| mask.as_submask());
                 if !transform.is_identity() {
                     paint.shader.transform(transform);
@@ -435,9 +653,11 @@ impl PixmapMut<'_> {
                         }
                     };
 
-                    Self::stroke_hairline(&path, &paint, stroke.line_cap, submask, subpix);
+                    let line_cap = stroke.line_cap;
+                    Self::stroke_hairline(&path, &paint, line_cap, submask, subpix, executor);
                 } else {
-                    Self::stroke_hairline(path, &paint, stroke.line_cap, submask, subpix);
+                    let line_cap = stroke.line_cap;
+                    Self::stroke_hairline(path, &paint, line_cap, submask, subpix, executor);
                 }
             }
         } else {
@@ -449,7 +669,7 @@ impl PixmapMut<'_> {
                 }
             };
 
-            self.fill_path(&path, paint, FillRule::Winding, transform, mask);
+            self.fill_path_impl(&path, paint, FillRule::Winding, transform, mask, executor);
         }
     }
 
@@ -459,18 +679,16 @@ impl PixmapMut<'_> {
         paint: &Paint,
         line_cap: LineCap,
         mask: Option<SubMaskRef>,
-        pixmap: &mut SubPixmapMut,
+        pixmap: SubPixmapMut,
+        executor: Option<&dyn TileExecutor>,
     ) {
-        let clip = pixmap.size.to_screen_int_rect(0, 0);
-        let mut blitter = match RasterPipelineBlitter::new(paint, mask, pixmap) {
-            Some(v) => v,
-            None => return, // nothing to do, all good
-        };
-        if paint.anti_alias {
-            scan::hairline_aa::stroke_path(path, line_cap, &clip, &mut blitter);
-        } else {
-            scan::hairline::stroke_path(path, line_cap, &clip, &mut blitter);
-        }
+        tiling::render(paint, mask, pixmap, executor, |clip, blitter| {
+            if paint.anti_alias {
+                scan::hairline_aa::stroke_path(path, line_cap, clip, blitter);
+            } else {
+                scan::hairline::stroke_path(path, line_cap, clip, blitter);
+            }
+        });
     }
 
     /// Draws a `Pixmap` on top of the current `Pixmap`.
@@ -484,6 +702,38 @@ impl PixmapMut<'_> {
         paint: &PixmapPaint,
         transform: Transform,
         mask: Option<&Mask>,
+    ) {
+        self.draw_pixmap_impl(x, y, pixmap, paint, transform, mask, None);
+    }
+
+    /// Draws a `Pixmap` on top of the current `Pixmap` using multiple threads.
+    ///
+    /// The pixmap is split into row bands, which are rasterized, shaded and blended by `executor`.
+    /// Untransformed pixmaps are still rasterized on the calling thread,
+    /// see [`TileExecutor`] for details.
+    /// The output is identical to [`draw_pixmap`](Self::draw_pixmap).
+    pub fn draw_pixmap_parallel(
+        &mut self,
+        x: i32,
+        y: i32,
+        pixmap: PixmapRef,
+        paint: &PixmapPaint,
+        transform: Transform,
+        mask: Option<&Mask>,
+        executor: &dyn TileExecutor,
+    ) {
+        self.draw_pixmap_impl(x, y, pixmap, paint, transform, mask, Some(executor));
+    }
+
+    fn draw_pixmap_impl(
+        &mut self,
+        x: i32,
+        y: i32,
+        pixmap: PixmapRef,
+        paint: &PixmapPaint,
+        transform: Transform,
+        mask: Option<&Mask>,
+        executor: Option<&dyn TileExecutor>,
     ) {
         let rect = pixmap.size().to_int_rect(x, y).to_rect();
 
@@ -503,12 +753,58 @@ impl PixmapMut<'_> {
                 patt_transform,
             ),
             blend_mode: paint.blend_mode,
-            anti_alias: false,        // Skia doesn't use it too.
+            anti_alias: false, // Skia doesn't use it too.
+            rasterizer: Rasterizer::default(),
+            anti_alias_quality: AntiAliasQuality::default(),
             force_hq_pipeline: false, // Pattern will use hq anyway.
             colorspace: ColorSpace::default(),
         };
 
-        self.fill_rect(rect, &paint, transform, mask);
+        self.fill_rect_impl(rect, &paint, transform, mask, executor);
+    }
+
+    /// Composites a layer at the specified position.
+    ///
+    /// Same as `draw_pixmap` with a translation, but only the pixmap region is affected
+    /// and `mask` must have the same size as the layer rather than the target.
+    pub(crate) fn draw_layer(
+        &mut self,
+        x: i32,
+        y: i32,
+        layer: PixmapRef,
+        paint: &PixmapPaint,
+        mask: Option<&Mask>,
+    ) {
+        let rect = layer.size().to_int_rect(x, y);
+        let mut subpix = match self.subpixmap(rect) {
+            Some(v) => v,
+            None => return, // clipped out
+        };
+
+        // Layers are always inside the target.
+        debug_assert_eq!(subpix.size, layer.size());
+
+        let paint = Paint {
+            shader: Pattern::new(
+                layer,
+                SpreadMode::Pad,
+                paint.quality,
+                paint.opacity,
+                Transform::identity(),
+            ),
+            blend_mode: paint.blend_mode,
+            anti_alias: false,
+            rasterizer: Rasterizer::default(),
+            anti_alias_quality: AntiAliasQuality::default(),
+            force_hq_pipeline: false,
+            colorspace: ColorSpace::default(),
+        };
+
+        let clip = subpix.size.to_screen_int_rect(0, 0);
+        let mask = mask.map(|mask| mask.as_submask());
+        if let Some(mut blitter) = RasterPipelineBlitter::new(&paint, mask, &mut subpix) {
+            blitter.blit_rect(&clip);
+        }
     }
 
     /// Applies a masks.
@@ -548,6 +844,28 @@ impl PixmapMut<'_> {
     }
 }
 
+fn fill_path_scan(
+    path: &Path,
+    paint: &Paint,
+    fill_rule: FillRule,
+    mask: Option<SubMaskRef>,
+    pixmap: SubPixmapMut,
+    executor: Option<&dyn TileExecutor>,
+) {
+    let clip = pixmap.size.to_screen_int_rect(0, 0);
+    let path = scan::PreparedPath::new(
+        path,
+        fill_rule,
+        paint.anti_alias,
+        paint.rasterizer,
+        paint.anti_alias_quality,
+        &clip,
+    );
+    if let Some(path) = path {
+        tiling::render_path(paint, mask, pixmap, executor, &path);
+    }
+}
+
 fn treat_as_hairline(paint: &Paint, stroke: &Stroke, mut ts: Transform) -> Option<f32> {
     fn fast_len(p: Point) -> f32 {
         let mut x = p.x.abs();
@@ -565,7 +883,8 @@ fn treat_as_hairline(paint: &Paint, stroke: &Stroke, mut ts: Transform) -> Optio
         return Some(1.0);
     }
 
-    if !paint.anti_alias {
+    // Hairlines are always centered.
+    if !paint.anti_alias || stroke.alignment != StrokeAlignment::Center {
         return None;
     }
 
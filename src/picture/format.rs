@@ -0,0 +1,920 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+/*!
+A binary `Picture` format.
+
+All values are little-endian. `f32` is stored as IEEE 754 bits,
+`bool` as a single byte, either 0 or 1.
+
+```text
+Picture:
+    signature       [u8; 4] = "TSKP"
+    version         u16 = 1
+    images          u32 count, then per image: u32 width, u32 height,
+                    width * height premultiplied RGBA8 pixels
+    masks           u32 count, then per mask: u32 width, u32 height, width * height bytes
+    records         u32 count, then per record: Rect bounds, u8 command type, command
+
+Rect:       f32 left, f32 top, f32 right, f32 bottom
+Transform:  f32 sx, f32 ky, f32 kx, f32 sy, f32 tx, f32 ty
+Color:      f32 red, f32 green, f32 blue, f32 alpha
+Path:       u32 verbs count, then per verb: u8 verb type, 1-3 points of f32 x, f32 y
+Option<T>:  bool is some, then T when set
+```
+
+Enums are stored as u8 indices in the order of their declaration.
+See the `write_*` functions below for commands, paints and strokes layout.
+*/
+
+use alloc::vec::Vec;
+
+use tiny_skia_path::{IntRect, IntSize, NormalizedF32, Path, PathBuilder, Point, Rect};
+use tiny_skia_path::{LineCap, LineJoin, PathVerb, Stroke, StrokeAlignment, StrokeDash, Transform};
+
+use crate::shaders::{FocalData, Gradient, RadialKind};
+use crate::{AntiAliasQuality, BlendMode, Color, ColorSpace, FillRule, FilterQuality};
+use crate::{GradientInterpolation, HueInterpolation, InterpolationColorSpace};
+use crate::{GradientStop, LinearGradient, Mask, Pixmap, PixmapPaint, RadialGradient, Rasterizer};
+use crate::{Shader, SpreadMode, SweepGradient};
+
+use super::{is_too_big_for_math, union, Command, Picture, Record, RecordedPaint, RecordedShader};
+
+const SIGNATURE: &[u8; 4] = b"TSKP";
+const VERSION: u16 = 1;
+
+/// A `Picture` encoding or decoding error.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PictureError {
+    /// The data doesn't start with a picture signature.
+    InvalidSignature,
+    /// The format version is not supported.
+    UnsupportedVersion(u16),
+    /// The data ended unexpectedly.
+    UnexpectedEof,
+    /// The data contains an invalid value or has trailing bytes.
+    InvalidData,
+    /// The picture contains a custom shader, which cannot be serialized.
+    UnsupportedShader,
+}
+
+impl core::fmt::Display for PictureError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PictureError::InvalidSignature => write!(f, "not a picture"),
+            PictureError::UnsupportedVersion(v) => write!(f, "unsupported picture version {}", v),
+            PictureError::UnexpectedEof => write!(f, "unexpected end of picture data"),
+            PictureError::InvalidData => write!(f, "malformed picture data"),
+            PictureError::UnsupportedShader => write!(f, "custom shaders cannot be serialized"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PictureError {}
+
+type Result<T> = core::result::Result<T, PictureError>;
+
+const BLEND_MODES: [BlendMode; 29] = [
+    BlendMode::Clear,
+    BlendMode::Source,
+    BlendMode::Destination,
+    BlendMode::SourceOver,
+    BlendMode::DestinationOver,
+    BlendMode::SourceIn,
+    BlendMode::DestinationIn,
+    BlendMode::SourceOut,
+    BlendMode::DestinationOut,
+    BlendMode::SourceAtop,
+    BlendMode::DestinationAtop,
+    BlendMode::Xor,
+    BlendMode::Plus,
+    BlendMode::Modulate,
+    BlendMode::Screen,
+    BlendMode::Overlay,
+    BlendMode::Darken,
+    BlendMode::Lighten,
+    BlendMode::ColorDodge,
+    BlendMode::ColorBurn,
+    BlendMode::HardLight,
+    BlendMode::SoftLight,
+    BlendMode::Difference,
+    BlendMode::Exclusion,
+    BlendMode::Multiply,
+    BlendMode::Hue,
+    BlendMode::Saturation,
+    BlendMode::Color,
+    BlendMode::Luminosity,
+];
+const COLOR_SPACES: [ColorSpace; 4] = [
+    ColorSpace::Linear,
+    ColorSpace::Gamma2,
+    ColorSpace::SimpleSRGB,
+    ColorSpace::FullSRGBGamma,
+];
+const RASTERIZERS: [Rasterizer; 2] = [Rasterizer::Supersampling, Rasterizer::Analytic];
+const AA_QUALITIES: [AntiAliasQuality; 3] = [
+    AntiAliasQuality::Low,
+    AntiAliasQuality::Normal,
+    AntiAliasQuality::High,
+];
+const FILL_RULES: [FillRule; 2] = [FillRule::Winding, FillRule::EvenOdd];
+const SPREAD_MODES: [SpreadMode; 3] = [SpreadMode::Pad, SpreadMode::Reflect, SpreadMode::Repeat];
+const INTERPOLATION_COLOR_SPACES: [InterpolationColorSpace; 8] = [
+    InterpolationColorSpace::Destination,
+    InterpolationColorSpace::SRGB,
+    InterpolationColorSpace::LinearSRGB,
+    InterpolationColorSpace::OKLab,
+    InterpolationColorSpace::OKLCH(HueInterpolation::Shorter),
+    InterpolationColorSpace::OKLCH(HueInterpolation::Longer),
+    InterpolationColorSpace::OKLCH(HueInterpolation::Increasing),
+    InterpolationColorSpace::OKLCH(HueInterpolation::Decreasing),
+];
+const FILTER_QUALITIES: [FilterQuality; 3] = [
+    FilterQuality::Nearest,
+    FilterQuality::Bilinear,
+    FilterQuality::Bicubic,
+];
+const LINE_CAPS: [LineCap; 3] = [LineCap::Butt, LineCap::Round, LineCap::Square];
+const LINE_JOINS: [LineJoin; 4] = [
+    LineJoin::Miter,
+    LineJoin::MiterClip,
+    LineJoin::Round,
+    LineJoin::Bevel,
+];
+const STROKE_ALIGNMENTS: [StrokeAlignment; 3] = [
+    StrokeAlignment::Center,
+    StrokeAlignment::Inside,
+    StrokeAlignment::Outside,
+];
+
+const FILL_RECT: u8 = 0;
+const FILL_PATH: u8 = 1;
+const STROKE_PATH: u8 = 2;
+const DRAW_PIXMAP: u8 = 3;
+const APPLY_MASK: u8 = 4;
+
+const SOLID_COLOR: u8 = 0;
+const LINEAR_GRADIENT: u8 = 1;
+const RADIAL_GRADIENT: u8 = 2;
+const PATTERN: u8 = 3;
+const SWEEP_GRADIENT: u8 = 4;
+
+impl Picture {
+    /// Serializes the picture into a binary format.
+    ///
+    /// The format is versioned and can be read back via [`from_bytes`](Self::from_bytes).
+    ///
+    /// Returns [`PictureError::UnsupportedShader`] when the picture contains custom shaders.
+    pub fn to_bytes(&self) -> core::result::Result<Vec<u8>, PictureError> {
+        if self.records.iter().any(|r| has_custom_shader(&r.command)) {
+            return Err(PictureError::UnsupportedShader);
+        }
+
+        let mut w = Writer(Vec::new());
+        w.0.extend_from_slice(SIGNATURE);
+        w.u16(VERSION);
+
+        w.u32(self.images.len() as u32);
+        for image in &self.images {
+            w.u32(image.width());
+            w.u32(image.height());
+            w.0.extend_from_slice(image.data());
+        }
+
+        w.u32(self.masks.len() as u32);
+        for mask in &self.masks {
+            w.u32(mask.width());
+            w.u32(mask.height());
+            w.0.extend_from_slice(mask.data());
+        }
+
+        w.u32(self.records.len() as u32);
+        for record in &self.records {
+            w.rect(record.bounds);
+            write_command(&record.command, &mut w);
+        }
+
+        Ok(w.0)
+    }
+
+    /// Deserializes a picture produced by [`to_bytes`](Self::to_bytes).
+    ///
+    /// All values are validated, so arbitrary data can be passed safely.
+    pub fn from_bytes(data: &[u8]) -> core::result::Result<Self, PictureError> {
+        let mut r = Reader(data);
+        if r.bytes(SIGNATURE.len())? != SIGNATURE {
+            return Err(PictureError::InvalidSignature);
+        }
+
+        let version = r.u16()?;
+        if version != VERSION {
+            return Err(PictureError::UnsupportedVersion(version));
+        }
+
+        let mut images = Vec::new();
+        for _ in 0..r.count(8)? {
+            images.push(read_image(&mut r)?);
+        }
+
+        let mut masks = Vec::new();
+        for _ in 0..r.count(8)? {
+            let size = r.size()?;
+            let len = (size.width() as usize)
+                .checked_mul(size.height() as usize)
+                .ok_or(PictureError::InvalidData)?;
+            let data = r.bytes(len)?;
+            masks.push(Mask::from_vec(data.to_vec(), size).ok_or(PictureError::InvalidData)?);
+        }
+
+        let mut records = Vec::new();
+        let mut bounds: Option<Rect> = None;
+        for _ in 0..r.count(17)? {
+            let record_bounds = r.rect()?;
+            let command = read_command(&mut r, &images, &masks)?;
+            bounds = Some(match bounds {
+                Some(bounds) => union(bounds, record_bounds),
+                None => record_bounds,
+            });
+            records.push(Record {
+                command,
+                bounds: record_bounds,
+            });
+        }
+
+        if !r.0.is_empty() {
+            return Err(PictureError::InvalidData);
+        }
+
+        Ok(Picture {
+            records,
+            images,
+            masks,
+            bounds,
+        })
+    }
+}
+
+// Command:
+//
+// FillRect:    Rect, Paint, Transform, Option<u32 mask>
+// FillPath:    Path, Paint, u8 fill rule, Transform, Option<u32 mask>
+// StrokePath:  Path, Paint, Stroke, Transform, Option<u32 mask>
+// DrawPixmap:  i32 x, i32 y, u32 image, f32 opacity, u8 blend mode, u8 quality,
+//              Transform, Option<u32 mask>
+// ApplyMask:   u32 mask
+fn has_custom_shader(command: &Command) -> bool {
+    match *command {
+        Command::FillRect { ref paint, .. }
+        | Command::FillPath { ref paint, .. }
+        | Command::StrokePath { ref paint, .. } => {
+            matches!(paint.shader, RecordedShader::Shader(Shader::Custom(_)))
+        }
+        Command::DrawPixmap { .. } | Command::ApplyMask { .. } => false,
+    }
+}
+
+fn write_command(command: &Command, w: &mut Writer) {
+    match *command {
+        Command::FillRect {
+            rect,
+            ref paint,
+            transform,
+            mask,
+        } => {
+            w.u8(FILL_RECT);
+            w.rect(rect);
+            write_paint(paint, w);
+            w.transform(transform);
+            w.index(mask);
+        }
+        Command::FillPath {
+            ref path,
+            ref paint,
+            fill_rule,
+            transform,
+            mask,
+        } => {
+            w.u8(FILL_PATH);
+            write_path(path, w);
+            write_paint(paint, w);
+            w.enumeration(&FILL_RULES, fill_rule);
+            w.transform(transform);
+            w.index(mask);
+        }
+        Command::StrokePath {
+            ref path,
+            ref paint,
+            ref stroke,
+            transform,
+            mask,
+        } => {
+            w.u8(STROKE_PATH);
+            write_path(path, w);
+            write_paint(paint, w);
+            write_stroke(stroke, w);
+            w.transform(transform);
+            w.index(mask);
+        }
+        Command::DrawPixmap {
+            x,
+            y,
+            image,
+            paint,
+            transform,
+            mask,
+        } => {
+            w.u8(DRAW_PIXMAP);
+            w.u32(x as u32);
+            w.u32(y as u32);
+            w.u32(image as u32);
+            w.f32(paint.opacity);
+            w.enumeration(&BLEND_MODES, paint.blend_mode);
+            w.enumeration(&FILTER_QUALITIES, paint.quality);
+            w.transform(transform);
+            w.index(mask);
+        }
+        Command::ApplyMask { mask } => {
+            w.u8(APPLY_MASK);
+            w.u32(mask as u32);
+        }
+    }
+}
+
+fn read_command(r: &mut Reader, images: &[Pixmap], masks: &[Mask]) -> Result<Command> {
+    let mask_index = |r: &mut Reader| -> Result<usize> {
+        let index = r.u32()? as usize;
+        if index < masks.len() {
+            Ok(index)
+        } else {
+            Err(PictureError::InvalidData)
+        }
+    };
+    let optional_mask_index = |r: &mut Reader| -> Result<Option<usize>> {
+        if r.bool()? {
+            mask_index(r).map(Some)
+        } else {
+            Ok(None)
+        }
+    };
+
+    match r.u8()? {
+        FILL_RECT => Ok(Command::FillRect {
+            rect: r.rect()?,
+            paint: read_paint(r, images)?,
+            transform: r.transform()?,
+            mask: optional_mask_index(r)?,
+        }),
+        FILL_PATH => {
+            let path = read_path(r)?;
+            let paint = read_paint(r, images)?;
+            let fill_rule = r.enumeration(&FILL_RULES)?;
+            let transform = r.transform()?;
+            check_path_size(&path, transform)?;
+            Ok(Command::FillPath {
+                path,
+                paint,
+                fill_rule,
+                transform,
+                mask: optional_mask_index(r)?,
+            })
+        }
+        STROKE_PATH => {
+            let path = read_path(r)?;
+            let paint = read_paint(r, images)?;
+            let stroke = read_stroke(r)?;
+            let transform = r.transform()?;
+            check_path_size(&path, transform)?;
+            Ok(Command::StrokePath {
+                path,
+                paint,
+                stroke,
+                transform,
+                mask: optional_mask_index(r)?,
+            })
+        }
+        DRAW_PIXMAP => {
+            let x = r.u32()? as i32;
+            let y = r.u32()? as i32;
+            let image = read_image_index(r, images)?;
+            let opacity = r.f32()?;
+            if !(0.0..=1.0).contains(&opacity) {
+                return Err(PictureError::InvalidData);
+            }
+
+            // The image rect must be representable.
+            let size = images[image].size();
+            IntRect::from_xywh(x, y, size.width(), size.height())
+                .ok_or(PictureError::InvalidData)?;
+
+            Ok(Command::DrawPixmap {
+                x,
+                y,
+                image,
+                paint: PixmapPaint {
+                    opacity,
+                    blend_mode: r.enumeration(&BLEND_MODES)?,
+                    quality: r.enumeration(&FILTER_QUALITIES)?,
+                },
+                transform: r.transform()?,
+                mask: optional_mask_index(r)?,
+            })
+        }
+        APPLY_MASK => Ok(Command::ApplyMask {
+            mask: mask_index(r)?,
+        }),
+        _ => Err(PictureError::InvalidData),
+    }
+}
+
+fn read_image(r: &mut Reader) -> Result<Pixmap> {
+    let size = r.size()?;
+    let len = (size.width() as usize)
+        .checked_mul(size.height() as usize)
+        .and_then(|n| n.checked_mul(4))
+        .ok_or(PictureError::InvalidData)?;
+    let data = r.bytes(len)?;
+
+    // Colors must be premultiplied.
+    for pixel in data.chunks_exact(4) {
+        if pixel[0] > pixel[3] || pixel[1] > pixel[3] || pixel[2] > pixel[3] {
+            return Err(PictureError::InvalidData);
+        }
+    }
+
+    Pixmap::from_vec(data.to_vec(), size).ok_or(PictureError::InvalidData)
+}
+
+fn read_image_index(r: &mut Reader, images: &[Pixmap]) -> Result<usize> {
+    let index = r.u32()? as usize;
+    if index < images.len() {
+        Ok(index)
+    } else {
+        Err(PictureError::InvalidData)
+    }
+}
+
+// Paint:   u8 shader type, shader, u8 blend mode, bool anti-alias, u8 rasterizer,
+//          u8 anti-aliasing quality, u8 color space, bool force high quality pipeline
+//
+// SolidColor:      Color
+// LinearGradient:  Gradient
+// RadialGradient:  Gradient, u8 kind, f32 start radius, f32 end radius
+// Pattern:         u32 image, u8 spread mode, u8 quality, f32 opacity, Transform
+// SweepGradient:   Gradient, f32 t bias, f32 t scale
+//
+// Gradient:    u32 stops count, then per stop: f32 position, Color,
+//              u8 spread mode, Transform, Transform points to unit,
+//              u8 interpolation color space, bool premultiplied interpolation
+fn write_paint(paint: &RecordedPaint, w: &mut Writer) {
+    match paint.shader {
+        RecordedShader::Shader(Shader::SolidColor(color)) => {
+            w.u8(SOLID_COLOR);
+            w.color(color);
+        }
+        RecordedShader::Shader(Shader::LinearGradient(ref g)) => {
+            w.u8(LINEAR_GRADIENT);
+            write_gradient(&g.base, w);
+        }
+        RecordedShader::Shader(Shader::RadialGradient(ref g)) => {
+            w.u8(RADIAL_GRADIENT);
+            write_gradient(&g.base, w);
+            w.u8(match g.kind {
+                RadialKind::Radial => 0,
+                RadialKind::Strip => 1,
+                RadialKind::Focal(_) => 2,
+            });
+            w.f32(g.r0);
+            w.f32(g.r1);
+        }
+        RecordedShader::Shader(Shader::SweepGradient(ref g)) => {
+            w.u8(SWEEP_GRADIENT);
+            write_gradient(&g.base, w);
+            w.f32(g.t_bias);
+            w.f32(g.t_scale);
+        }
+        RecordedShader::Shader(Shader::Pattern(_)) => {
+            unreachable!("patterns are recorded separately")
+        }
+        RecordedShader::Shader(Shader::Custom(_)) => {
+            unreachable!("pictures with custom shaders are not serialized")
+        }
+        RecordedShader::Pattern {
+            image,
+            spread_mode,
+            quality,
+            opacity,
+            transform,
+        } => {
+            w.u8(PATTERN);
+            w.u32(image as u32);
+            w.enumeration(&SPREAD_MODES, spread_mode);
+            w.enumeration(&FILTER_QUALITIES, quality);
+            w.f32(opacity);
+            w.transform(transform);
+        }
+    }
+
+    w.enumeration(&BLEND_MODES, paint.blend_mode);
+    w.bool(paint.anti_alias);
+    w.enumeration(&RASTERIZERS, paint.rasterizer);
+    w.enumeration(&AA_QUALITIES, paint.anti_alias_quality);
+    w.enumeration(&COLOR_SPACES, paint.colorspace);
+    w.bool(paint.force_hq_pipeline);
+}
+
+fn read_paint(r: &mut Reader, images: &[Pixmap]) -> Result<RecordedPaint> {
+    let shader = match r.u8()? {
+        SOLID_COLOR => RecordedShader::Shader(Shader::SolidColor(r.color()?)),
+        LINEAR_GRADIENT => RecordedShader::Shader(Shader::LinearGradient(LinearGradient {
+            base: read_gradient(r)?,
+        })),
+        RADIAL_GRADIENT => {
+            let base = read_gradient(r)?;
+            let kind = r.u8()?;
+            let r0 = r.f32()?;
+            let r1 = r.f32()?;
+            if !r0.is_finite() || !r1.is_finite() {
+                return Err(PictureError::InvalidData);
+            }
+
+            let kind = match kind {
+                0 => RadialKind::Radial,
+                1 => RadialKind::Strip,
+                2 => RadialKind::Focal(FocalData::new(r0, r1)),
+                _ => return Err(PictureError::InvalidData),
+            };
+
+            RecordedShader::Shader(Shader::RadialGradient(RadialGradient {
+                base,
+                kind,
+                r0,
+                r1,
+            }))
+        }
+        SWEEP_GRADIENT => {
+            let base = read_gradient(r)?;
+            let t_bias = r.f32()?;
+            let t_scale = r.f32()?;
+            if !t_bias.is_finite() || !t_scale.is_finite() {
+                return Err(PictureError::InvalidData);
+            }
+
+            RecordedShader::Shader(Shader::SweepGradient(SweepGradient {
+                base,
+                t_bias,
+                t_scale,
+            }))
+        }
+        PATTERN => {
+            let image = read_image_index(r, images)?;
+            let spread_mode = r.enumeration(&SPREAD_MODES)?;
+            let quality = r.enumeration(&FILTER_QUALITIES)?;
+            let opacity = r.f32()?;
+            if !(0.0..=1.0).contains(&opacity) {
+                return Err(PictureError::InvalidData);
+            }
+
+            RecordedShader::Pattern {
+                image,
+                spread_mode,
+                quality,
+                opacity,
+                transform: r.transform()?,
+            }
+        }
+        _ => return Err(PictureError::InvalidData),
+    };
+
+    Ok(RecordedPaint {
+        shader,
+        blend_mode: r.enumeration(&BLEND_MODES)?,
+        anti_alias: r.bool()?,
+        rasterizer: r.enumeration(&RASTERIZERS)?,
+        anti_alias_quality: r.enumeration(&AA_QUALITIES)?,
+        colorspace: r.enumeration(&COLOR_SPACES)?,
+        force_hq_pipeline: r.bool()?,
+    })
+}
+
+fn write_gradient(gradient: &Gradient, w: &mut Writer) {
+    w.u32(gradient.stops.len() as u32);
+    for stop in &gradient.stops {
+        w.f32(stop.position.get());
+        w.color(stop.color);
+    }
+
+    w.enumeration(&SPREAD_MODES, gradient.tile_mode);
+    w.transform(gradient.transform);
+    w.transform(gradient.points_to_unit);
+    w.enumeration(
+        &INTERPOLATION_COLOR_SPACES,
+        gradient.interpolation.color_space,
+    );
+    w.bool(gradient.interpolation.premultiplied);
+}
+
+fn read_gradient(r: &mut Reader) -> Result<Gradient> {
+    let count = r.count(20)?;
+    if count < 2 {
+        return Err(PictureError::InvalidData);
+    }
+
+    let mut stops = Vec::new();
+    for _ in 0..count {
+        let position = NormalizedF32::new(r.f32()?).ok_or(PictureError::InvalidData)?;
+        let color = r.color()?;
+        stops.push(GradientStop { position, color });
+    }
+
+    let tile_mode = r.enumeration(&SPREAD_MODES)?;
+    let transform = r.transform()?;
+    let points_to_unit = r.transform()?;
+    let interpolation = GradientInterpolation {
+        color_space: r.enumeration(&INTERPOLATION_COLOR_SPACES)?,
+        premultiplied: r.bool()?,
+    };
+
+    // Stops are normalized already, so this only recomputes cached properties.
+    let mut gradient = Gradient::new(stops, tile_mode, transform, points_to_unit);
+    gradient.interpolation = interpolation;
+    Ok(gradient)
+}
+
+fn write_path(path: &Path, w: &mut Writer) {
+    w.u32(path.verbs().len() as u32);
+    let mut points = path.points().iter();
+    for verb in path.verbs() {
+        let (tag, count) = match verb {
+            PathVerb::Move => (0, 1),
+            PathVerb::Line => (1, 1),
+            PathVerb::Quad => (2, 2),
+            PathVerb::Cubic => (3, 3),
+            PathVerb::Close => (4, 0),
+        };
+
+        w.u8(tag);
+        for p in points.by_ref().take(count) {
+            w.f32(p.x);
+            w.f32(p.y);
+        }
+    }
+}
+
+fn read_path(r: &mut Reader) -> Result<Path> {
+    let count = r.count(1)?;
+    let mut pb = PathBuilder::new();
+    for _ in 0..count {
+        match r.u8()? {
+            0 => {
+                let p = r.point()?;
+                pb.move_to(p.x, p.y);
+            }
+            1 => {
+                let p = r.point()?;
+                pb.line_to(p.x, p.y);
+            }
+            2 => {
+                let (p1, p2) = (r.point()?, r.point()?);
+                pb.quad_to(p1.x, p1.y, p2.x, p2.y);
+            }
+            3 => {
+                let (p1, p2, p3) = (r.point()?, r.point()?, r.point()?);
+                pb.cubic_to(p1.x, p1.y, p2.x, p2.y, p3.x, p3.y);
+            }
+            4 => pb.close(),
+            _ => return Err(PictureError::InvalidData),
+        }
+    }
+
+    // Validates that the path is not empty and all points are finite.
+    pb.finish().ok_or(PictureError::InvalidData)
+}
+
+// The recorder skips such paths, so they can only come from corrupted data.
+fn check_path_size(path: &Path, transform: Transform) -> Result<()> {
+    if is_too_big_for_math(path, transform) {
+        Err(PictureError::InvalidData)
+    } else {
+        Ok(())
+    }
+}
+
+// Stroke:  f32 width, f32 miter limit, u8 line cap, u8 line join, Option<Dash>,
+//          u8 alignment, u8 fill rule, Option<f32 precision>
+//
+// Dash:    u32 array length, f32 values, f32 offset,
+//          bool fit to contour, bool continue across contours, bool center on corners
+fn write_stroke(stroke: &Stroke, w: &mut Writer) {
+    w.f32(stroke.width);
+    w.f32(stroke.miter_limit);
+    w.enumeration(&LINE_CAPS, stroke.line_cap);
+    w.enumeration(&LINE_JOINS, stroke.line_join);
+    w.bool(stroke.dash.is_some());
+    if let Some(ref dash) = stroke.dash {
+        w.u32(dash.array().len() as u32);
+        for n in dash.array() {
+            w.f32(*n);
+        }
+
+        w.f32(dash.offset());
+        w.bool(dash.fit_to_contour());
+        w.bool(dash.continue_across_contours());
+        w.bool(dash.center_on_corners());
+    }
+
+    w.enumeration(&STROKE_ALIGNMENTS, stroke.alignment);
+    w.enumeration(&FILL_RULES, stroke.fill_rule);
+    w.bool(stroke.precision.is_some());
+    if let Some(precision) = stroke.precision {
+        w.f32(precision);
+    }
+}
+
+fn read_stroke(r: &mut Reader) -> Result<Stroke> {
+    let width = r.f32()?;
+    let miter_limit = r.f32()?;
+    if !(width >= 0.0 && width.is_finite() && miter_limit.is_finite()) {
+        return Err(PictureError::InvalidData);
+    }
+
+    let line_cap = r.enumeration(&LINE_CAPS)?;
+    let line_join = r.enumeration(&LINE_JOINS)?;
+
+    let dash = if r.bool()? {
+        let mut array = Vec::new();
+        for _ in 0..r.count(4)? {
+            array.push(r.f32()?);
+        }
+
+        let mut dash = StrokeDash::new(array, r.f32()?).ok_or(PictureError::InvalidData)?;
+        dash.set_fit_to_contour(r.bool()?);
+        dash.set_continue_across_contours(r.bool()?);
+        dash.set_center_on_corners(r.bool()?);
+        Some(dash)
+    } else {
+        None
+    };
+
+    let alignment = r.enumeration(&STROKE_ALIGNMENTS)?;
+    let fill_rule = r.enumeration(&FILL_RULES)?;
+    let precision = if r.bool()? { Some(r.f32()?) } else { None };
+
+    Ok(Stroke {
+        width,
+        miter_limit,
+        line_cap,
+        line_join,
+        dash,
+        alignment,
+        fill_rule,
+        precision,
+    })
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, n: u8) {
+        self.0.push(n);
+    }
+
+    fn u16(&mut self, n: u16) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn u32(&mut self, n: u32) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn f32(&mut self, n: f32) {
+        self.u32(n.to_bits());
+    }
+
+    fn bool(&mut self, flag: bool) {
+        self.u8(flag as u8);
+    }
+
+    fn enumeration<T: PartialEq + Copy>(&mut self, values: &[T], value: T) {
+        let index = values.iter().position(|v| *v == value).unwrap_or(0);
+        self.u8(index as u8);
+    }
+
+    fn index(&mut self, index: Option<usize>) {
+        self.bool(index.is_some());
+        if let Some(index) = index {
+            self.u32(index as u32);
+        }
+    }
+
+    fn rect(&mut self, rect: Rect) {
+        self.f32(rect.left());
+        self.f32(rect.top());
+        self.f32(rect.right());
+        self.f32(rect.bottom());
+    }
+
+    fn transform(&mut self, ts: Transform) {
+        for n in &[ts.sx, ts.ky, ts.kx, ts.sy, ts.tx, ts.ty] {
+            self.f32(*n);
+        }
+    }
+
+    fn color(&mut self, color: Color) {
+        self.f32(color.red());
+        self.f32(color.green());
+        self.f32(color.blue());
+        self.f32(color.alpha());
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.0.len() {
+            return Err(PictureError::UnexpectedEof);
+        }
+
+        let (data, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(data)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let data = self.bytes(2)?;
+        Ok(u16::from_le_bytes([data[0], data[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let data = self.bytes(4)?;
+        Ok(u32::from_le_bytes([data[0], data[1], data[2], data[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PictureError::InvalidData),
+        }
+    }
+
+    // Reads a number of items, each taking at least `min_len` bytes.
+    //
+    // Rejects counts that cannot fit into the remaining data,
+    // so a malformed count cannot cause a huge allocation.
+    fn count(&mut self, min_len: usize) -> Result<usize> {
+        let count = self.u32()? as usize;
+        match count.checked_mul(min_len) {
+            Some(len) if len <= self.0.len() => Ok(count),
+            _ => Err(PictureError::UnexpectedEof),
+        }
+    }
+
+    fn enumeration<T: Copy>(&mut self, values: &[T]) -> Result<T> {
+        let index = self.u8()? as usize;
+        values.get(index).copied().ok_or(PictureError::InvalidData)
+    }
+
+    fn size(&mut self) -> Result<IntSize> {
+        let width = self.u32()?;
+        let height = self.u32()?;
+        IntSize::from_wh(width, height).ok_or(PictureError::InvalidData)
+    }
+
+    fn point(&mut self) -> Result<Point> {
+        Ok(Point::from_xy(self.f32()?, self.f32()?))
+    }
+
+    fn rect(&mut self) -> Result<Rect> {
+        let (l, t, r, b) = (self.f32()?, self.f32()?, self.f32()?, self.f32()?);
+        Rect::from_ltrb(l, t, r, b).ok_or(PictureError::InvalidData)
+    }
+
+    fn transform(&mut self) -> Result<Transform> {
+        let mut n = [0.0; 6];
+        for v in &mut n {
+            *v = self.f32()?;
+        }
+
+        let ts = Transform::from_row(n[0], n[1], n[2], n[3], n[4], n[5]);
+        if ts.is_finite() {
+            Ok(ts)
+        } else {
+            Err(PictureError::InvalidData)
+        }
+    }
+
+    fn color(&mut self) -> Result<Color> {
+        let (r, g, b, a) = (self.f32()?, self.f32()?, self.f32()?, self.f32()?);
+        Color::from_rgba(r, g, b, a).ok_or(PictureError::InvalidData)
+    }
+}
@@ -0,0 +1,650 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+mod format;
+mod pdf;
+#[cfg(feature = "png-format")]
+mod svg;
+
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use tiny_skia_path::{IntSize, Path, Point, Rect, Stroke, Transform};
+
+use crate::{AntiAliasQuality, BlendMode, ColorSpace, FillRule, FilterQuality, Mask, MaskType};
+use crate::{Paint, Pattern, Pixmap, PixmapMut, PixmapPaint, PixmapRef, Rasterizer};
+use crate::{Shader, SpreadMode};
+
+use crate::color::{premultiply_u8, PremultipliedColorU8};
+
+#[cfg(all(not(feature = "std"), feature = "no-std-float"))]
+use tiny_skia_path::NoStdFloat;
+
+pub use format::PictureError;
+pub use pdf::PdfDocument;
+
+#[derive(Clone, PartialEq, Debug)]
+enum RecordedShader {
+    // Anything but a pattern.
+    Shader(Shader<'static>),
+    Pattern {
+        // An index in `Picture::images`.
+        image: usize,
+        spread_mode: SpreadMode,
+        quality: FilterQuality,
+        opacity: f32,
+        transform: Transform,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct RecordedPaint {
+    shader: RecordedShader,
+    blend_mode: BlendMode,
+    anti_alias: bool,
+    rasterizer: Rasterizer,
+    anti_alias_quality: AntiAliasQuality,
+    colorspace: ColorSpace,
+    force_hq_pipeline: bool,
+}
+
+impl RecordedPaint {
+    fn to_paint<'a>(&self, images: &'a [Pixmap], origin: (i32, i32)) -> Paint<'a> {
+        let mut shader = match self.shader {
+            RecordedShader::Shader(ref shader) => shader.clone(),
+            RecordedShader::Pattern {
+                image,
+                spread_mode,
+                quality,
+                opacity,
+                transform,
+            } => Pattern::new(
+                images[image].as_ref(),
+                spread_mode,
+                quality,
+                opacity,
+                transform,
+            ),
+        };
+        shader.offset_origin(origin.0, origin.1);
+
+        Paint {
+            shader,
+            blend_mode: self.blend_mode,
+            anti_alias: self.anti_alias,
+            rasterizer: self.rasterizer,
+            anti_alias_quality: self.anti_alias_quality,
+            colorspace: self.colorspace,
+            force_hq_pipeline: self.force_hq_pipeline,
+        }
+    }
+}
+
+// Mask indices point to `Picture::masks`, image indices to `Picture::images`.
+#[derive(Clone, PartialEq, Debug)]
+enum Command {
+    FillRect {
+        rect: Rect,
+        paint: RecordedPaint,
+        transform: Transform,
+        mask: Option<usize>,
+    },
+    FillPath {
+        path: Path,
+        paint: RecordedPaint,
+        fill_rule: FillRule,
+        transform: Transform,
+        mask: Option<usize>,
+    },
+    StrokePath {
+        path: Path,
+        paint: RecordedPaint,
+        stroke: Stroke,
+        transform: Transform,
+        mask: Option<usize>,
+    },
+    DrawPixmap {
+        x: i32,
+        y: i32,
+        image: usize,
+        paint: PixmapPaint,
+        transform: Transform,
+        mask: Option<usize>,
+    },
+    ApplyMask {
+        mask: usize,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Record {
+    command: Command,
+    // Device bounds of the affected area in the recording coordinates.
+    bounds: Rect,
+}
+
+/// A recorded list of drawing commands.
+///
+/// Created by [`PictureRecorder`] and can be played back onto any [`PixmapMut`]
+/// via [`PixmapMut::draw_picture`], any number of times.
+///
+/// Owns copies of all pixmaps and masks used by the recorded commands
+/// and can be stored via [`to_bytes`](Self::to_bytes), unless it uses custom shaders.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Picture {
+    records: Vec<Record>,
+    images: Vec<Pixmap>,
+    masks: Vec<Mask>,
+    bounds: Option<Rect>,
+}
+
+impl Picture {
+    /// Returns the cull bounds of the picture in the recording coordinates.
+    ///
+    /// Nothing outside of this rectangle is affected during playback
+    /// with an identity transform.
+    ///
+    /// Returns `None` for an empty picture.
+    pub fn bounds(&self) -> Option<Rect> {
+        self.bounds
+    }
+
+    /// Returns the number of recorded commands.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Checks that the picture has no commands.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+/// A drawing commands recorder.
+///
+/// Has the same drawing methods as [`PixmapMut`], but instead of rendering,
+/// records them into a [`Picture`].
+///
+/// Masks are in the recording coordinates and are placed at the origin.
+/// Commands that wouldn't affect any pixels, e.g. because of an invalid transform,
+/// are not recorded. Neither are paths with coordinates close to the `f32` limits
+/// after the transform is applied.
+#[derive(Clone, Default, Debug)]
+pub struct PictureRecorder {
+    records: Vec<Record>,
+    images: Vec<Pixmap>,
+    masks: Vec<Mask>,
+}
+
+impl PictureRecorder {
+    /// Creates a new, empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a filled rectangle.
+    ///
+    /// See [`PixmapMut::fill_rect`] for details.
+    pub fn fill_rect(
+        &mut self,
+        rect: Rect,
+        paint: &Paint,
+        transform: Transform,
+        mask: Option<&Mask>,
+    ) {
+        let bounds = match map_rect(rect, transform) {
+            Some(v) => v,
+            None => return,
+        };
+
+        let paint = self.record_paint(paint);
+        let mask = self.record_mask(mask);
+        self.push(
+            Command::FillRect {
+                rect,
+                paint,
+                transform,
+                mask,
+            },
+            bounds,
+        );
+    }
+
+    /// Records a filled path.
+    ///
+    /// See [`PixmapMut::fill_path`] for details.
+    pub fn fill_path(
+        &mut self,
+        path: &Path,
+        paint: &Paint,
+        fill_rule: FillRule,
+        transform: Transform,
+        mask: Option<&Mask>,
+    ) {
+        if is_too_big_for_math(path, transform) {
+            return;
+        }
+
+        let bounds = match map_rect(path.bounds(), transform) {
+            Some(v) => v,
+            None => return,
+        };
+
+        let paint = self.record_paint(paint);
+        let mask = self.record_mask(mask);
+        self.push(
+            Command::FillPath {
+                path: path.clone(),
+                paint,
+                fill_rule,
+                transform,
+                mask,
+            },
+            bounds,
+        );
+    }
+
+    /// Records a stroked path.
+    ///
+    /// See [`PixmapMut::stroke_path`] for details.
+    pub fn stroke_path(
+        &mut self,
+        path: &Path,
+        paint: &Paint,
+        stroke: &Stroke,
+        transform: Transform,
+        mask: Option<&Mask>,
+    ) {
+        if is_too_big_for_math(path, transform) {
+            return;
+        }
+
+        let bounds = match path.compute_stroke_bounds(stroke, transform) {
+            Some(v) => v,
+            None => return,
+        };
+
+        let paint = self.record_paint(paint);
+        let mask = self.record_mask(mask);
+        self.push(
+            Command::StrokePath {
+                path: path.clone(),
+                paint,
+                stroke: stroke.clone(),
+                transform,
+                mask,
+            },
+            bounds,
+        );
+    }
+
+    /// Records a `Pixmap` drawing.
+    ///
+    /// See [`PixmapMut::draw_pixmap`] for details.
+    pub fn draw_pixmap(
+        &mut self,
+        x: i32,
+        y: i32,
+        pixmap: PixmapRef,
+        paint: &PixmapPaint,
+        transform: Transform,
+        mask: Option<&Mask>,
+    ) {
+        let rect = pixmap.size().to_int_rect(x, y).to_rect();
+        let bounds = match map_rect(rect, transform) {
+            Some(v) => v,
+            None => return,
+        };
+
+        let image = self.record_image(pixmap);
+        let mask = self.record_mask(mask);
+        self.push(
+            Command::DrawPixmap {
+                x,
+                y,
+                image,
+                paint: *paint,
+                transform,
+                mask,
+            },
+            bounds,
+        );
+    }
+
+    /// Records a mask application.
+    ///
+    /// See [`PixmapMut::apply_mask`] for details.
+    ///
+    /// During playback, only the area covered by the mask is affected.
+    pub fn apply_mask(&mut self, mask: &Mask) {
+        let bounds = mask.size().to_int_rect(0, 0).to_rect();
+        let mask = self.record_mask(Some(mask)).unwrap();
+        self.records.push(Record {
+            command: Command::ApplyMask { mask },
+            bounds,
+        });
+    }
+
+    /// Finishes recording.
+    pub fn finish(self) -> Picture {
+        let mut bounds: Option<Rect> = None;
+        for record in &self.records {
+            bounds = Some(match bounds {
+                Some(bounds) => union(bounds, record.bounds),
+                None => record.bounds,
+            });
+        }
+
+        Picture {
+            records: self.records,
+            images: self.images,
+            masks: self.masks,
+            bounds,
+        }
+    }
+
+    fn push(&mut self, command: Command, bounds: Rect) {
+        // Anti-aliasing and filtering can touch an extra pixel.
+        let mut bounds = match bounds.outset(1.0, 1.0) {
+            Some(v) => v,
+            None => return,
+        };
+
+        let mask = match command {
+            Command::FillRect { mask, .. }
+            | Command::FillPath { mask, .. }
+            | Command::StrokePath { mask, .. }
+            | Command::DrawPixmap { mask, .. } => mask,
+            Command::ApplyMask { .. } => None,
+        };
+
+        if let Some(index) = mask {
+            let mask_rect = self.masks[index].size().to_int_rect(0, 0).to_rect();
+            bounds = match bounds.intersect(&mask_rect) {
+                Some(v) => v,
+                None => return, // masked out
+            };
+        }
+
+        self.records.push(Record { command, bounds });
+    }
+
+    fn record_paint(&mut self, paint: &Paint) -> RecordedPaint {
+        let shader = match paint.shader {
+            Shader::SolidColor(color) => RecordedShader::Shader(Shader::SolidColor(color)),
+            Shader::LinearGradient(ref g) => {
+                RecordedShader::Shader(Shader::LinearGradient(g.clone()))
+            }
+            Shader::RadialGradient(ref g) => {
+                RecordedShader::Shader(Shader::RadialGradient(g.clone()))
+            }
+            Shader::SweepGradient(ref g) => {
+                RecordedShader::Shader(Shader::SweepGradient(g.clone()))
+            }
+            Shader::Pattern(ref p) => RecordedShader::Pattern {
+                image: self.record_image(p.pixmap),
+                spread_mode: p.spread_mode,
+                quality: p.quality,
+                opacity: p.opacity.get(),
+                transform: p.transform,
+            },
+            Shader::Custom(ref c) => RecordedShader::Shader(Shader::Custom(c.clone())),
+        };
+
+        RecordedPaint {
+            shader,
+            blend_mode: paint.blend_mode,
+            anti_alias: paint.anti_alias,
+            rasterizer: paint.rasterizer,
+            anti_alias_quality: paint.anti_alias_quality,
+            colorspace: paint.colorspace,
+            force_hq_pipeline: paint.force_hq_pipeline,
+        }
+    }
+
+    // Consecutive commands usually share the same image or clip mask,
+    // so we reuse the last stored copy when it's equal.
+    fn record_image(&mut self, pixmap: PixmapRef) -> usize {
+        if self.images.last().map(|image| image.as_ref()) != Some(pixmap) {
+            self.images.push(pixmap.to_owned());
+        }
+
+        self.images.len() - 1
+    }
+
+    fn record_mask(&mut self, mask: Option<&Mask>) -> Option<usize> {
+        let mask = mask?;
+        if self.masks.last() != Some(mask) {
+            self.masks.push(mask.clone());
+        }
+
+        Some(self.masks.len() - 1)
+    }
+}
+
+impl PixmapMut<'_> {
+    /// Draws a `Picture`.
+    ///
+    /// `transform` is applied on top of the recorded transforms and masks.
+    /// `mask` clips all recorded commands and must have the pixmap size.
+    ///
+    /// Commands that are outside of the pixmap are skipped.
+    pub fn draw_picture(&mut self, picture: &Picture, transform: Transform, mask: Option<&Mask>) {
+        self.draw_picture_at(picture, transform, mask, (0, 0));
+    }
+
+    // `origin` is the device position of the pixmap, which custom shaders are relative to.
+    pub(crate) fn draw_picture_at(
+        &mut self,
+        picture: &Picture,
+        transform: Transform,
+        mask: Option<&Mask>,
+        origin: (i32, i32),
+    ) {
+        if let Some(mask) = mask {
+            if mask.size() != self.size() {
+                log::warn!("Pixmap and Mask are expected to have the same size");
+                return;
+            }
+        }
+
+        let target = self.size().to_int_rect(0, 0).to_rect();
+        let is_visible = |bounds: Rect| {
+            map_rect(bounds, transform)
+                .and_then(|bounds| bounds.intersect(&target))
+                .is_some()
+        };
+
+        match picture.bounds {
+            Some(bounds) if is_visible(bounds) => {}
+            _ => return,
+        }
+
+        let size = self.size();
+
+        // Recorded masks adjusted to the pixmap, created on demand.
+        let mut masks: Vec<Option<Cow<Mask>>> = vec![None; picture.masks.len()];
+
+        for record in &picture.records {
+            if !is_visible(record.bounds) {
+                continue;
+            }
+
+            let mask_index = match record.command {
+                Command::FillRect { mask, .. }
+                | Command::FillPath { mask, .. }
+                | Command::StrokePath { mask, .. }
+                | Command::DrawPixmap { mask, .. } => mask,
+                Command::ApplyMask { .. } => None,
+            };
+
+            let draw_mask = match mask_index {
+                Some(index) => {
+                    if masks[index].is_none() {
+                        masks[index] = prepare_mask(&picture.masks[index], transform, size, mask);
+                    }
+
+                    match masks[index] {
+                        Some(ref v) => Some(v.as_ref()),
+                        None => continue,
+                    }
+                }
+                None => mask,
+            };
+
+            match record.command {
+                Command::FillRect {
+                    rect,
+                    ref paint,
+                    transform: ts,
+                    ..
+                } => {
+                    let paint = paint.to_paint(&picture.images, origin);
+                    self.fill_rect(rect, &paint, transform.pre_concat(ts), draw_mask);
+                }
+                Command::FillPath {
+                    ref path,
+                    ref paint,
+                    fill_rule,
+                    transform: ts,
+                    ..
+                } => {
+                    let paint = paint.to_paint(&picture.images, origin);
+                    self.fill_path(path, &paint, fill_rule, transform.pre_concat(ts), draw_mask);
+                }
+                Command::StrokePath {
+                    ref path,
+                    ref paint,
+                    ref stroke,
+                    transform: ts,
+                    ..
+                } => {
+                    let paint = paint.to_paint(&picture.images, origin);
+                    self.stroke_path(path, &paint, stroke, transform.pre_concat(ts), draw_mask);
+                }
+                Command::DrawPixmap {
+                    x,
+                    y,
+                    image,
+                    ref paint,
+                    transform: ts,
+                    ..
+                } => {
+                    let pixmap = picture.images[image].as_ref();
+                    self.draw_pixmap(x, y, pixmap, paint, transform.pre_concat(ts), draw_mask);
+                }
+                Command::ApplyMask { mask: index } => {
+                    let recorded = &picture.masks[index];
+                    if mask.is_none() && transform.is_identity() && recorded.size() == size {
+                        self.apply_mask(recorded);
+                        continue;
+                    }
+
+                    // Compute the amount of content to remove, which is zero outside
+                    // of the recorded mask and the clip.
+                    if let Some(mut erase) = transform_mask(recorded, transform, size, true) {
+                        if let Some(mask) = mask {
+                            multiply_masks(&mut erase, mask);
+                        }
+                        erase.invert();
+                        self.apply_mask(&erase);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Adjusts a recorded mask to the target pixmap and intersects it with the clip.
+fn prepare_mask<'a>(
+    recorded: &'a Mask,
+    ts: Transform,
+    size: IntSize,
+    clip: Option<&Mask>,
+) -> Option<Cow<'a, Mask>> {
+    if clip.is_none() && ts.is_identity() && recorded.size() == size {
+        return Some(Cow::Borrowed(recorded));
+    }
+
+    let mut mask = transform_mask(recorded, ts, size, false)?;
+    if let Some(clip) = clip {
+        multiply_masks(&mut mask, clip);
+    }
+
+    Some(Cow::Owned(mask))
+}
+
+// Resamples a mask into a new mask of the specified size.
+//
+// When `inverted` is set, stores inverted mask values.
+// Pixels outside the transformed mask are zero in both cases.
+fn transform_mask(mask: &Mask, ts: Transform, size: IntSize, inverted: bool) -> Option<Mask> {
+    let mut src = Pixmap::new(mask.width(), mask.height())?;
+    for (p, a) in src.pixels_mut().iter_mut().zip(mask.data()) {
+        let a = if inverted { 255 - *a } else { *a };
+        *p = PremultipliedColorU8::from_rgba(0, 0, 0, a)?;
+    }
+
+    // Pixel-aligned transforms do not require filtering.
+    let quality = if ts.is_translate() && ts.tx.trunc() == ts.tx && ts.ty.trunc() == ts.ty {
+        FilterQuality::Nearest
+    } else {
+        FilterQuality::Bilinear
+    };
+
+    let paint = PixmapPaint {
+        quality,
+        ..PixmapPaint::default()
+    };
+
+    let mut dst = Pixmap::new(size.width(), size.height())?;
+    dst.draw_pixmap(0, 0, src.as_ref(), &paint, ts, None);
+    Some(Mask::from_pixmap(dst.as_ref(), MaskType::Alpha))
+}
+
+fn multiply_masks(mask: &mut Mask, other: &Mask) {
+    for (a, b) in mask.data_mut().iter_mut().zip(other.data()) {
+        *a = premultiply_u8(*a, *b);
+    }
+}
+
+// Checks whether the path is too big to be drawn.
+// Like the painter, checks the path after the transform is applied,
+// since it may bring large coordinates back into range.
+fn is_too_big_for_math(path: &Path, ts: Transform) -> bool {
+    if ts.is_identity() {
+        return crate::painter::is_too_big_for_math(path);
+    }
+
+    match path.clone().transform(ts) {
+        Some(path) => crate::painter::is_too_big_for_math(&path),
+        None => true,
+    }
+}
+
+// Returns the bounding box of a transformed rectangle.
+fn map_rect(rect: Rect, ts: Transform) -> Option<Rect> {
+    if ts.is_identity() {
+        return Some(rect);
+    }
+
+    let mut points = [
+        Point::from_xy(rect.left(), rect.top()),
+        Point::from_xy(rect.right(), rect.top()),
+        Point::from_xy(rect.right(), rect.bottom()),
+        Point::from_xy(rect.left(), rect.bottom()),
+    ];
+    ts.map_points(&mut points);
+    Rect::from_points(&points)
+}
+
+fn union(a: Rect, b: Rect) -> Rect {
+    Rect::from_ltrb(
+        a.left().min(b.left()),
+        a.top().min(b.top()),
+        a.right().max(b.right()),
+        a.bottom().max(b.bottom()),
+    )
+    .unwrap_or(a)
+}
@@ -0,0 +1,978 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+/*!
+A minimal PDF writer.
+
+Each page is a [`Picture`] converted into a content stream. The page content is stored
+inside a form XObject with a top-left origin and a y-down axis, so all transforms, patterns
+and masks are in the recording coordinates, just like during playback.
+
+- Solid colors are converted into `DeviceRGB` colors.
+- Gradients are converted into axial and radial shadings painted inside a clip.
+  Non-opaque stops are handled by a luminosity soft mask with an identical `DeviceGray` shading.
+- Patterns are converted into tiling patterns.
+- Images are stored as uncompressed `DeviceRGB` image XObjects with an optional soft mask.
+- Masks are stored as `DeviceGray` images inside luminosity soft masks.
+- Blend modes are set via extended graphics states.
+
+Objects `1` and `2` are always the catalog and the page tree.
+*/
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use tiny_skia_path::{LineCap, LineJoin, Path, PathSegment, PathStroker, Point, Stroke};
+use tiny_skia_path::{StrokeAlignment, Transform};
+
+use crate::shaders::{Gradient, RadialKind};
+use crate::InterpolationColorSpace;
+use crate::{BlendMode, Color, FillRule, GradientStop, Pixmap, Shader, SpreadMode};
+
+use super::{Command, Picture, RecordedPaint, RecordedShader};
+
+// The number of repeated gradient tiles in each direction.
+// Shadings cannot be repeated, so repeating and reflecting gradients are unrolled.
+const GRADIENT_REPEATS: i32 = 16;
+
+const CATALOG_ID: usize = 1;
+const PAGES_ID: usize = 2;
+
+impl Picture {
+    /// Converts the picture into a single-page PDF document of the specified size.
+    ///
+    /// A shorthand for [`PdfDocument::add_page`] and [`PdfDocument::finish`].
+    pub fn to_pdf(&self, width: u32, height: u32) -> Vec<u8> {
+        let mut doc = PdfDocument::new();
+        doc.add_page(self, width, height);
+        doc.finish()
+    }
+}
+
+/// A PDF document writer.
+///
+/// Pages are [`Picture`]s, so anything that can be drawn onto a [`PixmapMut`](crate::PixmapMut)
+/// can be recorded via [`PictureRecorder`](crate::PictureRecorder) and written into a PDF.
+/// One pixel is one PDF point.
+///
+/// Some features have no PDF equivalent and are approximated:
+///
+/// - Porter-Duff blend modes, other than `SourceOver`, are ignored.
+/// - Patterns are always repeated.
+/// - Repeating and reflecting gradients are padded after 16 repetitions.
+/// - `FilterQuality`, `ColorSpace` and anti-aliasing flags are ignored.
+/// - Gradients are interpolated in sRGB, without premultiplication.
+/// - Sweep gradients and custom shaders are not drawn.
+#[derive(Clone, Default, Debug)]
+pub struct PdfDocument {
+    // Objects starting from id 3.
+    objects: Vec<Vec<u8>>,
+    // Page object ids.
+    pages: Vec<usize>,
+}
+
+impl PdfDocument {
+    /// Creates a new, empty document.
+    pub fn new() -> Self {
+        PdfDocument::default()
+    }
+
+    /// Appends a page of the specified size.
+    pub fn add_page(&mut self, picture: &Picture, width: u32, height: u32) {
+        let page_id = self.alloc();
+        let resources_id = self.alloc();
+
+        let mut writer = PageWriter {
+            doc: self,
+            picture,
+            width,
+            height,
+            resources_id,
+            content: String::new(),
+            ext_g_states: Vec::new(),
+            shadings: Vec::new(),
+            patterns: Vec::new(),
+            xobjects: Vec::new(),
+            images: vec![None; picture.images.len()],
+            masks: vec![None; picture.masks.len()],
+        };
+
+        for record in &picture.records {
+            writer.write_command(&record.command);
+        }
+
+        let body = core::mem::take(&mut writer.content);
+        let body_name = writer.form(body);
+        let resources = writer.resources();
+
+        let content = format!("1 0 0 -1 0 {} cm\n/{} Do\n", height, body_name);
+        let content_id = self.alloc();
+        self.set(content_id, stream("", content.as_bytes()));
+        self.set(resources_id, resources.into_bytes());
+        self.set(
+            page_id,
+            format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] \
+                 /Resources {} 0 R /Contents {} 0 R >>",
+                PAGES_ID, width, height, resources_id, content_id,
+            )
+            .into_bytes(),
+        );
+
+        self.pages.push(page_id);
+    }
+
+    /// Returns the number of pages.
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Writes the document.
+    ///
+    /// A document without pages is still valid, but most readers will not open it.
+    pub fn finish(self) -> Vec<u8> {
+        let mut kids = String::new();
+        for (i, id) in self.pages.iter().enumerate() {
+            if i != 0 {
+                kids.push(' ');
+            }
+            let _ = write!(kids, "{} 0 R", id);
+        }
+
+        let catalog = format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES_ID);
+        let pages = format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids,
+            self.pages.len()
+        );
+
+        let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len() + 2);
+        let header = [catalog.as_bytes(), pages.as_bytes()];
+        let objects = header
+            .iter()
+            .copied()
+            .chain(self.objects.iter().map(|o| o.as_slice()));
+        for (i, object) in objects.enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = pdf.len();
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(xref, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            xref,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.objects.len() + 3,
+            CATALOG_ID,
+            xref_offset,
+        );
+        pdf.extend_from_slice(xref.as_bytes());
+        pdf
+    }
+
+    fn alloc(&mut self) -> usize {
+        self.objects.push(Vec::new());
+        self.objects.len() + 2
+    }
+
+    fn set(&mut self, id: usize, data: Vec<u8>) {
+        self.objects[id - 3] = data;
+    }
+
+    fn add(&mut self, data: Vec<u8>) -> usize {
+        let id = self.alloc();
+        self.set(id, data);
+        id
+    }
+}
+
+// How a shape should be painted.
+enum PaintOps {
+    // Color or pattern operators.
+    Color {
+        ops: String,
+        ext_g_state: Option<String>,
+    },
+    // A shading that should be painted inside a clip.
+    Shading {
+        name: String,
+        // Maps the shading space into the user space.
+        transform: Transform,
+        ext_g_state: Option<String>,
+    },
+}
+
+struct PageWriter<'a> {
+    doc: &'a mut PdfDocument,
+    picture: &'a Picture,
+    width: u32,
+    height: u32,
+    // Shared by the page and all forms.
+    resources_id: usize,
+    content: String,
+    // Resource entries. Names are prefixes followed by indices.
+    ext_g_states: Vec<String>,
+    shadings: Vec<usize>,
+    patterns: Vec<usize>,
+    xobjects: Vec<(String, usize)>,
+    // Object ids of already written images and mask groups.
+    images: Vec<Option<usize>>,
+    masks: Vec<Option<usize>>,
+}
+
+impl PageWriter<'_> {
+    fn write_command(&mut self, command: &Command) {
+        let (content, blend_mode, mask) = match *command {
+            Command::FillRect {
+                rect,
+                ref paint,
+                transform,
+                mask,
+            } => {
+                let path = format!(
+                    "{} {} {} {} re\n",
+                    rect.x(),
+                    rect.y(),
+                    rect.width(),
+                    rect.height()
+                );
+                let content = self.fill(&path, paint, FillRule::Winding, transform);
+                (content, paint.blend_mode, mask)
+            }
+            Command::FillPath {
+                ref path,
+                ref paint,
+                fill_rule,
+                transform,
+                mask,
+            } => {
+                let content = self.fill(&path_data(path), paint, fill_rule, transform);
+                (content, paint.blend_mode, mask)
+            }
+            Command::StrokePath {
+                ref path,
+                ref paint,
+                ref stroke,
+                transform,
+                mask,
+            } => {
+                let content = match self.stroke(path, paint, stroke, transform) {
+                    Some(v) => v,
+                    None => return,
+                };
+                (content, paint.blend_mode, mask)
+            }
+            Command::DrawPixmap {
+                x,
+                y,
+                image,
+                paint,
+                transform,
+                mask,
+            } => {
+                let name = self.image(image);
+                let size = self.picture.images[image].size();
+
+                let mut content = String::from("q\n");
+                if paint.opacity != 1.0 {
+                    let gs = self.ext_g_state(format!("/ca {}", paint.opacity));
+                    let _ = writeln!(content, "/{} gs", gs);
+                }
+                content.push_str(&cm(transform));
+                content.push_str(&cm(image_transform(x as f32, y as f32, size)));
+                let _ = write!(content, "/{} Do\nQ\n", name);
+                (content, paint.blend_mode, mask)
+            }
+            Command::ApplyMask { mask } => {
+                let body = core::mem::take(&mut self.content);
+                let form = self.form(body);
+                let soft_mask = self.soft_mask(mask);
+                let gs = self.ext_g_state(format!("/SMask {}", soft_mask));
+                let _ = write!(self.content, "q\n/{} gs\n/{} Do\nQ\n", gs, form);
+                return;
+            }
+        };
+
+        let mut dict = String::new();
+        if let Some(name) = blend_mode_name(blend_mode) {
+            let _ = write!(dict, "/BM /{}", name);
+        }
+
+        // Paint soft masks would override the mask, therefore the command is painted
+        // into a group, which is then masked.
+        let content = match mask {
+            Some(index) => {
+                let form = self.form(content);
+                if !dict.is_empty() {
+                    dict.push(' ');
+                }
+                let _ = write!(dict, "/SMask {}", self.soft_mask(index));
+                format!("/{} Do\n", form)
+            }
+            None => content,
+        };
+
+        if dict.is_empty() {
+            self.content.push_str(&content);
+        } else {
+            let gs = self.ext_g_state(dict);
+            let _ = write!(self.content, "q\n/{} gs\n{}Q\n", gs, content);
+        }
+    }
+
+    fn fill(
+        &mut self,
+        path: &str,
+        paint: &RecordedPaint,
+        fill_rule: FillRule,
+        transform: Transform,
+    ) -> String {
+        let even_odd = if fill_rule == FillRule::EvenOdd {
+            "*"
+        } else {
+            ""
+        };
+
+        let ops = match self.paint(paint, false, transform) {
+            Some(v) => v,
+            None => return String::new(),
+        };
+
+        let mut content = String::from("q\n");
+        match ops {
+            PaintOps::Color { ops, ext_g_state } => {
+                if let Some(gs) = ext_g_state {
+                    let _ = writeln!(content, "/{} gs", gs);
+                }
+                content.push_str(&cm(transform));
+                let _ = write!(content, "{}\n{}f{}\n", ops, path, even_odd);
+            }
+            PaintOps::Shading {
+                name,
+                transform: shading_ts,
+                ext_g_state,
+            } => {
+                if let Some(gs) = ext_g_state {
+                    let _ = writeln!(content, "/{} gs", gs);
+                }
+                content.push_str(&cm(transform));
+                let _ = writeln!(content, "{}W{} n", path, even_odd);
+                content.push_str(&cm(shading_ts));
+                let _ = writeln!(content, "/{} sh", name);
+            }
+        }
+        content.push_str("Q\n");
+        content
+    }
+
+    // Returns `None` when there is nothing to draw.
+    fn stroke(
+        &mut self,
+        path: &Path,
+        paint: &RecordedPaint,
+        stroke: &Stroke,
+        transform: Transform,
+    ) -> Option<String> {
+        // Shadings can only fill the clip, so gradient strokes are always filled outlines.
+        let is_shading = matches!(
+            paint.shader,
+            RecordedShader::Shader(Shader::LinearGradient(_))
+                | RecordedShader::Shader(Shader::RadialGradient(_))
+        );
+
+        let is_simple = match stroke.dash {
+            Some(ref dash) => {
+                !dash.fit_to_contour()
+                    && !dash.continue_across_contours()
+                    && !dash.center_on_corners()
+            }
+            None => true,
+        };
+
+        if is_shading || !is_simple || stroke.alignment != StrokeAlignment::Center {
+            let res_scale = PathStroker::compute_resolution_scale(&transform);
+            let outline = path.stroke(stroke, res_scale)?;
+            return Some(self.fill(&path_data(&outline), paint, FillRule::Winding, transform));
+        }
+
+        let (ops, ext_g_state) = match self.paint(paint, true, transform)? {
+            PaintOps::Color { ops, ext_g_state } => (ops, ext_g_state),
+            PaintOps::Shading { .. } => return None,
+        };
+
+        let mut content = String::from("q\n");
+        if let Some(gs) = ext_g_state {
+            let _ = writeln!(content, "/{} gs", gs);
+        }
+        content.push_str(&cm(transform));
+        content.push_str(&ops);
+        content.push('\n');
+
+        // A zero width is the thinnest line that can be rendered, i.e. a hairline.
+        let _ = write!(content, "{} w", stroke.width);
+
+        match stroke.line_cap {
+            LineCap::Butt => {}
+            LineCap::Round => content.push_str(" 1 J"),
+            LineCap::Square => content.push_str(" 2 J"),
+        }
+
+        match stroke.line_join {
+            LineJoin::Miter | LineJoin::MiterClip => {}
+            LineJoin::Round => content.push_str(" 1 j"),
+            LineJoin::Bevel => content.push_str(" 2 j"),
+        }
+
+        if stroke.miter_limit != 10.0 {
+            let _ = write!(content, " {} M", stroke.miter_limit);
+        }
+
+        if let Some(ref dash) = stroke.dash {
+            content.push_str(" [");
+            for (i, n) in dash.array().iter().enumerate() {
+                if i != 0 {
+                    content.push(' ');
+                }
+                let _ = write!(content, "{}", n);
+            }
+            let _ = write!(content, "] {} d", dash.offset());
+        }
+
+        let _ = write!(content, "\n{}S\nQ\n", path_data(path));
+        Some(content)
+    }
+
+    // `transform` is the command transform and is required by patterns and soft masks,
+    // which are not affected by the current transformation matrix.
+    fn paint(
+        &mut self,
+        paint: &RecordedPaint,
+        stroke: bool,
+        transform: Transform,
+    ) -> Option<PaintOps> {
+        let opacity = |opacity: f32| {
+            if opacity == 1.0 {
+                None
+            } else if stroke {
+                Some(format!("/CA {}", opacity))
+            } else {
+                Some(format!("/ca {}", opacity))
+            }
+        };
+
+        let ops = match paint.shader {
+            RecordedShader::Shader(Shader::SolidColor(color)) => {
+                let ops = format!(
+                    "{} {} {} {}",
+                    color.red(),
+                    color.green(),
+                    color.blue(),
+                    if stroke { "RG" } else { "rg" }
+                );
+                PaintOps::Color {
+                    ops,
+                    ext_g_state: opacity(color.alpha()).map(|dict| self.ext_g_state(dict)),
+                }
+            }
+            RecordedShader::Shader(Shader::LinearGradient(ref g)) => {
+                self.shading(&g.base, ShadingKind::Axial, transform)?
+            }
+            RecordedShader::Shader(Shader::RadialGradient(ref g)) => {
+                let kind = match g.kind {
+                    RadialKind::Radial => ShadingKind::Radial(0.0, g.r0, g.r1),
+                    RadialKind::Strip | RadialKind::Focal(_) => {
+                        ShadingKind::Radial(1.0, g.r0, g.r1)
+                    }
+                };
+                let unscale = g.unscale()?;
+
+                let mut base = g.base.clone();
+                base.points_to_unit = base.points_to_unit.post_concat(unscale);
+                self.shading(&base, kind, transform)?
+            }
+            RecordedShader::Shader(Shader::Pattern(_)) => return None,
+            RecordedShader::Shader(Shader::SweepGradient(_)) => {
+                log::warn!("sweep gradients cannot be converted into PDF");
+                return None;
+            }
+            RecordedShader::Shader(Shader::Custom(_)) => {
+                log::warn!("custom shaders cannot be converted into PDF");
+                return None;
+            }
+            RecordedShader::Pattern {
+                image,
+                opacity: pattern_opacity,
+                transform: pattern_ts,
+                ..
+            } => {
+                let image_id = self.image(image);
+                let size = self.picture.images[image].size();
+                let content = format!("{}/Im0 Do\n", cm(image_transform(0.0, 0.0, size)));
+
+                // Pattern matrices map into the form space and ignore the current transform.
+                let id = self.doc.add(stream(
+                    &format!(
+                        "/Type /Pattern /PatternType 1 /PaintType 1 /TilingType 1 \
+                         /BBox [0 0 {w} {h}] /XStep {w} /YStep {h} /Matrix {m} \
+                         /Resources << /XObject << /Im0 {id} 0 R >> >>",
+                        w = size.width(),
+                        h = size.height(),
+                        m = matrix(transform.pre_concat(pattern_ts)),
+                        id = image_id,
+                    ),
+                    content.as_bytes(),
+                ));
+
+                self.patterns.push(id);
+                let ops = format!(
+                    "/Pattern {} /P{} {}",
+                    if stroke { "CS" } else { "cs" },
+                    self.patterns.len() - 1,
+                    if stroke { "SCN" } else { "scn" },
+                );
+                PaintOps::Color {
+                    ops,
+                    ext_g_state: opacity(pattern_opacity).map(|dict| self.ext_g_state(dict)),
+                }
+            }
+        };
+
+        Some(ops)
+    }
+
+    // Writes a gradient shading and returns its paint operations.
+    //
+    // Gradients are stored in the unit space, so we have to map the unit space back
+    // into the user space.
+    fn shading(
+        &mut self,
+        gradient: &Gradient,
+        kind: ShadingKind,
+        transform: Transform,
+    ) -> Option<PaintOps> {
+        let points_to_unit = gradient.points_to_unit.invert()?;
+        let shading_ts = gradient.transform.pre_concat(points_to_unit);
+
+        let is_srgb = matches!(
+            gradient.interpolation.color_space,
+            InterpolationColorSpace::Destination | InterpolationColorSpace::SRGB
+        );
+        if !is_srgb || gradient.interpolation.premultiplied {
+            log::warn!("gradient interpolation settings are not supported by PDF");
+        }
+
+        let color = self
+            .doc
+            .add(shading_dict(gradient, kind, false).into_bytes());
+        self.shadings.push(color);
+        let name = format!("Sh{}", self.shadings.len() - 1);
+
+        // Alpha is stored in a luminosity soft mask, which has to repeat all transforms,
+        // since it ignores the current transform as well.
+        let ext_g_state = if gradient.colors_are_opaque {
+            None
+        } else {
+            let alpha = self
+                .doc
+                .add(shading_dict(gradient, kind, true).into_bytes());
+            let content = format!("{}{}/Sh0 sh\n", cm(transform), cm(shading_ts));
+            let group = self.doc.add(stream(
+                &format!(
+                    "/Type /XObject /Subtype /Form /BBox [0 0 {} {}] \
+                     /Group << /S /Transparency /CS /DeviceGray >> \
+                     /Resources << /Shading << /Sh0 {} 0 R >> >>",
+                    self.width, self.height, alpha,
+                ),
+                content.as_bytes(),
+            ));
+
+            let dict = format!("/SMask << /Type /Mask /S /Luminosity /G {} 0 R >>", group);
+            Some(self.ext_g_state(dict))
+        };
+
+        Some(PaintOps::Shading {
+            name,
+            transform: shading_ts,
+            ext_g_state,
+        })
+    }
+
+    // Writes an image once and returns its XObject name.
+    fn image(&mut self, index: usize) -> String {
+        let name = format!("Im{}", index);
+        if self.images[index].is_some() {
+            return name;
+        }
+
+        let image = &self.picture.images[index];
+        let mut rgb = Vec::with_capacity(image.pixels().len() * 3);
+        let mut alpha = Vec::with_capacity(image.pixels().len());
+        for p in image.pixels() {
+            let c = p.demultiply();
+            rgb.extend_from_slice(&[c.red(), c.green(), c.blue()]);
+            alpha.push(c.alpha());
+        }
+
+        let soft_mask = if alpha.iter().all(|a| *a == 255) {
+            String::new()
+        } else {
+            let id = self.doc.add(image_stream(image, "DeviceGray", "", &alpha));
+            format!(" /SMask {} 0 R", id)
+        };
+
+        let id = self
+            .doc
+            .add(image_stream(image, "DeviceRGB", &soft_mask, &rgb));
+        self.images[index] = Some(id);
+        self.xobjects.push((name.clone(), id));
+        name
+    }
+
+    // Writes a mask group once and returns a soft mask dictionary.
+    fn soft_mask(&mut self, index: usize) -> String {
+        let id = match self.masks[index] {
+            Some(id) => id,
+            None => {
+                let mask = &self.picture.masks[index];
+                let image = self.doc.add(stream(
+                    &format!(
+                        "/Type /XObject /Subtype /Image /Width {} /Height {} \
+                         /ColorSpace /DeviceGray /BitsPerComponent 8",
+                        mask.width(),
+                        mask.height(),
+                    ),
+                    mask.data(),
+                ));
+
+                // The group backdrop is black, so everything outside the mask is masked out.
+                let content = format!("{}/Im0 Do\n", cm(image_transform(0.0, 0.0, mask.size())));
+                let id = self.doc.add(stream(
+                    &format!(
+                        "/Type /XObject /Subtype /Form /BBox [0 0 {} {}] \
+                         /Group << /S /Transparency /CS /DeviceGray >> \
+                         /Resources << /XObject << /Im0 {} 0 R >> >>",
+                        mask.width(),
+                        mask.height(),
+                        image,
+                    ),
+                    content.as_bytes(),
+                ));
+                self.masks[index] = Some(id);
+                id
+            }
+        };
+
+        format!("<< /Type /Mask /S /Luminosity /G {} 0 R >>", id)
+    }
+
+    // Writes a transparency group of the page size and returns its XObject name.
+    fn form(&mut self, content: String) -> String {
+        let id = self.doc.add(stream(
+            &format!(
+                "/Type /XObject /Subtype /Form /BBox [0 0 {} {}] \
+                 /Group << /S /Transparency >> /Resources {} 0 R",
+                self.width, self.height, self.resources_id,
+            ),
+            content.as_bytes(),
+        ));
+
+        let name = format!("Fm{}", self.xobjects.len());
+        self.xobjects.push((name.clone(), id));
+        name
+    }
+
+    // Adds an extended graphics state once and returns its name.
+    fn ext_g_state(&mut self, dict: String) -> String {
+        let index = match self.ext_g_states.iter().position(|d| *d == dict) {
+            Some(index) => index,
+            None => {
+                self.ext_g_states.push(dict);
+                self.ext_g_states.len() - 1
+            }
+        };
+
+        format!("GS{}", index)
+    }
+
+    fn resources(&self) -> String {
+        let mut s = String::from("<<");
+
+        if !self.ext_g_states.is_empty() {
+            s.push_str(" /ExtGState <<");
+            for (i, dict) in self.ext_g_states.iter().enumerate() {
+                let _ = write!(s, " /GS{} << {} >>", i, dict);
+            }
+            s.push_str(" >>");
+        }
+
+        if !self.shadings.is_empty() {
+            s.push_str(" /Shading <<");
+            for (i, id) in self.shadings.iter().enumerate() {
+                let _ = write!(s, " /Sh{} {} 0 R", i, id);
+            }
+            s.push_str(" >>");
+        }
+
+        if !self.patterns.is_empty() {
+            s.push_str(" /Pattern <<");
+            for (i, id) in self.patterns.iter().enumerate() {
+                let _ = write!(s, " /P{} {} 0 R", i, id);
+            }
+            s.push_str(" >>");
+        }
+
+        if !self.xobjects.is_empty() {
+            s.push_str(" /XObject <<");
+            for (name, id) in &self.xobjects {
+                let _ = write!(s, " /{} {} 0 R", name, id);
+            }
+            s.push_str(" >>");
+        }
+
+        s.push_str(" >>");
+        s
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ShadingKind {
+    Axial,
+    // Contains the end center x, the start radius and the end radius.
+    // The start center is at (0, 0) and the end center at (x, 0).
+    Radial(f32, f32, f32),
+}
+
+fn shading_dict(gradient: &Gradient, kind: ShadingKind, alpha: bool) -> String {
+    // The gradient `t` range covered by the shading.
+    let (start, end) = match (gradient.tile_mode, kind) {
+        (SpreadMode::Pad, _) => (0, 1),
+        (_, ShadingKind::Axial) => (-GRADIENT_REPEATS, GRADIENT_REPEATS),
+        (_, ShadingKind::Radial(_, r0, r1)) if r1 < r0 => {
+            // Stop before the radius becomes negative.
+            let end = (r0 / (r0 - r1)) as i32;
+            (0, end.clamp(1, GRADIENT_REPEATS))
+        }
+        (_, _) => (0, GRADIENT_REPEATS),
+    };
+
+    let (shading_type, coords) = match kind {
+        ShadingKind::Axial => (2, format!("{} 0 {} 0", start, end)),
+        ShadingKind::Radial(x1, r0, r1) => {
+            let end = end as f32;
+            let coords = format!("0 0 {} {} 0 {}", r0, end * x1, r0 + end * (r1 - r0));
+            (3, coords)
+        }
+    };
+
+    let stops = stops_function(&gradient.stops, alpha);
+    let function = if gradient.tile_mode == SpreadMode::Pad {
+        stops
+    } else {
+        let mut functions = String::new();
+        let mut bounds = String::new();
+        let mut encode = String::new();
+        for t in start..end {
+            if t != start {
+                let _ = write!(bounds, "{} ", t);
+            }
+            functions.push_str(&stops);
+            functions.push(' ');
+
+            let reflect = gradient.tile_mode == SpreadMode::Reflect && t.rem_euclid(2) == 1;
+            encode.push_str(if reflect { "1 0 " } else { "0 1 " });
+        }
+
+        format!(
+            "<< /FunctionType 3 /Domain [{} {}] /Functions [{}] /Bounds [{}] /Encode [{}] >>",
+            start,
+            end,
+            functions.trim_end(),
+            bounds.trim_end(),
+            encode.trim_end(),
+        )
+    };
+
+    format!(
+        "<< /ShadingType {} /ColorSpace /{} /Coords [{}] /Domain [{} {}] \
+         /Function {} /Extend [true true] >>",
+        shading_type,
+        if alpha { "DeviceGray" } else { "DeviceRGB" },
+        coords,
+        start,
+        end,
+        function,
+    )
+}
+
+// Returns a function that maps `t` in the 0..1 range into a color or alpha.
+fn stops_function(stops: &[GradientStop], alpha: bool) -> String {
+    let color = |c: Color| {
+        if alpha {
+            format!("[{}]", c.alpha())
+        } else {
+            format!("[{} {} {}]", c.red(), c.green(), c.blue())
+        }
+    };
+
+    let mut functions: Vec<String> = stops
+        .windows(2)
+        .map(|w| {
+            format!(
+                "<< /FunctionType 2 /Domain [0 1] /C0 {} /C1 {} /N 1 >>",
+                color(w[0].color),
+                color(w[1].color),
+            )
+        })
+        .collect();
+
+    // `Gradient` always has at least two stops.
+    if functions.len() == 1 {
+        return functions.remove(0);
+    }
+
+    let bounds: Vec<String> = stops[1..stops.len() - 1]
+        .iter()
+        .map(|s| format!("{}", s.position.get()))
+        .collect();
+
+    format!(
+        "<< /FunctionType 3 /Domain [0 1] /Functions [{}] /Bounds [{}] /Encode [{}] >>",
+        functions.join(" "),
+        bounds.join(" "),
+        vec!["0 1"; functions.len()].join(" "),
+    )
+}
+
+fn stream(dict: &str, data: &[u8]) -> Vec<u8> {
+    let mut s = Vec::with_capacity(data.len() + dict.len() + 40);
+    s.extend_from_slice(b"<< ");
+    s.extend_from_slice(dict.as_bytes());
+    if !dict.is_empty() {
+        s.push(b' ');
+    }
+    s.extend_from_slice(format!("/Length {} >>\nstream\n", data.len()).as_bytes());
+    s.extend_from_slice(data);
+    s.extend_from_slice(b"\nendstream");
+    s
+}
+
+fn image_stream(image: &Pixmap, color_space: &str, extra: &str, data: &[u8]) -> Vec<u8> {
+    stream(
+        &format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} \
+             /ColorSpace /{} /BitsPerComponent 8{}",
+            image.width(),
+            image.height(),
+            color_space,
+            extra,
+        ),
+        data,
+    )
+}
+
+// Images are painted into a unit square with the first row at the top.
+fn image_transform(x: f32, y: f32, size: tiny_skia_path::IntSize) -> Transform {
+    let h = size.height() as f32;
+    Transform::from_row(size.width() as f32, 0.0, 0.0, -h, x, y + h)
+}
+
+fn path_data(path: &Path) -> String {
+    let mut d = String::new();
+    let mut start = Point::zero();
+    let mut last = Point::zero();
+    for segment in path.segments() {
+        let _ = match segment {
+            PathSegment::MoveTo(p) => {
+                start = p;
+                last = p;
+                writeln!(d, "{} {} m", p.x, p.y)
+            }
+            PathSegment::LineTo(p) => {
+                last = p;
+                writeln!(d, "{} {} l", p.x, p.y)
+            }
+            PathSegment::QuadTo(p1, p) => {
+                // PDF has no quadratic curves.
+                let c1 = lerp(last, p1, 2.0 / 3.0);
+                let c2 = lerp(p, p1, 2.0 / 3.0);
+                last = p;
+                writeln!(d, "{} {} {} {} {} {} c", c1.x, c1.y, c2.x, c2.y, p.x, p.y)
+            }
+            PathSegment::CubicTo(p1, p2, p) => {
+                last = p;
+                writeln!(d, "{} {} {} {} {} {} c", p1.x, p1.y, p2.x, p2.y, p.x, p.y)
+            }
+            PathSegment::Close => {
+                last = start;
+                writeln!(d, "h")
+            }
+        };
+    }
+
+    d
+}
+
+fn lerp(a: Point, b: Point, t: f32) -> Point {
+    Point::from_xy(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+fn cm(ts: Transform) -> String {
+    if ts.is_identity() {
+        return String::new();
+    }
+
+    format!("{} cm\n", numbers(ts))
+}
+
+fn matrix(ts: Transform) -> String {
+    format!("[{}]", numbers(ts))
+}
+
+fn numbers(ts: Transform) -> String {
+    // Adding a zero turns a negative zero into a positive one.
+    format!(
+        "{} {} {} {} {} {}",
+        ts.sx + 0.0,
+        ts.ky + 0.0,
+        ts.kx + 0.0,
+        ts.sy + 0.0,
+        ts.tx + 0.0,
+        ts.ty + 0.0
+    )
+}
+
+fn blend_mode_name(mode: BlendMode) -> Option<&'static str> {
+    let name = match mode {
+        BlendMode::SourceOver => return None,
+        BlendMode::Multiply => "Multiply",
+        BlendMode::Screen => "Screen",
+        BlendMode::Overlay => "Overlay",
+        BlendMode::Darken => "Darken",
+        BlendMode::Lighten => "Lighten",
+        BlendMode::ColorDodge => "ColorDodge",
+        BlendMode::ColorBurn => "ColorBurn",
+        BlendMode::HardLight => "HardLight",
+        BlendMode::SoftLight => "SoftLight",
+        BlendMode::Difference => "Difference",
+        BlendMode::Exclusion => "Exclusion",
+        BlendMode::Hue => "Hue",
+        BlendMode::Saturation => "Saturation",
+        BlendMode::Color => "Color",
+        BlendMode::Luminosity => "Luminosity",
+        _ => {
+            log::warn!("{:?} blend mode is not supported by PDF", mode);
+            return None;
+        }
+    };
+
+    Some(name)
+}
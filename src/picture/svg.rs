@@ -0,0 +1,558 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use tiny_skia_path::{LineCap, LineJoin, Path, PathSegment, Stroke, StrokeAlignment};
+use tiny_skia_path::{PathStroker, Transform};
+
+use crate::shaders::{Gradient, RadialKind};
+use crate::InterpolationColorSpace;
+use crate::{BlendMode, Color, FillRule, FilterQuality, Mask, Pixmap, Shader, SpreadMode};
+
+use super::{Command, Picture, RecordedPaint, RecordedShader};
+
+impl Picture {
+    /// Converts the picture into an SVG document of the specified size.
+    ///
+    /// Shaders are converted into SVG gradients and patterns, blend modes into
+    /// `mix-blend-mode` and masks into `<mask>` elements. Images and masks are embedded
+    /// as PNG data.
+    ///
+    /// Some features have no SVG equivalent and are approximated:
+    ///
+    /// - Porter-Duff blend modes, other than `SourceOver` and `Plus`, are ignored.
+    /// - Patterns are always repeated.
+    /// - Strokes that cannot be represented by SVG stroke properties are filled outlines.
+    /// - `ColorSpace` is ignored.
+    /// - Gradients are interpolated in sRGB or linear sRGB, without premultiplication.
+    /// - Sweep gradients and custom shaders are not drawn.
+    pub fn to_svg(&self, width: u32, height: u32) -> String {
+        let mut writer = SvgWriter {
+            picture: self,
+            defs: String::new(),
+            body: String::new(),
+            images: vec![false; self.images.len()],
+            masks: vec![false; self.masks.len()],
+            next_id: 0,
+        };
+
+        for record in &self.records {
+            writer.write_command(&record.command);
+        }
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+             xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+             width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+            w = width,
+            h = height,
+        );
+
+        if !writer.defs.is_empty() {
+            svg.push_str("<defs>\n");
+            svg.push_str(&writer.defs);
+            svg.push_str("</defs>\n");
+        }
+
+        svg.push_str(&writer.body);
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+struct SvgWriter<'a> {
+    picture: &'a Picture,
+    defs: String,
+    body: String,
+    // Whether images and masks are already written to `defs`.
+    images: Vec<bool>,
+    masks: Vec<bool>,
+    next_id: usize,
+}
+
+impl SvgWriter<'_> {
+    fn write_command(&mut self, command: &Command) {
+        let (element, blend_mode, mask) = match *command {
+            Command::FillRect {
+                rect,
+                ref paint,
+                transform,
+                mask,
+            } => {
+                let element = format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}{}/>",
+                    rect.x(),
+                    rect.y(),
+                    rect.width(),
+                    rect.height(),
+                    self.fill(paint, FillRule::Winding),
+                    transform_attr("transform", transform),
+                );
+                (element, paint.blend_mode, mask)
+            }
+            Command::FillPath {
+                ref path,
+                ref paint,
+                fill_rule,
+                transform,
+                mask,
+            } => {
+                let element = format!(
+                    "<path d=\"{}\"{}{}/>",
+                    path_data(path),
+                    self.fill(paint, fill_rule),
+                    transform_attr("transform", transform),
+                );
+                (element, paint.blend_mode, mask)
+            }
+            Command::StrokePath {
+                ref path,
+                ref paint,
+                ref stroke,
+                transform,
+                mask,
+            } => {
+                let element = match self.stroke(paint, stroke) {
+                    Some(attrs) => format!(
+                        "<path d=\"{}\" fill=\"none\"{}{}/>",
+                        path_data(path),
+                        attrs,
+                        transform_attr("transform", transform),
+                    ),
+                    None => {
+                        let res_scale = PathStroker::compute_resolution_scale(&transform);
+                        let outline = match path.stroke(stroke, res_scale) {
+                            Some(v) => v,
+                            None => return,
+                        };
+
+                        format!(
+                            "<path d=\"{}\"{}{}/>",
+                            path_data(&outline),
+                            self.fill(paint, FillRule::Winding),
+                            transform_attr("transform", transform),
+                        )
+                    }
+                };
+                (element, paint.blend_mode, mask)
+            }
+            Command::DrawPixmap {
+                x,
+                y,
+                image,
+                paint,
+                transform,
+                mask,
+            } => {
+                let id = self.image(image);
+                let mut element = format!("<use xlink:href=\"#{}\" x=\"{}\" y=\"{}\"", id, x, y);
+                if paint.opacity != 1.0 {
+                    let _ = write!(element, " opacity=\"{}\"", paint.opacity);
+                }
+                if paint.quality == FilterQuality::Nearest {
+                    element.push_str(" image-rendering=\"optimizeSpeed\"");
+                }
+                element.push_str(&transform_attr("transform", transform));
+                element.push_str("/>");
+                (element, paint.blend_mode, mask)
+            }
+            Command::ApplyMask { mask } => {
+                let id = self.mask(mask);
+                let body = core::mem::take(&mut self.body);
+                let _ = write!(self.body, "<g mask=\"url(#{})\">\n{}</g>\n", id, body);
+                return;
+            }
+        };
+
+        let style = match blend_mode_name(blend_mode) {
+            Some(name) => format!(" style=\"mix-blend-mode:{}\"", name),
+            None => String::new(),
+        };
+
+        // Masked groups are isolated, so blending must be set on the group itself.
+        match mask {
+            Some(index) => {
+                let id = self.mask(index);
+                let _ = writeln!(
+                    self.body,
+                    "<g mask=\"url(#{})\"{}>{}</g>",
+                    id, style, element
+                );
+            }
+            None if style.is_empty() => {
+                self.body.push_str(&element);
+                self.body.push('\n');
+            }
+            None => {
+                let _ = writeln!(self.body, "<g{}>{}</g>", style, element);
+            }
+        }
+    }
+
+    // Returns the fill attributes.
+    fn fill(&mut self, paint: &RecordedPaint, fill_rule: FillRule) -> String {
+        let mut attrs = self.paint("fill", paint);
+        if fill_rule == FillRule::EvenOdd {
+            attrs.push_str(" fill-rule=\"evenodd\"");
+        }
+        attrs
+    }
+
+    // Returns the stroke attributes or `None` when the stroke has to be converted into a fill.
+    fn stroke(&mut self, paint: &RecordedPaint, stroke: &Stroke) -> Option<String> {
+        if stroke.alignment != StrokeAlignment::Center {
+            return None;
+        }
+
+        if let Some(ref dash) = stroke.dash {
+            if dash.fit_to_contour() || dash.continue_across_contours() || dash.center_on_corners()
+            {
+                return None;
+            }
+        }
+
+        let mut attrs = self.paint("stroke", paint);
+        if stroke.width == 0.0 {
+            // Hairlines are always one pixel wide.
+            attrs.push_str(" stroke-width=\"1\" vector-effect=\"non-scaling-stroke\"");
+        } else {
+            let _ = write!(attrs, " stroke-width=\"{}\"", stroke.width);
+        }
+
+        match stroke.line_cap {
+            LineCap::Butt => {}
+            LineCap::Round => attrs.push_str(" stroke-linecap=\"round\""),
+            LineCap::Square => attrs.push_str(" stroke-linecap=\"square\""),
+        }
+
+        match stroke.line_join {
+            LineJoin::Miter => {}
+            LineJoin::MiterClip => attrs.push_str(" stroke-linejoin=\"miter-clip\""),
+            LineJoin::Round => attrs.push_str(" stroke-linejoin=\"round\""),
+            LineJoin::Bevel => attrs.push_str(" stroke-linejoin=\"bevel\""),
+        }
+
+        if stroke.miter_limit != 4.0 {
+            let _ = write!(attrs, " stroke-miterlimit=\"{}\"", stroke.miter_limit);
+        }
+
+        if let Some(ref dash) = stroke.dash {
+            attrs.push_str(" stroke-dasharray=\"");
+            for (i, n) in dash.array().iter().enumerate() {
+                if i != 0 {
+                    attrs.push(' ');
+                }
+                let _ = write!(attrs, "{}", n);
+            }
+            attrs.push('"');
+
+            if dash.offset() != 0.0 {
+                let _ = write!(attrs, " stroke-dashoffset=\"{}\"", dash.offset());
+            }
+        }
+
+        Some(attrs)
+    }
+
+    // Returns `fill` or `stroke` paint attributes, adding shaders to `defs`.
+    fn paint(&mut self, kind: &str, paint: &RecordedPaint) -> String {
+        let mut attrs = match paint.shader {
+            RecordedShader::Shader(Shader::SolidColor(color)) => {
+                let mut attrs = format!(" {}=\"{}\"", kind, color_value(color));
+                if !color.is_opaque() {
+                    let _ = write!(attrs, " {}-opacity=\"{}\"", kind, color.alpha());
+                }
+                attrs
+            }
+            RecordedShader::Shader(Shader::LinearGradient(ref g)) => {
+                match self.gradient("linearGradient", &g.base, Transform::identity(), "") {
+                    Some(id) => format!(" {}=\"url(#{})\"", kind, id),
+                    None => format!(" {}=\"none\"", kind),
+                }
+            }
+            RecordedShader::Shader(Shader::RadialGradient(ref g)) => {
+                let mut attrs = match g.kind {
+                    RadialKind::Radial => format!(" cx=\"0\" cy=\"0\" r=\"{}\"", g.r1),
+                    RadialKind::Strip | RadialKind::Focal(_) => {
+                        format!(" fx=\"0\" fy=\"0\" cx=\"1\" cy=\"0\" r=\"{}\"", g.r1)
+                    }
+                };
+                if g.r0 != 0.0 {
+                    let _ = write!(attrs, " fr=\"{}\"", g.r0);
+                }
+
+                let gradient = g
+                    .unscale()
+                    .and_then(|unscale| self.gradient("radialGradient", &g.base, unscale, &attrs));
+
+                match gradient {
+                    Some(id) => format!(" {}=\"url(#{})\"", kind, id),
+                    None => format!(" {}=\"none\"", kind),
+                }
+            }
+            RecordedShader::Shader(Shader::Pattern(_)) => format!(" {}=\"none\"", kind),
+            RecordedShader::Shader(Shader::SweepGradient(_)) => {
+                log::warn!("sweep gradients cannot be converted into SVG");
+                format!(" {}=\"none\"", kind)
+            }
+            RecordedShader::Shader(Shader::Custom(_)) => {
+                log::warn!("custom shaders cannot be converted into SVG");
+                format!(" {}=\"none\"", kind)
+            }
+            RecordedShader::Pattern {
+                image,
+                quality,
+                opacity,
+                transform,
+                ..
+            } => {
+                let image_id = self.image(image);
+                let id = self.next_id("pattern");
+                let size = self.picture.images[image].size();
+                let _ = write!(
+                    self.defs,
+                    "<pattern id=\"{}\" patternUnits=\"userSpaceOnUse\" \
+                     width=\"{}\" height=\"{}\"{}><use xlink:href=\"#{}\"",
+                    id,
+                    size.width(),
+                    size.height(),
+                    transform_attr("patternTransform", transform),
+                    image_id,
+                );
+                if opacity != 1.0 {
+                    let _ = write!(self.defs, " opacity=\"{}\"", opacity);
+                }
+                if quality == FilterQuality::Nearest {
+                    self.defs.push_str(" image-rendering=\"optimizeSpeed\"");
+                }
+                self.defs.push_str("/></pattern>\n");
+
+                format!(" {}=\"url(#{})\"", kind, id)
+            }
+        };
+
+        if !paint.anti_alias {
+            attrs.push_str(" shape-rendering=\"crispEdges\"");
+        }
+
+        attrs
+    }
+
+    // Writes a gradient to `defs` and returns its id.
+    //
+    // Gradients are stored in the unit space, so we have to map the unit space back
+    // into the user space using `gradientTransform`.
+    fn gradient(
+        &mut self,
+        tag: &str,
+        gradient: &Gradient,
+        unscale: Transform,
+        attrs: &str,
+    ) -> Option<String> {
+        let points_to_unit = gradient.points_to_unit.post_concat(unscale).invert()?;
+        let ts = gradient.transform.pre_concat(points_to_unit);
+
+        let spread_method = match gradient.tile_mode {
+            SpreadMode::Pad => "",
+            SpreadMode::Reflect => " spreadMethod=\"reflect\"",
+            SpreadMode::Repeat => " spreadMethod=\"repeat\"",
+        };
+
+        let color_interpolation = match gradient.interpolation.color_space {
+            InterpolationColorSpace::Destination | InterpolationColorSpace::SRGB => "",
+            InterpolationColorSpace::LinearSRGB => " color-interpolation=\"linearRGB\"",
+            InterpolationColorSpace::OKLab | InterpolationColorSpace::OKLCH(_) => {
+                log::warn!("OKLab and OKLCH gradients are interpolated in sRGB in SVG");
+                ""
+            }
+        };
+        if gradient.interpolation.premultiplied {
+            log::warn!("premultiplied gradient interpolation is not supported by SVG");
+        }
+
+        let id = self.next_id("gradient");
+        let _ = writeln!(
+            self.defs,
+            "<{} id=\"{}\" gradientUnits=\"userSpaceOnUse\"{}{}{}{}>",
+            tag,
+            id,
+            attrs,
+            spread_method,
+            color_interpolation,
+            transform_attr("gradientTransform", ts),
+        );
+
+        for stop in &gradient.stops {
+            let _ = write!(
+                self.defs,
+                "<stop offset=\"{}\" stop-color=\"{}\"",
+                stop.position.get(),
+                color_value(stop.color),
+            );
+            if !stop.color.is_opaque() {
+                let _ = write!(self.defs, " stop-opacity=\"{}\"", stop.color.alpha());
+            }
+            self.defs.push_str("/>\n");
+        }
+
+        let _ = writeln!(self.defs, "</{}>", tag);
+        Some(id)
+    }
+
+    // Writes an image to `defs` once and returns its id.
+    fn image(&mut self, index: usize) -> String {
+        let id = format!("image{}", index);
+        if !self.images[index] {
+            self.images[index] = true;
+            let image = &self.picture.images[index];
+            let _ = writeln!(
+                self.defs,
+                "<image id=\"{}\" width=\"{}\" height=\"{}\" xlink:href=\"{}\"/>",
+                id,
+                image.width(),
+                image.height(),
+                png_data_url(image),
+            );
+        }
+
+        id
+    }
+
+    // Writes a mask to `defs` once and returns its id.
+    fn mask(&mut self, index: usize) -> String {
+        let id = format!("mask{}", index);
+        if !self.masks[index] {
+            self.masks[index] = true;
+            let mask = &self.picture.masks[index];
+            let _ = writeln!(
+                self.defs,
+                "<mask id=\"{id}\" maskUnits=\"userSpaceOnUse\" x=\"0\" y=\"0\" \
+                 width=\"{w}\" height=\"{h}\"><image width=\"{w}\" height=\"{h}\" \
+                 xlink:href=\"{data}\"/></mask>",
+                id = id,
+                w = mask.width(),
+                h = mask.height(),
+                data = png_data_url(&mask_to_luminance(mask)),
+            );
+        }
+
+        id
+    }
+
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}{}", prefix, self.next_id)
+    }
+}
+
+// SVG masks use luminance, so a mask is stored as an opaque grayscale image.
+fn mask_to_luminance(mask: &Mask) -> Pixmap {
+    // A mask size is always valid.
+    let mut pixmap = Pixmap::new(mask.width(), mask.height()).unwrap();
+    for (p, a) in pixmap.data_mut().chunks_exact_mut(4).zip(mask.data()) {
+        p.copy_from_slice(&[*a, *a, *a, 255]);
+    }
+
+    pixmap
+}
+
+fn png_data_url(pixmap: &Pixmap) -> String {
+    match pixmap.encode_png() {
+        Ok(data) => format!("data:image/png;base64,{}", base64(&data)),
+        Err(_) => String::new(),
+    }
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut s = String::with_capacity(data.len() / 3 * 4 + 4);
+    for chunk in data.chunks(3) {
+        let n = (u32::from(chunk[0]) << 16)
+            | (u32::from(*chunk.get(1).unwrap_or(&0)) << 8)
+            | u32::from(*chunk.get(2).unwrap_or(&0));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (n >> (18 - i * 6)) & 0x3F;
+                s.push(ALPHABET[index as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+
+    s
+}
+
+fn path_data(path: &Path) -> String {
+    let mut d = String::new();
+    for segment in path.segments() {
+        if !d.is_empty() {
+            d.push(' ');
+        }
+
+        let _ = match segment {
+            PathSegment::MoveTo(p) => write!(d, "M {} {}", p.x, p.y),
+            PathSegment::LineTo(p) => write!(d, "L {} {}", p.x, p.y),
+            PathSegment::QuadTo(p1, p) => write!(d, "Q {} {} {} {}", p1.x, p1.y, p.x, p.y),
+            PathSegment::CubicTo(p1, p2, p) => {
+                write!(d, "C {} {} {} {} {} {}", p1.x, p1.y, p2.x, p2.y, p.x, p.y)
+            }
+            PathSegment::Close => write!(d, "Z"),
+        };
+    }
+
+    d
+}
+
+fn transform_attr(name: &str, ts: Transform) -> String {
+    if ts.is_identity() {
+        return String::new();
+    }
+
+    format!(
+        " {}=\"matrix({} {} {} {} {} {})\"",
+        name, ts.sx, ts.ky, ts.kx, ts.sy, ts.tx, ts.ty
+    )
+}
+
+fn color_value(color: Color) -> String {
+    let c = color.to_color_u8();
+    format!("rgb({},{},{})", c.red(), c.green(), c.blue())
+}
+
+fn blend_mode_name(mode: BlendMode) -> Option<&'static str> {
+    let name = match mode {
+        BlendMode::SourceOver => return None,
+        BlendMode::Plus => "plus-lighter",
+        BlendMode::Multiply => "multiply",
+        BlendMode::Screen => "screen",
+        BlendMode::Overlay => "overlay",
+        BlendMode::Darken => "darken",
+        BlendMode::Lighten => "lighten",
+        BlendMode::ColorDodge => "color-dodge",
+        BlendMode::ColorBurn => "color-burn",
+        BlendMode::HardLight => "hard-light",
+        BlendMode::SoftLight => "soft-light",
+        BlendMode::Difference => "difference",
+        BlendMode::Exclusion => "exclusion",
+        BlendMode::Hue => "hue",
+        BlendMode::Saturation => "saturation",
+        BlendMode::Color => "color",
+        BlendMode::Luminosity => "luminosity",
+        _ => {
+            log::warn!("{:?} blend mode is not supported by SVG", mode);
+            return None;
+        }
+    };
+
+    Some(name)
+}
@@ -21,6 +21,9 @@ use crate::geom::ScreenIntRect;
 use crate::pixmap::SubPixmapMut;
 use crate::wide::{f32x8, i32x8, u32x8};
 
+#[cfg(all(not(feature = "std"), feature = "no-std-float"))]
+use tiny_skia_path::NoStdFloat;
+
 pub const STAGE_WIDTH: usize = 8;
 
 pub type StageFn = fn(p: &mut Pipeline);
@@ -62,6 +65,8 @@ pub const STAGES: &[StageFn; super::STAGES_COUNT] = &[
     clamp_0,
     clamp_a,
     premultiply,
+    unpremultiply,
+    unpremultiply_polar,
     uniform_color,
     seed_shader,
     load_dst,
@@ -114,11 +119,19 @@ pub const STAGES: &[StageFn; super::STAGES_COUNT] = &[
     gradient,
     evenly_spaced_2_stop_gradient,
     xy_to_radius,
+    xy_to_angle,
+    xy_to_2pt_conical_strip,
     xy_to_2pt_conical_focal_on_circle,
     xy_to_2pt_conical_well_behaved,
+    xy_to_2pt_conical_smaller,
     xy_to_2pt_conical_greater,
+    negate_x,
+    alter_2pt_conical_compensate_focal,
+    alter_2pt_conical_unswap,
+    mask_2pt_conical_nan,
     mask_2pt_conical_degenerates,
     apply_vector_mask,
+    custom_shader,
     gamma_expand_2,
     gamma_expand_dst_2,
     gamma_compress_2,
@@ -128,6 +141,8 @@ pub const STAGES: &[StageFn; super::STAGES_COUNT] = &[
     gamma_expand_srgb,
     gamma_expand_dst_srgb,
     gamma_compress_srgb,
+    oklch_to_oklab,
+    oklab_to_linear_srgb,
 ];
 
 pub fn fn_ptr(f: StageFn) -> *const () {
@@ -170,6 +185,8 @@ pub fn start(
         let mut x = rect.x() as usize;
         let end = rect.right() as usize;
 
+        p.ctx.custom_shader.shade_span(x, y as usize, end - x);
+
         p.functions = functions;
         while x + STAGE_WIDTH <= end {
             p.index = 0;
@@ -208,6 +225,29 @@ fn premultiply(p: &mut Pipeline) {
     p.next_stage();
 }
 
+fn unpremultiply(p: &mut Pipeline) {
+    let scale = unpremultiply_scale(p.a);
+    p.r *= scale;
+    p.g *= scale;
+    p.b *= scale;
+
+    p.next_stage();
+}
+
+// Like `unpremultiply`, but leaves the hue in the blue channel intact.
+fn unpremultiply_polar(p: &mut Pipeline) {
+    let scale = unpremultiply_scale(p.a);
+    p.r *= scale;
+    p.g *= scale;
+
+    p.next_stage();
+}
+
+#[inline(always)]
+fn unpremultiply_scale(a: f32x8) -> f32x8 {
+    a.cmp_eq(f32x8::default()).blend(f32x8::default(), f32x8::splat(1.0) / a)
+}
+
 fn move_destination_to_source(p: &mut Pipeline) {
     p.r = p.dr;
     p.g = p.dg;
@@ -989,6 +1029,47 @@ fn xy_to_radius(p: &mut Pipeline) {
     p.next_stage();
 }
 
+fn xy_to_angle(p: &mut Pipeline) {
+    let ctx = &p.ctx.sweep_gradient;
+
+    let x = p.r;
+    let y = p.g;
+    let xabs = x.abs();
+    let yabs = y.abs();
+
+    let slope = xabs.min(yabs) / xabs.max(yabs);
+    let s = slope * slope;
+
+    // Use a 7th degree polynomial to approximate atan.
+    // This was generated using sollya.gforge.inria.fr.
+    // A float optimized polynomial was generated using the following command.
+    // P1 = fpminimax((1/(2*Pi))*atan(x),[|1,3,5,7|],[|24...|],[2^(-40),1],relative);
+    let mut phi = slope
+        * (f32x8::splat(0.15912117063999176025390625) + s
+        * (f32x8::splat(-5.185396969318389892578125e-2) + s
+        * (f32x8::splat(2.476101927459239959716796875e-2) + s
+        * (f32x8::splat(-7.0547382347285747528076171875e-3)))));
+
+    phi = xabs.cmp_lt(yabs).blend(f32x8::splat(1.0 / 4.0) - phi, phi);
+    phi = x.cmp_lt(f32x8::default()).blend(f32x8::splat(1.0 / 2.0) - phi, phi);
+    phi = y.cmp_lt(f32x8::default()).blend(f32x8::splat(1.0) - phi, phi);
+    phi = phi.cmp_ne(phi).blend(f32x8::default(), phi); // Check for NaN.
+
+    p.r = (phi + f32x8::splat(ctx.t_bias)) * f32x8::splat(ctx.t_scale);
+
+    p.next_stage();
+}
+
+fn xy_to_2pt_conical_strip(p: &mut Pipeline) {
+    let ctx = &p.ctx.two_point_conical_gradient;
+
+    let x = p.r;
+    let y = p.g;
+    p.r = x + (f32x8::splat(ctx.p0) - y * y).sqrt();
+
+    p.next_stage();
+}
+
 fn xy_to_2pt_conical_focal_on_circle(p: &mut Pipeline) {
     let x = p.r;
     let y = p.g;
@@ -1007,6 +1088,16 @@ fn xy_to_2pt_conical_well_behaved(p: &mut Pipeline) {
     p.next_stage();
 }
 
+fn xy_to_2pt_conical_smaller(p: &mut Pipeline) {
+    let ctx = &p.ctx.two_point_conical_gradient;
+
+    let x = p.r;
+    let y = p.g;
+    p.r = -(x * x - y * y).sqrt() - x * f32x8::splat(ctx.p0);
+
+    p.next_stage();
+}
+
 fn xy_to_2pt_conical_greater(p: &mut Pipeline) {
     let ctx = &p.ctx.two_point_conical_gradient;
 
@@ -1017,6 +1108,49 @@ fn xy_to_2pt_conical_greater(p: &mut Pipeline) {
     p.next_stage();
 }
 
+fn negate_x(p: &mut Pipeline) {
+    p.r = -p.r;
+
+    p.next_stage();
+}
+
+fn alter_2pt_conical_compensate_focal(p: &mut Pipeline) {
+    let ctx = &p.ctx.two_point_conical_gradient;
+
+    p.r = mad(p.r, f32x8::splat(ctx.p2), f32x8::splat(ctx.p1));
+
+    p.next_stage();
+}
+
+fn alter_2pt_conical_unswap(p: &mut Pipeline) {
+    p.r = f32x8::splat(1.0) - p.r;
+
+    p.next_stage();
+}
+
+fn mask_2pt_conical_nan(p: &mut Pipeline) {
+    let ctx = &mut p.ctx.two_point_conical_gradient;
+
+    let t = p.r;
+    let is_degenerate = t.cmp_ne(t);
+    p.r = is_degenerate.blend(f32x8::default(), t);
+
+    let is_not_degenerate = !is_degenerate.to_u32x8_bitcast();
+    let is_not_degenerate: [u32; 8] = bytemuck::cast(is_not_degenerate);
+    ctx.mask = bytemuck::cast([
+        if is_not_degenerate[0] != 0 { !0 } else { 0 },
+        if is_not_degenerate[1] != 0 { !0 } else { 0 },
+        if is_not_degenerate[2] != 0 { !0 } else { 0 },
+        if is_not_degenerate[3] != 0 { !0 } else { 0 },
+        if is_not_degenerate[4] != 0 { !0 } else { 0 },
+        if is_not_degenerate[5] != 0 { !0 } else { 0 },
+        if is_not_degenerate[6] != 0 { !0 } else { 0 },
+        if is_not_degenerate[7] != 0 { !0 } else { 0 },
+    ]);
+
+    p.next_stage();
+}
+
 fn mask_2pt_conical_degenerates(p: &mut Pipeline) {
     let ctx = &mut p.ctx.two_point_conical_gradient;
 
@@ -1051,6 +1185,30 @@ fn apply_vector_mask(p: &mut Pipeline) {
     p.next_stage();
 }
 
+fn custom_shader(p: &mut Pipeline) {
+    let ctx = &p.ctx.custom_shader;
+
+    let mut r = [0.0; STAGE_WIDTH];
+    let mut g = [0.0; STAGE_WIDTH];
+    let mut b = [0.0; STAGE_WIDTH];
+    let mut a = [0.0; STAGE_WIDTH];
+    let colors = &ctx.colors[p.dx - ctx.x..];
+    for i in 0..p.tail {
+        r[i] = colors[i].red();
+        g[i] = colors[i].green();
+        b[i] = colors[i].blue();
+        a[i] = colors[i].alpha();
+    }
+
+    let opacity = f32x8::splat(ctx.opacity);
+    p.r = f32x8::from(r) * opacity;
+    p.g = f32x8::from(g) * opacity;
+    p.b = f32x8::from(b) * opacity;
+    p.a = f32x8::from(a) * opacity;
+
+    p.next_stage();
+}
+
 fn gamma_expand_2(p: &mut Pipeline) {
     p.r = p.r * p.r;
     p.g = p.g * p.g;
@@ -1137,6 +1295,41 @@ fn gamma_compress_srgb(p: &mut Pipeline) {
     p.next_stage();
 }
 
+fn oklch_to_oklab(p: &mut Pipeline) {
+    // There is no vectorized sin/cos, so process lanes one by one.
+    let chroma: [f32; STAGE_WIDTH] = bytemuck::cast(p.g);
+    let hue: [f32; STAGE_WIDTH] = bytemuck::cast(p.b);
+    let mut a = [0.0; STAGE_WIDTH];
+    let mut b = [0.0; STAGE_WIDTH];
+    for i in 0..STAGE_WIDTH {
+        let hue = hue[i].to_radians();
+        a[i] = chroma[i] * hue.cos();
+        b[i] = chroma[i] * hue.sin();
+    }
+
+    p.g = f32x8::from(a);
+    p.b = f32x8::from(b);
+
+    p.next_stage();
+}
+
+// From https://bottosson.github.io/posts/oklab/
+fn oklab_to_linear_srgb(p: &mut Pipeline) {
+    let l = p.r + p.g * f32x8::splat(0.3963377774) + p.b * f32x8::splat(0.2158037573);
+    let m = p.r - p.g * f32x8::splat(0.1055613458) - p.b * f32x8::splat(0.0638541728);
+    let s = p.r - p.g * f32x8::splat(0.0894841775) - p.b * f32x8::splat(1.2914855480);
+
+    let l = l * l * l;
+    let m = m * m * m;
+    let s = s * s * s;
+
+    p.r = l * f32x8::splat(4.0767416621) - m * f32x8::splat(3.3077115913) + s * f32x8::splat(0.2309699292);
+    p.g = m * f32x8::splat(2.6097574011) - l * f32x8::splat(1.2684380046) - s * f32x8::splat(0.3413193965);
+    p.b = s * f32x8::splat(1.7076147010) - l * f32x8::splat(0.0041960863) - m * f32x8::splat(0.7034186147);
+
+    p.next_stage();
+}
+
 pub fn just_return(_: &mut Pipeline) {
     // Ends the loop.
 }
@@ -75,6 +75,8 @@ pub const STAGES: &[StageFn; super::STAGES_COUNT] = &[
     null_fn, // Clamp0
     null_fn, // ClampA
     premultiply,
+    null_fn, // Unpremultiply
+    null_fn, // UnpremultiplyPolar
     uniform_color,
     seed_shader,
     load_dst,
@@ -127,11 +129,19 @@ pub const STAGES: &[StageFn; super::STAGES_COUNT] = &[
     gradient,
     evenly_spaced_2_stop_gradient,
     xy_to_radius,
+    xy_to_angle,
+    null_fn, // XYTo2PtConicalStrip
     null_fn, // XYTo2PtConicalFocalOnCircle
     null_fn, // XYTo2PtConicalWellBehaved
+    null_fn, // XYTo2PtConicalSmaller
     null_fn, // XYTo2PtConicalGreater
+    negate_x,
+    alter_2pt_conical_compensate_focal,
+    null_fn, // Alter2PtConicalUnswap
+    null_fn, // Mask2PtConicalNan
     null_fn, // Mask2PtConicalDegenerates
     null_fn, // ApplyVectorMask
+    null_fn, // CustomShader
     null_fn, // GammaExpand2
     null_fn, // GammaExpandDestination2
     null_fn, // GammaCompress2
@@ -141,6 +151,8 @@ pub const STAGES: &[StageFn; super::STAGES_COUNT] = &[
     null_fn, // GammaExpandSrgb
     null_fn, // GammaExpandDestinationSrgb
     null_fn, // GammaCompressSrgb
+    null_fn, // OKLCHToOKLab
+    null_fn, // OKLabToLinearSRGB
 ];
 
 pub fn fn_ptr(f: StageFn) -> *const () {
@@ -646,6 +658,53 @@ fn xy_to_radius(p: &mut Pipeline) {
     p.next_stage();
 }
 
+fn xy_to_angle(p: &mut Pipeline) {
+    let ctx = &p.ctx.sweep_gradient;
+
+    let x = join(&p.r, &p.g);
+    let y = join(&p.b, &p.a);
+    let xabs = x.abs();
+    let yabs = y.abs();
+
+    let slope = xabs.min(&yabs) / xabs.max(&yabs);
+    let s = slope * slope;
+
+    // See highp for details.
+    let mut phi = slope
+        * (f32x16::splat(0.15912117063999176025390625) + s
+        * (f32x16::splat(-5.185396969318389892578125e-2) + s
+        * (f32x16::splat(2.476101927459239959716796875e-2) + s
+        * (f32x16::splat(-7.0547382347285747528076171875e-3)))));
+
+    phi = xabs.cmp_lt(&yabs).blend(f32x16::splat(1.0 / 4.0) - phi, phi);
+    phi = x.cmp_lt(&f32x16::default()).blend(f32x16::splat(1.0 / 2.0) - phi, phi);
+    phi = y.cmp_lt(&f32x16::default()).blend(f32x16::splat(1.0) - phi, phi);
+    phi = phi.cmp_ne(&phi).blend(f32x16::default(), phi); // Check for NaN.
+
+    let t = (phi + f32x16::splat(ctx.t_bias)) * f32x16::splat(ctx.t_scale);
+    split(&t, &mut p.r, &mut p.g);
+
+    p.next_stage();
+}
+
+fn negate_x(p: &mut Pipeline) {
+    let x = join(&p.r, &p.g);
+    let x = f32x16::default() - x;
+    split(&x, &mut p.r, &mut p.g);
+
+    p.next_stage();
+}
+
+fn alter_2pt_conical_compensate_focal(p: &mut Pipeline) {
+    let ctx = &p.ctx.two_point_conical_gradient;
+
+    let x = join(&p.r, &p.g);
+    let x = mad(x, f32x16::splat(ctx.p2), f32x16::splat(ctx.p1));
+    split(&x, &mut p.r, &mut p.g);
+
+    p.next_stage();
+}
+
 // We are using u16 for index, not u32 as Skia, to simplify the code a bit.
 // The gradient creation code will not allow that many stops anyway.
 fn gradient_lookup(
@@ -44,13 +44,14 @@ There are still some exceptions, which are basically an imperfect implementation
 and should be optimized out in the future.
 */
 
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 
 use arrayvec::ArrayVec;
 
 use tiny_skia_path::NormalizedF32;
 
-use crate::{Color, PremultipliedColor, PremultipliedColorU8, SpreadMode};
+use crate::{Color, CustomShader, PremultipliedColor, PremultipliedColorU8, SpreadMode};
 use crate::{PixmapRef, Transform};
 
 pub use blitter::RasterPipelineBlitter;
@@ -73,6 +74,8 @@ pub enum Stage {
     Clamp0,
     ClampA,
     Premultiply,
+    Unpremultiply,
+    UnpremultiplyPolar,
     UniformColor,
     SeedShader,
     LoadDestination,
@@ -125,11 +128,19 @@ pub enum Stage {
     Gradient,
     EvenlySpaced2StopGradient,
     XYToRadius,
+    XYToAngle,
+    XYTo2PtConicalStrip,
     XYTo2PtConicalFocalOnCircle,
     XYTo2PtConicalWellBehaved,
+    XYTo2PtConicalSmaller,
     XYTo2PtConicalGreater,
+    NegateX,
+    Alter2PtConicalCompensateFocal,
+    Alter2PtConicalUnswap,
+    Mask2PtConicalNan,
     Mask2PtConicalDegenerates,
     ApplyVectorMask,
+    CustomShader,
     GammaExpand2,
     GammaExpandDestination2,
     GammaCompress2,
@@ -139,9 +150,11 @@ pub enum Stage {
     GammaExpandSrgb,
     GammaExpandDestinationSrgb,
     GammaCompressSrgb,
+    OKLCHToOKLab,
+    OKLabToLinearSRGB,
 }
 
-pub const STAGES_COUNT: usize = Stage::GammaCompressSrgb as usize + 1;
+pub const STAGES_COUNT: usize = Stage::OKLabToLinearSRGB as usize + 1;
 
 impl<'a> PixmapRef<'a> {
     #[inline(always)]
@@ -164,7 +177,7 @@ impl<'a> PixmapRef<'a> {
 impl<'a> SubPixmapMut<'a> {
     #[inline(always)]
     pub(crate) fn offset(&self, dx: usize, dy: usize) -> usize {
-        self.real_width * dy + dx
+        self.real_width * dy + dx - self.data_offset
     }
 
     #[inline(always)]
@@ -249,9 +262,11 @@ pub struct Context {
     pub evenly_spaced_2_stop_gradient: EvenlySpaced2StopGradientCtx,
     pub gradient: GradientCtx,
     pub two_point_conical_gradient: TwoPointConicalGradientCtx,
+    pub sweep_gradient: SweepGradientCtx,
     pub limit_x: TileCtx,
     pub limit_y: TileCtx,
     pub transform: Transform,
+    pub custom_shader: CustomShaderCtx,
 }
 
 #[derive(Copy, Clone, Default, Debug)]
@@ -272,7 +287,7 @@ pub struct UniformColorCtx {
 
 // A gradient color is an unpremultiplied RGBA not in a 0..1 range.
 // It basically can have any float value.
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
 pub struct GradientColor {
     pub r: f32,
     pub g: f32,
@@ -327,6 +342,41 @@ pub struct TwoPointConicalGradientCtx {
     // This context is used only in highp, where we use Tx4.
     pub mask: u32x8,
     pub p0: f32,
+    pub p1: f32,
+    pub p2: f32,
+}
+
+#[derive(Default)]
+pub struct CustomShaderCtx {
+    pub shader: Option<Arc<dyn CustomShader>>,
+    pub origin: (i32, i32),
+    pub opacity: f32,
+    // The current span start and colors.
+    pub x: usize,
+    pub colors: Vec<PremultipliedColor>,
+}
+
+impl CustomShaderCtx {
+    // Shades a span before it is processed by the pipeline.
+    #[inline]
+    pub fn shade_span(&mut self, x: usize, y: usize, width: usize) {
+        if let Some(ref shader) = self.shader {
+            self.x = x;
+            self.colors.clear();
+            self.colors.resize(width, Color::TRANSPARENT.premultiply());
+
+            let x = x as i32 + self.origin.0;
+            let y = y as i32 + self.origin.1;
+            shader.shade_span(x, y, &mut self.colors);
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct SweepGradientCtx {
+    // Maps a unit angle into the gradient `t`.
+    pub t_bias: f32,
+    pub t_scale: f32,
 }
 
 #[derive(Copy, Clone, Default, Debug)]
@@ -526,6 +526,7 @@ impl<'a> PixmapMut<'a> {
             size: self.size(),
             real_width: self.width() as usize,
             data: self.data,
+            data_offset: 0,
         }
     }
 
@@ -541,6 +542,7 @@ impl<'a> PixmapMut<'a> {
             size: rect.size(),
             real_width: self.width() as usize,
             data: &mut self.data[offset..],
+            data_offset: 0,
         })
     }
 }
@@ -569,6 +571,10 @@ pub struct SubPixmapMut<'a> {
     pub data: &'a mut [u8],
     pub size: IntSize,
     pub real_width: usize,
+    /// The position of the first `data` pixel relative to the subregion origin.
+    ///
+    /// Non-zero only for row bands, which reference just a part of the subregion data.
+    pub data_offset: usize,
 }
 
 impl<'a> SubPixmapMut<'a> {
@@ -576,6 +582,40 @@ impl<'a> SubPixmapMut<'a> {
     pub fn pixels_mut(&mut self) -> &mut [PremultipliedColorU8] {
         bytemuck::cast_slice_mut(self.data)
     }
+
+    /// Splits a `PixmapMut` subregion into row bands with disjoint data.
+    ///
+    /// Unlike `subpixmap`, bands preserve the subregion coordinates and size,
+    /// but can be accessed only inside their rows.
+    pub(crate) fn split_rows(self, band_height: u32) -> Vec<SubPixmapMut<'a>> {
+        debug_assert_eq!(self.data_offset, 0);
+
+        let mut bands = Vec::new();
+        let mut data = self.data;
+        let mut y = 0;
+        while y < self.size.height() {
+            let next_y = y.saturating_add(band_height).min(self.size.height());
+            let band_data = if next_y == self.size.height() {
+                core::mem::take(&mut data)
+            } else {
+                let len = (next_y - y) as usize * self.real_width * BYTES_PER_PIXEL;
+                let (band_data, rest) = data.split_at_mut(len);
+                data = rest;
+                band_data
+            };
+
+            bands.push(SubPixmapMut {
+                data: band_data,
+                size: self.size,
+                real_width: self.real_width,
+                data_offset: y as usize * self.real_width,
+            });
+
+            y = next_y;
+        }
+
+        bands
+    }
 }
 
 /// Returns minimum bytes per row as usize.
@@ -8,8 +8,11 @@ pub mod hairline;
 pub mod hairline_aa;
 pub mod path;
 pub mod path_aa;
+pub mod path_aaa;
 
-use crate::{IntRect, Rect};
+use core::ops::Range;
+
+use crate::{AntiAliasQuality, FillRule, IntRect, Path, Rasterizer, Rect};
 
 use crate::blitter::Blitter;
 use crate::geom::{IntRectExt, ScreenIntRect};
@@ -37,3 +40,73 @@ fn fill_int_rect(rect: &IntRect, clip: &ScreenIntRect, blitter: &mut dyn Blitter
 pub fn fill_rect_aa(rect: &Rect, clip: &ScreenIntRect, blitter: &mut dyn Blitter) {
     hairline_aa::fill_rect(rect, clip, blitter);
 }
+
+pub fn fill_path_aa(
+    path: &Path,
+    fill_rule: FillRule,
+    rasterizer: Rasterizer,
+    quality: AntiAliasQuality,
+    clip: &ScreenIntRect,
+    blitter: &mut dyn Blitter,
+) {
+    match rasterizer {
+        Rasterizer::Supersampling => path_aa::fill_path(path, fill_rule, quality, clip, blitter),
+        Rasterizer::Analytic => path_aaa::fill_path(path, fill_rule, quality, clip, blitter),
+    }
+}
+
+/// The rows of a prepared path can be split at multiples of this value.
+pub const ROW_ALIGNMENT: u32 = path_aaa::FALLBACK_ROWS;
+
+/// A path prepared for filling.
+///
+/// Rows are filled independently, so they can be filled separately, even in parallel,
+/// as long as they are split at multiples of `ROW_ALIGNMENT`.
+/// The output is the same as when filling all of them at once.
+pub enum PreparedPath<'a> {
+    Aliased(path::EdgeList),
+    Supersampled(path_aa::PreparedPath),
+    Analytic(path_aaa::PreparedPath<'a>),
+}
+
+impl<'a> PreparedPath<'a> {
+    pub fn new(
+        path: &'a Path,
+        fill_rule: FillRule,
+        anti_alias: bool,
+        rasterizer: Rasterizer,
+        quality: AntiAliasQuality,
+        clip: &ScreenIntRect,
+    ) -> Option<Self> {
+        if !anti_alias {
+            return path::prepare(path, fill_rule, clip).map(PreparedPath::Aliased);
+        }
+
+        match rasterizer {
+            Rasterizer::Supersampling => {
+                path_aa::prepare(path, fill_rule, quality, clip).map(PreparedPath::Supersampled)
+            }
+            Rasterizer::Analytic => {
+                path_aaa::prepare(path, fill_rule, quality, clip).map(PreparedPath::Analytic)
+            }
+        }
+    }
+
+    /// Returns the rows that can be filled.
+    pub fn rows(&self) -> Range<u32> {
+        match self {
+            PreparedPath::Aliased(edges) => edges.rows(),
+            PreparedPath::Supersampled(path) => path.rows(),
+            PreparedPath::Analytic(path) => path.rows(),
+        }
+    }
+
+    /// Fills the specified rows.
+    pub fn fill_rows(&self, rows: Range<u32>, blitter: &mut dyn Blitter) {
+        match self {
+            PreparedPath::Aliased(edges) => edges.fill_rows(rows, blitter),
+            PreparedPath::Supersampled(path) => path.fill_rows(rows, blitter),
+            PreparedPath::Analytic(path) => path.fill_rows(rows, blitter),
+        }
+    }
+}
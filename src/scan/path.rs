@@ -4,7 +4,9 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the LICENSE file.
 
+use alloc::vec::Vec;
 use core::convert::TryFrom;
+use core::ops::Range;
 
 use tiny_skia_path::SaturateCast;
 
@@ -25,10 +27,13 @@ pub fn fill_path(
     clip: &ScreenIntRect,
     blitter: &mut dyn Blitter,
 ) {
-    let ir = match conservative_round_to_int(&path.bounds()) {
-        Some(v) => v,
-        None => return,
-    };
+    if let Some(edges) = prepare(path, fill_rule, clip) {
+        edges.fill_rows(clip.top()..clip.bottom(), blitter);
+    }
+}
+
+pub fn prepare(path: &Path, fill_rule: FillRule, clip: &ScreenIntRect) -> Option<EdgeList> {
+    let ir = conservative_round_to_int(&path.bounds())?;
 
     let path_contained_in_clip = if let Some(bounds) = ir.to_screen_int_rect() {
         clip.contains(&bounds)
@@ -40,7 +45,7 @@ pub fn fill_path(
 
     // TODO: SkScanClipper
 
-    fill_path_impl(
+    EdgeList::new(
         path,
         fill_rule,
         clip,
@@ -48,8 +53,7 @@ pub fn fill_path(
         ir.bottom(),
         0,
         path_contained_in_clip,
-        blitter,
-    );
+    )
 }
 
 // Conservative rounding function, which effectively nudges the int-rect to be slightly larger
@@ -97,31 +101,152 @@ fn round_up_to_int(x: f32) -> i32 {
     i32::saturate_from(xx.floor())
 }
 
-pub fn fill_path_impl(
-    path: &Path,
+/// Path edges sorted by their first row.
+///
+/// Each fill walks its own copy of the edges starting from the first requested row,
+/// therefore rows can be filled separately, even in parallel,
+/// and produce the same output as when filling all of them at once.
+pub struct EdgeList {
+    edges: Vec<Edge>,
+    // The last row of each edge, including the remaining curve segments.
+    last_rows: Vec<i32>,
     fill_rule: FillRule,
-    clip_rect: &ScreenIntRect,
-    mut start_y: i32,
-    mut stop_y: i32,
-    shift_edges_up: i32,
-    path_contained_in_clip: bool,
-    blitter: &mut dyn Blitter,
-) {
-    let shifted_clip = match ShiftedIntRect::new(clip_rect, shift_edges_up) {
-        Some(v) => v,
-        None => return,
-    };
+    // Rows and the right clip edge in shifted coordinates.
+    start_y: u32,
+    stop_y: u32,
+    right_clip: u32,
+    shift: u32,
+}
 
-    let clip = if path_contained_in_clip {
-        None
-    } else {
-        Some(&shifted_clip)
-    };
-    let mut edges = match BasicEdgeBuilder::build_edges(path, clip, shift_edges_up) {
-        Some(v) => v,
-        None => return, // no edges to render, just return
-    };
+impl EdgeList {
+    pub fn new(
+        path: &Path,
+        fill_rule: FillRule,
+        clip_rect: &ScreenIntRect,
+        mut start_y: i32,
+        mut stop_y: i32,
+        shift_edges_up: i32,
+        path_contained_in_clip: bool,
+    ) -> Option<Self> {
+        let shifted_clip = ShiftedIntRect::new(clip_rect, shift_edges_up)?;
+
+        let clip = if path_contained_in_clip {
+            None
+        } else {
+            Some(&shifted_clip)
+        };
+        // No edges to render.
+        let mut edges = BasicEdgeBuilder::build_edges(path, clip, shift_edges_up)?;
+        sort_edges(&mut edges);
+        let last_rows = edges.iter().map(last_row).collect();
+
+        start_y <<= shift_edges_up;
+        stop_y <<= shift_edges_up;
+
+        let top = shifted_clip.shifted().y() as i32;
+        if !path_contained_in_clip && start_y < top {
+            start_y = top;
+        }
+
+        let bottom = shifted_clip.shifted().bottom() as i32;
+        if !path_contained_in_clip && stop_y > bottom {
+            stop_y = bottom;
+        }
+
+        Some(EdgeList {
+            edges,
+            last_rows,
+            fill_rule,
+            start_y: u32::try_from(start_y).ok()?,
+            stop_y: u32::try_from(stop_y).ok()?,
+            right_clip: shifted_clip.shifted().right(),
+            shift: shift_edges_up as u32,
+        })
+    }
+
+    /// Returns the rows that can be filled, in destination coordinates.
+    pub fn rows(&self) -> Range<u32> {
+        let round = (1 << self.shift) - 1;
+        (self.start_y >> self.shift)..((self.stop_y + round) >> self.shift)
+    }
+
+    /// Fills the specified rows, in destination coordinates.
+    pub fn fill_rows(&self, rows: Range<u32>, blitter: &mut dyn Blitter) {
+        let start_y = self.start_y.max(rows.start << self.shift);
+        let stop_y = self.stop_y.min(rows.end << self.shift);
+        if start_y >= stop_y {
+            return;
+        }
+
+        const EDGE_HEAD_Y: i32 = i32::MIN;
+        const EDGE_TAIL_Y: i32 = i32::MAX;
+
+        let mut edges = Vec::new();
+        edges.push(Edge::Line(LineEdge {
+            prev: None,
+            next: Some(1),
+            x: i32::MIN,
+            first_y: EDGE_HEAD_Y,
+            ..LineEdge::default()
+        }));
+
+        // Edges that start above are moved onto the first row, like `walk_edges` would do.
+        let mut skipped = false;
+        for (edge, last_row) in self.edges.iter().zip(&self.last_rows) {
+            if edge.first_y >= stop_y as i32 {
+                break;
+            }
+
+            if *last_row < start_y as i32 {
+                continue;
+            }
 
+            let mut edge = edge.clone();
+            if edge.first_y < start_y as i32 {
+                if !skip_rows(&mut edge, start_y as i32) {
+                    continue;
+                }
+
+                skipped = true;
+            }
+
+            edges.push(edge);
+        }
+
+        if edges.len() == 1 {
+            return;
+        }
+
+        if skipped {
+            sort_edges(&mut edges[1..]);
+        }
+
+        for i in 1..edges.len() {
+            edges[i].prev = Some(i as u32 - 1);
+            edges[i].next = Some(i as u32 + 1);
+        }
+
+        edges.push(Edge::Line(LineEdge {
+            prev: Some(edges.len() as u32 - 1),
+            next: None,
+            first_y: EDGE_TAIL_Y,
+            ..LineEdge::default()
+        }));
+
+        // TODO: walk_simple_edges
+
+        walk_edges(
+            self.fill_rule,
+            start_y,
+            stop_y,
+            self.right_clip,
+            &mut edges,
+            blitter,
+        );
+    }
+}
+
+fn sort_edges(edges: &mut [Edge]) {
     edges.sort_by(|a, b| {
         let mut value_a = a.as_line().first_y;
         let mut value_b = b.as_line().first_y;
@@ -133,66 +258,49 @@ pub fn fill_path_impl(
 
         value_a.cmp(&value_b)
     });
+}
 
-    for i in 0..edges.len() {
-        // 0 will be set later, so start with 1.
-        edges[i].prev = Some(i as u32 + 0);
-        edges[i].next = Some(i as u32 + 2);
+// Switches a curve edge to its next segment, the same way `walk_edges` does.
+fn next_segment(edge: &mut Edge) -> bool {
+    match edge {
+        Edge::Line(_) => false,
+        Edge::Quadratic(ref mut quad) => quad.curve_count > 0 && quad.update(),
+        Edge::Cubic(ref mut cubic) => cubic.curve_count < 0 && cubic.update(),
     }
+}
 
-    const EDGE_HEAD_Y: i32 = i32::MIN;
-    const EDGE_TAIL_Y: i32 = i32::MAX;
-
-    edges.insert(
-        0,
-        Edge::Line(LineEdge {
-            prev: None,
-            next: Some(1),
-            x: i32::MIN,
-            first_y: EDGE_HEAD_Y,
-            ..LineEdge::default()
-        }),
-    );
-
-    edges.push(Edge::Line(LineEdge {
-        prev: Some(edges.len() as u32 - 1),
-        next: None,
-        first_y: EDGE_TAIL_Y,
-        ..LineEdge::default()
-    }));
-
-    start_y <<= shift_edges_up;
-    stop_y <<= shift_edges_up;
-
-    let top = shifted_clip.shifted().y() as i32;
-    if !path_contained_in_clip && start_y < top {
-        start_y = top;
+fn last_row(edge: &Edge) -> i32 {
+    if let Edge::Line(ref line) = edge {
+        return line.last_y;
     }
 
-    let bottom = shifted_clip.shifted().bottom() as i32;
-    if !path_contained_in_clip && stop_y > bottom {
-        stop_y = bottom;
+    let mut edge = edge.clone();
+    let mut last_y = edge.last_y;
+    while next_segment(&mut edge) {
+        last_y = edge.last_y;
     }
 
-    let start_y = match u32::try_from(start_y) {
-        Ok(v) => v,
-        Err(_) => return,
-    };
-    let stop_y = match u32::try_from(stop_y) {
-        Ok(v) => v,
-        Err(_) => return,
-    };
+    last_y
+}
 
-    // TODO: walk_simple_edges
+// Moves an edge onto the `y` row, the same way `walk_edges` does.
+//
+// Returns `false` when the edge ends above it.
+fn skip_rows(edge: &mut Edge, y: i32) -> bool {
+    while edge.last_y < y {
+        if !next_segment(edge) {
+            return false;
+        }
+    }
 
-    walk_edges(
-        fill_rule,
-        start_y,
-        stop_y,
-        shifted_clip.shifted().right(),
-        &mut edges,
-        blitter,
-    );
+    if edge.first_y < y {
+        // `walk_edges` adds `dx` once per row.
+        let rows = y - edge.first_y;
+        edge.x = edge.x.wrapping_add(edge.dx.wrapping_mul(rows));
+        edge.first_y = y;
+    }
+
+    true
 }
 
 // TODO: simplify!
@@ -215,28 +323,37 @@ fn walk_edges(
         let mut w = 0i32;
         let mut left = 0u32;
         let mut prev_x = edges[0].x;
+        let mut same_x = false;
 
         let mut curr_idx = edges[0].next.unwrap() as usize;
         while edges[curr_idx].first_y <= curr_y as i32 {
             debug_assert!(edges[curr_idx].last_y >= curr_y as i32);
 
-            let x = fdot16::round_to_i32(edges[curr_idx].x) as u32; // TODO: check
+            let curr_x = edges[curr_idx].x;
+            let x = fdot16::round_to_i32(curr_x) as u32; // TODO: check
 
-            if (w & winding_mask) == 0 {
+            if (w & winding_mask) == 0 && !same_x {
                 // we're starting interval
                 left = x;
             }
 
             w += i32::from(edges[curr_idx].winding);
 
-            if (w & winding_mask) == 0 {
+            let next_idx = edges[curr_idx].next.unwrap();
+
+            // The order of edges at the same position depends on the rows above,
+            // so an interval is finished only after all of them.
+            // This way rows can be walked starting from any of them.
+            let next = &edges[next_idx as usize];
+            same_x = next.first_y <= curr_y as i32 && next.x == curr_x;
+
+            if (w & winding_mask) == 0 && !same_x {
                 // we finished an interval
                 if let Some(width) = LengthU32::new(x - left) {
                     blitter.blit_h(left, curr_y, width);
                 }
             }
 
-            let next_idx = edges[curr_idx].next.unwrap();
             let new_x;
 
             if edges[curr_idx].last_y == curr_y as i32 {
@@ -4,57 +4,68 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the LICENSE file.
 
+use alloc::vec::Vec;
 use core::convert::TryFrom;
+use core::ops::Range;
 
-use crate::{FillRule, IntRect, LengthU32, Path, Rect};
+use tiny_skia_path::SaturateRound;
 
-use crate::alpha_runs::AlphaRuns;
+use crate::{AntiAliasQuality, FillRule, IntRect, LengthU32, Path, Transform};
+
+use crate::alpha_runs::{AlphaRun, AlphaRuns};
 use crate::blitter::Blitter;
 use crate::color::AlphaU8;
 use crate::geom::{IntRectExt, ScreenIntRect};
 use crate::math::left_shift;
 
-#[cfg(all(not(feature = "std"), feature = "no-std-float"))]
-use tiny_skia_path::NoStdFloat;
-
-/// controls how much we super-sample (when we use that scan conversion)
+/// The default supersampling shift.
+///
+/// Areas that overflow a higher one are rendered in strips.
 const SUPERSAMPLE_SHIFT: u32 = 2;
 
-const SHIFT: u32 = SUPERSAMPLE_SHIFT;
-const SCALE: u32 = 1 << SHIFT;
-const MASK: u32 = SCALE - 1;
-
 pub fn fill_path(
     path: &Path,
     fill_rule: FillRule,
+    quality: AntiAliasQuality,
     clip: &ScreenIntRect,
     blitter: &mut dyn Blitter,
 ) {
-    // Unlike `path.bounds.to_rect()?.round_out()`,
-    // this method rounds out first and then converts into a Rect.
-    let ir = Rect::from_ltrb(
-        path.bounds().left().floor(),
-        path.bounds().top().floor(),
-        path.bounds().right().ceil(),
-        path.bounds().bottom().ceil(),
-    )
-    .and_then(|r| r.round_out());
-    let ir = match ir {
-        Some(v) => v,
-        None => return,
-    };
+    if let Some(path) = prepare(path, fill_rule, quality, clip) {
+        path.fill_rows(clip.top()..clip.bottom(), blitter);
+    }
+}
+
+pub fn prepare(
+    path: &Path,
+    fill_rule: FillRule,
+    quality: AntiAliasQuality,
+    clip: &ScreenIntRect,
+) -> Option<PreparedPath> {
+    // Unlike `path.bounds().round_out()`, this method rounds out the edges
+    // instead of the width and height, which lose precision for large coordinates.
+    // The bounds are limited, so they can be shifted into supersampled coordinates.
+    let limit = i32::MAX >> quality.supersample_shift();
+    let bounds = path.bounds();
+    let left = i32::saturate_floor(bounds.left()).clamp(-limit, limit);
+    let top = i32::saturate_floor(bounds.top()).clamp(-limit, limit);
+    let right = i32::saturate_ceil(bounds.right()).clamp(-limit, limit);
+    let bottom = i32::saturate_ceil(bounds.bottom()).clamp(-limit, limit);
+    let ir = IntRect::from_ltrb(left, top, right.max(left + 1), bottom.max(top + 1))?;
 
     // TODO: remove
     // If the intersection of the path bounds and the clip bounds
-    // will overflow 32767 when << by SHIFT, we can't supersample,
+    // will overflow 32767 when << by shift, we can't supersample,
     // so draw without antialiasing.
-    let clipped_ir = match ir.intersect(&clip.to_int_rect()) {
-        Some(v) => v,
-        None => return,
-    };
-    if rect_overflows_short_shift(&clipped_ir, SHIFT as i32) != 0 {
-        super::path::fill_path(path, fill_rule, clip, blitter);
-        return;
+    let clipped_ir = ir.intersect(&clip.to_int_rect())?;
+
+    // Higher qualities support only small areas, so larger ones are split into strips.
+    let shift = quality.supersample_shift();
+    if shift > SUPERSAMPLE_SHIFT && rect_overflows_short_shift(&clipped_ir, shift as i32) != 0 {
+        return prepare_strips(path, fill_rule, quality, &clipped_ir);
+    }
+
+    if rect_overflows_short_shift(&clipped_ir, shift as i32) != 0 {
+        return super::path::prepare(path, fill_rule, clip).map(PreparedPath::Aliased);
     }
 
     // TODO: remove
@@ -63,21 +74,168 @@ pub fn fill_path(
     {
         const MAX_CLIP_COORD: u32 = 32767;
         if clip.right() > MAX_CLIP_COORD || clip.bottom() > MAX_CLIP_COORD {
-            return;
+            return None;
         }
     }
 
     // TODO: SkScanClipper
     // TODO: AAA
+    // TODO: MaskSuperBlitter
+
+    let path_contained_in_clip = if let Some(bounds) = ir.to_screen_int_rect() {
+        clip.contains(&bounds)
+    } else {
+        // If bounds cannot be converted into ScreenIntRect,
+        // the path is out of clip.
+        false
+    };
+
+    let edges = super::path::EdgeList::new(
+        path,
+        fill_rule,
+        clip,
+        ir.top(),
+        ir.bottom(),
+        shift as i32,
+        path_contained_in_clip,
+    )?;
+
+    Some(PreparedPath::Supersampled {
+        edges,
+        bounds: ir,
+        shift,
+        clip: *clip,
+    })
+}
+
+/// A path prepared for supersampling.
+///
+/// Rows can be filled separately, like with [`EdgeList`](super::path::EdgeList).
+pub enum PreparedPath {
+    /// Areas that cannot be supersampled are filled without anti-aliasing.
+    Aliased(super::path::EdgeList),
+    Supersampled {
+        edges: super::path::EdgeList,
+        bounds: IntRect,
+        shift: u32,
+        clip: ScreenIntRect,
+    },
+    Strips(Vec<Strip>),
+}
+
+/// A part of an area, prepared at the origin.
+pub struct Strip {
+    x: u32,
+    y: u32,
+    height: u32,
+    path: PreparedPath,
+}
+
+impl PreparedPath {
+    /// Returns the rows that can be filled.
+    pub fn rows(&self) -> Range<u32> {
+        match self {
+            PreparedPath::Aliased(edges) => edges.rows(),
+            PreparedPath::Supersampled { edges, .. } => edges.rows(),
+            PreparedPath::Strips(strips) => match (strips.first(), strips.last()) {
+                (Some(first), Some(last)) => first.y..last.y + last.height,
+                _ => 0..0,
+            },
+        }
+    }
+
+    /// Fills the specified rows.
+    pub fn fill_rows(&self, rows: Range<u32>, blitter: &mut dyn Blitter) {
+        match self {
+            PreparedPath::Aliased(edges) => edges.fill_rows(rows, blitter),
+            PreparedPath::Supersampled {
+                edges,
+                bounds,
+                shift,
+                clip,
+            } => {
+                // TODO: 15% slower than skia, find out why
+                let mut blitter = match SuperBlitter::new(bounds, *shift, clip, blitter) {
+                    Some(v) => v,
+                    None => return, // clipped out, nothing else to do
+                };
+
+                edges.fill_rows(rows, &mut blitter);
+            }
+            PreparedPath::Strips(strips) => {
+                for strip in strips {
+                    let top = rows.start.max(strip.y);
+                    let bottom = rows.end.min(strip.y + strip.height);
+                    if top < bottom {
+                        let mut blitter = StripBlitter {
+                            blitter,
+                            x: strip.x,
+                            y: strip.y,
+                        };
+                        let rows = top - strip.y..bottom - strip.y;
+                        strip.path.fill_rows(rows, &mut blitter);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Renders each strip of the area translated to the origin,
+// so its supersampled coordinates fit in a short.
+fn prepare_strips(
+    path: &Path,
+    fill_rule: FillRule,
+    quality: AntiAliasQuality,
+    area: &IntRect,
+) -> Option<PreparedPath> {
+    let max_size = 32767 >> quality.supersample_shift();
+    let area = area.to_screen_int_rect()?;
+
+    let mut strips = Vec::new();
+    let mut y = area.top();
+    while y < area.bottom() {
+        let mut x = area.left();
+        while x < area.right() {
+            let width = (area.right() - x).min(max_size);
+            let height = (area.bottom() - y).min(max_size);
+            let ts = Transform::from_translate(-(x as f32), -(y as f32));
+            if let (Some(path), Some(clip)) = (
+                path.clone().transform(ts),
+                ScreenIntRect::from_xywh(0, 0, width, height),
+            ) {
+                if let Some(path) = prepare(&path, fill_rule, quality, &clip) {
+                    strips.push(Strip { x, y, height, path });
+                }
+            }
+
+            x += max_size;
+        }
+
+        y += max_size;
+    }
 
-    fill_path_impl(path, fill_rule, &ir, clip, blitter)
+    Some(PreparedPath::Strips(strips))
+}
+
+/// Moves rows rendered in a strip to the strip position.
+struct StripBlitter<'a> {
+    blitter: &'a mut dyn Blitter,
+    x: u32,
+    y: u32,
+}
+
+impl Blitter for StripBlitter<'_> {
+    fn blit_anti_h(&mut self, x: u32, y: u32, aa: &mut [AlphaU8], runs: &mut [AlphaRun]) {
+        self.blitter.blit_anti_h(x + self.x, y + self.y, aa, runs);
+    }
 }
 
 // Would any of the coordinates of this rectangle not fit in a short,
 // when left-shifted by shift?
 fn rect_overflows_short_shift(rect: &IntRect, shift: i32) -> i32 {
-    debug_assert!(overflows_short_shift(8191, shift) == 0);
-    debug_assert!(overflows_short_shift(8192, shift) != 0);
+    debug_assert!(overflows_short_shift(32767 >> shift, shift) == 0);
+    debug_assert!(overflows_short_shift(32768 >> shift, shift) != 0);
     debug_assert!(overflows_short_shift(32767, 0) == 0);
     debug_assert!(overflows_short_shift(32768, 0) != 0);
 
@@ -94,41 +252,6 @@ fn overflows_short_shift(value: i32, shift: i32) -> i32 {
     (left_shift(value, s) >> s) - value
 }
 
-fn fill_path_impl(
-    path: &Path,
-    fill_rule: FillRule,
-    bounds: &IntRect,
-    clip: &ScreenIntRect,
-    blitter: &mut dyn Blitter,
-) {
-    // TODO: MaskSuperBlitter
-
-    // TODO: 15% slower than skia, find out why
-    let mut blitter = match SuperBlitter::new(bounds, clip, blitter) {
-        Some(v) => v,
-        None => return, // clipped out, nothing else to do
-    };
-
-    let path_contained_in_clip = if let Some(bounds) = bounds.to_screen_int_rect() {
-        clip.contains(&bounds)
-    } else {
-        // If bounds cannot be converted into ScreenIntRect,
-        // the path is out of clip.
-        false
-    };
-
-    super::path::fill_path_impl(
-        path,
-        fill_rule,
-        clip,
-        bounds.top(),
-        bounds.bottom(),
-        SHIFT as i32,
-        path_contained_in_clip,
-        &mut blitter,
-    );
-}
-
 struct BaseSuperBlitter<'a> {
     real_blitter: &'a mut dyn Blitter,
 
@@ -145,11 +268,15 @@ struct BaseSuperBlitter<'a> {
     curr_y: i32,
     /// Initial y coordinate (top of bounds).
     top: i32,
+
+    /// Controls how much we super-sample.
+    shift: u32,
 }
 
 impl<'a> BaseSuperBlitter<'a> {
     fn new(
         bounds: &IntRect,
+        shift: u32,
         clip_rect: &ScreenIntRect,
         blitter: &'a mut dyn Blitter,
     ) -> Option<Self> {
@@ -161,9 +288,10 @@ impl<'a> BaseSuperBlitter<'a> {
             curr_iy: sect.top() as i32 - 1,
             width: sect.width_safe(),
             left: sect.left(),
-            super_left: sect.left() << SHIFT,
-            curr_y: (sect.top() << SHIFT) as i32 - 1,
+            super_left: sect.left() << shift,
+            curr_y: (sect.top() << shift) as i32 - 1,
             top: sect.top() as i32,
+            shift,
         })
     }
 }
@@ -177,10 +305,11 @@ struct SuperBlitter<'a> {
 impl<'a> SuperBlitter<'a> {
     fn new(
         bounds: &IntRect,
+        shift: u32,
         clip_rect: &ScreenIntRect,
         blitter: &'a mut dyn Blitter,
     ) -> Option<Self> {
-        let base = BaseSuperBlitter::new(bounds, clip_rect, blitter)?;
+        let base = BaseSuperBlitter::new(bounds, shift, clip_rect, blitter)?;
         let runs_width = base.width;
         Some(SuperBlitter {
             base,
@@ -219,7 +348,11 @@ impl Blitter for SuperBlitter<'_> {
     /// Blits a row of pixels, with location and width specified
     /// in supersampled coordinates.
     fn blit_h(&mut self, mut x: u32, y: u32, mut width: LengthU32) {
-        let iy = (y >> SHIFT) as i32;
+        let shift = self.base.shift;
+        let scale = 1 << shift;
+        let mask = scale - 1;
+
+        let iy = (y >> shift) as i32;
         debug_assert!(iy >= self.base.curr_iy);
 
         // hack, until I figure out why my cubics (I think) go beyond the bounds
@@ -248,9 +381,9 @@ impl Blitter for SuperBlitter<'_> {
 
         debug_assert!(stop > start);
         // integer-pixel-aligned ends of blit, rounded out
-        let mut fb = start & MASK;
-        let mut fe = stop & MASK;
-        let mut n: i32 = (stop as i32 >> SHIFT) - (start as i32 >> SHIFT) - 1;
+        let mut fb = start & mask;
+        let mut fe = stop & mask;
+        let mut n: i32 = (stop as i32 >> shift) - (start as i32 >> shift) - 1;
 
         if n < 0 {
             fb = fe - fb;
@@ -260,16 +393,16 @@ impl Blitter for SuperBlitter<'_> {
             if fb == 0 {
                 n += 1;
             } else {
-                fb = SCALE - fb;
+                fb = scale - fb;
             }
         }
 
-        let max_value = u8::try_from((1 << (8 - SHIFT)) - (((y & MASK) + 1) >> SHIFT)).unwrap();
+        let max_value = u8::try_from((1 << (8 - shift)) - (((y & mask) + 1) >> shift)).unwrap();
         self.offset_x = self.runs.add(
-            x >> SHIFT,
-            coverage_to_partial_alpha(fb),
+            x >> shift,
+            coverage_to_partial_alpha(fb, shift),
             n as usize,
-            coverage_to_partial_alpha(fe),
+            coverage_to_partial_alpha(fe, shift),
             max_value,
             self.offset_x,
         );
@@ -277,11 +410,11 @@ impl Blitter for SuperBlitter<'_> {
 }
 
 // coverage_to_partial_alpha() is being used by AlphaRuns, which
-// *accumulates* SCALE pixels worth of "alpha" in [0,(256/SCALE)]
+// *accumulates* `1 << shift` pixels worth of "alpha" in [0,(256 >> shift)]
 // to produce a final value in [0, 255] and handles clamping 256->255
 // itself, with the same (alpha - (alpha >> 8)) correction as
 // coverage_to_exact_alpha().
-fn coverage_to_partial_alpha(mut aa: u32) -> AlphaU8 {
-    aa <<= 8 - 2 * SHIFT;
+fn coverage_to_partial_alpha(mut aa: u32, shift: u32) -> AlphaU8 {
+    aa <<= 8 - 2 * shift;
     aa as AlphaU8
 }
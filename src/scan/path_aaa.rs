@@ -0,0 +1,852 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+// An analytic anti-aliasing rasterizer.
+//
+// Unlike the supersampling one, computes the exact area covered by a path inside each pixel.
+// Paths are flattened into lines and each line accumulates its signed area into a row buffer,
+// which is then converted into coverage via a prefix sum. This is the approach used by font-rs
+// and stb_truetype.
+//
+// Each row is processed independently, so only a single row buffer is required.
+// To support overlapping and self-intersecting paths, lines of a row are grouped into clusters
+// that overlap horizontally and each cluster is split into bands at line ends and crossings.
+// Inside a band, lines are ordered left to right, so the winding between them is known
+// and only lines separating filled and unfilled spans are accumulated.
+//
+// The number of bands grows quickly with the number of crossings, so rows that need
+// too many of them are rendered by the supersampling rasterizer instead.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::num::NonZeroU16;
+use core::ops::Range;
+
+use tiny_skia_path::{PathSegment, Point};
+
+use crate::{AntiAliasQuality, FillRule, Path};
+
+use crate::alpha_runs::AlphaRun;
+use crate::blitter::Blitter;
+use crate::geom::ScreenIntRect;
+use crate::scan::path_aa;
+
+#[cfg(all(not(feature = "std"), feature = "no-std-float"))]
+use tiny_skia_path::NoStdFloat;
+
+// The maximum distance between a curve and its flattened version, in pixels.
+const TOLERANCE: f32 = 0.05;
+
+// Prevents huge curves from producing too many lines.
+const MAX_CURVE_LINES: f32 = 1024.0;
+
+// Crossings closer to a band edge do not split it, to avoid splitting indefinitely
+// because of rounding errors.
+const MIN_BAND_HEIGHT: f32 = 1.0 / 1024.0;
+
+// The number of line visits per row line, after which the row is supersampled instead.
+// Supersampling visits each line once per sample row.
+const ROW_WORK_PER_LINE: usize = 4;
+
+// Line ends split rows into bands as well, so rows of small curves
+// made of many short lines have a bit more work to do.
+const MIN_ROW_WORK: usize = 256;
+
+// Rows after a complex one are supersampled without trying them first, up to a multiple
+// of this value. Complex rows are usually next to each other, and trying them is as slow
+// as supersampling. Supersampled strips do not cross multiples of it either,
+// therefore rows split at them can be filled separately.
+pub const FALLBACK_ROWS: u32 = 16;
+
+pub fn fill_path(
+    path: &Path,
+    fill_rule: FillRule,
+    quality: AntiAliasQuality,
+    clip: &ScreenIntRect,
+    blitter: &mut dyn Blitter,
+) {
+    if let Some(path) = prepare(path, fill_rule, quality, clip) {
+        path.fill_rows(clip.top()..clip.bottom(), blitter);
+    }
+}
+
+pub fn prepare<'a>(
+    path: &'a Path,
+    fill_rule: FillRule,
+    quality: AntiAliasQuality,
+    clip: &ScreenIntRect,
+) -> Option<PreparedPath<'a>> {
+    // Pixels to the left of the path do not require any processing.
+    // Edges to the left of the clip are still affecting visible pixels, so they are moved
+    // onto the left clip edge instead.
+    let bounds = path.bounds();
+    let left = (bounds.left().floor() as i64).max(i64::from(clip.left()));
+    let top = (bounds.top().floor() as i64).max(i64::from(clip.top()));
+    let right = (bounds.right().ceil() as i64).min(i64::from(clip.right()));
+    let bottom = (bounds.bottom().ceil() as i64).min(i64::from(clip.bottom()));
+    if left >= right || top >= bottom {
+        return None;
+    }
+
+    let mut rasterizer = AnalyticRasterizer {
+        origin: Point::from_xy(left as f32, top as f32),
+        width: (right - left) as f32,
+        height: (bottom - top) as f32,
+        lines: Vec::new(),
+    };
+
+    rasterizer.add_path(path);
+    if rasterizer.lines.is_empty() {
+        return None;
+    }
+
+    // Lines contain only finite values.
+    rasterizer
+        .lines
+        .sort_by(|a, b| a.y0.partial_cmp(&b.y0).unwrap_or(Ordering::Equal));
+
+    Some(PreparedPath {
+        path,
+        fill_rule,
+        quality,
+        clip: *clip,
+        left: left as u32,
+        top: top as u32,
+        rasterizer,
+    })
+}
+
+/// A path prepared for the analytic rasterizer.
+///
+/// Rows are processed independently, so they can be filled separately,
+/// as long as they are split at multiples of `FALLBACK_ROWS`.
+pub struct PreparedPath<'a> {
+    path: &'a Path,
+    fill_rule: FillRule,
+    quality: AntiAliasQuality,
+    clip: ScreenIntRect,
+    left: u32,
+    top: u32,
+    rasterizer: AnalyticRasterizer,
+}
+
+impl PreparedPath<'_> {
+    /// Returns the rows that can be filled.
+    pub fn rows(&self) -> Range<u32> {
+        self.top..self.top + self.rasterizer.height as u32
+    }
+
+    /// Fills the specified rows.
+    pub fn fill_rows(&self, rows: Range<u32>, blitter: &mut dyn Blitter) {
+        let mut fallback = Fallback {
+            path: self.path,
+            fill_rule: self.fill_rule,
+            quality: self.quality,
+            clip: &self.clip,
+            rows: None,
+        };
+        let (left, top) = (self.left, self.top);
+        let rows = rows.start.saturating_sub(top)..rows.end.saturating_sub(top);
+        let rasterizer = &self.rasterizer;
+        rasterizer.fill(self.fill_rule, left, top, rows, &mut fallback, blitter);
+        fallback.flush(blitter);
+    }
+}
+
+// Supersamples consecutive rows that are too complex for the analytic rasterizer.
+struct Fallback<'a> {
+    path: &'a Path,
+    fill_rule: FillRule,
+    quality: AntiAliasQuality,
+    clip: &'a ScreenIntRect,
+    // Pending rows in screen coordinates.
+    rows: Option<(u32, u32)>,
+}
+
+impl Fallback<'_> {
+    fn push(&mut self, y: u32, blitter: &mut dyn Blitter) {
+        match self.rows {
+            Some((start, ref mut end))
+                if *end == y && start / FALLBACK_ROWS == y / FALLBACK_ROWS =>
+            {
+                *end += 1
+            }
+            _ => {
+                self.flush(blitter);
+                self.rows = Some((y, y + 1));
+            }
+        }
+    }
+
+    // Rows are rendered in order, so pending ones are flushed before the next analytic row.
+    fn flush(&mut self, blitter: &mut dyn Blitter) {
+        if let Some((start, end)) = self.rows.take() {
+            let strip =
+                ScreenIntRect::from_xywh(self.clip.x(), start, self.clip.width(), end - start);
+            if let Some(strip) = strip {
+                path_aa::fill_path(self.path, self.fill_rule, self.quality, &strip, blitter);
+            }
+        }
+    }
+}
+
+// A line that goes from top to bottom.
+#[derive(Copy, Clone, Debug)]
+struct Line {
+    x0: f32,
+    y0: f32,
+    y1: f32,
+    dxdy: f32,
+    // 1 for lines that go down, -1 for lines that go up.
+    dir: i32,
+}
+
+impl Line {
+    fn x_at(&self, y: f32) -> f32 {
+        self.x0 + (y - self.y0) * self.dxdy
+    }
+}
+
+struct AnalyticRasterizer {
+    origin: Point,
+    width: f32,
+    height: f32,
+    // Lines are relative to the origin.
+    lines: Vec<Line>,
+}
+
+impl AnalyticRasterizer {
+    fn add_path(&mut self, path: &Path) {
+        let mut start = Point::zero();
+        let mut last = Point::zero();
+        for segment in path.segments() {
+            match segment {
+                PathSegment::MoveTo(p) => {
+                    // Contours are closed implicitly.
+                    self.add_line(last, start);
+                    start = p - self.origin;
+                    last = start;
+                }
+                PathSegment::LineTo(p) => {
+                    let p = p - self.origin;
+                    self.add_line(last, p);
+                    last = p;
+                }
+                PathSegment::QuadTo(p1, p2) => {
+                    let p1 = p1 - self.origin;
+                    let p2 = p2 - self.origin;
+                    self.add_curve(&[last, p1, p2]);
+                    last = p2;
+                }
+                PathSegment::CubicTo(p1, p2, p3) => {
+                    let p1 = p1 - self.origin;
+                    let p2 = p2 - self.origin;
+                    let p3 = p3 - self.origin;
+                    self.add_curve(&[last, p1, p2, p3]);
+                    last = p3;
+                }
+                PathSegment::Close => {
+                    self.add_line(last, start);
+                    last = start;
+                }
+            }
+        }
+
+        self.add_line(last, start);
+    }
+
+    // Flattens a quadratic or a cubic curve.
+    fn add_curve(&mut self, points: &[Point]) {
+        let first = points[0];
+        let last = points[points.len() - 1];
+
+        let mut min = first;
+        let mut max = first;
+        for p in points {
+            min = Point::from_xy(min.x.min(p.x), min.y.min(p.y));
+            max = Point::from_xy(max.x.max(p.x), max.y.max(p.y));
+        }
+
+        // Rows are independent, so curves outside of them can be skipped.
+        if max.y <= 0.0 || min.y >= self.height {
+            return;
+        }
+
+        // Curves outside of the clip are clamped onto its edges, where only their winding matters.
+        if max.x <= 0.0 || min.x >= self.width {
+            self.add_line(first, last);
+            return;
+        }
+
+        // The flattening error for N uniform steps is bounded by
+        // the maximum of the second derivative divided by 8 * N^2.
+        let second_derivative = if points.len() == 3 {
+            2.0 * (points[0] - points[1] - points[1] + points[2]).length()
+        } else {
+            let d1 = (points[0] - points[1] - points[1] + points[2]).length();
+            let d2 = (points[1] - points[2] - points[2] + points[3]).length();
+            6.0 * d1.max(d2)
+        };
+        let count = (second_derivative / (8.0 * TOLERANCE))
+            .sqrt()
+            .ceil()
+            .clamp(1.0, MAX_CURVE_LINES) as u32;
+
+        let mut prev = first;
+        for i in 1..count {
+            let t = i as f32 / count as f32;
+            let p = eval_curve(points, t);
+            self.add_line(prev, p);
+            prev = p;
+        }
+
+        self.add_line(prev, last);
+    }
+
+    fn add_line(&mut self, p0: Point, p1: Point) {
+        if p0.y == p1.y {
+            return;
+        }
+
+        let (dir, mut a, mut b) = if p0.y < p1.y {
+            (1, p0, p1)
+        } else {
+            (-1, p1, p0)
+        };
+
+        if b.y <= 0.0 || a.y >= self.height {
+            return;
+        }
+
+        if a.y < 0.0 {
+            a = Point::from_xy(x_at_y(a, b, 0.0), 0.0);
+        }
+
+        if b.y > self.height {
+            b = Point::from_xy(x_at_y(a, b, self.height), self.height);
+        }
+
+        if a.y >= b.y {
+            return;
+        }
+
+        // Split the line at the left and right edges and clamp the outside parts,
+        // which preserves the winding of all pixels to the right.
+        let mut splits = [b.y; 2];
+        for (i, x) in [0.0, self.width].iter().enumerate() {
+            if (a.x - x) * (b.x - x) < 0.0 {
+                splits[i] = a.y + (x - a.x) * (b.y - a.y) / (b.x - a.x);
+            }
+        }
+
+        if splits[1] < splits[0] {
+            splits.swap(0, 1);
+        }
+
+        let mut prev = a;
+        for y in splits.iter().copied().chain(core::iter::once(b.y)) {
+            let y = y.clamp(prev.y, b.y);
+            if y > prev.y {
+                let next = if y == b.y {
+                    b
+                } else {
+                    Point::from_xy(x_at_y(a, b, y), y)
+                };
+                self.push_line(prev, next, dir);
+                prev = next;
+            }
+        }
+    }
+
+    fn push_line(&mut self, a: Point, b: Point, dir: i32) {
+        let x0 = a.x.clamp(0.0, self.width);
+        let x1 = b.x.clamp(0.0, self.width);
+        self.lines.push(Line {
+            x0,
+            y0: a.y,
+            y1: b.y,
+            dxdy: (x1 - x0) / (b.y - a.y),
+            dir,
+        });
+    }
+
+    fn fill(
+        &self,
+        fill_rule: FillRule,
+        left: u32,
+        top: u32,
+        rows: Range<u32>,
+        fallback: &mut Fallback,
+        blitter: &mut dyn Blitter,
+    ) {
+        let width = self.width as usize;
+        let height = (self.height as usize).min(rows.end as usize);
+
+        // An extra cell is required for the area to the right of the last pixel
+        // and one more for lines at the right edge.
+        let mut accumulator = vec![0.0f32; width + 2];
+        let mut runs = RunBuilder::new(width);
+        // Cells touched by lines in the current row. Everything else is a copy
+        // of the previous prefix sum and can be emitted as a single run.
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+
+        let mut active: Vec<Line> = Vec::new();
+        let mut row_lines: Vec<RowLine> = Vec::new();
+        let mut bands = Bands::default();
+        let mut next = 0;
+        let mut row = rows.start as usize;
+        let mut sticky_until = 0;
+        while row < height {
+            let y = row as f32;
+            let y_end = y + 1.0;
+
+            // Lines are pushed before removing the ones above,
+            // so rows can be started from any of them.
+            while next < self.lines.len() && self.lines[next].y0 < y_end {
+                active.push(self.lines[next]);
+                next += 1;
+            }
+            active.retain(|line| line.y1 > y);
+
+            if active.is_empty() {
+                match self.lines.get(next) {
+                    Some(line) => {
+                        row = line.y0 as usize;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+
+            // Complex rows are usually followed by more of them.
+            if row < sticky_until {
+                fallback.push(top + row as u32, blitter);
+                row += 1;
+                continue;
+            }
+
+            ranges.clear();
+            row_lines.clear();
+            for line in &active {
+                let ya = line.y0.max(y);
+                let yb = line.y1.min(y_end);
+                if yb > ya {
+                    let (xa, xb) = (line.x_at(ya), line.x_at(yb));
+                    row_lines.push(RowLine {
+                        line: *line,
+                        min_x: xa.min(xb),
+                        max_x: xa.max(xb),
+                    });
+                }
+            }
+
+            // Lines that do not overlap horizontally cannot cross, so they're split
+            // into independent clusters. The winding to the left of a cluster comes
+            // from the lines of previous ones.
+            row_lines
+                .sort_unstable_by(|a, b| a.min_x.partial_cmp(&b.min_x).unwrap_or(Ordering::Equal));
+            let mut winding = 0;
+            let mut budget = ROW_WORK_PER_LINE * row_lines.len() + MIN_ROW_WORK;
+            bands.left.clear();
+            let mut start = 0;
+            while start < row_lines.len() {
+                let mut end = start + 1;
+                let mut max_x = row_lines[start].max_x;
+                while end < row_lines.len() && row_lines[end].min_x <= max_x {
+                    max_x = max_x.max(row_lines[end].max_x);
+                    end += 1;
+                }
+
+                let net = self.fill_cluster(
+                    &row_lines[start..end],
+                    y,
+                    winding,
+                    fill_rule,
+                    &mut budget,
+                    &mut bands,
+                    &mut accumulator,
+                    &mut ranges,
+                );
+
+                match net {
+                    Ok(Some(net)) => winding += net,
+                    Ok(None) => bands
+                        .left
+                        .extend(row_lines[start..end].iter().map(|row_line| row_line.line)),
+                    Err(TooComplex) => {
+                        for &(start, end) in &ranges {
+                            accumulator[start..=end].fill(0.0);
+                        }
+
+                        ranges.clear();
+                        let y = top + row as u32;
+                        fallback.push(y, blitter);
+                        // Aligned in screen coordinates, so it does not depend on the first filled row.
+                        sticky_until = ((y / FALLBACK_ROWS + 1) * FALLBACK_ROWS - top) as usize;
+                        break;
+                    }
+                }
+
+                start = end;
+            }
+
+            if !ranges.is_empty() {
+                ranges.sort_unstable_by_key(|range| range.0);
+
+                let min_x = ranges[0].0;
+                runs.reset();
+                let mut sum = 0.0;
+                let mut x = min_x;
+                let mut i = 0;
+                while i < ranges.len() {
+                    let (start, mut end) = ranges[i];
+                    i += 1;
+                    while i < ranges.len() && ranges[i].0 <= end + 1 {
+                        end = end.max(ranges[i].1);
+                        i += 1;
+                    }
+
+                    if start > x && x < width {
+                        runs.push(coverage_to_alpha(sum), start.min(width) - x);
+                    }
+
+                    for (cell, idx) in accumulator[start..=end].iter_mut().zip(start..) {
+                        sum += *cell;
+                        *cell = 0.0;
+                        if idx < width {
+                            runs.push(coverage_to_alpha(sum), 1);
+                        }
+                    }
+
+                    x = end + 1;
+                }
+
+                if runs.finish() != 0 {
+                    fallback.flush(blitter);
+                    blitter.blit_anti_h(
+                        left + min_x as u32,
+                        top + row as u32,
+                        &mut runs.aa,
+                        &mut runs.runs,
+                    );
+                }
+            }
+
+            row += 1;
+        }
+    }
+
+    // Accumulates filled spans of a cluster of lines inside the row starting at `y`.
+    //
+    // The winding to the left of the cluster is `winding` plus the one of `bands.left`.
+    // Returns the winding the cluster adds to the right of it, when it's the same
+    // for the whole row. Which is usually the case, unless there are horizontal edges.
+    //
+    // Each band costs the number of lines it visits, which is taken from `budget`.
+    // When it runs out, stops and returns an error, leaving partially accumulated spans.
+    fn fill_cluster(
+        &self,
+        lines: &[RowLine],
+        y: f32,
+        winding: i32,
+        fill_rule: FillRule,
+        budget: &mut usize,
+        bands: &mut Bands,
+        accumulator: &mut [f32],
+        ranges: &mut Vec<(usize, usize)>,
+    ) -> Result<Option<i32>, TooComplex> {
+        let y_end = y + 1.0;
+        if let [row_line] = lines {
+            let line = &row_line.line;
+            if bands.left.is_empty() && line.y0 <= y && line.y1 >= y_end {
+                if is_filled(winding, fill_rule) != is_filled(winding + line.dir, fill_rule) {
+                    let xa = line.x_at(y).clamp(0.0, self.width);
+                    let xb = line.x_at(y_end).clamp(0.0, self.width);
+                    let d = if is_filled(winding, fill_rule) {
+                        -1.0
+                    } else {
+                        1.0
+                    };
+                    ranges.push(accumulate(accumulator, xa, xb, d));
+                }
+
+                return Ok(Some(line.dir));
+            }
+        }
+
+        bands.edges.clear();
+        bands.edges.push(y);
+        bands.edges.push(y_end);
+        // The winding to the left changes at the ends of lines as well.
+        let lines_iter = lines.iter().map(|row_line| &row_line.line);
+        for line in lines_iter.chain(bands.left.iter()) {
+            for line_y in [line.y0, line.y1] {
+                if line_y > y && line_y < y_end {
+                    bands.edges.push(line_y);
+                }
+            }
+        }
+
+        bands
+            .edges
+            .sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        bands.edges.dedup();
+
+        // Starts from the top band.
+        bands.stack.clear();
+        bands
+            .stack
+            .extend(bands.edges.windows(2).rev().map(|pair| (pair[0], pair[1])));
+
+        let mut net = None;
+        let mut is_constant = true;
+        while let Some((ya, yb)) = bands.stack.pop() {
+            let cost = lines.len() + bands.left.len();
+            if cost > *budget {
+                return Err(TooComplex);
+            }
+            *budget -= cost;
+
+            // Lines end only at band edges, so they either span the whole band or miss it.
+            bands.spans.clear();
+            for row_line in lines {
+                let line = &row_line.line;
+                if line.y0 <= ya && line.y1 >= yb {
+                    bands.spans.push(Span {
+                        xa: line.x_at(ya),
+                        xb: line.x_at(yb),
+                        dir: line.dir,
+                    });
+                }
+            }
+
+            bands.spans.sort_unstable_by(|a, b| {
+                a.xa.partial_cmp(&b.xa)
+                    .unwrap_or(Ordering::Equal)
+                    .then(a.xb.partial_cmp(&b.xb).unwrap_or(Ordering::Equal))
+            });
+
+            // Lines that change their order cross each other. And when any lines do,
+            // some neighbouring ones do as well, so the band is split at the first
+            // such crossing until there are none.
+            let mut crossing = yb;
+            let mut is_sorted = true;
+            for pair in bands.spans.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                if b.xb < a.xb {
+                    is_sorted = false;
+                    let t = (b.xa - a.xa) / ((a.xb - a.xa) - (b.xb - b.xa));
+                    let yc = ya + (yb - ya) * t;
+                    if yc > ya + MIN_BAND_HEIGHT && yc < yb - MIN_BAND_HEIGHT {
+                        crossing = crossing.min(yc);
+                    }
+                }
+            }
+
+            if crossing < yb {
+                bands.stack.push((crossing, yb));
+                bands.stack.push((ya, crossing));
+                continue;
+            }
+
+            // Remaining crossings are negligible, so the order in the middle is used.
+            if !is_sorted {
+                bands.spans.sort_unstable_by(|a, b| {
+                    (a.xa + a.xb)
+                        .partial_cmp(&(b.xa + b.xb))
+                        .unwrap_or(Ordering::Equal)
+                });
+            }
+
+            let height = yb - ya;
+            let mut winding = winding
+                + bands
+                    .left
+                    .iter()
+                    .filter(|line| line.y0 <= ya && line.y1 >= yb)
+                    .map(|line| line.dir)
+                    .sum::<i32>();
+            let band_net: i32 = bands.spans.iter().map(|span| span.dir).sum();
+            is_constant &= *net.get_or_insert(band_net) == band_net;
+            for span in &bands.spans {
+                let was_filled = is_filled(winding, fill_rule);
+                winding += span.dir;
+                if is_filled(winding, fill_rule) != was_filled {
+                    let xa = span.xa.clamp(0.0, self.width);
+                    let xb = span.xb.clamp(0.0, self.width);
+                    let d = if was_filled { -height } else { height };
+                    ranges.push(accumulate(accumulator, xa, xb, d));
+                }
+            }
+        }
+
+        Ok(net.filter(|_| is_constant))
+    }
+}
+
+// A row that requires too many bands.
+struct TooComplex;
+
+// A line inside the current row.
+#[derive(Copy, Clone, Debug)]
+struct RowLine {
+    line: Line,
+    min_x: f32,
+    max_x: f32,
+}
+
+// A part of a line inside a band.
+#[derive(Copy, Clone, Debug)]
+struct Span {
+    xa: f32,
+    xb: f32,
+    dir: i32,
+}
+
+// Buffers reused between clusters.
+#[derive(Default)]
+struct Bands {
+    edges: Vec<f32>,
+    stack: Vec<(f32, f32)>,
+    spans: Vec<Span>,
+    // Lines of previous clusters that do not span the whole row.
+    left: Vec<Line>,
+}
+
+// Accumulates the signed area of a line inside a single row
+// and returns the range of affected cells.
+//
+// `d` is the signed line height.
+fn accumulate(acc: &mut [f32], xa: f32, xb: f32, d: f32) -> (usize, usize) {
+    let (x0, x1) = if xa < xb { (xa, xb) } else { (xb, xa) };
+    let x0_floor = x0.floor();
+    let x0i = x0_floor as usize;
+    let x1_ceil = x1.ceil();
+    let x1i = x1_ceil as usize;
+
+    if x1i <= x0i + 1 {
+        // The line is inside a single column.
+        let xmf = 0.5 * (x0 + x1) - x0_floor;
+        acc[x0i] += d - d * xmf;
+        acc[x0i + 1] += d * xmf;
+        return (x0i, x0i + 1);
+    }
+
+    let s = 1.0 / (x1 - x0);
+    let x0f = x0 - x0_floor;
+    let a0 = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
+    let x1f = x1 - x1_ceil + 1.0;
+    let am = 0.5 * s * x1f * x1f;
+
+    acc[x0i] += d * a0;
+    if x1i == x0i + 2 {
+        acc[x0i + 1] += d * (1.0 - a0 - am);
+    } else {
+        let a1 = s * (1.5 - x0f);
+        acc[x0i + 1] += d * (a1 - a0);
+        for cell in &mut acc[x0i + 2..x1i - 1] {
+            *cell += d * s;
+        }
+        let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+        acc[x1i - 1] += d * (1.0 - a2 - am);
+    }
+    acc[x1i] += d * am;
+
+    (x0i, x1i)
+}
+
+fn is_filled(winding: i32, fill_rule: FillRule) -> bool {
+    match fill_rule {
+        FillRule::Winding => winding != 0,
+        FillRule::EvenOdd => winding % 2 != 0,
+    }
+}
+
+// Filled spans do not overlap, so the sum is within 0..=1, up to rounding errors.
+fn coverage_to_alpha(sum: f32) -> u8 {
+    (sum.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+}
+
+// Builds sparse alpha runs for a single row, merging neighbouring pixels with the same alpha.
+struct RunBuilder {
+    aa: Vec<u8>,
+    runs: Vec<AlphaRun>,
+    start: usize,
+    len: usize,
+    alpha: u8,
+    count: usize,
+}
+
+impl RunBuilder {
+    fn new(width: usize) -> Self {
+        RunBuilder {
+            aa: vec![0; width + 1],
+            runs: vec![None; width + 1],
+            start: 0,
+            len: 0,
+            alpha: 0,
+            count: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.start = 0;
+        self.len = 0;
+        self.alpha = 0;
+        self.count = 0;
+    }
+
+    fn push(&mut self, alpha: u8, len: usize) {
+        if alpha != self.alpha {
+            self.flush();
+            self.alpha = alpha;
+        }
+
+        if alpha != 0 {
+            self.count += len;
+        }
+
+        self.len += len;
+    }
+
+    fn flush(&mut self) {
+        while self.len != 0 {
+            let len = self.len.min(usize::from(u16::MAX));
+            self.runs[self.start] = NonZeroU16::new(len as u16);
+            self.aa[self.start] = self.alpha;
+            self.start += len;
+            self.len -= len;
+        }
+    }
+
+    // Terminates the runs and returns the number of non-transparent pixels.
+    fn finish(&mut self) -> usize {
+        self.flush();
+        self.runs[self.start] = None;
+        self.count
+    }
+}
+
+fn x_at_y(a: Point, b: Point, y: f32) -> f32 {
+    a.x + (b.x - a.x) * (y - a.y) / (b.y - a.y)
+}
+
+fn eval_curve(points: &[Point], t: f32) -> Point {
+    let mt = 1.0 - t;
+    let (x, y) = if points.len() == 3 {
+        let (a, b, c) = (mt * mt, 2.0 * mt * t, t * t);
+        (
+            a * points[0].x + b * points[1].x + c * points[2].x,
+            a * points[0].y + b * points[1].y + c * points[2].y,
+        )
+    } else {
+        let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+        (
+            a * points[0].x + b * points[1].x + c * points[2].x + d * points[3].x,
+            a * points[0].y + b * points[1].y + c * points[2].y + d * points[3].y,
+        )
+    };
+
+    Point::from_xy(x, y)
+}
@@ -0,0 +1,76 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use alloc::sync::Arc;
+
+use tiny_skia_path::NormalizedF32;
+
+use crate::{ColorSpace, PremultipliedColor, Shader};
+
+use crate::pipeline;
+use crate::pipeline::RasterPipelineBuilder;
+
+/// A user-implemented shader.
+///
+/// Must be thread-safe, since it can be used by
+/// [`TileExecutor`](crate::TileExecutor) jobs.
+pub trait CustomShader: core::fmt::Debug + Send + Sync {
+    /// Fills a horizontal span of pixels.
+    ///
+    /// `x` and `y` are the device coordinates of the first pixel
+    /// and `colors` contains a transparent color per span pixel.
+    ///
+    /// Colors are expected to be in the paint's color space.
+    fn shade_span(&self, x: i32, y: i32, colors: &mut [PremultipliedColor]);
+}
+
+/// A custom shader.
+///
+/// Unlike other shaders, works in device coordinates and therefore is not affected
+/// by transforms. Shading results will still be modulated by the shader opacity,
+/// coverage and masks and then blended like any other shader.
+///
+/// Always uses the high precision pipeline.
+#[derive(Clone, Debug)]
+pub struct Custom {
+    pub(crate) shader: Arc<dyn CustomShader>,
+    pub(crate) opacity: NormalizedF32,
+    // Device coordinates of the pixmap origin. Non-zero only when rendering tiles.
+    pub(crate) origin: (i32, i32),
+}
+
+impl Custom {
+    /// Creates a new custom shader.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<'a>(shader: Arc<dyn CustomShader>) -> Shader<'a> {
+        Shader::Custom(Custom {
+            shader,
+            opacity: NormalizedF32::ONE,
+            origin: (0, 0),
+        })
+    }
+
+    pub(crate) fn push_stages(&self, cs: ColorSpace, p: &mut RasterPipelineBuilder) -> bool {
+        p.ctx.custom_shader.shader = Some(self.shader.clone());
+        p.ctx.custom_shader.origin = self.origin;
+        // Unlike Pattern, the opacity is applied by the stage itself,
+        // since `current_coverage` is overwritten by anti-aliased blitting.
+        p.ctx.custom_shader.opacity = self.opacity.get();
+        p.push(pipeline::Stage::CustomShader);
+
+        if let Some(stage) = cs.expand_stage() {
+            p.push(stage);
+        }
+
+        true
+    }
+}
+
+impl PartialEq for Custom {
+    fn eq(&self, other: &Self) -> bool {
+        // Compare data pointers only, since vtables are not guaranteed to be unique.
+        Arc::as_ptr(&self.shader) as *const () == Arc::as_ptr(&other.shader) as *const ()
+            && self.opacity == other.opacity
+            && self.origin == other.origin
+    }
+}
@@ -10,6 +10,9 @@ use tiny_skia_path::{NormalizedF32, Scalar};
 
 use crate::{Color, ColorSpace, SpreadMode, Transform};
 
+#[cfg(all(not(feature = "std"), feature = "no-std-float"))]
+use tiny_skia_path::NoStdFloat;
+
 use crate::pipeline::RasterPipelineBuilder;
 use crate::pipeline::{self, EvenlySpaced2StopGradientCtx, GradientColor, GradientCtx};
 
@@ -37,13 +40,81 @@ impl GradientStop {
     }
 }
 
+/// A color space used to interpolate gradient colors.
+///
+/// Gradient stops are always defined in sRGB.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InterpolationColorSpace {
+    /// Interpolate in the paint's color space.
+    ///
+    /// Matches `SRGB` when the paint's color space is `ColorSpace::Linear`.
+    Destination,
+
+    /// Interpolate gamma-encoded sRGB values, like CSS `in srgb`.
+    SRGB,
+
+    /// Interpolate linear sRGB values, like CSS `in srgb-linear`.
+    LinearSRGB,
+
+    /// Interpolate in the OKLab color space, like CSS `in oklab`.
+    OKLab,
+
+    /// Interpolate in the OKLCH color space, like CSS `in oklch`.
+    OKLCH(HueInterpolation),
+}
+
+impl Default for InterpolationColorSpace {
+    fn default() -> Self {
+        InterpolationColorSpace::Destination
+    }
+}
+
+/// A hue interpolation method for polar color spaces.
+///
+/// Matches the CSS `<hue-interpolation-method>`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum HueInterpolation {
+    /// Use the shortest arc between hues.
+    Shorter,
+    /// Use the longest arc between hues.
+    Longer,
+    /// Use the clockwise arc between hues.
+    Increasing,
+    /// Use the counterclockwise arc between hues.
+    Decreasing,
+}
+
+impl Default for HueInterpolation {
+    fn default() -> Self {
+        HueInterpolation::Shorter
+    }
+}
+
+/// Controls how gradient colors are interpolated.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct GradientInterpolation {
+    /// A color space used for interpolation.
+    ///
+    /// Default: `Destination`
+    pub color_space: InterpolationColorSpace,
+
+    /// Interpolate premultiplied colors.
+    ///
+    /// Prevents transparent stops from darkening neighbor colors.
+    /// For `OKLCH`, the hue is not premultiplied.
+    ///
+    /// Default: false
+    pub premultiplied: bool,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct Gradient {
-    stops: Vec<GradientStop>,
-    tile_mode: SpreadMode,
+    pub(crate) stops: Vec<GradientStop>,
+    pub(crate) tile_mode: SpreadMode,
     pub(crate) transform: Transform,
-    points_to_unit: Transform,
+    pub(crate) points_to_unit: Transform,
     pub(crate) colors_are_opaque: bool,
+    pub(crate) interpolation: GradientInterpolation,
     has_uniform_stops: bool,
 }
 
@@ -99,6 +170,7 @@ impl Gradient {
             transform,
             points_to_unit,
             colors_are_opaque,
+            interpolation: GradientInterpolation::default(),
             has_uniform_stops,
         }
     }
@@ -142,21 +214,18 @@ impl Gradient {
             }
         }
 
+        let stops = self.interpolated_stops(cs);
+
         // The two-stop case with stops at 0 and 1.
-        if self.stops.len() == 2 {
+        if stops.len() == 2 {
             debug_assert!(self.has_uniform_stops);
 
-            let c0 = cs.expand_color(self.stops[0].color);
-            let c1 = cs.expand_color(self.stops[1].color);
+            let c0 = stops[0].1;
+            let c1 = stops[1].1;
 
             p.ctx.evenly_spaced_2_stop_gradient = EvenlySpaced2StopGradientCtx {
-                factor: GradientColor::new(
-                    c1.red() - c0.red(),
-                    c1.green() - c0.green(),
-                    c1.blue() - c0.blue(),
-                    c1.alpha() - c0.alpha(),
-                ),
-                bias: GradientColor::from(c0),
+                factor: GradientColor::new(c1.r - c0.r, c1.g - c0.g, c1.b - c0.b, c1.a - c0.a),
+                bias: c0,
             };
 
             p.push(pipeline::Stage::EvenlySpaced2StopGradient);
@@ -171,22 +240,18 @@ impl Gradient {
             // Therefore, the max number of stops is `self.points.len()+1`.
             //
             // We also need at least 16 values for lowp pipeline.
-            ctx.factors.reserve((self.stops.len() + 1).max(16));
-            ctx.biases.reserve((self.stops.len() + 1).max(16));
+            ctx.factors.reserve((stops.len() + 1).max(16));
+            ctx.biases.reserve((stops.len() + 1).max(16));
 
-            ctx.t_values.reserve(self.stops.len() + 1);
+            ctx.t_values.reserve(stops.len() + 1);
 
             // Remove the dummy stops inserted by Gradient::new
             // because they are naturally handled by the search method.
-            let (first_stop, last_stop) = if self.stops.len() > 2 {
-                let first = if self.stops[0].color != self.stops[1].color {
-                    0
-                } else {
-                    1
-                };
+            let (first_stop, last_stop) = if stops.len() > 2 {
+                let first = if stops[0].1 != stops[1].1 { 0 } else { 1 };
 
-                let len = self.stops.len();
-                let last = if self.stops[len - 2].color != self.stops[len - 1].color {
+                let len = stops.len();
+                let last = if stops[len - 2].1 != stops[len - 1].1 {
                     len - 1
                 } else {
                     len - 2
@@ -196,14 +261,12 @@ impl Gradient {
                 (0, 1)
             };
 
-            let mut t_l = self.stops[first_stop].position.get();
-            let mut c_l = GradientColor::from(cs.expand_color(self.stops[first_stop].color));
+            let (mut t_l, mut c_l) = stops[first_stop];
             ctx.push_const_color(c_l);
             ctx.t_values.push(NormalizedF32::ZERO);
             // N.B. lastStop is the index of the last stop, not one after.
             for i in first_stop..last_stop {
-                let t_r = self.stops[i + 1].position.get();
-                let c_r = GradientColor::from(cs.expand_color(self.stops[i + 1].color));
+                let (t_r, c_r) = stops[i + 1];
                 debug_assert!(t_l <= t_r);
                 if t_l < t_r {
                     // For each stop we calculate a bias B and a scale factor F, such that
@@ -249,15 +312,112 @@ impl Gradient {
             p.ctx.gradient = ctx;
         }
 
-        if !self.colors_are_opaque {
-            p.push(pipeline::Stage::Premultiply);
-        }
+        self.push_conversion_stages(cs, p);
 
         push_stages_post(p);
 
         true
     }
 
+    // Converts stop colors into the interpolation color space.
+    //
+    // Returns positions and colors, since OKLCH may need more stops than the gradient has.
+    fn interpolated_stops(&self, cs: ColorSpace) -> Vec<(f32, GradientColor)> {
+        let mut stops: Vec<(f32, GradientColor)> = self
+            .stops
+            .iter()
+            .map(|stop| {
+                let color = match self.interpolation.color_space {
+                    InterpolationColorSpace::Destination => cs.expand_color(stop.color).into(),
+                    InterpolationColorSpace::SRGB => stop.color.into(),
+                    InterpolationColorSpace::LinearSRGB => {
+                        ColorSpace::FullSRGBGamma.expand_color(stop.color).into()
+                    }
+                    InterpolationColorSpace::OKLab | InterpolationColorSpace::OKLCH(_) => {
+                        linear_srgb_to_oklab(ColorSpace::FullSRGBGamma.expand_color(stop.color))
+                    }
+                };
+
+                (stop.position.get(), color)
+            })
+            .collect();
+
+        if let InterpolationColorSpace::OKLCH(method) = self.interpolation.color_space {
+            for (_, c) in &mut stops {
+                *c = oklab_to_oklch(*c);
+            }
+
+            stops = fix_hues(&stops, method);
+        }
+
+        if self.interpolation.premultiplied {
+            let is_polar = matches!(
+                self.interpolation.color_space,
+                InterpolationColorSpace::OKLCH(_)
+            );
+
+            for (_, c) in &mut stops {
+                c.r *= c.a;
+                c.g *= c.a;
+                // The hue is not premultiplied.
+                if !is_polar {
+                    c.b *= c.a;
+                }
+            }
+        }
+
+        stops
+    }
+
+    // Converts interpolated colors into the paint's color space.
+    fn push_conversion_stages(&self, cs: ColorSpace, p: &mut RasterPipelineBuilder) {
+        if self.interpolation.premultiplied && !self.colors_are_opaque {
+            match self.interpolation.color_space {
+                InterpolationColorSpace::OKLCH(_) => {
+                    p.push(pipeline::Stage::UnpremultiplyPolar);
+                }
+                _ => p.push(pipeline::Stage::Unpremultiply),
+            }
+        }
+
+        let is_linear = match self.interpolation.color_space {
+            InterpolationColorSpace::Destination => false,
+            InterpolationColorSpace::SRGB => {
+                if let Some(stage) = cs.expand_stage() {
+                    p.push(stage);
+                }
+                false
+            }
+            InterpolationColorSpace::LinearSRGB => true,
+            InterpolationColorSpace::OKLab => {
+                p.push(pipeline::Stage::OKLabToLinearSRGB);
+                true
+            }
+            InterpolationColorSpace::OKLCH(_) => {
+                p.push(pipeline::Stage::OKLCHToOKLab);
+                p.push(pipeline::Stage::OKLabToLinearSRGB);
+                true
+            }
+        };
+
+        if is_linear {
+            // OKLab colors can be outside of the sRGB gamut.
+            p.push(pipeline::Stage::Clamp0);
+            p.push(pipeline::Stage::ClampA);
+
+            if cs != ColorSpace::FullSRGBGamma {
+                p.push(pipeline::Stage::GammaCompressSrgb);
+                if let Some(stage) = cs.expand_stage() {
+                    p.push(stage);
+                }
+            }
+        }
+
+        if !self.colors_are_opaque {
+            p.push(pipeline::Stage::Premultiply);
+        }
+    }
+
     pub fn apply_opacity(&mut self, opacity: f32) {
         for stop in &mut self.stops {
             stop.color.apply_opacity(opacity);
@@ -266,3 +426,124 @@ impl Gradient {
         self.colors_are_opaque = self.stops.iter().all(|p| p.color.is_opaque());
     }
 }
+
+// From https://bottosson.github.io/posts/oklab/
+fn linear_srgb_to_oklab(c: Color) -> GradientColor {
+    let (r, g, b) = (c.red(), c.green(), c.blue());
+
+    let l = 0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b;
+    let m = 0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b;
+    let s = 0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b;
+
+    let l = cbrt(l);
+    let m = cbrt(m);
+    let s = cbrt(s);
+
+    GradientColor::new(
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        c.alpha(),
+    )
+}
+
+// Stores lightness, chroma and hue in degrees in the red, green and blue channels.
+fn oklab_to_oklch(c: GradientColor) -> GradientColor {
+    let chroma = (c.g * c.g + c.b * c.b).sqrt();
+    let hue = if chroma > 0.0 {
+        let hue = (c.g / chroma).bound(-1.0, 1.0).acos().to_degrees();
+        if c.b < 0.0 {
+            360.0 - hue
+        } else {
+            hue
+        }
+    } else {
+        0.0
+    };
+
+    GradientColor::new(c.r, chroma, hue, c.a)
+}
+
+fn cbrt(x: f32) -> f32 {
+    if x < 0.0 {
+        -(-x).powf(1.0 / 3.0)
+    } else {
+        x.powf(1.0 / 3.0)
+    }
+}
+
+// Adjusts hues according to the CSS hue interpolation method.
+fn fix_hues(stops: &[(f32, GradientColor)], method: HueInterpolation) -> Vec<(f32, GradientColor)> {
+    // The hue of achromatic colors is powerless and is taken from the other end of each segment.
+    // So an achromatic stop between two different hues is split into two coincident stops,
+    // one for each segment.
+    const POWERLESS_CHROMA: f32 = 1e-4;
+    let is_chromatic = |c: &GradientColor| c.g > POWERLESS_CHROMA;
+
+    let mut fixed = Vec::with_capacity(stops.len());
+    let mut prev_hue = None;
+    for (i, &(t, c)) in stops.iter().enumerate() {
+        if is_chromatic(&c) {
+            prev_hue = Some(c.b);
+            fixed.push((t, c));
+            continue;
+        }
+
+        let next_hue = stops[i + 1..]
+            .iter()
+            .find(|(_, c)| is_chromatic(c))
+            .map(|(_, c)| c.b);
+        let h0 = prev_hue.or(next_hue).unwrap_or(0.0);
+        let h1 = next_hue.unwrap_or(h0);
+        fixed.push((t, GradientColor::new(c.r, c.g, h0, c.a)));
+        if h1 != h0 {
+            fixed.push((t, GradientColor::new(c.r, c.g, h1, c.a)));
+        }
+    }
+
+    let mut prev = fixed[0].1;
+    for i in 1..fixed.len() {
+        let curr = fixed[i].1;
+        let h1 = fixed[i - 1].1.b;
+
+        // Duplicated stops, like the ones inserted by `Gradient::new`, must keep the same hue.
+        if curr == prev {
+            fixed[i].1.b = h1;
+            continue;
+        }
+        prev = curr;
+
+        // Hues are in the 0..360 range before adjustment, while `h1` may be already adjusted.
+        let mut delta = curr.b - (h1 - (h1 / 360.0).floor() * 360.0);
+        match method {
+            HueInterpolation::Shorter => {
+                if delta > 180.0 {
+                    delta -= 360.0;
+                } else if delta < -180.0 {
+                    delta += 360.0;
+                }
+            }
+            HueInterpolation::Longer => {
+                if 0.0 < delta && delta < 180.0 {
+                    delta -= 360.0;
+                } else if -180.0 < delta && delta <= 0.0 {
+                    delta += 360.0;
+                }
+            }
+            HueInterpolation::Increasing => {
+                if delta < 0.0 {
+                    delta += 360.0;
+                }
+            }
+            HueInterpolation::Decreasing => {
+                if delta > 0.0 {
+                    delta -= 360.0;
+                }
+            }
+        }
+
+        fixed[i].1.b = h1 + delta;
+    }
+
+    fixed
+}
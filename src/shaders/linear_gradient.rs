@@ -109,7 +109,7 @@ fn points_to_unit_ts(start: Point, end: Point) -> Option<Transform> {
     Some(ts)
 }
 
-fn average_gradient_color(points: &[GradientStop]) -> Color {
+pub(crate) fn average_gradient_color(points: &[GradientStop]) -> Color {
     use crate::wide::f32x4;
 
     fn load_color(c: Color) -> f32x4 {
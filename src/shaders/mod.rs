@@ -4,17 +4,26 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the LICENSE file.
 
+mod custom;
 mod gradient;
 mod linear_gradient;
 mod pattern;
 mod radial_gradient;
+mod sweep_gradient;
 
 use tiny_skia_path::{NormalizedF32, Scalar};
 
-pub use gradient::GradientStop;
+pub use custom::{Custom, CustomShader};
+pub use gradient::{
+    GradientInterpolation, GradientStop, HueInterpolation, InterpolationColorSpace,
+};
 pub use linear_gradient::LinearGradient;
 pub use pattern::{FilterQuality, Pattern, PixmapPaint};
 pub use radial_gradient::RadialGradient;
+pub use sweep_gradient::SweepGradient;
+
+pub(crate) use gradient::Gradient;
+pub(crate) use radial_gradient::{FocalData, RadialKind};
 
 use crate::{Color, ColorSpace, Transform};
 
@@ -57,8 +66,12 @@ pub enum Shader<'a> {
     LinearGradient(LinearGradient),
     /// A radial gradient shader.
     RadialGradient(RadialGradient),
+    /// A sweep gradient shader.
+    SweepGradient(SweepGradient),
     /// A pattern shader.
     Pattern(Pattern<'a>),
+    /// A custom shader.
+    Custom(Custom),
 }
 
 impl<'a> Shader<'a> {
@@ -68,7 +81,9 @@ impl<'a> Shader<'a> {
             Shader::SolidColor(ref c) => c.is_opaque(),
             Shader::LinearGradient(ref g) => g.is_opaque(),
             Shader::RadialGradient(_) => false,
+            Shader::SweepGradient(ref g) => g.is_opaque(),
             Shader::Pattern(_) => false,
+            Shader::Custom(_) => false,
         }
     }
 
@@ -85,26 +100,67 @@ impl<'a> Shader<'a> {
             }
             Shader::LinearGradient(ref g) => g.push_stages(cs, p),
             Shader::RadialGradient(ref g) => g.push_stages(cs, p),
+            Shader::SweepGradient(ref g) => g.push_stages(cs, p),
             Shader::Pattern(ref patt) => patt.push_stages(cs, p),
+            Shader::Custom(ref c) => c.push_stages(cs, p),
         }
     }
 
     /// Transforms the shader.
+    ///
+    /// Custom shaders are not affected, since they are working in device coordinates.
     pub fn transform(&mut self, ts: Transform) {
         match self {
-            Shader::SolidColor(_) => {}
+            Shader::SolidColor(_) | Shader::Custom(_) => {}
             Shader::LinearGradient(g) => {
                 g.base.transform = g.base.transform.post_concat(ts);
             }
             Shader::RadialGradient(g) => {
                 g.base.transform = g.base.transform.post_concat(ts);
             }
+            Shader::SweepGradient(g) => {
+                g.base.transform = g.base.transform.post_concat(ts);
+            }
             Shader::Pattern(p) => {
                 p.transform = p.transform.post_concat(ts);
             }
         }
     }
 
+    /// Sets how gradient colors are interpolated.
+    ///
+    /// Does nothing for non-gradient shaders.
+    pub fn set_gradient_interpolation(&mut self, interpolation: GradientInterpolation) {
+        match self {
+            Shader::SolidColor(_) | Shader::Pattern(_) | Shader::Custom(_) => {}
+            Shader::LinearGradient(g) => g.base.interpolation = interpolation,
+            Shader::RadialGradient(g) => g.base.interpolation = interpolation,
+            Shader::SweepGradient(g) => g.base.interpolation = interpolation,
+        }
+    }
+
+    /// Moves the shader into the coordinate system of a pixmap tile.
+    ///
+    /// Unlike `transform`, affects custom shaders too.
+    pub(crate) fn translate_tile(&mut self, ts: Transform) {
+        debug_assert!(ts.is_identity() || ts.is_translate());
+        match self {
+            Shader::Custom(_) => self.offset_origin(-ts.tx as i32, -ts.ty as i32),
+            _ => self.transform(ts),
+        }
+    }
+
+    /// Moves the device origin of custom shaders.
+    ///
+    /// Used when drawing into a pixmap positioned inside the device, like a canvas layer.
+    /// Other shaders are positioned by the drawing transform instead.
+    pub(crate) fn offset_origin(&mut self, dx: i32, dy: i32) {
+        if let Shader::Custom(c) = self {
+            c.origin.0 += dx;
+            c.origin.1 += dy;
+        }
+    }
+
     /// Shifts shader's opacity.
     ///
     /// `opacity` will be clamped to the 0..=1 range.
@@ -118,6 +174,7 @@ impl<'a> Shader<'a> {
     /// - For `SolidColor` this function will multiply `color.alpha` by `opacity`.
     /// - For gradients this function will multiply all colors by `opacity`.
     /// - For `Pattern` this function will multiply `Patter::opacity` by `opacity`.
+    /// - For `Custom` this function will multiply the shader output by `opacity`.
     pub fn apply_opacity(&mut self, opacity: f32) {
         match self {
             Shader::SolidColor(ref mut c) => {
@@ -129,9 +186,15 @@ impl<'a> Shader<'a> {
             Shader::RadialGradient(g) => {
                 g.base.apply_opacity(opacity);
             }
+            Shader::SweepGradient(g) => {
+                g.base.apply_opacity(opacity);
+            }
             Shader::Pattern(ref mut p) => {
                 p.opacity = NormalizedF32::new(p.opacity.get() * opacity.bound(0.0, 1.0)).unwrap();
             }
+            Shader::Custom(ref mut c) => {
+                c.opacity = NormalizedF32::new(c.opacity.get() * opacity.bound(0.0, 1.0)).unwrap();
+            }
         }
     }
 }
@@ -67,8 +67,8 @@ impl Default for PixmapPaint {
 #[derive(Clone, PartialEq, Debug)]
 pub struct Pattern<'a> {
     pub(crate) pixmap: PixmapRef<'a>,
-    quality: FilterQuality,
-    spread_mode: SpreadMode,
+    pub(crate) quality: FilterQuality,
+    pub(crate) spread_mode: SpreadMode,
     pub(crate) opacity: NormalizedF32,
     pub(crate) transform: Transform,
 }
@@ -4,6 +4,7 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the LICENSE file.
 
+use alloc::vec;
 use alloc::vec::Vec;
 
 use tiny_skia_path::Scalar;
@@ -11,6 +12,7 @@ use tiny_skia_path::Scalar;
 use crate::{ColorSpace, GradientStop, Point, Shader, SpreadMode, Transform};
 
 use super::gradient::{Gradient, DEGENERATE_THRESHOLD};
+use super::linear_gradient::average_gradient_color;
 use crate::pipeline;
 use crate::pipeline::RasterPipelineBuilder;
 use crate::wide::u32x8;
@@ -19,11 +21,55 @@ use crate::wide::u32x8;
 use tiny_skia_path::NoStdFloat;
 
 #[derive(Copy, Clone, PartialEq, Debug)]
-struct FocalData {
-    r1: f32, // r1 after mapping focal point to (0, 0)
+pub(crate) struct FocalData {
+    pub(crate) r1: f32,      // r1 after mapping focal point to (0, 0)
+    pub(crate) focal_x: f32, // f
+    pub(crate) is_swapped: bool,
 }
 
 impl FocalData {
+    // Expects radii in the space where the start center is at (0, 0)
+    // and the end center is at (1, 0).
+    pub(crate) fn new(r0: f32, r1: f32) -> Self {
+        let mut r1 = r1;
+        let mut is_swapped = false;
+        let mut focal_x = r0 / (r0 - r1);
+        if (focal_x - 1.0).is_nearly_zero() {
+            // swap r0, r1
+            r1 = r0;
+            focal_x = 0.0; // because r0 is now 0
+            is_swapped = true;
+        }
+
+        FocalData {
+            r1: r1 / (1.0 - focal_x).abs(),
+            focal_x,
+            is_swapped,
+        }
+    }
+
+    // Maps the space, where the start center is at (0, 0) and the end center is at (1, 0),
+    // into the scaled space, where the focal point is at (0, 0).
+    pub(crate) fn transform(&self) -> Option<Transform> {
+        let mut ts = Transform::identity();
+        if self.is_swapped {
+            ts = ts.post_translate(-1.0, 0.0).post_scale(-1.0, 1.0);
+        }
+
+        if self.focal_x != 0.0 {
+            // Map {focal point, (1, 0)} to {(0, 0), (1, 0)}
+            ts = ts.post_concat(ts_from_poly_to_poly(
+                Point::from_xy(self.focal_x, 0.0),
+                Point::from_xy(1.0, 0.0),
+                Point::from_xy(0.0, 0.0),
+                Point::from_xy(1.0, 0.0),
+            )?);
+        }
+
+        let (sx, sy) = self.scale();
+        Some(ts.post_scale(sx, sy))
+    }
+
     // Whether the focal point (0, 0) is on the end circle with center (1, 0) and radius r1. If
     // this is true, it's as if an aircraft is flying at Mach 1 and all circles (soundwaves)
     // will go through the focal point (aircraft). In our previous implementations, this was
@@ -34,24 +80,56 @@ impl FocalData {
         (1.0 - self.r1).is_nearly_zero()
     }
 
+    fn is_natively_focal(&self) -> bool {
+        self.focal_x.is_nearly_zero()
+    }
+
+    // The scale applied after mapping the focal point to (0, 0) and the end center to (1, 0).
+    // Saves some arithmetic operations during the shader computation.
+    fn scale(&self) -> (f32, f32) {
+        if self.is_focal_on_circle() {
+            (0.5, 0.5)
+        } else {
+            let r1 = self.r1;
+            (r1 / (r1 * r1 - 1.0), 1.0 / ((r1 * r1 - 1.0).abs()).sqrt())
+        }
+    }
+
     fn is_well_behaved(&self) -> bool {
         !self.is_focal_on_circle() && self.r1 > 1.0
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) enum RadialKind {
+    // Concentric circles.
+    Radial,
+    // Circles with the same radius.
+    Strip,
+    Focal(FocalData),
+}
+
 /// A radial gradient shader.
 ///
-/// This is not `SkRadialGradient` like in Skia, but rather `SkTwoPointConicalGradient`
-/// without the start radius.
+/// This is not `SkRadialGradient` like in Skia, but rather `SkTwoPointConicalGradient`.
 #[derive(Clone, PartialEq, Debug)]
 pub struct RadialGradient {
     pub(crate) base: Gradient,
-    focal_data: Option<FocalData>,
+    pub(crate) kind: RadialKind,
+    // Start and end radii in the unit space, where the start center is at (0, 0)
+    // and the end center is at (1, 0). For concentric circles, both centers are at (0, 0)
+    // and radii differ by 1.
+    pub(crate) r0: f32,
+    pub(crate) r1: f32,
 }
 
 impl RadialGradient {
     /// Creates a new radial gradient shader.
     ///
+    /// The gradient starts at the `start` point and ends at the circle
+    /// with the `end` center and the `radius`.
+    /// See [`RadialGradient::new_two_point_conical`] for a non-zero start radius.
+    ///
     /// Returns `Shader::SolidColor` when:
     /// - `stops.len()` == 1
     ///
@@ -68,10 +146,47 @@ impl RadialGradient {
         stops: Vec<GradientStop>,
         mode: SpreadMode,
         transform: Transform,
+    ) -> Option<Shader<'static>> {
+        if radius < 0.0 || radius.is_nearly_zero() {
+            return None;
+        }
+
+        Self::new_two_point_conical(start, 0.0, end, radius, stops, mode, transform)
+    }
+
+    /// Creates a new two-point conical gradient shader.
+    ///
+    /// The gradient is interpolated between the start circle
+    /// with the `start` center and the `start_radius`
+    /// and the end circle with the `end` center and the `end_radius`.
+    /// Like `createRadialGradient` in HTML canvas or `fr` in SVG.
+    ///
+    /// Returns `Shader::SolidColor` when:
+    /// - `stops.len()` == 1
+    /// - circles are very close and `mode` is not `Pad` or `end_radius` is very close to zero
+    ///
+    /// Returns `None` when:
+    ///
+    /// - `stops` is empty
+    /// - `start_radius` < 0 or `end_radius` < 0
+    /// - `transform` is not invertible
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new_two_point_conical(
+        start: Point,
+        start_radius: f32,
+        end: Point,
+        end_radius: f32,
+        stops: Vec<GradientStop>,
+        mode: SpreadMode,
+        transform: Transform,
     ) -> Option<Shader<'static>> {
         // From SkGradientShader::MakeTwoPointConical
 
-        if radius < 0.0 || radius.is_nearly_zero() {
+        if !(start_radius >= 0.0 && end_radius >= 0.0) {
+            return None;
+        }
+
+        if !start_radius.is_finite() || !end_radius.is_finite() {
             return None;
         }
 
@@ -90,24 +205,70 @@ impl RadialGradient {
             return None;
         }
 
+        let mut start_radius = start_radius;
         if length.is_nearly_zero_within_tolerance(DEGENERATE_THRESHOLD) {
             // If the center positions are the same, then the gradient
             // is the radial variant of a 2 pt conical gradient,
             // an actual radial gradient (startRadius == 0),
             // or it is fully degenerate (startRadius == endRadius).
 
-            let inv = radius.invert();
+            if (end_radius - start_radius).is_nearly_zero_within_tolerance(DEGENERATE_THRESHOLD) {
+                // Degenerate case, where the interpolation region area approaches zero.
+                // The proper behavior depends on the spread mode, which is consistent with
+                // the default degenerate gradient behavior, except when mode is `Pad`
+                // and the radius > DEGENERATE_THRESHOLD.
+                if mode == SpreadMode::Pad && end_radius > DEGENERATE_THRESHOLD {
+                    // The interpolation region becomes an infinitely thin ring at the radius,
+                    // so the final gradient will be the first color repeated from 0 to 1,
+                    // and then a hard stop switching to the last color at 1.
+                    let first = stops[0].color;
+                    let last = stops[stops.len() - 1].color;
+                    let stops = vec![
+                        GradientStop::new(0.0, first),
+                        GradientStop::new(1.0, first),
+                        GradientStop::new(1.0, last),
+                    ];
+                    return Self::new_two_point_conical(
+                        start, 0.0, start, end_radius, stops, mode, transform,
+                    );
+                }
+
+                return match mode {
+                    SpreadMode::Pad => Some(Shader::SolidColor(stops[stops.len() - 1].color)),
+                    SpreadMode::Reflect | SpreadMode::Repeat => {
+                        Some(Shader::SolidColor(average_gradient_color(&stops)))
+                    }
+                };
+            }
+
+            if start_radius.is_nearly_zero_within_tolerance(DEGENERATE_THRESHOLD) {
+                // We can treat this gradient as radial, which is faster. If we got here, we know
+                // that endRadius is not equal to 0, so this produces a meaningful gradient
+                start_radius = 0.0;
+            }
+        }
+
+        // From SkTwoPointConicalGradient::Create
+        if length.is_nearly_zero() {
+            if end_radius.max(start_radius).is_nearly_zero()
+                || (end_radius - start_radius).is_nearly_zero()
+            {
+                // Degenerate case; avoid dividing by zero.
+                return None;
+            }
+
+            // Concentric case: we can pretend we're radial (with a tiny twist).
+            let inv = (end_radius - start_radius).abs().invert();
             let mut ts = Transform::from_translate(-start.x, -start.y);
             ts = ts.post_scale(inv, inv);
 
-            // We can treat this gradient as radial, which is faster. If we got here, we know
-            // that endRadius is not equal to 0, so this produces a meaningful gradient
             Some(Shader::RadialGradient(RadialGradient {
                 base: Gradient::new(stops, mode, transform, ts),
-                focal_data: None,
+                kind: RadialKind::Radial,
+                r0: start_radius * inv,
+                r1: end_radius * inv,
             }))
         } else {
-            // From SkTwoPointConicalGradient::Create
             let mut ts = ts_from_poly_to_poly(
                 start,
                 end,
@@ -115,48 +276,87 @@ impl RadialGradient {
                 Point::from_xy(1.0, 0.0),
             )?;
 
-            let d_center = (start - end).length();
-            let r1 = radius / d_center;
-            let focal_data = FocalData { r1 };
+            let r0 = start_radius / length;
+            let r1 = end_radius / length;
 
-            // The following transformations are just to accelerate the shader computation by saving
-            // some arithmetic operations.
-            if focal_data.is_focal_on_circle() {
-                ts = ts.post_scale(0.5, 0.5);
+            let kind = if (end_radius - start_radius).is_nearly_zero() {
+                RadialKind::Strip
             } else {
-                ts = ts.post_scale(r1 / (r1 * r1 - 1.0), 1.0 / ((r1 * r1 - 1.0).abs()).sqrt());
-            }
+                let focal_data = FocalData::new(r0, r1);
+                ts = ts.post_concat(focal_data.transform()?);
+                RadialKind::Focal(focal_data)
+            };
 
             Some(Shader::RadialGradient(RadialGradient {
                 base: Gradient::new(stops, mode, transform, ts),
-                focal_data: Some(focal_data),
+                kind,
+                r0,
+                r1,
             }))
         }
     }
 
+    // Undoes transforms applied to focal gradients after mapping the start center to (0, 0)
+    // and the end center to (1, 0), which speed up the shader computation.
+    pub(crate) fn unscale(&self) -> Option<Transform> {
+        match self.kind {
+            RadialKind::Radial | RadialKind::Strip => Some(Transform::identity()),
+            RadialKind::Focal(focal_data) => focal_data.transform()?.invert(),
+        }
+    }
+
     pub(crate) fn push_stages(&self, cs: ColorSpace, p: &mut RasterPipelineBuilder) -> bool {
-        let p0 = if let Some(focal_data) = self.focal_data {
-            1.0 / focal_data.r1
-        } else {
-            1.0
+        let (p0, p1, p2) = match self.kind {
+            RadialKind::Radial => {
+                // Radial computes a t for [0, r1], but we want a t for [r0, r1].
+                if self.r1 < self.r0 {
+                    (1.0, self.r0, 1.0)
+                } else {
+                    (1.0, -self.r0, 1.0)
+                }
+            }
+            RadialKind::Strip => (self.r0 * self.r0, 0.0, 0.0),
+            RadialKind::Focal(focal_data) => {
+                // The focal t is relative to the focal point, which is mapped to (0, 0),
+                // and is scaled by 1/(1-f).
+                let f = focal_data.focal_x;
+                (1.0 / focal_data.r1, f, (1.0 - f).abs())
+            }
         };
 
         p.ctx.two_point_conical_gradient = pipeline::TwoPointConicalGradientCtx {
             mask: u32x8::default(),
             p0,
+            p1,
+            p2,
         };
 
         self.base.push_stages(
             p,
             cs,
-            &|p| {
-                if let Some(focal_data) = self.focal_data {
-                    // Unlike Skia, we have only the Focal radial gradient type.
+            &|p| match self.kind {
+                RadialKind::Radial => {
+                    p.push(pipeline::Stage::XYToRadius);
 
+                    if self.r1 < self.r0 {
+                        p.push(pipeline::Stage::NegateX);
+                    }
+
+                    if self.r0 != 0.0 {
+                        p.push(pipeline::Stage::Alter2PtConicalCompensateFocal);
+                    }
+                }
+                RadialKind::Strip => {
+                    p.push(pipeline::Stage::XYTo2PtConicalStrip);
+                    p.push(pipeline::Stage::Mask2PtConicalNan);
+                }
+                RadialKind::Focal(focal_data) => {
                     if focal_data.is_focal_on_circle() {
                         p.push(pipeline::Stage::XYTo2PtConicalFocalOnCircle);
                     } else if focal_data.is_well_behaved() {
                         p.push(pipeline::Stage::XYTo2PtConicalWellBehaved);
+                    } else if focal_data.is_swapped || 1.0 - focal_data.focal_x < 0.0 {
+                        p.push(pipeline::Stage::XYTo2PtConicalSmaller);
                     } else {
                         p.push(pipeline::Stage::XYTo2PtConicalGreater);
                     }
@@ -164,12 +364,24 @@ impl RadialGradient {
                     if !focal_data.is_well_behaved() {
                         p.push(pipeline::Stage::Mask2PtConicalDegenerates);
                     }
-                } else {
-                    p.push(pipeline::Stage::XYToRadius);
+
+                    if 1.0 - focal_data.focal_x < 0.0 {
+                        p.push(pipeline::Stage::NegateX);
+                    }
+
+                    if !focal_data.is_natively_focal() {
+                        p.push(pipeline::Stage::Alter2PtConicalCompensateFocal);
+                    }
+
+                    if focal_data.is_swapped {
+                        p.push(pipeline::Stage::Alter2PtConicalUnswap);
+                    }
                 }
             },
-            &|p| {
-                if let Some(focal_data) = self.focal_data {
+            &|p| match self.kind {
+                RadialKind::Radial => {}
+                RadialKind::Strip => p.push(pipeline::Stage::ApplyVectorMask),
+                RadialKind::Focal(focal_data) => {
                     if !focal_data.is_well_behaved() {
                         p.push(pipeline::Stage::ApplyVectorMask);
                     }
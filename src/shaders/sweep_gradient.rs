@@ -0,0 +1,127 @@
+// Copyright 2006 The Android Open Source Project
+// Copyright 2020 Yevhenii Reizner
+//
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use tiny_skia_path::Scalar;
+
+use crate::{ColorSpace, GradientStop, Point, Shader, SpreadMode, Transform};
+
+use super::gradient::{Gradient, DEGENERATE_THRESHOLD};
+use super::linear_gradient::average_gradient_color;
+use crate::pipeline;
+use crate::pipeline::RasterPipelineBuilder;
+
+/// A sweep gradient shader.
+///
+/// Also known as an angular or a conic gradient.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SweepGradient {
+    pub(crate) base: Gradient,
+    // Maps a unit angle into the gradient `t`: `t = (angle + t_bias) * t_scale`.
+    pub(crate) t_bias: f32,
+    pub(crate) t_scale: f32,
+}
+
+impl SweepGradient {
+    /// Creates a new sweep gradient shader.
+    ///
+    /// Angles are in degrees and are measured clockwise from the positive X axis.
+    /// The gradient starts at `start_angle` and ends at `end_angle`,
+    /// while angles outside of this range are handled by `mode`.
+    ///
+    /// Returns `Shader::SolidColor` when:
+    /// - `stops.len()` == 1
+    /// - `start_angle` and `end_angle` are very close and `mode` is not `Pad`
+    ///   or `end_angle` is very close to zero
+    ///
+    /// Returns `None` when:
+    ///
+    /// - `stops` is empty
+    /// - `start_angle` > `end_angle`
+    /// - `start_angle` or `end_angle` are not finite
+    /// - `transform` is not invertible
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        center: Point,
+        start_angle: f32,
+        end_angle: f32,
+        stops: Vec<GradientStop>,
+        mut mode: SpreadMode,
+        transform: Transform,
+    ) -> Option<Shader<'static>> {
+        // From SkGradientShader::MakeSweep
+
+        if !start_angle.is_finite() || !end_angle.is_finite() || start_angle > end_angle {
+            return None;
+        }
+
+        if stops.is_empty() {
+            return None;
+        }
+
+        if stops.len() == 1 {
+            return Some(Shader::SolidColor(stops[0].color));
+        }
+
+        transform.invert()?;
+
+        if (end_angle - start_angle).is_nearly_zero_within_tolerance(DEGENERATE_THRESHOLD) {
+            // Degenerate gradient, which should follow default degenerate behavior unless it is
+            // padded and the angle is greater than 0.
+            if mode == SpreadMode::Pad && end_angle > DEGENERATE_THRESHOLD {
+                // In this case, the first color is repeated from 0 to the angle, then a hardstop
+                // switches to the last color (all other colors are compressed to the infinitely
+                // thin interpolation region).
+                let first = stops[0].color;
+                let last = stops[stops.len() - 1].color;
+                let stops = vec![
+                    GradientStop::new(0.0, first),
+                    GradientStop::new(1.0, first),
+                    GradientStop::new(1.0, last),
+                ];
+                return SweepGradient::new(center, 0.0, end_angle, stops, mode, transform);
+            }
+
+            return match mode {
+                SpreadMode::Pad => Some(Shader::SolidColor(stops[stops.len() - 1].color)),
+                SpreadMode::Reflect | SpreadMode::Repeat => {
+                    Some(Shader::SolidColor(average_gradient_color(&stops)))
+                }
+            };
+        }
+
+        if start_angle <= 0.0 && end_angle >= 360.0 {
+            // If the t-range includes [0,1], then we can always use padding (presumably faster).
+            mode = SpreadMode::Pad;
+        }
+
+        let t0 = start_angle / 360.0;
+        let t1 = end_angle / 360.0;
+
+        let ts = Transform::from_translate(-center.x, -center.y);
+        Some(Shader::SweepGradient(SweepGradient {
+            base: Gradient::new(stops, mode, transform, ts),
+            t_bias: -t0,
+            t_scale: 1.0 / (t1 - t0),
+        }))
+    }
+
+    pub(crate) fn is_opaque(&self) -> bool {
+        self.base.colors_are_opaque
+    }
+
+    pub(crate) fn push_stages(&self, cs: ColorSpace, p: &mut RasterPipelineBuilder) -> bool {
+        p.ctx.sweep_gradient = pipeline::SweepGradientCtx {
+            t_bias: self.t_bias,
+            t_scale: self.t_scale,
+        };
+
+        self.base
+            .push_stages(p, cs, &|p| p.push(pipeline::Stage::XYToAngle), &|_| {})
+    }
+}
@@ -0,0 +1,435 @@
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+/*!
+Tile jobs for multithreaded rendering.
+
+The target is split into row bands and each band is rendered by a separate job.
+
+Paths are prepared on the calling thread, which builds and sorts their edges,
+and each job walks the edges starting from its first row. Edges are not clipped to bands,
+since that would change their fixed point setup and therefore the coverage along band borders.
+
+Rectangles and hairlines are cheap to rasterize, so their coverage is recorded
+on the calling thread instead and jobs only shade and blend it.
+
+Bands reference disjoint data, but preserve the target coordinates,
+therefore the output is identical to the one produced by a single-threaded rendering.
+*/
+
+use alloc::vec::Vec;
+use core::num::NonZeroU16;
+use core::ops::Range;
+
+use crate::{LengthU32, Paint};
+
+use crate::alpha_runs::AlphaRun;
+use crate::blitter::Blitter;
+use crate::color::AlphaU8;
+use crate::geom::{IntSizeExt, ScreenIntRect};
+use crate::mask::SubMaskRef;
+use crate::math::LENGTH_U32_ONE;
+use crate::pipeline::RasterPipelineBlitter;
+use crate::pixmap::SubPixmapMut;
+use crate::scan::{self, PreparedPath};
+
+/// The height of a single job region.
+///
+/// Must be a multiple of `scan::ROW_ALIGNMENT`.
+const BAND_HEIGHT: u32 = 64;
+
+/// Executes tile jobs.
+///
+/// Each job rasterizes, shades and blends a band of rows, while the output stays identical
+/// to a single-threaded rendering. Stroking, building path edges and rasterizing
+/// rectangles and hairlines are still done on the calling thread.
+///
+/// Jobs are independent and can be rendered in any order and on any thread,
+/// which allows using any thread pool. For example, using `rayon`:
+///
+/// ```ignore
+/// struct RayonExecutor;
+///
+/// impl tiny_skia::TileExecutor for RayonExecutor {
+///     fn execute(&self, jobs: Vec<tiny_skia::TileJob>) {
+///         use rayon::prelude::*;
+///         jobs.into_par_iter().for_each(tiny_skia::TileJob::render);
+///     }
+/// }
+/// ```
+pub trait TileExecutor {
+    /// Renders all jobs.
+    ///
+    /// Must not return until all jobs are rendered.
+    fn execute(&self, jobs: Vec<TileJob<'_>>);
+}
+
+/// A single tile rendering job.
+pub struct TileJob<'a> {
+    paint: &'a Paint<'a>,
+    mask: Option<SubMaskRef<'a>>,
+    pixmap: SubPixmapMut<'a>,
+    coverage: JobCoverage<'a>,
+}
+
+enum JobCoverage<'a> {
+    Recorded(Band),
+    Path {
+        path: &'a PreparedPath<'a>,
+        rows: Range<u32>,
+    },
+}
+
+impl TileJob<'_> {
+    /// Renders the tile.
+    pub fn render(mut self) {
+        let mut blitter = match RasterPipelineBlitter::new(self.paint, self.mask, &mut self.pixmap)
+        {
+            Some(v) => v,
+            None => return, // nothing to do, all good
+        };
+
+        let band = match self.coverage {
+            JobCoverage::Recorded(ref band) => band,
+            JobCoverage::Path { path, ref rows } => {
+                path.fill_rows(rows.clone(), &mut blitter);
+                return;
+            }
+        };
+
+        let mut aa: Vec<AlphaU8> = Vec::new();
+        let mut runs: Vec<AlphaRun> = Vec::new();
+        for op in &band.ops {
+            match *op {
+                BlitOp::Rect(ref rect) => blitter.blit_rect(rect),
+                BlitOp::AntiH { x, y, start, end } => {
+                    let runs_len = band.runs[start..end]
+                        .iter()
+                        .map(|(len, _)| usize::from(len.get()))
+                        .sum::<usize>();
+                    if runs.len() <= runs_len {
+                        aa.resize(runs_len + 1, 0);
+                        runs.resize(runs_len + 1, None);
+                    }
+
+                    let mut i = 0;
+                    for (len, alpha) in &band.runs[start..end] {
+                        runs[i] = Some(*len);
+                        aa[i] = *alpha;
+                        i += usize::from(len.get());
+                    }
+                    runs[i] = None;
+
+                    blitter.blit_anti_h(x, y, &mut aa, &mut runs);
+                }
+                BlitOp::V {
+                    x,
+                    y,
+                    height,
+                    alpha,
+                } => blitter.blit_v(x, y, height, alpha),
+                BlitOp::AntiH2 {
+                    x,
+                    y,
+                    alpha0,
+                    alpha1,
+                } => blitter.blit_anti_h2(x, y, alpha0, alpha1),
+                BlitOp::AntiV2 {
+                    x,
+                    y,
+                    alpha0,
+                    alpha1,
+                } => blitter.blit_anti_v2(x, y, alpha0, alpha1),
+            }
+        }
+    }
+}
+
+impl core::fmt::Debug for TileJob<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.coverage {
+            JobCoverage::Recorded(ref band) => f
+                .debug_struct("TileJob")
+                .field("ops", &band.ops.len())
+                .finish(),
+            JobCoverage::Path { ref rows, .. } => {
+                f.debug_struct("TileJob").field("rows", rows).finish()
+            }
+        }
+    }
+}
+
+/// A [`TileExecutor`] that renders jobs using a fixed number of scoped threads.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ThreadExecutor {
+    threads: usize,
+}
+
+#[cfg(feature = "std")]
+impl ThreadExecutor {
+    /// Creates a new executor.
+    ///
+    /// A zero number of threads will be treated as one,
+    /// in which case jobs are rendered on the calling thread.
+    pub fn new(threads: usize) -> Self {
+        ThreadExecutor {
+            threads: threads.max(1),
+        }
+    }
+
+    /// Returns the number of threads.
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for ThreadExecutor {
+    /// Creates an executor with the number of threads equal to the available parallelism.
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        ThreadExecutor::new(threads)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TileExecutor for ThreadExecutor {
+    fn execute(&self, jobs: Vec<TileJob<'_>>) {
+        fn run(jobs: &std::sync::Mutex<alloc::vec::IntoIter<TileJob>>) {
+            loop {
+                let job = jobs.lock().unwrap().next();
+                match job {
+                    Some(job) => job.render(),
+                    None => break,
+                }
+            }
+        }
+
+        let threads = self.threads.min(jobs.len());
+        let jobs = std::sync::Mutex::new(jobs.into_iter());
+        std::thread::scope(|s| {
+            for _ in 1..threads {
+                s.spawn(|| run(&jobs));
+            }
+
+            run(&jobs);
+        });
+    }
+}
+
+/// Renders coverage produced by `scan` either directly or via tile jobs.
+pub(crate) fn render(
+    paint: &Paint,
+    mask: Option<SubMaskRef>,
+    mut pixmap: SubPixmapMut,
+    executor: Option<&dyn TileExecutor>,
+    scan: impl FnOnce(&ScreenIntRect, &mut dyn Blitter),
+) {
+    let clip = pixmap.size.to_screen_int_rect(0, 0);
+
+    let executor = match executor {
+        Some(v) => v,
+        None => {
+            if let Some(mut blitter) = RasterPipelineBlitter::new(paint, mask, &mut pixmap) {
+                scan(&clip, &mut blitter);
+            }
+
+            return;
+        }
+    };
+
+    let mut recorder = CoverageRecorder { bands: Vec::new() };
+    scan(&clip, &mut recorder);
+
+    let jobs: Vec<_> = pixmap
+        .split_rows(BAND_HEIGHT)
+        .into_iter()
+        .zip(recorder.bands)
+        .filter(|(_, band)| !band.ops.is_empty())
+        .map(|(pixmap, band)| TileJob {
+            paint,
+            mask,
+            pixmap,
+            coverage: JobCoverage::Recorded(band),
+        })
+        .collect();
+
+    if !jobs.is_empty() {
+        executor.execute(jobs);
+    }
+}
+
+/// Fills a prepared path either directly or via tile jobs, which fill their own rows.
+pub(crate) fn render_path(
+    paint: &Paint,
+    mask: Option<SubMaskRef>,
+    mut pixmap: SubPixmapMut,
+    executor: Option<&dyn TileExecutor>,
+    path: &PreparedPath,
+) {
+    let executor = match executor {
+        Some(v) => v,
+        None => {
+            if let Some(mut blitter) = RasterPipelineBlitter::new(paint, mask, &mut pixmap) {
+                path.fill_rows(path.rows(), &mut blitter);
+            }
+
+            return;
+        }
+    };
+
+    debug_assert_eq!(BAND_HEIGHT % scan::ROW_ALIGNMENT, 0);
+
+    let path_rows = path.rows();
+    let jobs: Vec<_> = pixmap
+        .split_rows(BAND_HEIGHT)
+        .into_iter()
+        .enumerate()
+        .map(|(i, pixmap)| {
+            let top = i as u32 * BAND_HEIGHT;
+            (pixmap, top..top + BAND_HEIGHT)
+        })
+        .filter(|(_, rows)| rows.start < path_rows.end && path_rows.start < rows.end)
+        .map(|(pixmap, rows)| TileJob {
+            paint,
+            mask,
+            pixmap,
+            coverage: JobCoverage::Path { path, rows },
+        })
+        .collect();
+
+    if !jobs.is_empty() {
+        executor.execute(jobs);
+    }
+}
+
+enum BlitOp {
+    Rect(ScreenIntRect),
+    AntiH {
+        x: u32,
+        y: u32,
+        // A range in `Band::runs`.
+        start: usize,
+        end: usize,
+    },
+    V {
+        x: u32,
+        y: u32,
+        height: LengthU32,
+        alpha: AlphaU8,
+    },
+    AntiH2 {
+        x: u32,
+        y: u32,
+        alpha0: AlphaU8,
+        alpha1: AlphaU8,
+    },
+    AntiV2 {
+        x: u32,
+        y: u32,
+        alpha0: AlphaU8,
+        alpha1: AlphaU8,
+    },
+}
+
+#[derive(Default)]
+struct Band {
+    ops: Vec<BlitOp>,
+    runs: Vec<(NonZeroU16, AlphaU8)>,
+}
+
+/// Records blitter calls split by bands.
+///
+/// Calls that are affecting several bands are split by rows, which doesn't affect the output,
+/// since pixels are processed independently.
+struct CoverageRecorder {
+    bands: Vec<Band>,
+}
+
+impl CoverageRecorder {
+    fn band(&mut self, y: u32) -> &mut Band {
+        let index = (y / BAND_HEIGHT) as usize;
+        if index >= self.bands.len() {
+            self.bands.resize_with(index + 1, Band::default);
+        }
+
+        &mut self.bands[index]
+    }
+
+    // Calls `f` for each band part of the rows range.
+    fn split(&mut self, y: u32, height: u32, mut f: impl FnMut(&mut Band, u32, LengthU32)) {
+        let bottom = y + height;
+        let mut y = y;
+        while y < bottom {
+            let next_y = ((y / BAND_HEIGHT + 1) * BAND_HEIGHT).min(bottom);
+            // Cannot be zero, since `next_y > y`.
+            let height = LengthU32::new(next_y - y).unwrap();
+            f(self.band(y), y, height);
+            y = next_y;
+        }
+    }
+}
+
+impl Blitter for CoverageRecorder {
+    fn blit_h(&mut self, x: u32, y: u32, width: LengthU32) {
+        let rect = ScreenIntRect::from_xywh_safe(x, y, width, LENGTH_U32_ONE);
+        self.band(y).ops.push(BlitOp::Rect(rect));
+    }
+
+    fn blit_anti_h(&mut self, x: u32, y: u32, aa: &mut [AlphaU8], runs: &mut [AlphaRun]) {
+        let band = self.band(y);
+        let start = band.runs.len();
+        let mut offset = 0;
+        while let Some(run) = runs[offset] {
+            band.runs.push((run, aa[offset]));
+            offset += usize::from(run.get());
+        }
+
+        let end = band.runs.len();
+        band.ops.push(BlitOp::AntiH { x, y, start, end });
+    }
+
+    fn blit_v(&mut self, x: u32, y: u32, height: LengthU32, alpha: AlphaU8) {
+        self.split(y, height.get(), |band, y, height| {
+            band.ops.push(BlitOp::V {
+                x,
+                y,
+                height,
+                alpha,
+            });
+        });
+    }
+
+    fn blit_anti_h2(&mut self, x: u32, y: u32, alpha0: AlphaU8, alpha1: AlphaU8) {
+        self.band(y).ops.push(BlitOp::AntiH2 {
+            x,
+            y,
+            alpha0,
+            alpha1,
+        });
+    }
+
+    fn blit_anti_v2(&mut self, x: u32, y: u32, alpha0: AlphaU8, alpha1: AlphaU8) {
+        if y / BAND_HEIGHT == (y + 1) / BAND_HEIGHT {
+            self.band(y).ops.push(BlitOp::AntiV2 {
+                x,
+                y,
+                alpha0,
+                alpha1,
+            });
+        } else {
+            self.blit_v(x, y, LENGTH_U32_ONE, alpha0);
+            self.blit_v(x, y + 1, LENGTH_U32_ONE, alpha1);
+        }
+    }
+
+    fn blit_rect(&mut self, rect: &ScreenIntRect) {
+        let x = rect.x();
+        let width = rect.width_safe();
+        self.split(rect.y(), rect.height(), |band, y, height| {
+            let rect = ScreenIntRect::from_xywh_safe(x, y, width, height);
+            band.ops.push(BlitOp::Rect(rect));
+        });
+    }
+}
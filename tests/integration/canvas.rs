@@ -0,0 +1,253 @@
+use tiny_skia::*;
+
+fn paint() -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint
+}
+
+#[test]
+fn transform_and_clip() {
+    let path = PathBuilder::from_circle(10.0, 10.0, 8.0).unwrap();
+    let clip = Rect::from_xywh(5.0, 5.0, 10.0, 20.0).unwrap();
+
+    let mut pixmap1 = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(pixmap1.as_mut());
+    canvas.translate(20.0, 10.0);
+    canvas.scale(2.0, 3.0);
+    canvas.clip_rect(clip, true);
+    canvas.fill_path(&path, &paint(), FillRule::Winding);
+
+    let ts = Transform::from_translate(20.0, 10.0).pre_scale(2.0, 3.0);
+    let mut mask = Mask::new(100, 100).unwrap();
+    mask.fill_path(&PathBuilder::from_rect(clip), FillRule::Winding, true, ts);
+    let mut pixmap2 = Pixmap::new(100, 100).unwrap();
+    pixmap2.fill_path(&path, &paint(), FillRule::Winding, ts, Some(&mask));
+
+    assert_eq!(pixmap1, pixmap2);
+    assert!(pixmap1.pixel(40, 45).unwrap().alpha() > 0); // inside the clip
+    assert_eq!(pixmap1.pixel(25, 45).unwrap().alpha(), 0); // outside the clip
+}
+
+#[test]
+fn save_and_restore() {
+    let rect = Rect::from_xywh(0.0, 0.0, 20.0, 20.0).unwrap();
+
+    let mut pixmap1 = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(pixmap1.as_mut());
+    assert_eq!(canvas.save(), 0);
+    canvas.translate(50.0, 50.0);
+    canvas.clip_rect(Rect::from_xywh(0.0, 0.0, 10.0, 10.0).unwrap(), false);
+    assert_eq!(canvas.save(), 1);
+    canvas.rotate(45.0);
+    canvas.clip_rect(rect, false);
+    assert_eq!(canvas.save_count(), 2);
+    canvas.fill_rect(rect, &paint());
+    canvas.restore_to_count(1);
+    canvas.stroke_path(&PathBuilder::from_rect(rect), &paint(), &Stroke::default());
+    canvas.restore();
+    assert_eq!(canvas.save_count(), 0);
+    assert_eq!(canvas.transform(), Transform::identity());
+    assert!(canvas.clip_mask().is_none());
+    canvas.fill_rect(rect, &paint());
+
+    let mut clip1 = Mask::new(100, 100).unwrap();
+    let ts1 = Transform::from_translate(50.0, 50.0);
+    let clip_rect = PathBuilder::from_rect(Rect::from_xywh(0.0, 0.0, 10.0, 10.0).unwrap());
+    clip1.fill_path(&clip_rect, FillRule::Winding, false, ts1);
+    let mut clip2 = clip1.clone();
+    let ts2 = ts1.pre_rotate(45.0);
+    clip2.intersect_path(&PathBuilder::from_rect(rect), FillRule::Winding, false, ts2);
+
+    let mut pixmap2 = Pixmap::new(100, 100).unwrap();
+    pixmap2.fill_rect(rect, &paint(), ts2, Some(&clip2));
+    pixmap2.stroke_path(&PathBuilder::from_rect(rect), &paint(), &Stroke::default(), ts1, Some(&clip1));
+    pixmap2.fill_rect(rect, &paint(), Transform::identity(), None);
+
+    assert_eq!(pixmap1, pixmap2);
+}
+
+#[test]
+fn nested_clips() {
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(pixmap.as_mut());
+
+    canvas.clip_rect(Rect::from_xywh(0.0, 0.0, 50.0, 100.0).unwrap(), false);
+    let clip1 = canvas.clip_mask().unwrap().clone();
+
+    canvas.save();
+    canvas.clip_rect(Rect::from_xywh(0.0, 0.0, 100.0, 50.0).unwrap(), false);
+    canvas.clip_rect(Rect::from_xywh(0.0, 0.0, 25.0, 100.0).unwrap(), false);
+    let clip2 = canvas.clip_mask().unwrap().clone();
+    assert_eq!(clip2.data()[24], 255);
+    assert_eq!(clip2.data()[25], 0);
+    assert_eq!(clip2.data()[50 * 100], 0);
+
+    // A restore brings back the clip that was replaced.
+    canvas.restore();
+    assert!(canvas.clip_mask().unwrap() == &clip1);
+
+    canvas.fill_rect(Rect::from_xywh(0.0, 0.0, 100.0, 100.0).unwrap(), &paint());
+    assert!(pixmap.pixel(49, 99).unwrap().alpha() > 0);
+    assert_eq!(pixmap.pixel(50, 0).unwrap().alpha(), 0);
+}
+
+fn draw_group(canvas: &mut Canvas) {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(200, 50, 50, 255);
+    canvas.fill_rect(Rect::from_xywh(10.0, 10.0, 40.0, 40.0).unwrap(), &paint);
+    paint.set_color_rgba8(50, 50, 200, 255);
+    let path = PathBuilder::from_circle(45.0, 45.0, 20.0).unwrap();
+    canvas.fill_path(&path, &paint, FillRule::Winding);
+}
+
+// Renders a group into a separate pixmap, like a layer covering the whole canvas.
+fn render_group(ts: Transform) -> Pixmap {
+    let mut group = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(group.as_mut());
+    canvas.set_transform(ts);
+    draw_group(&mut canvas);
+    group
+}
+
+#[test]
+fn layer_opacity() {
+    let ts = Transform::from_translate(5.0, 10.0);
+    let bounds = Rect::from_xywh(10.0, 10.0, 55.0, 55.0).unwrap();
+
+    let mut pixmap1 = Pixmap::new(100, 100).unwrap();
+    pixmap1.fill(Color::WHITE);
+    let mut canvas = Canvas::new(pixmap1.as_mut());
+    canvas.set_transform(ts);
+    assert_eq!(canvas.push_layer(Some(bounds), 0.5, BlendMode::SourceOver, None), 0);
+    assert_eq!(canvas.layer_count(), 1);
+    draw_group(&mut canvas);
+    canvas.pop_layer();
+    assert_eq!(canvas.layer_count(), 0);
+    assert_eq!(canvas.save_count(), 0);
+
+    let group = render_group(ts);
+    let mut pixmap2 = Pixmap::new(100, 100).unwrap();
+    pixmap2.fill(Color::WHITE);
+    let paint = PixmapPaint {
+        opacity: 0.5,
+        ..PixmapPaint::default()
+    };
+    pixmap2.draw_pixmap(0, 0, group.as_ref(), &paint, Transform::identity(), None);
+
+    assert_eq!(pixmap1, pixmap2);
+}
+
+#[test]
+fn layer_blend_mode_clip_and_mask() {
+    let ts = Transform::from_scale(1.2, 1.2);
+    let clip = Rect::from_xywh(20.0, 0.0, 80.0, 80.0).unwrap();
+    let circle = PathBuilder::from_circle(50.0, 50.0, 30.0).unwrap();
+
+    let mut mask = Mask::new(100, 100).unwrap();
+    mask.fill_path(&circle, FillRule::Winding, true, Transform::identity());
+
+    let mut pixmap1 = Pixmap::new(100, 100).unwrap();
+    pixmap1.fill(Color::from_rgba8(100, 200, 100, 255));
+    let mut canvas = Canvas::new(pixmap1.as_mut());
+    canvas.clip_rect(clip, true);
+    canvas.set_transform(ts);
+    canvas.push_layer(None, 0.8, BlendMode::Multiply, Some(&mask));
+    // Layers start unclipped.
+    assert!(canvas.clip_mask().is_none());
+    draw_group(&mut canvas);
+    canvas.restore();
+    assert!(canvas.clip_mask().is_some());
+
+    let mut layer_mask = Mask::new(100, 100).unwrap();
+    let clip_path = PathBuilder::from_rect(clip);
+    layer_mask.fill_path(&clip_path, FillRule::Winding, true, Transform::identity());
+    layer_mask.intersect_path(&circle, FillRule::Winding, true, Transform::identity());
+
+    let group = render_group(ts);
+    let mut pixmap2 = Pixmap::new(100, 100).unwrap();
+    pixmap2.fill(Color::from_rgba8(100, 200, 100, 255));
+    let paint = PixmapPaint {
+        opacity: 0.8,
+        blend_mode: BlendMode::Multiply,
+        ..PixmapPaint::default()
+    };
+    pixmap2.draw_pixmap(0, 0, group.as_ref(), &paint, Transform::identity(), Some(&layer_mask));
+
+    assert_eq!(pixmap1, pixmap2);
+}
+
+#[test]
+fn nested_layers() {
+    // Only rectangles, so layer offsets don't affect anti-aliasing.
+    fn draw_rects(canvas: &mut Canvas) {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(200, 50, 50, 255);
+        canvas.fill_rect(Rect::from_xywh(10.0, 10.0, 40.5, 40.0).unwrap(), &paint);
+        paint.set_color_rgba8(50, 50, 200, 255);
+        canvas.fill_rect(Rect::from_xywh(30.0, 30.0, 40.0, 40.0).unwrap(), &paint);
+    }
+
+    let mut pixmap1 = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(pixmap1.as_mut());
+    canvas.push_layer(Some(Rect::from_xywh(5.0, 5.0, 80.0, 80.0).unwrap()), 0.5, BlendMode::SourceOver, None);
+    canvas.translate(10.0, 0.0);
+    let count = canvas.push_layer(Some(Rect::from_xywh(0.0, 0.0, 60.0, 60.0).unwrap()), 0.5, BlendMode::SourceOver, None);
+    assert_eq!(count, 1);
+    assert_eq!(canvas.layer_count(), 2);
+
+    // Clips inside a layer are relative to the layer.
+    canvas.clip_rect(Rect::from_xywh(0.0, 0.0, 40.0, 100.0).unwrap(), false);
+    assert_eq!(canvas.clip_mask().unwrap().width(), 60);
+    assert_eq!(canvas.clip_mask().unwrap().height(), 55);
+    draw_rects(&mut canvas);
+    canvas.restore_to_count(0);
+    assert_eq!(canvas.layer_count(), 0);
+
+    let mut inner = Pixmap::new(100, 100).unwrap();
+    let mut c = Canvas::new(inner.as_mut());
+    c.translate(10.0, 0.0);
+    c.clip_rect(Rect::from_xywh(0.0, 0.0, 40.0, 100.0).unwrap(), false);
+    c.clip_rect(Rect::from_xywh(0.0, 0.0, 60.0, 60.0).unwrap(), false);
+    draw_rects(&mut c);
+
+    let paint = PixmapPaint {
+        opacity: 0.5,
+        ..PixmapPaint::default()
+    };
+    let mut outer = Pixmap::new(100, 100).unwrap();
+    outer.draw_pixmap(0, 0, inner.as_ref(), &paint, Transform::identity(), None);
+    let mut pixmap2 = Pixmap::new(100, 100).unwrap();
+    pixmap2.draw_pixmap(0, 0, outer.as_ref(), &paint, Transform::identity(), None);
+
+    assert_eq!(pixmap1, pixmap2);
+}
+
+#[test]
+fn empty_layer() {
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(pixmap.as_mut());
+    canvas.push_layer(Some(Rect::from_xywh(200.0, 200.0, 10.0, 10.0).unwrap()), 1.0, BlendMode::SourceOver, None);
+    canvas.fill_rect(Rect::from_xywh(0.0, 0.0, 100.0, 100.0).unwrap(), &paint());
+    canvas.pop_layer();
+    // Pops nothing.
+    canvas.pop_layer();
+
+    assert!(pixmap.pixels().iter().all(|p| p.alpha() == 0));
+}
+
+#[test]
+fn restore_inside_layer_keeps_outer_clip() {
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(pixmap.as_mut());
+    canvas.clip_rect(Rect::from_xywh(0.0, 0.0, 50.0, 100.0).unwrap(), false);
+    canvas.push_layer(None, 0.5, BlendMode::SourceOver, None);
+    canvas.save();
+    canvas.clip_rect(Rect::from_xywh(0.0, 0.0, 100.0, 50.0).unwrap(), false);
+    canvas.restore();
+    canvas.pop_layer();
+    canvas.fill_rect(Rect::from_xywh(0.0, 0.0, 100.0, 100.0).unwrap(), &paint());
+
+    assert!(pixmap.pixel(49, 99).unwrap().alpha() > 0);
+    assert_eq!(pixmap.pixel(50, 0).unwrap().alpha(), 0);
+}
@@ -0,0 +1,163 @@
+use tiny_skia::*;
+
+// Writes coverage into a mask-like buffer.
+struct Canvas {
+    clip: IntRect,
+    data: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(clip: IntRect) -> Self {
+        Canvas {
+            clip,
+            data: vec![0; (clip.width() * clip.height()) as usize],
+        }
+    }
+}
+
+impl CoverageSink for Canvas {
+    fn span(&mut self, x: i32, y: i32, width: u32, coverage: u8) {
+        assert!(width > 0 && coverage > 0);
+        assert!(x >= self.clip.left() && x + width as i32 <= self.clip.right());
+        assert!(y >= self.clip.top() && y < self.clip.bottom());
+
+        let row = (y - self.clip.top()) as u32 * self.clip.width();
+        let start = (row + (x - self.clip.left()) as u32) as usize;
+        for a in &mut self.data[start..start + width as usize] {
+            // Each pixel is reported only once.
+            assert_eq!(*a, 0);
+            *a = coverage;
+        }
+    }
+}
+
+// Accumulates the covered area.
+struct Area(f64);
+
+impl CoverageSink for Area {
+    fn span(&mut self, _: i32, _: i32, width: u32, coverage: u8) {
+        self.0 += f64::from(width) * f64::from(coverage) / 255.0;
+    }
+}
+
+fn star() -> Path {
+    let mut pb = PathBuilder::new();
+    pb.move_to(50.0, 7.0);
+    pb.line_to(75.0, 93.0);
+    pb.line_to(7.0, 40.0);
+    pb.line_to(93.0, 40.0);
+    pb.line_to(25.0, 93.0);
+    pb.close();
+    pb.push_circle(50.0, 50.0, 15.0);
+    pb.finish().unwrap()
+}
+
+#[test]
+fn matches_mask() {
+    let path = star();
+    let clip = IntRect::from_xywh(0, 0, 100, 100).unwrap();
+    let ts = Transform::from_rotate_at(10.0, 50.0, 50.0);
+
+    for anti_alias in &[true, false] {
+        for fill_rule in &[FillRule::Winding, FillRule::EvenOdd] {
+            let options = CoverageOptions { anti_alias: *anti_alias, ..CoverageOptions::default() };
+            let mut canvas = Canvas::new(clip);
+            rasterize_path(&path, *fill_rule, options, ts, clip, &mut canvas);
+
+            let mut mask = Mask::new(100, 100).unwrap();
+            mask.fill_path(&path, *fill_rule, *anti_alias, ts);
+            assert_eq!(canvas.data, mask.data());
+        }
+    }
+}
+
+#[test]
+fn matches_mask_with_quality() {
+    let path = star();
+    let clip = IntRect::from_xywh(0, 0, 100, 100).unwrap();
+    let ts = Transform::from_rotate_at(10.0, 50.0, 50.0);
+
+    let options = CoverageOptions { anti_alias_quality: AntiAliasQuality::High, ..CoverageOptions::default() };
+    let mut canvas = Canvas::new(clip);
+    rasterize_path(&path, FillRule::Winding, options, ts, clip, &mut canvas);
+
+    let mut mask = Mask::new(100, 100).unwrap();
+    mask.fill_path_with_quality(&path, FillRule::Winding, AntiAliasQuality::High, ts);
+    assert_eq!(canvas.data, mask.data());
+
+    let mut default_canvas = Canvas::new(clip);
+    rasterize_path(&path, FillRule::Winding, CoverageOptions::default(), ts, clip, &mut default_canvas);
+    assert_ne!(canvas.data, default_canvas.data);
+}
+
+#[test]
+fn matches_analytic_fill() {
+    let path = star();
+    let clip = IntRect::from_xywh(0, 0, 100, 100).unwrap();
+    let ts = Transform::from_rotate_at(10.0, 50.0, 50.0);
+
+    let options = CoverageOptions { rasterizer: Rasterizer::Analytic, ..CoverageOptions::default() };
+    let mut canvas = Canvas::new(clip);
+    rasterize_path(&path, FillRule::EvenOdd, options, ts, clip, &mut canvas);
+
+    // Opaque black over a transparent pixmap leaves the coverage in the alpha channel.
+    let paint = Paint { rasterizer: Rasterizer::Analytic, ..Paint::default() };
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.fill_path(&path, &paint, FillRule::EvenOdd, ts, None);
+    let alpha: Vec<u8> = pixmap.pixels().iter().map(|p| p.alpha()).collect();
+    assert_eq!(canvas.data, alpha);
+}
+
+#[test]
+fn clip() {
+    let path = star();
+    let clip = IntRect::from_xywh(-20, 30, 80, 50).unwrap();
+
+    let mut canvas = Canvas::new(clip);
+    let options = CoverageOptions { anti_alias: false, ..CoverageOptions::default() };
+    rasterize_path(&path, FillRule::Winding, options, Transform::identity(), clip, &mut canvas);
+
+    let mut mask = Mask::new(100, 100).unwrap();
+    mask.fill_path(&path, FillRule::Winding, false, Transform::identity());
+
+    for y in 0..50 {
+        for x in 0..80 {
+            let expected = if x >= 20 {
+                mask.data()[((y + 30) * 100 + x - 20) as usize]
+            } else {
+                0
+            };
+            assert_eq!(canvas.data[(y * 80 + x) as usize], expected);
+        }
+    }
+}
+
+#[test]
+fn circle_area() {
+    let path = PathBuilder::from_circle(0.0, 0.0, 100.0).unwrap();
+    let clip = IntRect::from_xywh(-100, -100, 200, 200).unwrap();
+
+    let mut area = Area(0.0);
+    rasterize_path(&path, FillRule::Winding, CoverageOptions::default(), Transform::identity(), clip, &mut area);
+    // Curves are flattened into lines inside the circle, which reduces the area a bit.
+    let expected = std::f64::consts::PI * 100.0 * 100.0;
+    assert!((area.0 - expected).abs() < expected * 0.005);
+}
+
+#[test]
+fn large_clip() {
+    // Clips larger than 8191 pixels are rendered in tiles.
+    let path = PathBuilder::from_circle(8191.3, -10.0, 300.0).unwrap();
+    let clip = IntRect::from_xywh(-100, -400, 9000, 9000).unwrap();
+
+    let mut area = Area(0.0);
+    rasterize_path(&path, FillRule::Winding, CoverageOptions::default(), Transform::identity(), clip, &mut area);
+    let expected = std::f64::consts::PI * 300.0 * 300.0;
+    assert!((area.0 - expected).abs() < expected * 0.005);
+
+    // Only the bottom half is visible.
+    let clip = IntRect::from_xywh(7000, -10, 2000, 1000).unwrap();
+    let mut area = Area(0.0);
+    rasterize_path(&path, FillRule::Winding, CoverageOptions::default(), Transform::identity(), clip, &mut area);
+    assert!((area.0 - expected / 2.0).abs() < expected * 0.005);
+}
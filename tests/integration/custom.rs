@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use tiny_skia::*;
+
+// Produces the same color for all pixels.
+#[derive(Debug)]
+struct ConstShader(Color);
+
+impl CustomShader for ConstShader {
+    fn shade_span(&self, _: i32, _: i32, colors: &mut [PremultipliedColor]) {
+        colors.fill(self.0.premultiply());
+    }
+}
+
+// Encodes device coordinates into colors.
+#[derive(Debug)]
+struct CoordShader;
+
+impl CustomShader for CoordShader {
+    fn shade_span(&self, x: i32, y: i32, colors: &mut [PremultipliedColor]) {
+        for (i, color) in colors.iter_mut().enumerate() {
+            let x = x + i as i32;
+            *color = Color::from_rgba8((x % 256) as u8, (x / 256) as u8, y as u8, 255).premultiply();
+        }
+    }
+}
+
+fn circle() -> Path {
+    PathBuilder::from_circle(100.0, 100.0, 70.0).unwrap()
+}
+
+fn coord_color(x: u32, y: u32) -> PremultipliedColorU8 {
+    PremultipliedColorU8::from_rgba((x % 256) as u8, (x / 256) as u8, y as u8, 255).unwrap()
+}
+
+#[test]
+fn device_coordinates() {
+    let mut pixmap = Pixmap::new(300, 200).unwrap();
+    let paint = Paint {
+        shader: Custom::new(Arc::new(CoordShader)),
+        ..Paint::default()
+    };
+
+    pixmap.fill_rect(
+        Rect::from_xywh(0.0, 0.0, 300.0, 200.0).unwrap(),
+        &paint,
+        Transform::identity(),
+        None,
+    );
+
+    for &(x, y) in &[(0, 0), (7, 3), (8, 3), (150, 99), (299, 199)] {
+        assert_eq!(pixmap.pixel(x, y).unwrap(), coord_color(x, y));
+    }
+}
+
+#[test]
+fn ignores_transform() {
+    let mut pixmap = Pixmap::new(200, 200).unwrap();
+    let mut paint = Paint {
+        shader: Custom::new(Arc::new(CoordShader)),
+        ..Paint::default()
+    };
+    paint.shader.transform(Transform::from_scale(2.0, 2.0));
+
+    let ts = Transform::from_row(0.5, 0.2, -0.2, 0.5, 50.0, 30.0);
+    pixmap.fill_path(&circle(), &paint, FillRule::Winding, ts, None);
+
+    assert_eq!(pixmap.pixel(100, 80).unwrap(), coord_color(100, 80));
+}
+
+// Tiles are rendered with a shifted origin, which must not be visible to the shader.
+#[test]
+fn large_pixmap() {
+    let mut pixmap = Pixmap::new(9000, 20).unwrap();
+    let paint = Paint {
+        shader: Custom::new(Arc::new(CoordShader)),
+        ..Paint::default()
+    };
+
+    let path = PathBuilder::from_rect(Rect::from_xywh(0.0, 0.0, 9000.0, 20.0).unwrap());
+    pixmap.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+
+    for &(x, y) in &[(10, 5), (8191, 5), (8192, 10), (8999, 19)] {
+        assert_eq!(pixmap.pixel(x, y).unwrap(), coord_color(x, y));
+    }
+}
+
+// A constant custom shader must be identical to a solid color rendered by the same pipeline,
+// including anti-aliasing, masks, opacity and blending.
+#[test]
+fn matches_solid_color() {
+    let color = Color::from_rgba8(50, 127, 150, 200);
+
+    let mut mask = Mask::new(200, 200).unwrap();
+    mask.fill_path(
+        &PathBuilder::from_rect(Rect::from_xywh(20.0, 40.0, 120.0, 100.0).unwrap()),
+        FillRule::Winding,
+        true,
+        Transform::from_row(1.0, 0.1, 0.0, 1.0, 0.0, 0.0),
+    );
+
+    for &blend_mode in &[BlendMode::SourceOver, BlendMode::Multiply, BlendMode::Xor] {
+        for &mask in &[None, Some(&mask)] {
+            let mut expected_paint = Paint {
+                shader: Shader::SolidColor(color),
+                blend_mode,
+                force_hq_pipeline: true,
+                ..Paint::default()
+            };
+            expected_paint.shader.apply_opacity(0.5);
+
+            let mut paint = Paint {
+                shader: Custom::new(Arc::new(ConstShader(color))),
+                blend_mode,
+                ..Paint::default()
+            };
+            paint.shader.apply_opacity(0.5);
+
+            let mut expected = Pixmap::new(200, 200).unwrap();
+            expected.fill(Color::from_rgba8(220, 140, 75, 180));
+            let mut pixmap = expected.clone();
+
+            let ts = Transform::identity();
+            expected.fill_path(&circle(), &expected_paint, FillRule::Winding, ts, mask);
+            pixmap.fill_path(&circle(), &paint, FillRule::Winding, ts, mask);
+
+            assert!(pixmap == expected);
+        }
+    }
+}
+
+#[test]
+fn parallel() {
+    let paint = Paint {
+        shader: Custom::new(Arc::new(CoordShader)),
+        ..Paint::default()
+    };
+
+    let mut expected = Pixmap::new(200, 300).unwrap();
+    let mut pixmap = expected.clone();
+
+    let path = PathBuilder::from_circle(100.0, 150.0, 120.0).unwrap();
+    let ts = Transform::identity();
+    expected.fill_path(&path, &paint, FillRule::Winding, ts, None);
+    pixmap.fill_path_parallel(
+        &path,
+        &paint,
+        FillRule::Winding,
+        ts,
+        None,
+        &ThreadExecutor::new(4),
+    );
+
+    assert!(pixmap == expected);
+}
+
+// Layers are rendered into pixmaps with their own origin, which must not be visible to the shader.
+#[test]
+fn canvas_layer() {
+    let paint = Paint {
+        shader: Custom::new(Arc::new(CoordShader)),
+        ..Paint::default()
+    };
+    let rect = Rect::from_xywh(0.0, 0.0, 100.0, 100.0).unwrap();
+    let bounds = Rect::from_xywh(40.0, 40.0, 40.0, 40.0).unwrap();
+
+    let mut recorder = PictureRecorder::new();
+    recorder.fill_rect(rect, &paint, Transform::identity(), None);
+    let picture = recorder.finish();
+
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(pixmap.as_mut());
+    canvas.push_layer(Some(bounds), 1.0, BlendMode::SourceOver, None);
+    canvas.fill_rect(rect, &paint);
+    canvas.pop_layer();
+    assert_eq!(pixmap.pixel(50, 60).unwrap(), coord_color(50, 60));
+    assert_eq!(pixmap.pixel(79, 40).unwrap(), coord_color(79, 40));
+    assert_eq!(pixmap.pixel(30, 60).unwrap().alpha(), 0);
+
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(pixmap.as_mut());
+    canvas.push_layer(Some(bounds), 1.0, BlendMode::SourceOver, None);
+    canvas.draw_picture(&picture);
+    canvas.pop_layer();
+    assert_eq!(pixmap.pixel(50, 60).unwrap(), coord_color(50, 60));
+}
+
+#[test]
+fn picture() {
+    let paint = Paint {
+        shader: Custom::new(Arc::new(CoordShader)),
+        ..Paint::default()
+    };
+
+    let mut recorder = PictureRecorder::new();
+    recorder.fill_path(&circle(), &paint, FillRule::Winding, Transform::identity(), None);
+    let picture = recorder.finish();
+
+    let mut expected = Pixmap::new(200, 200).unwrap();
+    expected.fill_path(&circle(), &paint, FillRule::Winding, Transform::identity(), None);
+
+    let mut pixmap = Pixmap::new(200, 200).unwrap();
+    pixmap.draw_picture(&picture, Transform::identity(), None);
+    assert!(pixmap == expected);
+
+    // Custom shaders cannot be serialized.
+    assert_eq!(picture.to_bytes(), Err(PictureError::UnsupportedShader));
+}
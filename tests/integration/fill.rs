@@ -554,6 +554,34 @@ fn aa_endless_loop() {
     pixmap.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
 }
 
+#[test]
+fn aa_large_coordinates() {
+    let paint = Paint::default();
+
+    // The path bounds width was losing precision.
+    let mut pb = PathBuilder::new();
+    pb.move_to(35.0, 60.0);
+    pb.quad_to(-125943160.0, 59.999992, 25.432913, 58.096985);
+    pb.line_to(35.0, 10.0);
+    pb.close();
+    let path = pb.finish().unwrap();
+
+    // Must not panic.
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+
+    // The path bounds top was overflowing when converted into supersampled coordinates.
+    let mut pb = PathBuilder::new();
+    pb.move_to(60.0, -4.652298e37);
+    pb.quad_to(59.999992, 39.9728, 58.096985, 44.567078);
+    pb.line_to(10.0, 35.0);
+    pb.close();
+    let path = pb.finish().unwrap();
+
+    // Must not panic.
+    pixmap.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+}
+
 #[test]
 fn clear_aa() {
     // Make sure that Clear with AA doesn't fallback to memset.
@@ -634,3 +662,369 @@ fn fill_rect() {
     let expected = Pixmap::load_png("tests/images/canvas/fill-rect.png").unwrap();
     assert_eq!(pixmap, expected);
 }
+
+#[test]
+fn markers() {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint.anti_alias = false;
+
+    let mut pb = PathBuilder::new();
+    pb.move_to(30.0, 50.0);
+    pb.line_to(70.0, 50.0);
+    pb.line_to(70.0, 80.0);
+    let path = pb.finish().unwrap();
+
+    // An arrowhead pointing along the x-axis.
+    let mut pb = PathBuilder::new();
+    pb.move_to(0.0, -5.0);
+    pb.line_to(10.0, 0.0);
+    pb.line_to(0.0, 5.0);
+    pb.close();
+    let arrow = pb.finish().unwrap();
+
+    let positions: Vec<_> = path
+        .marker_positions(MarkerOrient::AutoStartReverse)
+        .into_iter()
+        .filter(|p| p.kind != MarkerKind::Mid)
+        .collect();
+
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.fill_markers(&arrow, &positions, &paint, FillRule::Winding, Transform::identity(), None);
+
+    let alpha = |x, y| pixmap.pixel(x, y).unwrap().alpha();
+    // The start arrow points left and the end one points down.
+    assert_eq!(alpha(22, 50), 200);
+    assert_eq!(alpha(37, 50), 0);
+    assert_eq!(alpha(70, 87), 200);
+    assert_eq!(alpha(70, 73), 0);
+    // No mid marker.
+    assert_eq!(alpha(72, 50), 0);
+}
+
+fn analytic_paint() -> Paint<'static> {
+    Paint {
+        rasterizer: Rasterizer::Analytic,
+        ..Paint::default()
+    }
+}
+
+fn alpha_sum(pixmap: &Pixmap) -> f32 {
+    pixmap.pixels().iter().map(|p| p.alpha() as f32 / 255.0).sum()
+}
+
+#[test]
+fn analytic_aa_rect() {
+    let mut pb = PathBuilder::new();
+    pb.move_to(10.25, 10.5);
+    pb.line_to(50.75, 10.5);
+    pb.line_to(50.75, 30.5);
+    pb.line_to(10.25, 30.5);
+    pb.close();
+    let path = pb.finish().unwrap();
+
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.fill_path(&path, &analytic_paint(), FillRule::Winding, Transform::identity(), None);
+
+    let alpha = |x, y| pixmap.pixel(x, y).unwrap().alpha();
+    assert_eq!(alpha(9, 20), 0);
+    assert_eq!(alpha(10, 20), 191);
+    assert_eq!(alpha(30, 20), 255);
+    assert_eq!(alpha(50, 20), 191);
+    assert_eq!(alpha(51, 20), 0);
+    assert_eq!(alpha(30, 10), 128);
+    assert_eq!(alpha(30, 30), 128);
+    assert_eq!(alpha(10, 10), 96);
+    assert_eq!(alpha(30, 31), 0);
+    assert!((alpha_sum(&pixmap) - 40.5 * 20.0).abs() < 0.5);
+}
+
+#[test]
+fn analytic_aa_circle() {
+    let path = PathBuilder::from_circle(50.3, 50.6, 30.0).unwrap();
+
+    let mut analytic = Pixmap::new(100, 100).unwrap();
+    analytic.fill_path(&path, &analytic_paint(), FillRule::Winding, Transform::identity(), None);
+
+    let area = core::f32::consts::PI * 30.0 * 30.0;
+    assert!((alpha_sum(&analytic) - area).abs() < area * 0.002);
+
+    // The exact coverage is 0.4676.
+    assert!((analytic.pixel(47, 80).unwrap().alpha() as i32 - 119).abs() <= 3);
+
+    // Supersampling uses only 4 sub-scanlines per pixel,
+    // so the difference near horizontal edges can be up to a quarter.
+    let mut supersampled = Pixmap::new(100, 100).unwrap();
+    supersampled.fill_path(&path, &Paint::default(), FillRule::Winding, Transform::identity(), None);
+    for (a, b) in analytic.pixels().iter().zip(supersampled.pixels()) {
+        assert!((a.alpha() as i32 - b.alpha() as i32).abs() <= 64);
+    }
+}
+
+#[test]
+fn analytic_aa_fill_rules() {
+    let mut pb = PathBuilder::new();
+    pb.push_rect(Rect::from_xywh(10.0, 10.0, 80.0, 80.0).unwrap());
+    pb.push_rect(Rect::from_xywh(30.5, 30.5, 40.0, 40.0).unwrap());
+    let path = pb.finish().unwrap();
+
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.fill_path(&path, &analytic_paint(), FillRule::EvenOdd, Transform::identity(), None);
+    assert_eq!(pixmap.pixel(20, 50).unwrap().alpha(), 255);
+    assert_eq!(pixmap.pixel(30, 50).unwrap().alpha(), 128);
+    assert_eq!(pixmap.pixel(50, 50).unwrap().alpha(), 0);
+    assert!((alpha_sum(&pixmap) - (80.0 * 80.0 - 40.0 * 40.0)).abs() < 0.5);
+
+    pixmap.fill(Color::TRANSPARENT);
+    pixmap.fill_path(&path, &analytic_paint(), FillRule::Winding, Transform::identity(), None);
+    assert_eq!(pixmap.pixel(30, 50).unwrap().alpha(), 255);
+    assert_eq!(pixmap.pixel(50, 50).unwrap().alpha(), 255);
+}
+
+#[test]
+fn analytic_aa_clipped() {
+    let path = PathBuilder::from_circle(10.0, 15.0, 30.0).unwrap();
+
+    let mut pixmap = Pixmap::new(50, 50).unwrap();
+    pixmap.fill_path(&path, &analytic_paint(), FillRule::Winding, Transform::identity(), None);
+
+    // Render the whole path and compare the visible part.
+    let mut full = Pixmap::new(100, 100).unwrap();
+    let ts = Transform::from_translate(40.0, 40.0);
+    full.fill_path(&path, &analytic_paint(), FillRule::Winding, ts, None);
+
+    for y in 0..50 {
+        for x in 0..50 {
+            let a = pixmap.pixel(x, y).unwrap().alpha() as i32;
+            let b = full.pixel(x + 40, y + 40).unwrap().alpha() as i32;
+            assert!((a - b).abs() <= 1);
+        }
+    }
+}
+
+#[test]
+fn analytic_aa_stroke() {
+    let mut pb = PathBuilder::new();
+    pb.move_to(10.0, 20.5);
+    pb.line_to(90.0, 20.5);
+    let path = pb.finish().unwrap();
+
+    let stroke = Stroke {
+        width: 3.0,
+        ..Stroke::default()
+    };
+
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.stroke_path(&path, &analytic_paint(), &stroke, Transform::identity(), None);
+    assert_eq!(pixmap.pixel(50, 18).unwrap().alpha(), 0);
+    assert_eq!(pixmap.pixel(50, 19).unwrap().alpha(), 255);
+    assert_eq!(pixmap.pixel(50, 20).unwrap().alpha(), 255);
+    assert_eq!(pixmap.pixel(50, 21).unwrap().alpha(), 255);
+    assert_eq!(pixmap.pixel(50, 22).unwrap().alpha(), 0);
+    assert!((alpha_sum(&pixmap) - 80.0 * 3.0).abs() < 0.5);
+}
+
+#[test]
+fn analytic_aa_self_intersection() {
+    // A bow tie, which contours cross in the middle of the (50, 50) pixel.
+    let mut pb = PathBuilder::new();
+    pb.move_to(10.5, 10.5);
+    pb.line_to(90.5, 90.5);
+    pb.line_to(90.5, 10.5);
+    pb.line_to(10.5, 90.5);
+    pb.close();
+    let path = pb.finish().unwrap();
+
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.fill_path(&path, &analytic_paint(), FillRule::Winding, Transform::identity(), None);
+    let alpha = |x, y| pixmap.pixel(x, y).unwrap().alpha();
+
+    // Pixels crossed by a single edge are exact.
+    assert_eq!(alpha(30, 50), 255);
+    assert_eq!(alpha(30, 30), 128);
+    assert_eq!(alpha(50, 30), 0);
+
+    // The triangles have the opposite winding, but both are filled,
+    // so the pixel with the crossing is half covered.
+    assert!((alpha(50, 50) as i32 - 128).abs() <= 1);
+
+    let mut supersampled = Pixmap::new(100, 100).unwrap();
+    supersampled.fill_path(&path, &Paint::default(), FillRule::Winding, Transform::identity(), None);
+    assert_eq!(supersampled.pixel(50, 50).unwrap().alpha(), 128);
+}
+
+#[test]
+fn analytic_aa_overlap() {
+    // Both rects have the same direction and their right edges are in the middle of a pixel.
+    let mut pb = PathBuilder::new();
+    pb.push_rect(Rect::from_ltrb(10.0, 10.0, 50.5, 90.0).unwrap());
+    pb.push_rect(Rect::from_ltrb(30.0, 10.0, 50.5, 90.0).unwrap());
+    let path = pb.finish().unwrap();
+
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.fill_path(&path, &analytic_paint(), FillRule::Winding, Transform::identity(), None);
+    assert_eq!(pixmap.pixel(20, 50).unwrap().alpha(), 255);
+    assert_eq!(pixmap.pixel(40, 50).unwrap().alpha(), 255);
+    assert_eq!(pixmap.pixel(50, 50).unwrap().alpha(), 128);
+
+    pixmap.fill(Color::TRANSPARENT);
+    pixmap.fill_path(&path, &analytic_paint(), FillRule::EvenOdd, Transform::identity(), None);
+    assert_eq!(pixmap.pixel(20, 50).unwrap().alpha(), 255);
+    assert_eq!(pixmap.pixel(40, 50).unwrap().alpha(), 0);
+    assert_eq!(pixmap.pixel(50, 50).unwrap().alpha(), 0);
+}
+
+#[test]
+fn analytic_aa_tiled() {
+    // Large pixmaps are rendered in 8191x8191 tiles.
+    let path = PathBuilder::from_circle(8191.3, 10.0, 8.0).unwrap();
+
+    let mut pixmap = Pixmap::new(8300, 20).unwrap();
+    pixmap.fill_path(&path, &analytic_paint(), FillRule::Winding, Transform::identity(), None);
+
+    let mut small = Pixmap::new(20, 20).unwrap();
+    let ts = Transform::from_translate(-8181.0, 0.0);
+    small.fill_path(&path, &analytic_paint(), FillRule::Winding, ts, None);
+
+    for y in 0..20 {
+        for x in 0..20 {
+            let a = pixmap.pixel(x + 8181, y).unwrap().alpha() as i32;
+            let b = small.pixel(x, y).unwrap().alpha() as i32;
+            assert!((a - b).abs() <= 1);
+        }
+    }
+}
+
+#[test]
+fn analytic_aa_complex_rows() {
+    // A star polygon with 500 edges crossing each other.
+    let mut pb = PathBuilder::new();
+    for i in 0..500 {
+        let a = (i * 249 % 500) as f32 / 500.0 * 2.0 * core::f32::consts::PI;
+        let (x, y) = (50.0 + 45.0 * a.cos(), 50.0 + 45.0 * a.sin());
+        if i == 0 {
+            pb.move_to(x, y);
+        } else {
+            pb.line_to(x, y);
+        }
+    }
+    pb.close();
+    let path = pb.finish().unwrap();
+
+    for fill_rule in [FillRule::Winding, FillRule::EvenOdd] {
+        // Rows with too many crossings are supersampled instead.
+        let mut pixmap = Pixmap::new(100, 100).unwrap();
+        pixmap.fill_path(&path, &analytic_paint(), fill_rule, Transform::identity(), None);
+
+        let mut supersampled = Pixmap::new(100, 100).unwrap();
+        supersampled.fill_path(&path, &Paint::default(), fill_rule, Transform::identity(), None);
+
+        // Each row is rendered exactly once, either way.
+        let row_sum = |pixmap: &Pixmap, y| {
+            (0..100).map(|x| pixmap.pixel(x, y).unwrap().alpha() as f32 / 255.0).sum::<f32>()
+        };
+        for y in 0..100 {
+            assert!((row_sum(&pixmap, y) - row_sum(&supersampled, y)).abs() < 3.0);
+        }
+    }
+}
+
+// Mean absolute alpha error of a rendered circle against its exact per-pixel coverage.
+fn circle_error(pixmap: &Pixmap, cx: f32, cy: f32, r: f32) -> f32 {
+    const SAMPLES: u32 = 128;
+
+    let mut error = 0.0;
+    for y in 0..pixmap.height() {
+        for x in 0..pixmap.width() {
+            let dx = x as f32 + 0.5 - cx;
+            let dy = y as f32 + 0.5 - cy;
+            let dist = (dx * dx + dy * dy).sqrt();
+            let coverage = if dist < r - 1.0 {
+                1.0
+            } else if dist > r + 1.0 {
+                0.0
+            } else {
+                let mut inside = 0;
+                for sy in 0..SAMPLES {
+                    for sx in 0..SAMPLES {
+                        let px = x as f32 + (sx as f32 + 0.5) / SAMPLES as f32 - cx;
+                        let py = y as f32 + (sy as f32 + 0.5) / SAMPLES as f32 - cy;
+                        if px * px + py * py < r * r {
+                            inside += 1;
+                        }
+                    }
+                }
+
+                inside as f32 / (SAMPLES * SAMPLES) as f32
+            };
+
+            let alpha = pixmap.pixel(x, y).unwrap().alpha() as f32;
+            error += (alpha - coverage * 255.0).abs();
+        }
+    }
+
+    error / (pixmap.width() * pixmap.height()) as f32
+}
+
+#[test]
+fn aa_quality_converges() {
+    let (cx, cy, r) = (25.3, 24.7, 19.6);
+    let path = PathBuilder::from_circle(cx, cy, r).unwrap();
+
+    let mut errors = Vec::new();
+    for quality in &[
+        AntiAliasQuality::Low,
+        AntiAliasQuality::Normal,
+        AntiAliasQuality::High,
+    ] {
+        let paint = Paint {
+            anti_alias_quality: *quality,
+            ..Paint::default()
+        };
+
+        let mut pixmap = Pixmap::new(50, 50).unwrap();
+        pixmap.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+        errors.push(circle_error(&pixmap, cx, cy, r));
+    }
+
+    assert!(errors[0] > errors[1]);
+    assert!(errors[1] > errors[2]);
+    assert!(errors[2] < 0.25);
+}
+
+#[test]
+fn aa_quality_large_path() {
+    // High quality renders paths larger than 2047 pixels in strips.
+    let paint = Paint {
+        anti_alias_quality: AntiAliasQuality::High,
+        ..Paint::default()
+    };
+
+    // The circle crosses the strips boundary.
+    let mut pb = PathBuilder::new();
+    pb.push_rect(Rect::from_ltrb(0.5, 0.5, 3000.5, 9.5).unwrap());
+    pb.push_circle(2047.3, 20.6, 8.0);
+    let path = pb.finish().unwrap();
+
+    let mut large = Pixmap::new(3001, 30).unwrap();
+    large.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+    assert_eq!(large.pixel(0, 0).unwrap().alpha(), 64);
+    assert_eq!(large.pixel(3000, 9).unwrap().alpha(), 64);
+
+    // Differs only because of the coordinates precision.
+    let circle = PathBuilder::from_circle(27.3, 20.6, 8.0).unwrap();
+    let mut small = Pixmap::new(60, 30).unwrap();
+    small.fill_path(&circle, &paint, FillRule::Winding, Transform::identity(), None);
+    for y in 10..30 {
+        for x in 0..60 {
+            let a = large.pixel(x + 2020, y).unwrap().alpha() as i32;
+            let b = small.pixel(x, y).unwrap().alpha() as i32;
+            assert!((a - b).abs() <= 4);
+        }
+    }
+
+    // Not the Normal quality.
+    let mut normal = Pixmap::new(60, 30).unwrap();
+    normal.fill_path(&circle, &Paint::default(), FillRule::Winding, Transform::identity(), None);
+    assert_ne!(small, normal);
+}
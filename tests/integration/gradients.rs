@@ -433,3 +433,622 @@ fn global_opacity() {
     let expected = Pixmap::load_png("tests/images/gradients/global-opacity.png").unwrap();
     assert_eq!(pixmap, expected);
 }
+
+// Sweep gradients are checked against `SkSweepGradient`'s definition evaluated in f64:
+// the angle is measured clockwise from the positive X axis
+// and `t = (angle / 360 - start / 360) / (end / 360 - start / 360)`.
+fn sweep_reference(
+    x: f64, y: f64, ts: Transform, start: f64, end: f64, stops: &[(f64, Color)], mode: SpreadMode,
+) -> [f64; 4] {
+    let ts = ts.invert().unwrap();
+    let dx = ts.sx as f64 * x + ts.kx as f64 * y + ts.tx as f64 - 100.0;
+    let dy = ts.ky as f64 * x + ts.sy as f64 * y + ts.ty as f64 - 100.0;
+    let angle = dy.atan2(dx).rem_euclid(2.0 * std::f64::consts::PI) / (2.0 * std::f64::consts::PI);
+    let t = (angle - start / 360.0) / (end / 360.0 - start / 360.0);
+    gradient_reference(t, stops, mode)
+}
+
+fn gradient_reference(t: f64, stops: &[(f64, Color)], mode: SpreadMode) -> [f64; 4] {
+    let t = match mode {
+        SpreadMode::Pad => t.clamp(0.0, 1.0),
+        SpreadMode::Repeat => t - t.floor(),
+        SpreadMode::Reflect => ((t - 1.0).rem_euclid(2.0) - 1.0).abs(),
+    };
+
+    let mut color = stops[stops.len() - 1].1;
+    for w in stops.windows(2) {
+        if t <= w[1].0 {
+            let k = if w[1].0 > w[0].0 { (t - w[0].0).max(0.0) / (w[1].0 - w[0].0) } else { 0.0 };
+            let lerp = |a: f32, b: f32| a as f64 + (b as f64 - a as f64) * k;
+            let (c0, c1) = (w[0].1, w[1].1);
+            let a = lerp(c0.alpha(), c1.alpha());
+            return [
+                lerp(c0.red(), c1.red()) * a * 255.0,
+                lerp(c0.green(), c1.green()) * a * 255.0,
+                lerp(c0.blue(), c1.blue()) * a * 255.0,
+                a * 255.0,
+            ];
+        }
+        color = w[1].1;
+    }
+
+    let a = color.alpha() as f64;
+    [color.red() as f64 * a * 255.0, color.green() as f64 * a * 255.0, color.blue() as f64 * a * 255.0, a * 255.0]
+}
+
+// Renders `shader` over the whole pixmap and compares it with the reference image
+// and with `reference`, which evaluates the gradient at a pixel center
+// or returns `None` where it cannot be predicted.
+fn check_gradient(
+    name: &str, shader: Shader, hq: bool, min_checked: usize, reference: impl Fn(f64, f64) -> Option<[f64; 4]>,
+) {
+    let paint = Paint {
+        shader,
+        anti_alias: false,
+        force_hq_pipeline: hq,
+        ..Paint::default()
+    };
+
+    let mut pixmap = Pixmap::new(200, 200).unwrap();
+    pixmap.fill_rect(Rect::from_xywh(0.0, 0.0, 200.0, 200.0).unwrap(), &paint, Transform::identity(), None);
+
+    let mut checked = 0;
+    for y in 0..200 {
+        for x in 0..200 {
+            let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+            let expected = match reference(cx, cy) {
+                Some(c) => c,
+                None => continue,
+            };
+
+            // Skip pixels next to hard edges, where a tiny error changes the color.
+            let is_edge = [(cx + 1.0, cy), (cx - 1.0, cy), (cx, cy + 1.0), (cx, cy - 1.0)]
+                .iter()
+                .filter_map(|&(nx, ny)| reference(nx, ny))
+                .any(|c| (0..4).any(|i| (c[i] - expected[i]).abs() > 8.0));
+            if is_edge {
+                continue;
+            }
+
+            let c = pixmap.pixel(x, y).unwrap();
+            let actual = [c.red(), c.green(), c.blue(), c.alpha()];
+            for i in 0..4 {
+                assert!(
+                    (actual[i] as f64 - expected[i]).abs() <= 2.0,
+                    "({}, {}): {:?} != {:?}", x, y, actual, expected,
+                );
+            }
+            checked += 1;
+        }
+    }
+
+    assert!(checked > min_checked, "{}", checked);
+
+    let expected = Pixmap::load_png(format!("tests/images/gradients/{}.png", name)).unwrap();
+    assert_eq!(pixmap, expected);
+}
+
+// Unlike most reference images, the sweep ones were rendered by tiny-skia itself,
+// not by Skia, so they only guard against regressions.
+// The match with `SkSweepGradient` relies on the `sweep_reference` check.
+fn check_sweep(name: &str, start: f32, end: f32, mode: SpreadMode, ts: Transform, hq: bool) {
+    let stops = [
+        (0.0, Color::from_rgba8(50, 127, 150, 200)),
+        (0.3, Color::from_rgba8(220, 140, 75, 180)),
+        (1.0, Color::from_rgba8(40, 180, 55, 160)),
+    ];
+
+    let shader = SweepGradient::new(
+        Point::from_xy(100.0, 100.0),
+        start,
+        end,
+        stops.iter().map(|(pos, color)| GradientStop::new(*pos as f32, *color)).collect(),
+        mode,
+        ts,
+    ).unwrap();
+
+    let (start, end) = (start as f64, end as f64);
+    check_gradient(name, shader, hq, 35000, |x, y| {
+        // The angle is unstable next to the center.
+        if (x - 100.0).hypot(y - 100.0) < 2.0 {
+            return None;
+        }
+
+        Some(sweep_reference(x, y, ts, start, end, &stops, mode))
+    });
+}
+
+#[test]
+fn sweep_full_lq() {
+    check_sweep("sweep-full-lq", 0.0, 360.0, SpreadMode::Pad, Transform::identity(), false);
+}
+
+#[test]
+fn sweep_full_hq() {
+    check_sweep("sweep-full-hq", 0.0, 360.0, SpreadMode::Pad, Transform::identity(), true);
+}
+
+#[test]
+fn sweep_partial_pad_lq() {
+    check_sweep("sweep-partial-pad-lq", 45.0, 200.0, SpreadMode::Pad, Transform::identity(), false);
+}
+
+#[test]
+fn sweep_partial_pad_hq() {
+    check_sweep("sweep-partial-pad-hq", 45.0, 200.0, SpreadMode::Pad, Transform::identity(), true);
+}
+
+#[test]
+fn sweep_repeat_lq() {
+    check_sweep("sweep-repeat-lq", -30.0, 60.0, SpreadMode::Repeat, Transform::identity(), false);
+}
+
+#[test]
+fn sweep_repeat_hq() {
+    check_sweep("sweep-repeat-hq", -30.0, 60.0, SpreadMode::Repeat, Transform::identity(), true);
+}
+
+#[test]
+fn sweep_reflect_lq() {
+    check_sweep("sweep-reflect-lq", 90.0, 150.0, SpreadMode::Reflect, Transform::identity(), false);
+}
+
+#[test]
+fn sweep_reflect_hq() {
+    check_sweep("sweep-reflect-hq", 90.0, 150.0, SpreadMode::Reflect, Transform::identity(), true);
+}
+
+#[test]
+fn sweep_with_ts_hq() {
+    let ts = Transform::from_row(1.2, 0.3, -0.4, 0.8, 30.0, -10.0);
+    check_sweep("sweep-with-ts-hq", 20.0, 300.0, SpreadMode::Pad, ts, true);
+}
+
+// A full sweep is always padded, since `t` never leaves the 0..=1 range.
+#[test]
+fn sweep_full_ignores_mode() {
+    let stops = vec![
+        GradientStop::new(0.0, Color::BLACK),
+        GradientStop::new(1.0, Color::WHITE),
+    ];
+
+    let pad = SweepGradient::new(
+        Point::from_xy(100.0, 100.0), -10.0, 370.0, stops.clone(), SpreadMode::Pad, Transform::identity(),
+    );
+    let repeat = SweepGradient::new(
+        Point::from_xy(100.0, 100.0), -10.0, 370.0, stops, SpreadMode::Repeat, Transform::identity(),
+    );
+    assert_eq!(pad, repeat);
+}
+
+#[test]
+fn sweep_degenerate() {
+    let stops = vec![
+        GradientStop::new(0.0, Color::BLACK),
+        GradientStop::new(0.5, Color::from_rgba8(255, 0, 0, 255)),
+        GradientStop::new(1.0, Color::WHITE),
+    ];
+    let center = Point::from_xy(100.0, 100.0);
+    let ts = Transform::identity();
+
+    // Padded gradients use the first color up to the angle and the last one after.
+    let paint = Paint {
+        shader: SweepGradient::new(center, 90.0, 90.0, stops.clone(), SpreadMode::Pad, ts).unwrap(),
+        ..Paint::default()
+    };
+    let mut pixmap = Pixmap::new(200, 200).unwrap();
+    pixmap.fill_rect(Rect::from_xywh(0.0, 0.0, 200.0, 200.0).unwrap(), &paint, ts, None);
+    assert_eq!(pixmap.pixel(150, 110).unwrap(), ColorU8::from_rgba(0, 0, 0, 255).premultiply());
+    assert_eq!(pixmap.pixel(50, 110).unwrap(), ColorU8::from_rgba(255, 255, 255, 255).premultiply());
+
+    // Zero angle gradients are solid.
+    let shader = SweepGradient::new(center, 0.0, 0.0, stops.clone(), SpreadMode::Pad, ts).unwrap();
+    assert_eq!(shader, Shader::SolidColor(Color::WHITE));
+
+    let shader = SweepGradient::new(center, 90.0, 90.0, stops.clone(), SpreadMode::Repeat, ts).unwrap();
+    assert!(matches!(shader, Shader::SolidColor(_)));
+
+    assert!(SweepGradient::new(center, 90.0, 45.0, stops.clone(), SpreadMode::Pad, ts).is_none());
+    assert!(SweepGradient::new(center, 0.0, f32::NAN, stops, SpreadMode::Pad, ts).is_none());
+}
+
+// Returns the color of the largest `t` with a non-negative radius, where the point is on
+// the circle interpolated between the start and the end circles.
+fn conical_reference(
+    x: f64, y: f64, ts: Transform, c0: (f64, f64, f64), c1: (f64, f64, f64), stops: &[(f64, Color)], mode: SpreadMode,
+) -> [f64; 4] {
+    let ts = ts.invert().unwrap();
+    let px = ts.sx as f64 * x + ts.kx as f64 * y + ts.tx as f64 - c0.0;
+    let py = ts.ky as f64 * x + ts.sy as f64 * y + ts.ty as f64 - c0.1;
+    let (cdx, cdy, dr) = (c1.0 - c0.0, c1.1 - c0.1, c1.2 - c0.2);
+
+    // |p - t * cd|^2 = (r0 + t * dr)^2
+    let a = cdx * cdx + cdy * cdy - dr * dr;
+    let b = px * cdx + py * cdy + c0.2 * dr;
+    let c = px * px + py * py - c0.2 * c0.2;
+
+    let mut roots = Vec::new();
+    if a.abs() < 1e-9 {
+        roots.push(c / (2.0 * b));
+    } else {
+        let disc = b * b - a * c;
+        if disc >= 0.0 {
+            let t1 = (b + disc.sqrt()) / a;
+            let t2 = (b - disc.sqrt()) / a;
+            roots.push(t1.max(t2));
+            roots.push(t1.min(t2));
+        }
+    }
+
+    match roots.iter().find(|t| t.is_finite() && c0.2 + *t * dr >= 0.0) {
+        Some(t) => gradient_reference(*t, stops, mode),
+        None => [0.0; 4],
+    }
+}
+
+fn check_conical(
+    name: &str, c0: (f32, f32, f32), c1: (f32, f32, f32), mode: SpreadMode, ts: Transform, hq: bool, min_checked: usize,
+) {
+    let stops = [
+        (0.0, Color::from_rgba8(50, 127, 150, 200)),
+        (0.3, Color::from_rgba8(220, 140, 75, 180)),
+        (1.0, Color::from_rgba8(40, 180, 55, 160)),
+    ];
+
+    let shader = RadialGradient::new_two_point_conical(
+        Point::from_xy(c0.0, c0.1),
+        c0.2,
+        Point::from_xy(c1.0, c1.1),
+        c1.2,
+        stops.iter().map(|(pos, color)| GradientStop::new(*pos as f32, *color)).collect(),
+        mode,
+        ts,
+    ).unwrap();
+
+    let c0 = (c0.0 as f64, c0.1 as f64, c0.2 as f64);
+    let c1 = (c1.0 as f64, c1.1 as f64, c1.2 as f64);
+    check_gradient(name, shader, hq, min_checked, |x, y| {
+        Some(conical_reference(x, y, ts, c0, c1, &stops, mode))
+    });
+}
+
+#[test]
+fn conical_concentric_lq() {
+    check_conical("conical-concentric-lq", (100.0, 100.0, 30.0), (100.0, 100.0, 90.0), SpreadMode::Pad, Transform::identity(), false, 35000);
+}
+
+#[test]
+fn conical_concentric_hq() {
+    check_conical("conical-concentric-hq", (100.0, 100.0, 30.0), (100.0, 100.0, 90.0), SpreadMode::Pad, Transform::identity(), true, 35000);
+}
+
+#[test]
+fn conical_concentric_shrinking() {
+    check_conical("conical-concentric-shrinking", (100.0, 100.0, 90.0), (100.0, 100.0, 30.0), SpreadMode::Repeat, Transform::identity(), false, 35000);
+}
+
+#[test]
+fn conical_strip() {
+    check_conical("conical-strip", (60.0, 100.0, 30.0), (140.0, 100.0, 30.0), SpreadMode::Pad, Transform::identity(), true, 10000);
+}
+
+#[test]
+fn conical_strip_reflect() {
+    check_conical("conical-strip-reflect", (90.0, 90.0, 20.0), (110.0, 110.0, 20.0), SpreadMode::Reflect, Transform::identity(), true, 5000);
+}
+
+#[test]
+fn conical_well_behaved() {
+    check_conical("conical-well-behaved", (80.0, 100.0, 20.0), (120.0, 100.0, 80.0), SpreadMode::Pad, Transform::identity(), true, 35000);
+}
+
+#[test]
+fn conical_greater() {
+    check_conical("conical-greater", (40.0, 100.0, 10.0), (160.0, 100.0, 30.0), SpreadMode::Repeat, Transform::identity(), true, 10000);
+}
+
+#[test]
+fn conical_smaller() {
+    check_conical("conical-smaller", (100.0, 100.0, 80.0), (130.0, 100.0, 10.0), SpreadMode::Pad, Transform::identity(), true, 20000);
+}
+
+#[test]
+fn conical_swapped() {
+    check_conical("conical-swapped", (70.0, 100.0, 40.0), (130.0, 100.0, 0.0), SpreadMode::Pad, Transform::identity(), true, 10000);
+}
+
+#[test]
+fn conical_focal_on_circle() {
+    check_conical("conical-focal-on-circle", (60.0, 100.0, 20.0), (100.0, 100.0, 60.0), SpreadMode::Repeat, Transform::identity(), true, 10000);
+}
+
+#[test]
+fn conical_with_ts() {
+    let ts = Transform::from_row(1.2, 0.3, -0.2, 0.9, 10.0, -5.0);
+    check_conical("conical-with-ts", (80.0, 90.0, 15.0), (110.0, 100.0, 50.0), SpreadMode::Reflect, ts, true, 10000);
+}
+
+#[test]
+fn conical_degenerate() {
+    let stops = vec![
+        GradientStop::new(0.0, Color::BLACK),
+        GradientStop::new(0.5, Color::from_rgba8(255, 0, 0, 255)),
+        GradientStop::new(1.0, Color::WHITE),
+    ];
+    let center = Point::from_xy(100.0, 100.0);
+    let ts = Transform::identity();
+
+    // Padded gradients use the first color inside the circle and the last one outside.
+    let paint = Paint {
+        shader: RadialGradient::new_two_point_conical(center, 50.0, center, 50.0, stops.clone(), SpreadMode::Pad, ts).unwrap(),
+        ..Paint::default()
+    };
+    let mut pixmap = Pixmap::new(200, 200).unwrap();
+    pixmap.fill_rect(Rect::from_xywh(0.0, 0.0, 200.0, 200.0).unwrap(), &paint, ts, None);
+    assert_eq!(pixmap.pixel(110, 110).unwrap(), ColorU8::from_rgba(0, 0, 0, 255).premultiply());
+    assert_eq!(pixmap.pixel(10, 10).unwrap(), ColorU8::from_rgba(255, 255, 255, 255).premultiply());
+
+    let shader = RadialGradient::new_two_point_conical(center, 0.0, center, 0.0, stops.clone(), SpreadMode::Pad, ts).unwrap();
+    assert_eq!(shader, Shader::SolidColor(Color::WHITE));
+
+    let shader = RadialGradient::new_two_point_conical(center, 50.0, center, 50.0, stops.clone(), SpreadMode::Repeat, ts).unwrap();
+    assert!(matches!(shader, Shader::SolidColor(_)));
+
+    assert!(RadialGradient::new_two_point_conical(center, -1.0, center, 50.0, stops.clone(), SpreadMode::Pad, ts).is_none());
+    assert!(RadialGradient::new_two_point_conical(center, 0.0, center, f32::NAN, stops, SpreadMode::Pad, ts).is_none());
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(c: f64) -> f64 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.0031308 { c * 12.92 } else { c.powf(1.0 / 2.4) * 1.055 - 0.055 }
+}
+
+fn srgb_to_oklab(c: Color) -> [f64; 3] {
+    let (r, g, b) = (srgb_to_linear(c.red() as f64), srgb_to_linear(c.green() as f64), srgb_to_linear(c.blue() as f64));
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+fn oklab_to_srgb(c: [f64; 3]) -> [f64; 3] {
+    let l = (c[0] + 0.3963377774 * c[1] + 0.2158037573 * c[2]).powi(3);
+    let m = (c[0] - 0.1055613458 * c[1] - 0.0638541728 * c[2]).powi(3);
+    let s = (c[0] - 0.0894841775 * c[1] - 1.2914855480 * c[2]).powi(3);
+    [
+        linear_to_srgb(4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s),
+        linear_to_srgb(-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s),
+        linear_to_srgb(-0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s),
+    ]
+}
+
+// Draws a horizontal gradient with evenly spaced stops and compares it with an opaque reference.
+fn check_interpolation(
+    colors: &[Color], color_space: InterpolationColorSpace, reference: &dyn Fn(f64) -> [f64; 3],
+) {
+    let step = 1.0 / (colors.len() - 1) as f32;
+    let mut paint = Paint {
+        shader: LinearGradient::new(
+            Point::from_xy(0.0, 0.0),
+            Point::from_xy(100.0, 0.0),
+            colors.iter().enumerate().map(|(i, c)| GradientStop::new(i as f32 * step, *c)).collect(),
+            SpreadMode::Pad,
+            Transform::identity(),
+        ).unwrap(),
+        ..Paint::default()
+    };
+    paint.shader.set_gradient_interpolation(GradientInterpolation { color_space, premultiplied: false });
+
+    let mut pixmap = Pixmap::new(100, 1).unwrap();
+    pixmap.fill_rect(Rect::from_xywh(0.0, 0.0, 100.0, 1.0).unwrap(), &paint, Transform::identity(), None);
+
+    for x in 0..100 {
+        let expected = reference((x as f64 + 0.5) / 100.0);
+        let c = pixmap.pixel(x, 0).unwrap();
+        let actual = [c.red(), c.green(), c.blue()];
+        for i in 0..3 {
+            assert!(
+                (actual[i] as f64 - expected[i] * 255.0).abs() <= 2.0,
+                "{}: {:?} != {:?}", x, actual, expected,
+            );
+        }
+        assert_eq!(c.alpha(), 255);
+    }
+}
+
+fn red() -> Color {
+    Color::from_rgba8(255, 0, 0, 255)
+}
+
+fn blue() -> Color {
+    Color::from_rgba8(0, 0, 255, 255)
+}
+
+#[test]
+fn interpolation_srgb() {
+    check_interpolation(&[red(), blue()], InterpolationColorSpace::SRGB, &|t| [1.0 - t, 0.0, t]);
+}
+
+#[test]
+fn interpolation_linear_srgb() {
+    check_interpolation(&[red(), blue()], InterpolationColorSpace::LinearSRGB, &|t| {
+        [linear_to_srgb(1.0 - t), 0.0, linear_to_srgb(t)]
+    });
+}
+
+#[test]
+fn interpolation_oklab() {
+    let (c0, c1) = (srgb_to_oklab(red()), srgb_to_oklab(blue()));
+    check_interpolation(&[red(), blue()], InterpolationColorSpace::OKLab, &|t| {
+        oklab_to_srgb([
+            c0[0] + (c1[0] - c0[0]) * t,
+            c0[1] + (c1[1] - c0[1]) * t,
+            c0[2] + (c1[2] - c0[2]) * t,
+        ])
+    });
+}
+
+fn srgb_to_oklch(c: Color) -> [f64; 3] {
+    let lab = srgb_to_oklab(c);
+    [lab[0], lab[1].hypot(lab[2]), lab[2].atan2(lab[1]).to_degrees().rem_euclid(360.0)]
+}
+
+fn oklch_lerp(c0: [f64; 3], c1: [f64; 3], h0: f64, h1: f64, t: f64) -> [f64; 3] {
+    let l = c0[0] + (c1[0] - c0[0]) * t;
+    let c = c0[1] + (c1[1] - c0[1]) * t;
+    let h = (h0 + (h1 - h0) * t).to_radians();
+    oklab_to_srgb([l, c * h.cos(), c * h.sin()])
+}
+
+fn check_oklch(c0: Color, c1: Color, method: HueInterpolation, hue_delta: &dyn Fn(f64) -> f64) {
+    let colors = [c0, c1];
+    let (c0, c1) = (srgb_to_oklch(c0), srgb_to_oklch(c1));
+    let delta = hue_delta(c1[2] - c0[2]);
+
+    check_interpolation(&colors, InterpolationColorSpace::OKLCH(method), &|t| {
+        oklch_lerp(c0, c1, c0[2], c0[2] + delta, t)
+    });
+}
+
+#[test]
+fn interpolation_oklch() {
+    // Red is at ~29 degrees and blue is at ~264.
+    check_oklch(red(), blue(), HueInterpolation::Shorter, &|d| d - 360.0);
+    check_oklch(red(), blue(), HueInterpolation::Longer, &|d| d);
+    check_oklch(red(), blue(), HueInterpolation::Increasing, &|d| d);
+    check_oklch(red(), blue(), HueInterpolation::Decreasing, &|d| d - 360.0);
+}
+
+#[test]
+fn interpolation_oklch_achromatic_stop() {
+    // White has no hue, so it takes the hue of red in the first half and the one of blue in the second.
+    let white = Color::WHITE;
+    let (c0, c1, c2) = (srgb_to_oklch(red()), srgb_to_oklch(white), srgb_to_oklch(blue()));
+    check_interpolation(&[red(), white, blue()], InterpolationColorSpace::OKLCH(HueInterpolation::Shorter), &|t| {
+        if t < 0.5 {
+            oklch_lerp(c0, c1, c0[2], c0[2], t * 2.0)
+        } else {
+            oklch_lerp(c1, c2, c2[2], c2[2], t * 2.0 - 1.0)
+        }
+    });
+}
+
+#[test]
+fn interpolation_premultiplied() {
+    let stops = vec![
+        GradientStop::new(0.0, red()),
+        GradientStop::new(1.0, Color::TRANSPARENT),
+    ];
+
+    let draw = |interpolation: GradientInterpolation| {
+        let mut paint = Paint {
+            shader: LinearGradient::new(
+                Point::from_xy(0.0, 0.0),
+                Point::from_xy(100.0, 0.0),
+                stops.clone(),
+                SpreadMode::Pad,
+                Transform::identity(),
+            ).unwrap(),
+            ..Paint::default()
+        };
+        paint.shader.set_gradient_interpolation(interpolation);
+
+        let mut pixmap = Pixmap::new(100, 1).unwrap();
+        pixmap.fill_rect(Rect::from_xywh(0.0, 0.0, 100.0, 1.0).unwrap(), &paint, Transform::identity(), None);
+        pixmap.pixel(50, 0).unwrap()
+    };
+
+    // Unpremultiplied interpolation darkens colors next to the transparent stop.
+    let c = draw(GradientInterpolation::default());
+    assert!(c.red() < c.alpha() - 50);
+
+    for &color_space in &[
+        InterpolationColorSpace::Destination,
+        InterpolationColorSpace::LinearSRGB,
+        InterpolationColorSpace::OKLab,
+        InterpolationColorSpace::OKLCH(HueInterpolation::Shorter),
+    ] {
+        let c = draw(GradientInterpolation { color_space, premultiplied: true });
+        assert!((c.alpha() as i32 - 126).abs() <= 1, "{:?}: {:?}", color_space, c);
+        assert!((c.red() as i32 - c.alpha() as i32).abs() <= 1, "{:?}: {:?}", color_space, c);
+        assert!(c.green() <= 1 && c.blue() <= 1, "{:?}: {:?}", color_space, c);
+    }
+}
+
+#[test]
+fn interpolation_with_color_space() {
+    // sRGB interpolation is independent from the paint's color space.
+    let draw = |colorspace: ColorSpace| {
+        let mut paint = Paint {
+            shader: LinearGradient::new(
+                Point::from_xy(0.0, 0.0),
+                Point::from_xy(100.0, 0.0),
+                vec![GradientStop::new(0.0, red()), GradientStop::new(1.0, blue())],
+                SpreadMode::Pad,
+                Transform::identity(),
+            ).unwrap(),
+            colorspace,
+            ..Paint::default()
+        };
+        paint.shader.set_gradient_interpolation(GradientInterpolation {
+            color_space: InterpolationColorSpace::OKLab,
+            premultiplied: false,
+        });
+
+        let mut pixmap = Pixmap::new(100, 1).unwrap();
+        pixmap.fill_rect(Rect::from_xywh(0.0, 0.0, 100.0, 1.0).unwrap(), &paint, Transform::identity(), None);
+        pixmap
+    };
+
+    let expected = draw(ColorSpace::Linear);
+    for &cs in &[ColorSpace::Gamma2, ColorSpace::SimpleSRGB, ColorSpace::FullSRGBGamma] {
+        let pixmap = draw(cs);
+        for x in 0..100 {
+            let (a, b) = (pixmap.pixel(x, 0).unwrap(), expected.pixel(x, 0).unwrap());
+            assert!((a.red() as i32 - b.red() as i32).abs() <= 2, "{:?} {}: {:?} != {:?}", cs, x, a, b);
+            assert!((a.green() as i32 - b.green() as i32).abs() <= 2, "{:?} {}: {:?} != {:?}", cs, x, a, b);
+            assert!((a.blue() as i32 - b.blue() as i32).abs() <= 2, "{:?} {}: {:?} != {:?}", cs, x, a, b);
+        }
+    }
+}
+
+#[test]
+fn interpolation_oklch_padded_stops() {
+    let mut paint = Paint {
+        shader: LinearGradient::new(
+            Point::from_xy(0.0, 0.0),
+            Point::from_xy(100.0, 0.0),
+            vec![GradientStop::new(0.3, red()), GradientStop::new(0.7, blue())],
+            SpreadMode::Pad,
+            Transform::identity(),
+        ).unwrap(),
+        ..Paint::default()
+    };
+    paint.shader.set_gradient_interpolation(GradientInterpolation {
+        color_space: InterpolationColorSpace::OKLCH(HueInterpolation::Longer),
+        premultiplied: false,
+    });
+
+    let mut pixmap = Pixmap::new(100, 1).unwrap();
+    pixmap.fill_rect(Rect::from_xywh(0.0, 0.0, 100.0, 1.0).unwrap(), &paint, Transform::identity(), None);
+
+    // Stops inserted at 0 and 1 must not make a full hue turn.
+    for x in (0..30).chain(70..100) {
+        let c = pixmap.pixel(x, 0).unwrap();
+        let expected = if x < 30 { (255, 0, 0) } else { (0, 0, 255) };
+        assert!(
+            (c.red() as i32 - expected.0).abs() <= 2
+                && c.green() <= 2
+                && (c.blue() as i32 - expected.2).abs() <= 2,
+            "{}: {:?}", x, c,
+        );
+    }
+}
@@ -1,12 +1,19 @@
 #[rustfmt::skip] mod mask;
+#[rustfmt::skip] mod canvas;
+#[rustfmt::skip] mod coverage;
+#[rustfmt::skip] mod custom;
 #[rustfmt::skip] mod dash;
 #[rustfmt::skip] mod fill;
 #[rustfmt::skip] mod gamma;
 #[rustfmt::skip] mod gradients;
 #[rustfmt::skip] mod hairline;
 #[rustfmt::skip] mod path;
+#[rustfmt::skip] mod parallel;
+#[rustfmt::skip] mod pdf;
 #[rustfmt::skip] mod pattern;
+#[rustfmt::skip] mod picture;
 #[rustfmt::skip] mod pixmap;
 #[rustfmt::skip] mod png;
 #[rustfmt::skip] mod skia_dash;
 #[rustfmt::skip] mod stroke;
+#[rustfmt::skip] mod svg;
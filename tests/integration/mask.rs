@@ -261,3 +261,32 @@ fn mask_from_luma() {
     let expected = Mask::load_png("tests/images/mask/mask-from-luma.png").unwrap();
     assert_eq!(mask, expected);
 }
+
+#[test]
+fn fill_path_with_quality() {
+    let path = PathBuilder::from_circle(25.3, 24.7, 19.6).unwrap();
+
+    let mut mask = Mask::new(50, 50).unwrap();
+    mask.fill_path(&path, FillRule::Winding, true, Transform::identity());
+
+    let mut normal = Mask::new(50, 50).unwrap();
+    let quality = AntiAliasQuality::Normal;
+    normal.fill_path_with_quality(&path, FillRule::Winding, quality, Transform::identity());
+    assert_eq!(mask, normal);
+
+    // The exact area is 1206.9 pixels.
+    let area = |mask: &Mask| mask.data().iter().map(|a| *a as f32 / 255.0).sum::<f32>();
+    let mut errors = Vec::new();
+    for quality in &[
+        AntiAliasQuality::Low,
+        AntiAliasQuality::Normal,
+        AntiAliasQuality::High,
+    ] {
+        let mut mask = Mask::new(50, 50).unwrap();
+        mask.fill_path_with_quality(&path, FillRule::Winding, *quality, Transform::identity());
+        errors.push((area(&mask) - 1206.9).abs());
+    }
+
+    assert!(errors[0] > errors[1]);
+    assert!(errors[1] > errors[2]);
+}
@@ -0,0 +1,303 @@
+use tiny_skia::*;
+
+// Renders jobs on the calling thread in the reverse order.
+struct ReverseExecutor;
+
+impl TileExecutor for ReverseExecutor {
+    fn execute(&self, jobs: Vec<TileJob>) {
+        for job in jobs.into_iter().rev() {
+            job.render();
+        }
+    }
+}
+
+fn star() -> Path {
+    let mut pb = PathBuilder::new();
+    pb.move_to(150.0, 5.0);
+    pb.line_to(240.0, 295.0);
+    pb.line_to(5.0, 110.0);
+    pb.line_to(295.0, 110.0);
+    pb.line_to(60.0, 295.0);
+    pb.close();
+    pb.push_circle(150.0, 150.0, 40.0);
+    pb.finish().unwrap()
+}
+
+fn gradient_paint<'a>() -> Paint<'a> {
+    Paint {
+        shader: LinearGradient::new(
+            Point::from_xy(0.0, 0.0),
+            Point::from_xy(300.0, 300.0),
+            vec![
+                GradientStop::new(0.0, Color::from_rgba8(50, 127, 150, 200)),
+                GradientStop::new(1.0, Color::from_rgba8(220, 140, 75, 180)),
+            ],
+            SpreadMode::Pad,
+            Transform::identity(),
+        )
+        .unwrap(),
+        ..Paint::default()
+    }
+}
+
+#[test]
+fn fill_path() {
+    let path = star();
+    let executor = ThreadExecutor::new(4);
+
+    for anti_alias in &[true, false] {
+        for fill_rule in &[FillRule::Winding, FillRule::EvenOdd] {
+            let paint = Paint {
+                anti_alias: *anti_alias,
+                ..gradient_paint()
+            };
+            let ts = Transform::from_rotate_at(15.0, 150.0, 150.0);
+
+            let mut expected = Pixmap::new(300, 300).unwrap();
+            expected.fill_path(&path, &paint, *fill_rule, ts, None);
+
+            let mut pixmap = Pixmap::new(300, 300).unwrap();
+            pixmap.fill_path_parallel(&path, &paint, *fill_rule, ts, None, &executor);
+            assert_eq!(pixmap, expected);
+        }
+    }
+}
+
+// Edges that span several bands, curves, and edges that are shared or cross each other.
+fn complex_path() -> Path {
+    let mut pb = PathBuilder::new();
+    for i in 0..5 {
+        for j in 0..6 {
+            let rect = Rect::from_xywh(20.0 + i as f32 * 30.3, 10.0 + j as f32 * 45.7, 30.3, 45.7);
+            pb.push_rect(rect.unwrap());
+        }
+    }
+
+    // Rows with that many crossings are too complex for the analytic rasterizer.
+    let n = 501;
+    for i in 0..n {
+        let a = ((i * 250) % n) as f32 / n as f32 * std::f32::consts::TAU;
+        let (x, y) = (190.0 + 100.0 * a.cos(), 160.0 + 130.0 * a.sin());
+        if i == 0 {
+            pb.move_to(x, y);
+        } else {
+            pb.line_to(x, y);
+        }
+    }
+    pb.close();
+
+    pb.push_oval(Rect::from_xywh(40.0, 30.0, 220.0, 250.0).unwrap());
+    pb.move_to(10.0, 290.0);
+    pb.cubic_to(400.0, 200.0, -100.0, 100.0, 290.0, 5.0);
+    pb.quad_to(150.0, 300.0, 10.0, 290.0);
+    pb.finish().unwrap()
+}
+
+#[test]
+fn fill_complex_path() {
+    let path = complex_path();
+
+    let rasterizers = [
+        (false, Rasterizer::Supersampling, AntiAliasQuality::Normal),
+        (true, Rasterizer::Supersampling, AntiAliasQuality::Low),
+        (true, Rasterizer::Supersampling, AntiAliasQuality::Normal),
+        (true, Rasterizer::Supersampling, AntiAliasQuality::High),
+        (true, Rasterizer::Analytic, AntiAliasQuality::Normal),
+    ];
+    for (anti_alias, rasterizer, anti_alias_quality) in rasterizers {
+        for fill_rule in [FillRule::Winding, FillRule::EvenOdd] {
+            let paint = Paint {
+                anti_alias,
+                rasterizer,
+                anti_alias_quality,
+                ..gradient_paint()
+            };
+
+            for ts in [
+                Transform::identity(),
+                Transform::from_row(0.9, 0.3, -0.2, 1.1, 13.7, -20.3),
+            ] {
+                let mut expected = Pixmap::new(300, 300).unwrap();
+                expected.fill_path(&path, &paint, fill_rule, ts, None);
+
+                let mut pixmap = Pixmap::new(300, 300).unwrap();
+                pixmap.fill_path_parallel(&path, &paint, fill_rule, ts, None, &ReverseExecutor);
+                assert_eq!(pixmap, expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn fill_coincident_edges() {
+    // Vertical edges at x=42.5 overlap, but start at different rows,
+    // so their order depends on the rows above.
+    let mut pb = PathBuilder::new();
+    pb.move_to(25.5, 115.5);
+    pb.quad_to(38.25, 44.0, 34.0, 154.0);
+    pb.cubic_to(63.75, 203.5, 46.75, 214.5, 12.75, 16.5);
+    pb.close();
+    pb.move_to(0.0, 93.5);
+    pb.cubic_to(42.5, 60.5, 59.5, 165.0, 38.25, 11.0);
+    pb.cubic_to(17.0, 187.0, 63.75, 209.0, 51.0, 132.0);
+    pb.close();
+    pb.move_to(42.5, 181.5);
+    pb.line_to(42.5, 49.5);
+    pb.line_to(17.0, 159.5);
+    pb.line_to(42.5, 44.0);
+    pb.line_to(42.5, 170.5);
+    pb.line_to(17.0, 66.0);
+    pb.close();
+    let path = pb.finish().unwrap();
+
+    for anti_alias in [true, false] {
+        let paint = Paint {
+            anti_alias,
+            ..Paint::default()
+        };
+
+        let mut expected = Pixmap::new(70, 220).unwrap();
+        expected.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+
+        let mut pixmap = Pixmap::new(70, 220).unwrap();
+        let ts = Transform::identity();
+        pixmap.fill_path_parallel(&path, &paint, FillRule::Winding, ts, None, &ReverseExecutor);
+        assert_eq!(pixmap, expected);
+    }
+}
+
+#[test]
+fn fill_path_in_strips() {
+    // High quality paths taller than 2047 pixels are rendered in several strips.
+    let path = PathBuilder::from_circle(60.0, 1100.0, 1090.0).unwrap();
+    let paint = Paint {
+        anti_alias_quality: AntiAliasQuality::High,
+        ..gradient_paint()
+    };
+
+    let mut expected = Pixmap::new(100, 2200).unwrap();
+    expected.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+
+    let mut pixmap = Pixmap::new(100, 2200).unwrap();
+    let ts = Transform::identity();
+    pixmap.fill_path_parallel(&path, &paint, FillRule::Winding, ts, None, &ReverseExecutor);
+    assert_eq!(pixmap, expected);
+}
+
+#[test]
+fn fill_path_with_mask() {
+    let path = star();
+
+    let mut mask = Mask::new(300, 300).unwrap();
+    let clip_path = PathBuilder::from_circle(150.0, 150.0, 120.0).unwrap();
+    mask.fill_path(&clip_path, FillRule::Winding, true, Transform::identity());
+
+    let paint = gradient_paint();
+
+    let mut expected = Pixmap::new(300, 300).unwrap();
+    expected.fill(Color::WHITE);
+    expected.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), Some(&mask));
+
+    let mut pixmap = Pixmap::new(300, 300).unwrap();
+    pixmap.fill(Color::WHITE);
+    let ts = Transform::identity();
+    pixmap.fill_path_parallel(&path, &paint, FillRule::Winding, ts, Some(&mask), &ReverseExecutor);
+    assert_eq!(pixmap, expected);
+}
+
+#[test]
+fn fill_path_tiled() {
+    // Pixmaps larger than 8191 pixels are rendered in tiles.
+    let path = PathBuilder::from_circle(8191.3, 100.0, 90.0).unwrap();
+    let paint = Paint {
+        anti_alias_quality: AntiAliasQuality::Low,
+        ..gradient_paint()
+    };
+
+    let mut expected = Pixmap::new(8300, 200).unwrap();
+    expected.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+
+    let mut pixmap = Pixmap::new(8300, 200).unwrap();
+    let ts = Transform::identity();
+    let executor = ThreadExecutor::new(3);
+    pixmap.fill_path_parallel(&path, &paint, FillRule::Winding, ts, None, &executor);
+    assert_eq!(pixmap, expected);
+}
+
+#[test]
+fn stroke_path() {
+    let path = star();
+    let paint = gradient_paint();
+    let executor = ThreadExecutor::default();
+
+    // Thick strokes are filled, while thin ones are rendered as hairlines.
+    for width in &[0.0, 0.5, 1.0, 7.0] {
+        let stroke = Stroke {
+            width: *width,
+            line_cap: LineCap::Round,
+            ..Stroke::default()
+        };
+        let ts = Transform::from_scale(1.1, 0.9);
+
+        let mut expected = Pixmap::new(300, 300).unwrap();
+        expected.stroke_path(&path, &paint, &stroke, ts, None);
+
+        let mut pixmap = Pixmap::new(300, 300).unwrap();
+        pixmap.stroke_path_parallel(&path, &paint, &stroke, ts, None, &executor);
+        assert_eq!(pixmap, expected);
+    }
+}
+
+#[test]
+fn draw_pixmap() {
+    let mut image = Pixmap::new(100, 100).unwrap();
+    image.fill_path(&star(), &gradient_paint(), FillRule::Winding, Transform::from_scale(0.33, 0.33), None);
+
+    let executor = ThreadExecutor::new(4);
+    for quality in &[FilterQuality::Nearest, FilterQuality::Bicubic] {
+        let paint = PixmapPaint {
+            quality: *quality,
+            ..PixmapPaint::default()
+        };
+        let ts = Transform::from_row(2.3, 0.4, -0.3, 2.1, 20.0, 10.0);
+
+        let mut expected = Pixmap::new(300, 300).unwrap();
+        expected.fill(Color::from_rgba8(0, 80, 0, 255));
+        expected.draw_pixmap(10, 5, image.as_ref(), &paint, ts, None);
+
+        let mut pixmap = Pixmap::new(300, 300).unwrap();
+        pixmap.fill(Color::from_rgba8(0, 80, 0, 255));
+        pixmap.draw_pixmap_parallel(10, 5, image.as_ref(), &paint, ts, None, &executor);
+        assert_eq!(pixmap, expected);
+    }
+
+    // Untransformed pixmaps are drawn as rectangles.
+    let mut expected = Pixmap::new(300, 300).unwrap();
+    expected.draw_pixmap(150, 150, image.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+
+    let mut pixmap = Pixmap::new(300, 300).unwrap();
+    let ts = Transform::identity();
+    pixmap.draw_pixmap_parallel(150, 150, image.as_ref(), &PixmapPaint::default(), ts, None, &executor);
+    assert_eq!(pixmap, expected);
+}
+
+#[test]
+fn only_covered_bands_are_rendered() {
+    struct CountingExecutor(std::cell::Cell<usize>);
+
+    impl TileExecutor for CountingExecutor {
+        fn execute(&self, jobs: Vec<TileJob>) {
+            self.0.set(self.0.get() + jobs.len());
+            for job in jobs {
+                job.render();
+            }
+        }
+    }
+
+    let path = PathBuilder::from_rect(Rect::from_xywh(10.0, 70.0, 100.0, 100.0).unwrap());
+    let executor = CountingExecutor(std::cell::Cell::new(0));
+    let mut pixmap = Pixmap::new(300, 300).unwrap();
+    let ts = Transform::identity();
+    pixmap.fill_path_parallel(&path, &Paint::default(), FillRule::Winding, ts, None, &executor);
+    assert_eq!(executor.0.get(), 2);
+}
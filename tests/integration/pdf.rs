@@ -0,0 +1,305 @@
+use tiny_skia::*;
+
+fn record(f: impl FnOnce(&mut PictureRecorder)) -> Picture {
+    let mut recorder = PictureRecorder::new();
+    f(&mut recorder);
+    recorder.finish()
+}
+
+fn triangle() -> Path {
+    let mut pb = PathBuilder::new();
+    pb.move_to(10.0, 10.0);
+    pb.line_to(50.0, 10.0);
+    pb.quad_to(50.0, 40.0, 20.0, 40.0);
+    pb.close();
+    pb.finish().unwrap()
+}
+
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).position(|w| w == needle)
+}
+
+fn parse_number(data: &[u8]) -> usize {
+    let len = data.iter().take_while(|c| c.is_ascii_digit()).count();
+    std::str::from_utf8(&data[..len]).unwrap().parse().unwrap()
+}
+
+// Parses the cross-reference table and returns all objects.
+//
+// Checks that all offsets point to the corresponding objects
+// and that stream lengths are correct.
+fn parse(data: &[u8]) -> Vec<Vec<u8>> {
+    assert!(data.starts_with(b"%PDF-1.4\n"));
+    assert!(data.ends_with(b"%%EOF\n"));
+
+    let start = find(data, b"startxref\n").unwrap() + 10;
+    let xref = parse_number(&data[start..]);
+    assert!(data[xref..].starts_with(b"xref\n0 "));
+    let count = parse_number(&data[xref + 7..]);
+    let trailer = find(&data[xref..], b"trailer\n").unwrap() + xref;
+    let trailer = std::str::from_utf8(&data[trailer..]).unwrap();
+    assert!(trailer.contains(&format!("/Size {} /Root 1 0 R", count)));
+
+    let entries = find(&data[xref..], b" f \n").unwrap() + xref + 4;
+    let mut objects = Vec::new();
+    for id in 1..count {
+        let entry = &data[entries + (id - 1) * 20..entries + id * 20];
+        assert!(entry.ends_with(b" 00000 n \n"));
+        let offset = parse_number(entry);
+
+        let header = format!("{} 0 obj\n", id);
+        assert!(data[offset..].starts_with(header.as_bytes()));
+        let body = &data[offset + header.len()..];
+        let mut end = find(body, b"\nendobj\n").unwrap();
+
+        if let Some(stream) = find(body, b">>\nstream\n") {
+            if stream < end {
+                let length = find(body, b"/Length ").unwrap() + 8;
+                let length = parse_number(&body[length..]);
+                let stream = stream + 10;
+                assert!(body[stream + length..].starts_with(b"\nendstream\nendobj\n"));
+                end = stream + length + 10;
+            }
+        }
+
+        objects.push(body[..end].to_vec());
+    }
+
+    objects
+}
+
+fn object(objects: &[Vec<u8>], id: usize) -> &[u8] {
+    &objects[id - 1]
+}
+
+fn text(object: &[u8]) -> String {
+    String::from_utf8_lossy(object).into_owned()
+}
+
+// Returns the id of a `/key N 0 R` reference.
+fn reference(object: &[u8], key: &str) -> usize {
+    let start = find(object, format!("/{} ", key).as_bytes()).unwrap() + key.len() + 2;
+    parse_number(&object[start..])
+}
+
+fn stream_data(object: &[u8]) -> &[u8] {
+    let start = find(object, b"stream\n").unwrap() + 7;
+    let end = find(object, b"\nendstream").unwrap();
+    &object[start..end]
+}
+
+// Returns the content of the form that contains the page body.
+fn page_body(objects: &[Vec<u8>], page: usize) -> String {
+    let contents = reference(object(objects, page), "Contents");
+    let contents = text(stream_data(object(objects, contents)));
+    let name = contents.split('/').nth(1).unwrap().split(' ').next().unwrap();
+
+    let resources = reference(object(objects, page), "Resources");
+    let form = reference(object(objects, resources), name);
+    text(stream_data(object(objects, form)))
+}
+
+#[test]
+fn fill_and_stroke() {
+    let picture = record(|r| {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(255, 0, 0, 255);
+        r.fill_path(&triangle(), &paint, FillRule::EvenOdd, Transform::from_translate(5.0, 0.0), None);
+
+        paint.set_color(Color::from_rgba(0.0, 0.0, 1.0, 0.5).unwrap());
+        let stroke = Stroke {
+            width: 3.0,
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Bevel,
+            dash: StrokeDash::new(vec![5.0, 2.0], 1.0),
+            ..Stroke::default()
+        };
+        r.stroke_path(&triangle(), &paint, &stroke, Transform::identity(), None);
+    });
+
+    let objects = parse(&picture.to_pdf(100, 80));
+    assert_eq!(text(object(&objects, 1)), "<< /Type /Catalog /Pages 2 0 R >>");
+    assert_eq!(text(object(&objects, 2)), "<< /Type /Pages /Kids [3 0 R] /Count 1 >>");
+
+    let page = text(object(&objects, 3));
+    assert!(page.starts_with("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 100 80]"));
+
+    let contents = reference(object(&objects, 3), "Contents");
+    assert!(text(stream_data(object(&objects, contents))).starts_with("1 0 0 -1 0 80 cm\n"));
+
+    let body = page_body(&objects, 3);
+    assert!(body.contains(
+        "1 0 0 1 5 0 cm\n1 0 0 rg\n10 10 m\n50 10 l\n50 30 40 40 20 40 c\nh\nf*\n"
+    ));
+    assert!(body.contains("0 0 1 RG\n3 w 1 J 2 j 4 M [5 2] 1 d\n10 10 m\n"));
+    assert!(body.contains("h\nS\nQ\n"));
+
+    let resources = text(object(&objects, reference(object(&objects, 3), "Resources")));
+    assert!(resources.contains("/ExtGState << /GS0 << /CA 0.5 >> >>"));
+}
+
+#[test]
+fn multiple_pages() {
+    let mut doc = PdfDocument::new();
+    for i in 1..4 {
+        let picture = record(|r| {
+            let paint = Paint::default();
+            let rect = Rect::from_xywh(0.0, 0.0, i as f32 * 10.0, 10.0).unwrap();
+            r.fill_rect(rect, &paint, Transform::identity(), None);
+        });
+        doc.add_page(&picture, 100 * i, 50);
+    }
+    assert_eq!(doc.page_count(), 3);
+
+    let objects = parse(&doc.finish());
+    let pages = text(object(&objects, 2));
+    assert!(pages.ends_with("] /Count 3 >>"));
+
+    let kids: Vec<usize> = pages["<< /Type /Pages /Kids [".len()..pages.find(']').unwrap()]
+        .split(" 0 R")
+        .filter(|s| !s.is_empty())
+        .map(|s| s.trim().parse().unwrap())
+        .collect();
+    assert_eq!(kids.len(), 3);
+
+    for (i, id) in kids.into_iter().enumerate() {
+        let page = text(object(&objects, id));
+        assert!(page.contains(&format!("/MediaBox [0 0 {} 50]", 100 * (i + 1))));
+        let body = page_body(&objects, id);
+        assert!(body.contains(&format!("0 0 {} 10 re\nf\n", 10 * (i + 1))));
+    }
+}
+
+#[test]
+fn gradients() {
+    let picture = record(|r| {
+        let paint = Paint {
+            shader: LinearGradient::new(
+                Point::from_xy(10.0, 0.0),
+                Point::from_xy(90.0, 0.0),
+                vec![
+                    GradientStop::new(0.0, Color::from_rgba8(255, 0, 0, 255)),
+                    GradientStop::new(0.5, Color::from_rgba8(0, 255, 0, 128)),
+                    GradientStop::new(1.0, Color::from_rgba8(0, 0, 255, 255)),
+                ],
+                SpreadMode::Pad,
+                Transform::identity(),
+            )
+            .unwrap(),
+            ..Paint::default()
+        };
+        r.fill_rect(Rect::from_xywh(0.0, 0.0, 100.0, 50.0).unwrap(), &paint, Transform::identity(), None);
+
+        let paint = Paint {
+            shader: RadialGradient::new(
+                Point::from_xy(50.0, 75.0),
+                Point::from_xy(50.0, 75.0),
+                20.0,
+                vec![
+                    GradientStop::new(0.0, Color::WHITE),
+                    GradientStop::new(1.0, Color::BLACK),
+                ],
+                SpreadMode::Reflect,
+                Transform::identity(),
+            )
+            .unwrap(),
+            ..Paint::default()
+        };
+        r.fill_path(&PathBuilder::from_circle(50.0, 75.0, 25.0).unwrap(), &paint, FillRule::Winding, Transform::identity(), None);
+
+        let paint = Paint {
+            shader: RadialGradient::new_two_point_conical(
+                Point::from_xy(50.0, 75.0),
+                20.0,
+                Point::from_xy(50.0, 75.0),
+                10.0,
+                vec![
+                    GradientStop::new(0.0, Color::WHITE),
+                    GradientStop::new(1.0, Color::BLACK),
+                ],
+                SpreadMode::Repeat,
+                Transform::identity(),
+            )
+            .unwrap(),
+            ..Paint::default()
+        };
+        r.fill_rect(Rect::from_xywh(30.0, 55.0, 40.0, 40.0).unwrap(), &paint, Transform::identity(), None);
+    });
+
+    let objects = parse(&picture.to_pdf(100, 100));
+    let all: Vec<String> = objects.iter().map(|o| text(o)).collect();
+
+    let axial: Vec<&String> = all.iter().filter(|o| o.contains("/ShadingType 2")).collect();
+    assert_eq!(axial.len(), 2);
+    assert!(axial[0].starts_with(
+        "<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [0 0 1 0] /Domain [0 1] \
+         /Function << /FunctionType 3 /Domain [0 1] /Functions [<< /FunctionType 2 \
+         /Domain [0 1] /C0 [1 0 0] /C1 [0 1 0] /N 1 >>"
+    ));
+    assert!(axial[0].contains("/Bounds [0.5] /Encode [0 1 0 1] >> /Extend [true true] >>"));
+    // Alpha is stored separately.
+    assert!(axial[1].contains("/ColorSpace /DeviceGray"));
+    assert!(axial[1].contains("/C0 [1] /C1 [0.5019608]"));
+    assert!(all.iter().any(|o| o.contains("/S /Luminosity")));
+
+    let radial = all.iter().find(|o| o.contains("/ShadingType 3")).unwrap();
+    assert!(radial.contains("/Coords [0 0 0 0 0 16] /Domain [0 16]"));
+    assert!(radial.contains("/Encode [0 1 1 0 0 1 1 0"));
+    let conical = all.iter().filter(|o| o.contains("/ShadingType 3")).nth(1).unwrap();
+    // The radius shrinks to zero at t = 2.
+    assert!(conical.contains("/Coords [0 0 2 0 0 0] /Domain [0 2]"));
+
+    let body = page_body(&objects, 3);
+    assert!(body.contains(
+        "0 0 100 50 re\nW n\n80 0 0 80 10 0 cm\n/Sh0 sh\n"
+    ));
+    assert!(body.contains("h\nW n\n20 0 0 20 50 75 cm\n/Sh1 sh\n"));
+}
+
+#[test]
+fn images_masks_and_blend_modes() {
+    let mut image = Pixmap::new(2, 2).unwrap();
+    image.fill(Color::from_rgba8(0, 255, 0, 128));
+
+    let mut mask = Mask::new(100, 100).unwrap();
+    mask.fill_path(&PathBuilder::from_circle(50.0, 50.0, 30.0).unwrap(), FillRule::Winding, true, Transform::identity());
+
+    let picture = record(|r| {
+        let paint = PixmapPaint {
+            opacity: 0.5,
+            blend_mode: BlendMode::Multiply,
+            ..PixmapPaint::default()
+        };
+        r.draw_pixmap(10, 20, image.as_ref(), &paint, Transform::identity(), None);
+
+        let paint = Paint::default();
+        r.fill_rect(Rect::from_xywh(0.0, 0.0, 20.0, 30.0).unwrap(), &paint, Transform::identity(), Some(&mask));
+        r.apply_mask(&mask);
+    });
+
+    let objects = parse(&picture.to_pdf(100, 100));
+    let all: Vec<String> = objects.iter().map(|o| text(o)).collect();
+
+    let image_id = all.iter().position(|o| o.contains("/ColorSpace /DeviceRGB /BitsPerComponent 8 /SMask")).unwrap() + 1;
+    let image_object = object(&objects, image_id);
+    assert!(text(image_object).contains("/Width 2 /Height 2"));
+    assert_eq!(stream_data(image_object), &[0, 255, 0].repeat(4)[..]);
+    let alpha = object(&objects, reference(image_object, "SMask"));
+    assert_eq!(stream_data(alpha), &[128; 4]);
+
+    let mask_id = all.iter().position(|o| o.contains("/Width 100 /Height 100 /ColorSpace /DeviceGray")).unwrap() + 1;
+    assert_eq!(stream_data(object(&objects, mask_id)), mask.data());
+
+    let resources = text(object(&objects, reference(object(&objects, 3), "Resources")));
+    assert!(resources.contains("/GS0 << /ca 0.5 >>"));
+    assert!(resources.contains("/GS1 << /BM /Multiply >>"));
+    // The mask group is written once and the graphics state is shared.
+    assert_eq!(resources.matches("/SMask << /Type /Mask /S /Luminosity /G ").count(), 1);
+    let group = reference(resources.as_bytes(), "G");
+    assert!(text(object(&objects, group)).contains("/Group << /S /Transparency /CS /DeviceGray >>"));
+
+    // The whole body is masked.
+    let body = page_body(&objects, 3);
+    assert!(body.starts_with("q\n/GS2 gs\n/Fm"));
+    assert!(body.ends_with(" Do\nQ\n"));
+}
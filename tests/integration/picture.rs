@@ -0,0 +1,387 @@
+use tiny_skia::*;
+
+fn draw_scene(
+    pixmap: &mut PixmapMut,
+    recorder: &mut PictureRecorder,
+    ts: Transform,
+    mask: Option<&Mask>,
+) {
+    let mut paint = Paint {
+        shader: LinearGradient::new(
+            Point::from_xy(10.0, 10.0),
+            Point::from_xy(60.0, 60.0),
+            vec![
+                GradientStop::new(0.0, Color::from_rgba8(50, 127, 150, 200)),
+                GradientStop::new(1.0, Color::from_rgba8(220, 140, 75, 180)),
+            ],
+            SpreadMode::Pad,
+            Transform::identity(),
+        ).unwrap(),
+        ..Paint::default()
+    };
+    let path = PathBuilder::from_circle(35.0, 35.0, 25.0).unwrap();
+    pixmap.fill_path(&path, &paint, FillRule::Winding, ts, mask);
+    recorder.fill_path(&path, &paint, FillRule::Winding, ts, mask);
+
+    paint.set_color_rgba8(0, 0, 0, 255);
+    let stroke = Stroke { width: 4.0, ..Stroke::default() };
+    pixmap.stroke_path(&path, &paint, &stroke, ts, mask);
+    recorder.stroke_path(&path, &paint, &stroke, ts, mask);
+
+    let mut image = Pixmap::new(10, 10).unwrap();
+    image.fill(Color::from_rgba8(0, 200, 0, 255));
+    let ts2 = ts.pre_rotate(10.0);
+    pixmap.draw_pixmap(50, 20, image.as_ref(), &PixmapPaint::default(), ts2, mask);
+    recorder.draw_pixmap(50, 20, image.as_ref(), &PixmapPaint::default(), ts2, mask);
+
+    paint.shader = Pattern::new(image.as_ref(), SpreadMode::Repeat, FilterQuality::Bicubic, 0.5, Transform::identity());
+    let rect = Rect::from_xywh(5.0, 60.0, 30.0, 15.0).unwrap();
+    pixmap.fill_rect(rect, &paint, ts, mask);
+    recorder.fill_rect(rect, &paint, ts, mask);
+}
+
+#[test]
+fn record_and_play() {
+    let mut mask = Mask::new(100, 100).unwrap();
+    let clip = PathBuilder::from_rect(Rect::from_xywh(20.0, 0.0, 60.0, 100.0).unwrap());
+    mask.fill_path(&clip, FillRule::Winding, true, Transform::identity());
+
+    let mut pixmap1 = Pixmap::new(100, 100).unwrap();
+    let mut recorder = PictureRecorder::new();
+    draw_scene(&mut pixmap1.as_mut(), &mut recorder, Transform::identity(), None);
+    draw_scene(&mut pixmap1.as_mut(), &mut recorder, Transform::from_translate(30.0, 20.0), Some(&mask));
+
+    let mut erase = Mask::new(100, 100).unwrap();
+    erase.fill_path(&PathBuilder::from_circle(50.0, 50.0, 30.0).unwrap(), FillRule::Winding, true, Transform::identity());
+    pixmap1.apply_mask(&erase);
+    recorder.apply_mask(&erase);
+
+    let picture = recorder.finish();
+    assert_eq!(picture.len(), 9);
+
+    let mut pixmap2 = Pixmap::new(100, 100).unwrap();
+    pixmap2.draw_picture(&picture, Transform::identity(), None);
+    assert_eq!(pixmap1, pixmap2);
+
+    // Can be played more than once.
+    let mut pixmap3 = Pixmap::new(100, 100).unwrap();
+    pixmap3.draw_picture(&picture, Transform::identity(), None);
+    assert_eq!(pixmap1, pixmap3);
+}
+
+#[test]
+fn play_with_transform_and_clip() {
+    let ts = Transform::from_row(2.0, 0.3, -0.2, 1.5, 10.0, 5.0);
+    let mut clip = Mask::new(200, 200).unwrap();
+    let clip_path = PathBuilder::from_circle(100.0, 80.0, 60.0).unwrap();
+    clip.fill_path(&clip_path, FillRule::Winding, true, Transform::identity());
+
+    let mut scratch = Pixmap::new(100, 100).unwrap();
+    let mut recorder = PictureRecorder::new();
+    draw_scene(&mut scratch.as_mut(), &mut recorder, Transform::identity(), None);
+    let picture = recorder.finish();
+
+    let mut pixmap1 = Pixmap::new(200, 200).unwrap();
+    let mut ignored = PictureRecorder::new();
+    draw_scene(&mut pixmap1.as_mut(), &mut ignored, ts, Some(&clip));
+
+    let mut pixmap2 = Pixmap::new(200, 200).unwrap();
+    pixmap2.draw_picture(&picture, ts, Some(&clip));
+
+    assert_eq!(pixmap1, pixmap2);
+}
+
+#[test]
+fn cull_bounds() {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+
+    let mut recorder = PictureRecorder::new();
+    assert!(recorder.clone().finish().is_empty());
+    assert_eq!(recorder.clone().finish().bounds(), None);
+
+    recorder.fill_rect(Rect::from_xywh(10.0, 20.0, 30.0, 40.0).unwrap(), &paint, Transform::identity(), None);
+    let stroke = Stroke { width: 4.0, line_join: LineJoin::Round, ..Stroke::default() };
+    let path = PathBuilder::from_rect(Rect::from_xywh(100.0, 100.0, 10.0, 10.0).unwrap());
+    recorder.stroke_path(&path, &paint, &stroke, Transform::from_scale(2.0, 2.0), None);
+
+    // Masked out commands are not recorded.
+    let mask = Mask::new(50, 50).unwrap();
+    recorder.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), Some(&mask));
+
+    // Paths that are too big after the transform are not recorded.
+    let mut pb = PathBuilder::new();
+    pb.move_to(0.0, 0.0);
+    pb.line_to(1e38, 10.0);
+    pb.line_to(0.0, 10.0);
+    let huge = pb.finish().unwrap();
+    recorder.fill_path(&huge, &paint, FillRule::Winding, Transform::from_scale(3.0, 1.0), None);
+    recorder.stroke_path(&huge, &paint, &stroke, Transform::from_scale(3.0, 1.0), None);
+
+    let picture = recorder.finish();
+    assert_eq!(picture.len(), 2);
+    // The stroke is outset by the radius, the approximation error and the anti-aliasing pixel.
+    assert_eq!(picture.bounds(), Rect::from_ltrb(9.0, 19.0, 225.25, 225.25));
+}
+
+#[test]
+fn scaled_down_huge_path() {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+
+    let mut pb = PathBuilder::new();
+    pb.move_to(0.0, 10.0);
+    pb.line_to(1e38, 20.0);
+    pb.line_to(0.0, 80.0);
+    pb.close();
+    let huge = pb.finish().unwrap();
+    let ts = Transform::from_scale(8e-37, 1.0);
+    let stroke = Stroke { width: 4.0, ..Stroke::default() };
+
+    // The transformed path is in range, so it's drawn like by the painter.
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.fill_path(&huge, &paint, FillRule::Winding, ts, None);
+    pixmap.stroke_path(&huge, &paint, &stroke, ts, None);
+
+    let mut recorder = PictureRecorder::new();
+    recorder.fill_path(&huge, &paint, FillRule::Winding, ts, None);
+    recorder.stroke_path(&huge, &paint, &stroke, ts, None);
+    let picture = recorder.finish();
+    assert_eq!(picture.len(), 2);
+
+    let mut played = Pixmap::new(100, 100).unwrap();
+    played.draw_picture(&picture, Transform::identity(), None);
+    assert!(pixmap.pixels().iter().any(|p| p.alpha() != 0));
+    assert_eq!(pixmap, played);
+
+    // And survives serialization.
+    let decoded = Picture::from_bytes(&picture.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded, picture);
+}
+
+#[test]
+fn culling() {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    let rect1 = Rect::from_xywh(10.0, 10.0, 30.0, 30.0).unwrap();
+    let rect2 = Rect::from_xywh(1000.0, 10.0, 30.0, 30.0).unwrap();
+
+    let mut recorder = PictureRecorder::new();
+    recorder.fill_rect(rect1, &paint, Transform::identity(), None);
+    recorder.fill_rect(rect2, &paint, Transform::identity(), None);
+    let picture = recorder.finish();
+
+    let mut pixmap1 = Pixmap::new(100, 100).unwrap();
+    pixmap1.draw_picture(&picture, Transform::from_translate(-980.0, 0.0), None);
+    let mut pixmap2 = Pixmap::new(100, 100).unwrap();
+    pixmap2.fill_rect(rect2, &paint, Transform::from_translate(-980.0, 0.0), None);
+    assert_eq!(pixmap1, pixmap2);
+
+    // Everything is outside.
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.draw_picture(&picture, Transform::from_translate(0.0, 500.0), None);
+    assert!(pixmap.pixels().iter().all(|p| p.alpha() == 0));
+}
+
+#[test]
+fn apply_mask_with_transform() {
+    let mut recorder = PictureRecorder::new();
+    // An empty mask erases everything it covers.
+    recorder.apply_mask(&Mask::new(50, 50).unwrap());
+    let picture = recorder.finish();
+
+    let mut pixmap = Pixmap::new(100, 100).unwrap();
+    pixmap.fill(Color::BLACK);
+    pixmap.draw_picture(&picture, Transform::from_translate(30.0, 40.0), None);
+
+    assert_eq!(pixmap.pixel(29, 60).unwrap().alpha(), 255);
+    assert_eq!(pixmap.pixel(30, 40).unwrap().alpha(), 0);
+    assert_eq!(pixmap.pixel(79, 89).unwrap().alpha(), 0);
+    assert_eq!(pixmap.pixel(80, 89).unwrap().alpha(), 255);
+    assert_eq!(pixmap.pixel(50, 90).unwrap().alpha(), 255);
+}
+
+#[test]
+fn canvas_playback() {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    let path = PathBuilder::from_circle(20.0, 20.0, 15.0).unwrap();
+
+    let mut recorder = PictureRecorder::new();
+    recorder.fill_path(&path, &paint, FillRule::Winding, Transform::identity(), None);
+    let picture = recorder.finish();
+
+    let mut pixmap1 = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(pixmap1.as_mut());
+    canvas.translate(30.0, 30.0);
+    canvas.clip_rect(Rect::from_xywh(0.0, 0.0, 20.0, 40.0).unwrap(), false);
+    canvas.draw_picture(&picture);
+
+    let mut pixmap2 = Pixmap::new(100, 100).unwrap();
+    let mut canvas = Canvas::new(pixmap2.as_mut());
+    canvas.translate(30.0, 30.0);
+    canvas.clip_rect(Rect::from_xywh(0.0, 0.0, 20.0, 40.0).unwrap(), false);
+    canvas.fill_path(&path, &paint, FillRule::Winding);
+
+    assert_eq!(pixmap1, pixmap2);
+}
+
+fn record_all() -> Picture {
+    let mut recorder = PictureRecorder::new();
+    let mut scratch = Pixmap::new(100, 100).unwrap();
+    draw_scene(&mut scratch.as_mut(), &mut recorder, Transform::identity(), None);
+
+    let mut mask = Mask::new(100, 100).unwrap();
+    mask.fill_path(&PathBuilder::from_circle(40.0, 40.0, 30.0).unwrap(), FillRule::Winding, true, Transform::identity());
+
+    let paint = Paint {
+        shader: RadialGradient::new(
+            Point::from_xy(40.0, 40.0),
+            Point::from_xy(50.0, 50.0),
+            30.0,
+            vec![
+                GradientStop::new(0.0, Color::from_rgba8(50, 127, 150, 200)),
+                GradientStop::new(0.4, Color::from_rgba8(0, 0, 0, 255)),
+                GradientStop::new(1.0, Color::from_rgba8(220, 140, 75, 180)),
+            ],
+            SpreadMode::Reflect,
+            Transform::from_rotate(10.0),
+        ).unwrap(),
+        blend_mode: BlendMode::Multiply,
+        colorspace: ColorSpace::SimpleSRGB,
+        ..Paint::default()
+    };
+    let mut pb = PathBuilder::new();
+    pb.move_to(10.0, 10.0);
+    pb.quad_to(50.0, 0.0, 90.0, 10.0);
+    pb.cubic_to(90.0, 50.0, 50.0, 90.0, 10.0, 90.0);
+    pb.close();
+    let path = pb.finish().unwrap();
+    recorder.fill_path(&path, &paint, FillRule::EvenOdd, Transform::from_scale(0.8, 0.9), Some(&mask));
+
+    let mut dash = StrokeDash::new(vec![5.0, 3.0, 1.0, 3.0], 2.0).unwrap();
+    dash.set_fit_to_contour(true);
+    let stroke = Stroke {
+        width: 3.0,
+        line_cap: LineCap::Round,
+        line_join: LineJoin::Bevel,
+        dash: Some(dash),
+        precision: Some(0.1),
+        ..Stroke::default()
+    };
+    recorder.stroke_path(&path, &paint, &stroke, Transform::identity(), Some(&mask));
+
+    let mut paint = Paint {
+        shader: SweepGradient::new(
+            Point::from_xy(50.0, 50.0),
+            30.0,
+            120.0,
+            vec![
+                GradientStop::new(0.0, Color::from_rgba8(50, 127, 150, 200)),
+                GradientStop::new(1.0, Color::from_rgba8(220, 140, 75, 180)),
+            ],
+            SpreadMode::Repeat,
+            Transform::identity(),
+        ).unwrap(),
+        ..Paint::default()
+    };
+    paint.shader.set_gradient_interpolation(GradientInterpolation {
+        color_space: InterpolationColorSpace::OKLCH(HueInterpolation::Decreasing),
+        premultiplied: true,
+    });
+    recorder.fill_rect(Rect::from_xywh(20.0, 60.0, 50.0, 30.0).unwrap(), &paint, Transform::identity(), None);
+
+    let paint = Paint {
+        shader: RadialGradient::new_two_point_conical(
+            Point::from_xy(60.0, 30.0),
+            25.0,
+            Point::from_xy(70.0, 40.0),
+            5.0,
+            vec![
+                GradientStop::new(0.0, Color::from_rgba8(50, 127, 150, 200)),
+                GradientStop::new(1.0, Color::from_rgba8(220, 140, 75, 180)),
+            ],
+            SpreadMode::Pad,
+            Transform::identity(),
+        ).unwrap(),
+        ..Paint::default()
+    };
+    recorder.fill_rect(Rect::from_xywh(30.0, 10.0, 60.0, 50.0).unwrap(), &paint, Transform::identity(), None);
+    recorder.apply_mask(&mask);
+
+    recorder.finish()
+}
+
+#[test]
+fn serialization_round_trip() {
+    let picture = record_all();
+    let data = picture.to_bytes().unwrap();
+    let picture2 = Picture::from_bytes(&data).unwrap();
+    assert_eq!(picture, picture2);
+    assert_eq!(picture2.to_bytes().unwrap(), data);
+
+    let mut pixmap1 = Pixmap::new(100, 100).unwrap();
+    pixmap1.draw_picture(&picture, Transform::identity(), None);
+    let mut pixmap2 = Pixmap::new(100, 100).unwrap();
+    pixmap2.draw_picture(&picture2, Transform::identity(), None);
+    assert_eq!(pixmap1, pixmap2);
+
+    let empty = PictureRecorder::new().finish();
+    assert_eq!(Picture::from_bytes(&empty.to_bytes().unwrap()).unwrap(), empty);
+}
+
+#[test]
+fn serialization_errors() {
+    let data = record_all().to_bytes().unwrap();
+
+    assert_eq!(Picture::from_bytes(&[]), Err(PictureError::UnexpectedEof));
+    assert_eq!(Picture::from_bytes(b"PNG\0\0\0"), Err(PictureError::InvalidSignature));
+
+    let mut future = data.clone();
+    future[4] = 2;
+    assert_eq!(Picture::from_bytes(&future), Err(PictureError::UnsupportedVersion(2)));
+
+    let mut trailing = data.clone();
+    trailing.push(0);
+    assert_eq!(Picture::from_bytes(&trailing), Err(PictureError::InvalidData));
+
+    // A huge image count.
+    let mut huge = data[..6].to_vec();
+    huge.extend_from_slice(&[0xFF; 4]);
+    assert_eq!(Picture::from_bytes(&huge), Err(PictureError::UnexpectedEof));
+
+    for len in 0..data.len() {
+        assert!(Picture::from_bytes(&data[..len]).is_err());
+    }
+}
+
+#[test]
+fn corrupted_data_does_not_panic() {
+    let data = record_all().to_bytes().unwrap();
+
+    // Skip images and masks pixels, which are not validated anyway.
+    let u32_at = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+    assert_eq!(u32_at(6), 1);
+    let masks_start = 6 + 4 + 8 + u32_at(10) * u32_at(14) * 4;
+    assert_eq!(u32_at(masks_start), 1);
+    let records_start = masks_start + 4 + 8 + u32_at(masks_start + 4) * u32_at(masks_start + 8);
+    let positions = (0..masks_start + 12).chain(records_start..data.len());
+
+    // Corrupt each byte with random values and play back everything that was decoded.
+    // The playback is scaled down to keep the test fast.
+    let mut pixmap = Pixmap::new(10, 10).unwrap();
+    let mut state = 0x2545_F491u32;
+    for i in positions {
+        for _ in 0..4 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+
+            let mut corrupted = data.clone();
+            corrupted[i] = state as u8;
+            if let Ok(picture) = Picture::from_bytes(&corrupted) {
+                pixmap.draw_picture(&picture, Transform::from_scale(0.1, 0.1), None);
+            }
+        }
+    }
+}
@@ -132,3 +132,111 @@ fn round_cap_join() {
     let expected = Pixmap::load_png("tests/images/stroke/round-cap-join.png").unwrap();
     assert_eq!(pixmap, expected);
 }
+
+#[test]
+fn stroke_bounds_contain_drawn_pixels() {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint.anti_alias = true;
+
+    let mut pb = PathBuilder::new();
+    pb.move_to(30.0, 40.0);
+    pb.line_to(90.0, 60.0);
+    pb.line_to(40.0, 80.0);
+    pb.cubic_to(60.0, 180.0, 170.0, 20.0, 150.0, 150.0);
+    let path = pb.finish().unwrap();
+
+    let transform = Transform::from_row(1.1, 0.2, -0.3, 0.9, 10.0, 5.0);
+    for width in [0.0, 0.5, 12.0] {
+        let stroke = Stroke {
+            width,
+            line_cap: LineCap::Square,
+            miter_limit: 6.0,
+            ..Stroke::default()
+        };
+
+        let mut pixmap = Pixmap::new(220, 220).unwrap();
+        pixmap.stroke_path(&path, &paint, &stroke, transform, None);
+
+        let bounds = path
+            .compute_stroke_bounds(&stroke, transform)
+            .unwrap()
+            .round_out()
+            .unwrap();
+        for y in 0..pixmap.height() {
+            for x in 0..pixmap.width() {
+                if pixmap.pixel(x, y).unwrap().alpha() != 0 {
+                    assert!(bounds.contains(&IntRect::from_xywh(x as i32, y as i32, 1, 1).unwrap()));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn aligned_stroke() {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint.anti_alias = true;
+
+    let path = PathBuilder::from_circle(100.0, 100.0, 50.0).unwrap();
+    for alignment in [StrokeAlignment::Inside, StrokeAlignment::Outside] {
+        let stroke = Stroke {
+            width: 10.0,
+            alignment,
+            ..Stroke::default()
+        };
+
+        let mut pixmap = Pixmap::new(200, 200).unwrap();
+        pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+
+        let (min, max) = match alignment {
+            StrokeAlignment::Inside => (39.0, 51.0),
+            _ => (49.0, 61.0),
+        };
+
+        for y in 0..pixmap.height() {
+            for x in 0..pixmap.width() {
+                let dx = x as f32 + 0.5 - 100.0;
+                let dy = y as f32 + 0.5 - 100.0;
+                let dist = (dx * dx + dy * dy).sqrt();
+                let alpha = pixmap.pixel(x, y).unwrap().alpha();
+                if dist < min || dist > max {
+                    assert_eq!(alpha, 0);
+                } else if dist > min + 2.0 && dist < max - 2.0 {
+                    assert_eq!(alpha, 200);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn nib_stroke_even_odd() {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint.anti_alias = true;
+
+    // A self-intersecting curve with a sharp turn, so the pen imprints overlap a lot.
+    let mut pb = PathBuilder::new();
+    pb.move_to(30.0, 150.0);
+    pb.cubic_to(200.0, 20.0, 0.0, 20.0, 170.0, 150.0);
+    pb.line_to(100.0, 170.0);
+    let path = pb.finish().unwrap();
+
+    let nib = Nib::Ellipse {
+        rx: 12.0,
+        ry: 3.0,
+        angle: 30.0,
+    };
+    let stroked = path.stroke_nib(&nib, 1.0).unwrap();
+
+    let mut winding = Pixmap::new(200, 200).unwrap();
+    winding.fill_path(&stroked, &paint, FillRule::Winding, Transform::identity(), None);
+
+    let mut even_odd = Pixmap::new(200, 200).unwrap();
+    even_odd.fill_path(&stroked, &paint, FillRule::EvenOdd, Transform::identity(), None);
+
+    assert!(winding.pixels().iter().any(|p| p.alpha() != 0));
+    assert_eq!(winding, even_odd);
+}
@@ -0,0 +1,284 @@
+use tiny_skia::*;
+
+fn record(f: impl FnOnce(&mut PictureRecorder)) -> String {
+    let mut recorder = PictureRecorder::new();
+    f(&mut recorder);
+    recorder.finish().to_svg(100, 100)
+}
+
+fn triangle() -> Path {
+    let mut pb = PathBuilder::new();
+    pb.move_to(10.0, 10.0);
+    pb.line_to(50.0, 10.0);
+    pb.quad_to(60.0, 30.0, 50.0, 50.0);
+    pb.close();
+    pb.finish().unwrap()
+}
+
+// Returns the first `name="matrix(...)"` attribute value.
+fn parse_matrix(svg: &str, name: &str) -> Transform {
+    let start = svg.find(&format!("{}=\"matrix(", name)).unwrap() + name.len() + 9;
+    let end = start + svg[start..].find(')').unwrap();
+    let n: Vec<f32> = svg[start..end].split(' ').map(|n| n.parse().unwrap()).collect();
+    Transform::from_row(n[0], n[1], n[2], n[3], n[4], n[5])
+}
+
+fn map(ts: Transform, x: f32, y: f32) -> Point {
+    let mut p = [Point::from_xy(x, y)];
+    ts.map_points(&mut p);
+    p[0]
+}
+
+fn decode_base64(s: &str) -> Vec<u8> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut data = Vec::new();
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in s.bytes().take_while(|c| *c != b'=') {
+        acc = (acc << 6) | ALPHABET.iter().position(|a| *a == c).unwrap() as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            data.push((acc >> bits) as u8);
+        }
+    }
+    data
+}
+
+#[test]
+fn fill_path() {
+    let svg = record(|r| {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(50, 127, 150, 255);
+        r.fill_path(&triangle(), &paint, FillRule::EvenOdd, Transform::from_translate(5.0, 0.0), None);
+    });
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.contains("width=\"100\" height=\"100\" viewBox=\"0 0 100 100\""));
+    assert!(svg.contains(
+        "<path d=\"M 10 10 L 50 10 Q 60 30 50 50 Z\" fill=\"rgb(50,127,150)\" \
+         fill-rule=\"evenodd\" transform=\"matrix(1 0 0 1 5 0)\"/>"
+    ));
+    assert!(!svg.contains("<defs>"));
+}
+
+#[test]
+fn strokes() {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(0, 0, 0, 128);
+
+    let svg = record(|r| {
+        let stroke = Stroke {
+            width: 3.0,
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Bevel,
+            dash: StrokeDash::new(vec![5.0, 2.0], 1.0),
+            ..Stroke::default()
+        };
+        r.stroke_path(&triangle(), &paint, &stroke, Transform::identity(), None);
+    });
+    assert!(svg.contains(
+        "fill=\"none\" stroke=\"rgb(0,0,0)\" stroke-opacity=\"0.5019608\" stroke-width=\"3\" \
+         stroke-linecap=\"round\" stroke-linejoin=\"bevel\" stroke-dasharray=\"5 2\" \
+         stroke-dashoffset=\"1\"/>"
+    ));
+
+    // Not representable by SVG strokes.
+    let svg = record(|r| {
+        let stroke = Stroke {
+            width: 3.0,
+            alignment: StrokeAlignment::Inside,
+            ..Stroke::default()
+        };
+        r.stroke_path(&triangle(), &paint, &stroke, Transform::identity(), None);
+    });
+    assert!(!svg.contains("stroke="));
+    assert!(svg.contains("fill=\"rgb(0,0,0)\""));
+}
+
+#[test]
+fn gradients() {
+    let stops = vec![
+        GradientStop::new(0.0, Color::from_rgba8(50, 127, 150, 200)),
+        GradientStop::new(1.0, Color::from_rgba8(220, 140, 75, 255)),
+    ];
+
+    let svg = record(|r| {
+        let paint = Paint {
+            shader: LinearGradient::new(
+                Point::from_xy(10.0, 20.0),
+                Point::from_xy(90.0, 80.0),
+                stops.clone(),
+                SpreadMode::Reflect,
+                Transform::from_scale(0.5, 1.0),
+            ).unwrap(),
+            ..Paint::default()
+        };
+        r.fill_path(&triangle(), &paint, FillRule::Winding, Transform::identity(), None);
+    });
+    assert!(svg.contains("<linearGradient id=\"gradient1\" gradientUnits=\"userSpaceOnUse\" spreadMethod=\"reflect\""));
+    assert!(svg.contains("<stop offset=\"0\" stop-color=\"rgb(50,127,150)\" stop-opacity=\"0.78431374\"/>"));
+    assert!(svg.contains("<stop offset=\"1\" stop-color=\"rgb(220,140,75)\"/>"));
+    assert!(svg.contains("fill=\"url(#gradient1)\""));
+    // The gradient is along the x axis in the unit space.
+    let ts = parse_matrix(&svg, "gradientTransform");
+    assert!(map(ts, 0.0, 0.0).distance(Point::from_xy(5.0, 20.0)) < 0.001);
+    assert!(map(ts, 1.0, 0.0).distance(Point::from_xy(45.0, 80.0)) < 0.001);
+
+    let svg = record(|r| {
+        let paint = Paint {
+            shader: RadialGradient::new(
+                Point::from_xy(40.0, 40.0),
+                Point::from_xy(50.0, 60.0),
+                30.0,
+                stops.clone(),
+                SpreadMode::Pad,
+                Transform::identity(),
+            ).unwrap(),
+            ..Paint::default()
+        };
+        r.fill_path(&triangle(), &paint, FillRule::Winding, Transform::identity(), None);
+    });
+    let r1 = 30.0 / Point::from_xy(10.0, 20.0).length();
+    assert!(svg.contains(&format!("<radialGradient id=\"gradient1\" gradientUnits=\"userSpaceOnUse\" fx=\"0\" fy=\"0\" cx=\"1\" cy=\"0\" r=\"{}\"", r1)));
+    let ts = parse_matrix(&svg, "gradientTransform");
+    assert!(map(ts, 0.0, 0.0).distance(Point::from_xy(40.0, 40.0)) < 0.001);
+    assert!(map(ts, 1.0, 0.0).distance(Point::from_xy(50.0, 60.0)) < 0.001);
+    assert!((map(ts, 1.0 + r1, 0.0).distance(Point::from_xy(50.0, 60.0)) - 30.0).abs() < 0.001);
+
+    let svg = record(|r| {
+        let paint = Paint {
+            shader: RadialGradient::new(
+                Point::from_xy(40.0, 40.0),
+                Point::from_xy(40.0, 40.0),
+                30.0,
+                stops.clone(),
+                SpreadMode::Repeat,
+                Transform::identity(),
+            ).unwrap(),
+            ..Paint::default()
+        };
+        r.fill_path(&triangle(), &paint, FillRule::Winding, Transform::identity(), None);
+    });
+    assert!(svg.contains("cx=\"0\" cy=\"0\" r=\"1\" spreadMethod=\"repeat\""));
+    let ts = parse_matrix(&svg, "gradientTransform");
+    assert!(map(ts, 0.0, 0.0).distance(Point::from_xy(40.0, 40.0)) < 0.001);
+    assert!(map(ts, 0.0, 1.0).distance(Point::from_xy(40.0, 70.0)) < 0.001);
+
+    let svg = record(|r| {
+        let paint = Paint {
+            shader: RadialGradient::new_two_point_conical(
+                Point::from_xy(40.0, 40.0),
+                10.0,
+                Point::from_xy(50.0, 60.0),
+                30.0,
+                stops.clone(),
+                SpreadMode::Pad,
+                Transform::identity(),
+            ).unwrap(),
+            ..Paint::default()
+        };
+        r.fill_path(&triangle(), &paint, FillRule::Winding, Transform::identity(), None);
+    });
+    let d = Point::from_xy(10.0, 20.0).length();
+    assert!(svg.contains(&format!("fx=\"0\" fy=\"0\" cx=\"1\" cy=\"0\" r=\"{}\" fr=\"{}\"", 30.0 / d, 10.0 / d)));
+    let ts = parse_matrix(&svg, "gradientTransform");
+    assert!(map(ts, 0.0, 0.0).distance(Point::from_xy(40.0, 40.0)) < 0.001);
+    assert!(map(ts, 1.0, 0.0).distance(Point::from_xy(50.0, 60.0)) < 0.001);
+
+    let svg = record(|r| {
+        let paint = Paint {
+            shader: RadialGradient::new_two_point_conical(
+                Point::from_xy(40.0, 40.0),
+                10.0,
+                Point::from_xy(40.0, 40.0),
+                30.0,
+                stops.clone(),
+                SpreadMode::Pad,
+                Transform::identity(),
+            ).unwrap(),
+            ..Paint::default()
+        };
+        r.fill_path(&triangle(), &paint, FillRule::Winding, Transform::identity(), None);
+    });
+    assert!(svg.contains("cx=\"0\" cy=\"0\" r=\"1.5\" fr=\"0.5\""));
+    let ts = parse_matrix(&svg, "gradientTransform");
+    assert!(map(ts, 0.0, 1.0).distance(Point::from_xy(40.0, 60.0)) < 0.001);
+
+    let svg = record(|r| {
+        let mut paint = Paint {
+            shader: LinearGradient::new(
+                Point::from_xy(5.0, 20.0),
+                Point::from_xy(45.0, 80.0),
+                stops.clone(),
+                SpreadMode::Pad,
+                Transform::identity(),
+            ).unwrap(),
+            ..Paint::default()
+        };
+        paint.shader.set_gradient_interpolation(GradientInterpolation {
+            color_space: InterpolationColorSpace::LinearSRGB,
+            premultiplied: false,
+        });
+        r.fill_path(&triangle(), &paint, FillRule::Winding, Transform::identity(), None);
+    });
+    assert!(svg.contains("gradientUnits=\"userSpaceOnUse\" color-interpolation=\"linearRGB\""));
+}
+
+#[test]
+fn images_and_patterns() {
+    let mut image = Pixmap::new(4, 4).unwrap();
+    image.fill(Color::from_rgba8(0, 200, 0, 255));
+
+    let svg = record(|r| {
+        let paint = PixmapPaint { opacity: 0.5, ..PixmapPaint::default() };
+        r.draw_pixmap(10, 20, image.as_ref(), &paint, Transform::from_scale(2.0, 2.0), None);
+
+        let paint = Paint {
+            shader: Pattern::new(image.as_ref(), SpreadMode::Repeat, FilterQuality::Bilinear, 1.0, Transform::from_translate(1.0, 2.0)),
+            ..Paint::default()
+        };
+        r.fill_path(&triangle(), &paint, FillRule::Winding, Transform::identity(), None);
+    });
+
+    // The image is embedded once.
+    assert_eq!(svg.matches("data:image/png;base64,").count(), 1);
+    assert!(svg.contains("<use xlink:href=\"#image0\" x=\"10\" y=\"20\" opacity=\"0.5\" image-rendering=\"optimizeSpeed\" transform=\"matrix(2 0 0 2 0 0)\"/>"));
+    assert!(svg.contains("<pattern id=\"pattern1\" patternUnits=\"userSpaceOnUse\" width=\"4\" height=\"4\" patternTransform=\"matrix(1 0 0 1 1 2)\"><use xlink:href=\"#image0\"/></pattern>"));
+
+    let start = svg.find("base64,").unwrap() + 7;
+    let end = start + svg[start..].find('"').unwrap();
+    let decoded = Pixmap::decode_png(&decode_base64(&svg[start..end])).unwrap();
+    assert_eq!(decoded, image);
+}
+
+#[test]
+fn blend_modes_and_masks() {
+    let mut mask = Mask::new(100, 100).unwrap();
+    mask.fill_path(&PathBuilder::from_circle(50.0, 50.0, 30.0).unwrap(), FillRule::Winding, true, Transform::identity());
+
+    let svg = record(|r| {
+        let mut paint = Paint {
+            blend_mode: BlendMode::Multiply,
+            ..Paint::default()
+        };
+        r.fill_path(&triangle(), &paint, FillRule::Winding, Transform::identity(), None);
+        paint.blend_mode = BlendMode::ColorDodge;
+        r.fill_rect(Rect::from_xywh(0.0, 0.0, 20.0, 30.0).unwrap(), &paint, Transform::identity(), Some(&mask));
+        r.apply_mask(&mask);
+    });
+
+    assert!(svg.contains("<g style=\"mix-blend-mode:multiply\"><path d="));
+    assert!(svg.contains("<g mask=\"url(#mask0)\" style=\"mix-blend-mode:color-dodge\"><rect x=\"0\" y=\"0\" width=\"20\" height=\"30\" fill=\"rgb(0,0,0)\"/></g>"));
+    // `apply_mask` masks everything drawn before.
+    assert!(svg.contains("<g mask=\"url(#mask0)\">\n<g style="));
+    assert_eq!(svg.matches("<mask id=").count(), 1);
+
+    let start = svg.find("base64,").unwrap() + 7;
+    let end = start + svg[start..].find('"').unwrap();
+    let decoded = Pixmap::decode_png(&decode_base64(&svg[start..end])).unwrap();
+    // Stored as an opaque grayscale image.
+    let values: Vec<u8> = decoded.pixels().iter().map(|p| p.red()).collect();
+    assert!(decoded.pixels().iter().all(|p| p.alpha() == 255));
+    assert_eq!(values, mask.data());
+}